@@ -21,6 +21,9 @@ cargo +"$rust_stable" test-bpf -- --nocapture
 cargo +"$rust_stable" build
 cargo +"$rust_stable" test -- --nocapture
 
+# hmt-escrow serde JSON view and Borsh schema are behind a feature
+cargo +"$rust_stable" test --manifest-path=hmt-escrow/program/Cargo.toml --features serde -- --nocapture
+
 # Run test-client sanity check
 cargo +"$rust_stable" run --manifest-path=utils/test-client/Cargo.toml
 
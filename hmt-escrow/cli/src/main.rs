@@ -1,39 +1,83 @@
-use chrono::prelude::*;
+mod output;
+
+use async_trait::async_trait;
+use chrono::Utc;
 use clap::{
     crate_description, crate_name, crate_version, value_t, value_t_or_exit, App, AppSettings, Arg,
     SubCommand,
 };
+use futures::executor::block_on;
 use hmt_escrow::state::DataHash;
 use hmt_escrow::state::DataUrl;
 use hmt_escrow::{
     self, 
+    secp256k1::{
+        new_secp256k1_instruction, ETH_ADDRESS_LEN, ETH_ADDRESS_OFFSET, SIGNATURE_LEN,
+        SIGNATURE_OFFSET,
+    },
     instruction::{
-        initialize as initialize_escrow, payout, setup as setup_escrow, store_results,
-        cancel as cancel_escrow, complete as complete_escrow,
+        payout, store_results,
+        cancel as cancel_escrow, complete as complete_escrow, initialize_config,
+        pause as pause_escrow, payout_permit_message, payout_with_permit, set_permit_signer,
+        unpause as unpause_escrow, update_config, update_staking_config,
+        initialize_oracle_stake, deposit_oracle_stake, withdraw_oracle_stake, slash_oracle,
+        update_reputation, ReputationUpdate, store_intermediate_results,
+        initialize_allow_list, update_allow_list,
+        add_vault, store_vault_results, vault_payout, VaultRefund,
+        initialize_template, initialize_from_template,
     },
-    processor::Processor as EscrowProcessor, state::Escrow,
+    processor::Processor as EscrowProcessor,
+    state::{
+        AllowList, Escrow, EscrowConfig, EscrowState, EscrowTemplate, EscrowVault, OracleStake,
+        RecipientPayout, Reputation, ResultsLog, ResultsLogPage, MAX_ALLOWED_MINTS,
+        MAX_ALLOW_LIST_UPDATES, MAX_REPUTATION_UPDATES, RESULTS_LOG_PAGE_ENTRIES,
+    },
+};
+use hmt_escrow_client::{
+    amount::{amount_to_ui_amount, ui_amount_to_amount},
+    batch::transaction_ranges,
+    manifest::{fetch_hash, HashAlgorithm},
+    query::{find_escrows, find_token_accounts, find_vaults, EscrowFilter},
+    ClientError, EscrowClient, Transport,
 };
+use secp256k1::SecretKey;
 use solana_clap_utils::{
-    input_parsers::{pubkey_of, value_of},
-    input_validators::{is_amount, is_keypair, is_parsable, is_pubkey, is_url},
+    input_parsers::{pubkey_of, pubkey_of_signer, pubkeys_of, signer_of, value_of},
+    input_validators::{is_amount, is_parsable, is_pubkey, is_url, is_valid_signer},
     keypair::signer_from_path,
+    nonce::*,
+    offline::{self, *},
 };
-use solana_client::rpc_client::RpcClient;
+use output::{
+    CliAllowList, CliConfig, CliDataHash, CliDataVerification, CliEscrow, CliEscrowAction, CliEscrowList, CliEscrowStatus, CliEscrowSummary, CliKeeperEntry, CliOracle, CliOracleStake, CliPayout,
+    CliPayoutRecipient, CliPermit, CliReputation, CliResultsLog, CliResultsLogEntry,
+    CliStakingConfig, CliTemplate, CliTransaction,
+};
+use solana_cli_output::{return_signers, OutputFormat};
+use solana_client::{blockhash_query::BlockhashQuery, rpc_client::RpcClient};
 use solana_program::{
-    instruction::Instruction, program_option::COption, program_pack::Pack, pubkey::Pubkey,
+    hash::Hash, instruction::Instruction, message::Message, program_option::COption,
+    program_pack::Pack, pubkey::Pubkey, rent::Rent,
 };
 use solana_sdk::{
+    account::Account,
     commitment_config::CommitmentConfig,
     native_token::*,
-    signature::{Keypair, Signer},
+    signature::{Keypair, NullSigner, Signature, Signer},
     system_instruction,
     transaction::Transaction,
 };
 use spl_token::{
-    self, instruction::initialize_account, state::Account as TokenAccount, state::Mint as TokenMint,
+    self,
+    instruction::{initialize_account, transfer},
+    state::Account as TokenAccount,
+    state::Mint as TokenMint,
 };
-use std::fs::File;
-use std::io::BufReader;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Write};
+use std::thread::sleep;
+use std::time::Duration;
 use std::{fmt::Display, process::exit, str, str::FromStr};
 
 struct Config {
@@ -42,10 +86,16 @@ struct Config {
     owner: Box<dyn Signer>,
     fee_payer: Box<dyn Signer>,
     commitment_config: CommitmentConfig,
+    output_format: OutputFormat,
+    nonce_account: Option<Pubkey>,
+    nonce_authority: Option<Box<dyn Signer>>,
+    blockhash_query: BlockhashQuery,
+    sign_only: bool,
+    hash_algorithm: HashAlgorithm,
 }
 
 type Error = Box<dyn std::error::Error>;
-type CommandResult = Result<Option<Transaction>, Error>;
+type CommandResult = Result<String, Error>;
 
 macro_rules! unique_signers {
     ($vec:ident) => {
@@ -69,81 +119,223 @@ fn check_fee_payer_balance(config: &Config, required_balance: u64) -> Result<(),
     }
 }
 
+/// Prints progress messages in the display mode only, keeping JSON output parsable
+fn println_display(config: &Config, message: String) {
+    match config.output_format {
+        OutputFormat::Json | OutputFormat::JsonCompact => {}
+        _ => println!("{}", message),
+    }
+}
+
+/// Sends and confirms the signed transaction, returns its signature and created accounts
+fn send_transaction(
+    config: &Config,
+    transaction: Transaction,
+    created_accounts: BTreeMap<&'static str, String>,
+) -> CommandResult {
+    let signature = config
+        .rpc_client
+        .send_and_confirm_transaction_with_spinner_and_commitment(
+            &transaction,
+            config.commitment_config,
+        )?;
+    Ok(config.output_format.formatted_string(&CliTransaction {
+        created_accounts,
+        signature: signature.to_string(),
+    }))
+}
+
+/// Rent exempt balance, offline signing has no cluster to ask and assumes the default rent
+fn get_minimum_balance_for_rent_exemption(
+    config: &Config,
+    data_len: usize,
+) -> Result<u64, ClientError> {
+    if config.sign_only {
+        Ok(Rent::default().minimum_balance(data_len))
+    } else {
+        Ok(config
+            .rpc_client
+            .get_minimum_balance_for_rent_exemption(data_len)?)
+    }
+}
+
+/// Escrow client transport over the CLI RPC client, blocking like the RPC client itself
+struct CliTransport<'a> {
+    config: &'a Config,
+}
+
+#[async_trait(?Send)]
+impl Transport for CliTransport<'_> {
+    async fn get_account(&mut self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        Ok(self
+            .config
+            .rpc_client
+            .get_account_with_commitment(address, self.config.commitment_config)?
+            .value)
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(
+        &mut self,
+        data_len: usize,
+    ) -> Result<u64, ClientError> {
+        get_minimum_balance_for_rent_exemption(self.config, data_len)
+    }
+
+    async fn get_recent_blockhash(&mut self) -> Result<Hash, ClientError> {
+        let (recent_blockhash, _) = self.config.rpc_client.get_recent_blockhash()?;
+        Ok(recent_blockhash)
+    }
+
+    async fn process_transaction(&mut self, transaction: Transaction) -> Result<(), ClientError> {
+        self.config
+            .rpc_client
+            .send_and_confirm_transaction_with_spinner_and_commitment(
+                &transaction,
+                self.config.commitment_config,
+            )?;
+        Ok(())
+    }
+}
+
+/// Escrow client building the escrow instructions, the CLI signs and sends them itself
+/// to support offline signing and durable nonces
+fn escrow_client(config: &Config) -> EscrowClient<CliTransport> {
+    EscrowClient::new(
+        CliTransport { config },
+        hmt_escrow::id(),
+        Box::new(NullSigner::new(&config.fee_payer.pubkey())),
+    )
+}
+
+/// Signs instructions with the blockhash from `--blockhash` or the durable nonce if set,
+/// then either sends the transaction or, with `--sign-only`, prints its signatures
+fn process_instructions<'a>(
+    config: &'a Config,
+    instructions: Vec<Instruction>,
+    mut signers: Vec<&'a dyn Signer>,
+    required_balance: u64,
+    created_accounts: BTreeMap<&'static str, String>,
+) -> CommandResult {
+    let fee_payer = config.fee_payer.pubkey();
+    let message = match config.nonce_account {
+        Some(nonce_account) => {
+            let nonce_authority = config
+                .nonce_authority
+                .as_deref()
+                .unwrap_or_else(|| config.owner.as_ref());
+            signers.push(nonce_authority);
+            Message::new_with_nonce(
+                instructions,
+                Some(&fee_payer),
+                &nonce_account,
+                &nonce_authority.pubkey(),
+            )
+        }
+        None => Message::new(&instructions, Some(&fee_payer)),
+    };
+    let (recent_blockhash, fee_calculator) = config
+        .blockhash_query
+        .get_blockhash_and_fee_calculator(&config.rpc_client, config.commitment_config)?;
+
+    let mut transaction = Transaction::new_unsigned(message);
+    unique_signers!(signers);
+    if config.sign_only {
+        transaction.try_partial_sign(&signers, recent_blockhash)?;
+        return Ok(return_signers(&transaction, &config.output_format)?);
+    }
+
+    check_fee_payer_balance(
+        config,
+        required_balance + fee_calculator.calculate_fee(transaction.message()),
+    )?;
+    transaction.try_sign(&signers, recent_blockhash)?;
+    send_transaction(config, transaction, created_accounts)
+}
+
+/// Escrow and its token account are signers of the create transaction,
+/// broadcasting a transaction signed offline needs the same keys
+struct SignOnlyNeedsEscrowKeys {}
+impl offline::ArgsConfig for SignOnlyNeedsEscrowKeys {
+    fn sign_only_arg<'a, 'b>(&self, arg: Arg<'a, 'b>) -> Arg<'a, 'b> {
+        arg.requires_all(&["escrow_keypair", "escrow_token_keypair", "canceler_token"])
+    }
+}
+
+/// Offline signing cannot read the escrow to find its token accounts
+struct SignOnlyNeedsEscrowAccounts {}
+impl offline::ArgsConfig for SignOnlyNeedsEscrowAccounts {
+    fn sign_only_arg<'a, 'b>(&self, arg: Arg<'a, 'b>) -> Arg<'a, 'b> {
+        arg.requires_all(&["escrow_token_account", "canceler_token"])
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn command_create(
     config: &Config,
+    escrow_account: Box<dyn Signer>,
+    escrow_token_account: Box<dyn Signer>,
     mint: &Pubkey,
     launcher: &Option<Pubkey>,
     canceler: &Option<Pubkey>,
     canceler_token: &Option<Pubkey>,
+    guardian: &Option<Pubkey>,
     duration: u64,
 ) -> CommandResult {
-    let escrow_token_account = Keypair::new();
-    println!(
-        "Creating escrow token account {}",
-        escrow_token_account.pubkey()
+    println_display(
+        config,
+        format!(
+            "Creating escrow token account {}",
+            escrow_token_account.pubkey()
+        ),
+    );
+    let mut created_accounts = BTreeMap::new();
+    created_accounts.insert(
+        "escrowTokenAccount",
+        escrow_token_account.pubkey().to_string(),
     );
 
-    let escrow_account = Keypair::new();
-
-    let token_account_balance = config
-        .rpc_client
-        .get_minimum_balance_for_rent_exemption(TokenAccount::LEN)?;
-    let escrow_account_balance = config
-        .rpc_client
-        .get_minimum_balance_for_rent_exemption(Escrow::LEN)?;
+    let token_account_balance = get_minimum_balance_for_rent_exemption(config, TokenAccount::LEN)?;
+    let escrow_account_balance = get_minimum_balance_for_rent_exemption(config, Escrow::LEN)?;
     let mut total_rent_free_balances = token_account_balance + escrow_account_balance;
 
-    // Calculate withdraw authority used for minting pool tokens
-    let (authority, _) =
-        EscrowProcessor::find_authority_bump_seed(&hmt_escrow::id(), &escrow_account.pubkey());
-
+    let mut client = escrow_client(config);
     if config.verbose {
-        println!("Escrow authority {}", authority);
+        println_display(
+            config,
+            format!(
+                "Escrow authority {}",
+                client.authority_address(&escrow_account.pubkey())
+            ),
+        );
     }
 
-    let mut instructions: Vec<Instruction> = vec![
-        // Account for the escrow tokens
-        system_instruction::create_account(
-            &config.fee_payer.pubkey(),
-            &escrow_token_account.pubkey(),
-            token_account_balance,
-            TokenAccount::LEN as u64,
-            &spl_token::id(),
-        ),
-        // Account for the escrow
-        system_instruction::create_account(
-            &config.fee_payer.pubkey(),
-            &escrow_account.pubkey(),
-            escrow_account_balance,
-            Escrow::LEN as u64,
-            &hmt_escrow::id(),
-        ),
-        // Initialize escrow token account
-        initialize_account(
-            &spl_token::id(),
-            &escrow_token_account.pubkey(),
-            mint,
-            &authority,
-        )?,
-    ];
+    let mut instructions: Vec<Instruction> = vec![];
 
     let mut signers = vec![
         config.fee_payer.as_ref(),
-        &escrow_token_account,
-        &escrow_account,
+        escrow_token_account.as_ref(),
+        escrow_account.as_ref(),
     ];
 
     // Unwrap optionals
     let launcher: Pubkey = launcher.unwrap_or(config.owner.pubkey());
     let canceler: Pubkey = canceler.unwrap_or(config.owner.pubkey());
+    let guardian: Pubkey = guardian.unwrap_or(config.owner.pubkey());
 
     let canceler_token_account = Keypair::new();
     let canceler_token: Pubkey = match canceler_token {
         Some(value) => *value,
         None => {
-            println!(
-                "Creating canceler token account {}",
-                canceler_token_account.pubkey()
+            println_display(
+                config,
+                format!(
+                    "Creating canceler token account {}",
+                    canceler_token_account.pubkey()
+                ),
+            );
+            created_accounts.insert(
+                "cancelerTokenAccount",
+                canceler_token_account.pubkey().to_string(),
             );
 
             instructions.extend(vec![
@@ -172,43 +364,47 @@ fn command_create(
         }
     };
 
-    println!("Creating escrow {}", escrow_account.pubkey());
-    instructions.extend(vec![
-        // Initialize escrow account
-        initialize_escrow(
-            &hmt_escrow::id(),
-            &escrow_account.pubkey(),
-            mint,
-            &escrow_token_account.pubkey(),
-            &launcher,
-            &canceler,
-            &canceler_token,
-            duration,
-        )?,
-    ]);
-
-    let mut transaction =
-        Transaction::new_with_payer(&instructions, Some(&config.fee_payer.pubkey()));
+    println_display(config, format!("Creating escrow {}", escrow_account.pubkey()));
+    created_accounts.insert("escrow", escrow_account.pubkey().to_string());
+    instructions.extend(block_on(client.create_instructions(
+        &escrow_account.pubkey(),
+        &escrow_token_account.pubkey(),
+        mint,
+        &launcher,
+        &canceler,
+        &canceler_token,
+        &guardian,
+        duration,
+    ))?);
 
-    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
-    check_fee_payer_balance(
+    process_instructions(
         config,
-        total_rent_free_balances + fee_calculator.calculate_fee(&transaction.message()),
-    )?;
-    unique_signers!(signers);
-    transaction.sign(&signers, recent_blockhash);
-    Ok(Some(transaction))
+        instructions,
+        signers,
+        total_rent_free_balances,
+        created_accounts,
+    )
+}
+
+fn cancellation_fee_string(fee: u64, percent: u8, decimals: u8) -> String {
+    if percent != 0 {
+        format!("{}%", percent)
+    } else if fee != 0 {
+        spl_token::amount_to_ui_amount(fee, decimals).to_string()
+    } else {
+        "no".to_string()
+    }
 }
 
-fn format_coption_key<'a>(optional: &'a COption<Pubkey>) -> Box<dyn std::fmt::Display + 'a> {
+fn coption_key_string(optional: &COption<Pubkey>) -> Option<String> {
     match optional {
-        COption::Some(key) => Box::new(key),
-        COption::None => Box::new("None"),
+        COption::Some(key) => Some(key.to_string()),
+        COption::None => None,
     }
 }
 
-fn command_info(config: &Config, escrow: &Pubkey) -> CommandResult {
-    let account_data = config.rpc_client.get_account_data(escrow)?;
+fn command_info(config: &Config, address: &Pubkey) -> CommandResult {
+    let account_data = config.rpc_client.get_account_data(address)?;
     let escrow: Escrow = Escrow::unpack_from_slice(account_data.as_slice())?;
 
     // Check token mint to convert amount to float
@@ -219,71 +415,349 @@ fn command_info(config: &Config, escrow: &Pubkey) -> CommandResult {
     let mint_info: TokenMint = TokenMint::unpack_from_slice(account_data.as_slice())
         .map_err(|_| format!("{} is not a valid mint address", escrow.token_mint))?;
 
-    println!("Escrow information");
-    println!("==================");
-    println!("State: {:?}", escrow.state);
-    println!(
-        "Expires: {}",
-        NaiveDateTime::from_timestamp(escrow.expires, 0)
-            .format("%Y-%m-%d %H:%M:%S")
-            .to_string()
-    );
-    println!("Token mint: {}", escrow.token_mint);
-    println!("Token account: {}", escrow.token_account);
-    println!("Launcher: {}", escrow.launcher);
-    println!("Canceler: {}", escrow.canceler);
-    println!("Canceler token account: {}", escrow.canceler_token_account);
-    println!();
-    println!("Reputation oracle");
-    println!("=================");
-    println!("Account: {}", format_coption_key(&escrow.reputation_oracle));
-    println!(
-        "Token account: {}",
-        format_coption_key(&escrow.reputation_oracle_token_account)
-    );
-    println!("Fee: {}%", escrow.reputation_oracle_stake);
-    println!();
-    println!("Recording oracle");
-    println!("================");
-    println!("Account: {}", format_coption_key(&escrow.recording_oracle));
-    println!(
-        "Token account: {}",
-        format_coption_key(&escrow.recording_oracle_token_account)
-    );
-    println!("Fee: {}%", escrow.recording_oracle_stake);
-    println!();
-    println!("Data");
-    println!("====");
-    println!(
-        "Job manifest URL: {}",
-        str::from_utf8(escrow.manifest_url.as_ref()).unwrap_or("")
-    );
-    println!(
-        "Job manifest hash: {}",
-        hex::encode(escrow.manifest_hash.as_ref())
-    );
-    println!(
-        "Final results URL: {}",
-        str::from_utf8(escrow.final_results_url.as_ref()).unwrap_or("")
-    );
-    println!(
-        "Final results hash: {}",
-        hex::encode(escrow.final_results_hash.as_ref())
-    );
-    println!();
-    println!("Amounts and recipients");
-    println!("======================");
-    println!(
-        "Amount: {} ({} sent)",
-        spl_token::amount_to_ui_amount(escrow.total_amount, mint_info.decimals),
-        spl_token::amount_to_ui_amount(escrow.sent_amount, mint_info.decimals),
-    );
-    println!(
-        "Recipients: {} ({} sent)",
-        escrow.total_recipients, escrow.sent_recipients,
+    let cli_escrow = CliEscrow {
+        address: address.to_string(),
+        state: format!("{:?}", escrow.state),
+        expires: escrow.expires,
+        token_mint: escrow.token_mint.to_string(),
+        token_account: escrow.token_account.to_string(),
+        launcher: escrow.launcher.to_string(),
+        canceler: escrow.canceler.to_string(),
+        canceler_token_account: escrow.canceler_token_account.to_string(),
+        guardian: escrow.guardian.to_string(),
+        paused: escrow.paused,
+        reputation_oracle: CliOracle {
+            account: coption_key_string(&escrow.reputation_oracle),
+            token_account: coption_key_string(&escrow.reputation_oracle_token_account),
+            fee: escrow.reputation_oracle_stake,
+        },
+        recording_oracle: CliOracle {
+            account: coption_key_string(&escrow.recording_oracle),
+            token_account: coption_key_string(&escrow.recording_oracle_token_account),
+            fee: escrow.recording_oracle_stake,
+        },
+        manifest_url: str::from_utf8(escrow.manifest_url.as_ref())
+            .unwrap_or("")
+            .to_string(),
+        manifest_hash: hex::encode(escrow.manifest_hash.as_ref()),
+        final_results_url: str::from_utf8(escrow.final_results_url.as_ref())
+            .unwrap_or("")
+            .to_string(),
+        final_results_hash: hex::encode(escrow.final_results_hash.as_ref()),
+        results_log_count: escrow.results_log_count,
+        results_log_head: escrow.results_log_head.to_string(),
+        total_amount: spl_token::amount_to_ui_amount(escrow.total_amount, mint_info.decimals)
+            .to_string(),
+        sent_amount: spl_token::amount_to_ui_amount(escrow.sent_amount, mint_info.decimals)
+            .to_string(),
+        total_recipients: escrow.total_recipients,
+        sent_recipients: escrow.sent_recipients,
+        payout_nonce: escrow.payout_nonce,
+        permit_signer: if escrow.permit_signer == [0; ETH_ADDRESS_LEN] {
+            None
+        } else {
+            Some(hex::encode(escrow.permit_signer))
+        },
+        max_payout_per_recipient: if escrow.max_payout_per_recipient == 0 {
+            "no".to_string()
+        } else {
+            spl_token::amount_to_ui_amount(escrow.max_payout_per_recipient, mint_info.decimals)
+                .to_string()
+        },
+        min_payout: spl_token::amount_to_ui_amount(escrow.min_payout, mint_info.decimals)
+            .to_string(),
+        allow_list: coption_key_string(&escrow.allow_list),
+        vault_count: escrow.vault_count,
+        unpaid_vaults: escrow.unpaid_vaults,
+        cancellation_fee: cancellation_fee_string(
+            escrow.cancellation_fee,
+            escrow.cancellation_fee_percent,
+            mint_info.decimals,
+        ),
+        cancel_notice_end: if escrow.cancel_notice_end == 0 {
+            None
+        } else {
+            Some(escrow.cancel_notice_end)
+        },
+    };
+
+    Ok(config.output_format.formatted_string(&cli_escrow))
+}
+
+/// Lists escrows matching the filter
+fn command_list(config: &Config, filter: &EscrowFilter) -> CommandResult {
+    let escrows = find_escrows(&config.rpc_client, &hmt_escrow::id(), filter)?;
+    let cli_escrows = CliEscrowList {
+        escrows: escrows
+            .into_iter()
+            .map(|(address, escrow)| CliEscrowSummary {
+                address: address.to_string(),
+                state: format!("{:?}", escrow.state),
+                token_mint: escrow.token_mint.to_string(),
+                launcher: escrow.launcher.to_string(),
+                expires: escrow.expires,
+            })
+            .collect(),
+    };
+
+    Ok(config.output_format.formatted_string(&cli_escrows))
+}
+
+/// Transfers tokens from the owner's token account to the escrow token account
+fn command_fund(
+    config: &Config,
+    escrow: &Pubkey,
+    amount: &str,
+    from: &Option<Pubkey>,
+) -> CommandResult {
+    let account_data = config
+        .rpc_client
+        .get_account_data(escrow)
+        .or(Err("Cannot read escrow data"))?;
+    let escrow_info: Escrow = Escrow::unpack_from_slice(account_data.as_slice())
+        .map_err(|_| format!("{} is not a valid escrow address", escrow))?;
+
+    // Tokens sent after the escrow is finished could not be paid or returned
+    if let EscrowState::Paid | EscrowState::Complete | EscrowState::Cancelled = escrow_info.state
+    {
+        return Err(format!("Cannot fund {:?} escrow", escrow_info.state).into());
+    }
+
+    let decimals = get_mint_decimals(config, &escrow_info.token_mint)?;
+    let amount = ui_amount_to_amount(amount, decimals)?;
+    if amount == 0 {
+        return Err("Amount must be positive".into());
+    }
+
+    // Default to the first owner's token account of the escrow mint holding enough tokens
+    let from = match from {
+        Some(from) => *from,
+        None => find_token_accounts(
+            &config.rpc_client,
+            &config.owner.pubkey(),
+            &escrow_info.token_mint,
+        )?
+        .into_iter()
+        .find(|(_, account)| account.amount >= amount)
+        .map(|(address, _)| address)
+        .ok_or_else(|| {
+            format!(
+                "{} has no {} token account holding {}",
+                config.owner.pubkey(),
+                escrow_info.token_mint,
+                amount_to_ui_amount(amount, decimals)
+            )
+        })?,
+    };
+    println_display(
+        config,
+        format!(
+            "Funding escrow {} with {} from {}",
+            escrow,
+            amount_to_ui_amount(amount, decimals),
+            from
+        ),
     );
 
-    Ok(None)
+    process_instructions(
+        config,
+        vec![transfer(
+            &spl_token::id(),
+            &from,
+            &escrow_info.token_account,
+            &config.owner.pubkey(),
+            &[],
+            amount,
+        )?],
+        vec![config.fee_payer.as_ref(), config.owner.as_ref()],
+        0,
+        BTreeMap::new(),
+    )
+}
+
+/// Shows escrow balance against the amount left to pay and the operations it allows now
+fn command_status(config: &Config, escrow: &Pubkey) -> CommandResult {
+    let account_data = config
+        .rpc_client
+        .get_account_data(escrow)
+        .or(Err("Cannot read escrow data"))?;
+    let escrow_info: Escrow = Escrow::unpack_from_slice(account_data.as_slice())
+        .map_err(|_| format!("{} is not a valid escrow address", escrow))?;
+    let decimals = get_mint_decimals(config, &escrow_info.token_mint)?;
+    let account_data = config
+        .rpc_client
+        .get_account_data(&escrow_info.token_account)
+        .or(Err("Cannot read escrow token account data"))?;
+    let balance = TokenAccount::unpack_from_slice(account_data.as_slice())
+        .map_err(|_| {
+            format!(
+                "{} is not a valid token account address",
+                escrow_info.token_account
+            )
+        })?
+        .amount;
+
+    let remaining_amount = escrow_info
+        .total_amount
+        .saturating_sub(escrow_info.sent_amount);
+    let surplus = if balance >= remaining_amount {
+        amount_to_ui_amount(balance - remaining_amount, decimals)
+    } else {
+        format!("-{}", amount_to_ui_amount(remaining_amount - balance, decimals))
+    };
+    let fee = |stake: u8| remaining_amount.checked_mul(stake as u64).unwrap_or(0) / 100;
+
+    // Same checks the program does, expiry is judged by the local clock
+    let expires_in = escrow_info.expires - Utc::now().timestamp();
+    let state = escrow_info.state;
+    let payout = CliEscrowAction::new(&[
+        (escrow_info.paused, "escrow is paused"),
+        (expires_in < 0, "escrow is expired"),
+        (
+            state != EscrowState::Pending && state != EscrowState::Partial,
+            "results are not stored or escrow is finished",
+        ),
+        (remaining_amount == 0, "nothing left to pay"),
+        (balance == 0, "escrow token account is empty"),
+    ]);
+    let cancel = CliEscrowAction::new(&[
+        (
+            state == EscrowState::Paid || state == EscrowState::Complete,
+            "escrow is fully paid",
+        ),
+        (balance == 0, "escrow token account is empty"),
+    ]);
+    let complete = CliEscrowAction::new(&[
+        (escrow_info.paused, "escrow is paused"),
+        (expires_in < 0, "escrow is expired"),
+        (state != EscrowState::Paid, "escrow is not fully paid"),
+    ]);
+
+    Ok(config.output_format.formatted_string(&CliEscrowStatus {
+        address: escrow.to_string(),
+        state: format!("{:?}", state),
+        paused: escrow_info.paused,
+        expires: escrow_info.expires,
+        expires_in,
+        balance: amount_to_ui_amount(balance, decimals),
+        remaining_amount: amount_to_ui_amount(remaining_amount, decimals),
+        remaining_recipients: escrow_info
+            .total_recipients
+            .saturating_sub(escrow_info.sent_recipients),
+        surplus,
+        reputation_fee: amount_to_ui_amount(fee(escrow_info.reputation_oracle_stake), decimals),
+        reputation_oracle_stake: escrow_info.reputation_oracle_stake,
+        recording_fee: amount_to_ui_amount(fee(escrow_info.recording_oracle_stake), decimals),
+        recording_oracle_stake: escrow_info.recording_oracle_stake,
+        payout,
+        cancel,
+        complete,
+    }))
+}
+
+/// Parses the hash given on the command line, with `verify` fetches the data from `url`
+/// and checks it against the given hash or uses its hash when none is given
+fn data_hash(
+    config: &Config,
+    url: &str,
+    hash: &Option<String>,
+    verify: bool,
+) -> Result<DataHash, Error> {
+    let hash = match hash {
+        None => None,
+        Some(value) => {
+            let bytes = hex::decode(value).or(Err("Hash decoding error"))?;
+            Some(DataHash::new_from_slice(&bytes).or(Err("Wrong hash size"))?)
+        }
+    };
+    if !verify {
+        return Ok(hash.unwrap_or_default());
+    }
+
+    let actual = fetch_hash(url, config.hash_algorithm)
+        .map_err(|err| format!("Cannot fetch {}: {}", url, err))?;
+    match hash {
+        Some(hash) if hash != actual => Err(format!(
+            "{} {} hash is {}, expected {}",
+            url,
+            config.hash_algorithm,
+            hex::encode(actual),
+            hex::encode(hash)
+        )
+        .into()),
+        _ => Ok(actual),
+    }
+}
+
+/// Computes the manifest or results hash of a local file or URL
+fn command_hash(config: &Config, source: &str) -> CommandResult {
+    let hash = fetch_hash(source, config.hash_algorithm)?;
+    Ok(config.output_format.formatted_string(&CliDataHash {
+        source: source.to_string(),
+        algorithm: config.hash_algorithm.to_string(),
+        hash: hex::encode(hash),
+    }))
+}
+
+/// Fetches the escrow manifest or final results and checks them against the stored hash,
+/// `url` and `hash` replace the escrow values, `escrow` is optional when both are given
+fn command_verify(
+    config: &Config,
+    escrow: &Option<Pubkey>,
+    results: bool,
+    url: &Option<String>,
+    hash: &Option<String>,
+) -> CommandResult {
+    let escrow_info = match escrow {
+        Some(escrow) => {
+            let account_data = config
+                .rpc_client
+                .get_account_data(escrow)
+                .or(Err("Cannot read escrow data"))?;
+            Some(
+                Escrow::unpack_from_slice(account_data.as_slice())
+                    .map_err(|_| format!("{} is not a valid escrow address", escrow))?,
+            )
+        }
+        None => None,
+    };
+
+    let url = match (url, &escrow_info) {
+        (Some(url), _) => url.clone(),
+        (None, Some(escrow_info)) => {
+            let url = if results {
+                escrow_info.final_results_url
+            } else {
+                escrow_info.manifest_url
+            };
+            url.as_str()?.to_string()
+        }
+        (None, None) => return Err("Either escrow or URL is required".into()),
+    };
+    let expected = match (hash, &escrow_info) {
+        (Some(hash), _) => {
+            let bytes = hex::decode(hash).or(Err("Hash decoding error"))?;
+            DataHash::new_from_slice(&bytes).or(Err("Wrong hash size"))?
+        }
+        (None, Some(escrow_info)) if results => escrow_info.final_results_hash,
+        (None, Some(escrow_info)) => escrow_info.manifest_hash,
+        (None, None) => return Err("Either escrow or hash is required".into()),
+    };
+
+    let actual = fetch_hash(&url, config.hash_algorithm)
+        .map_err(|err| format!("Cannot fetch {}: {}", url, err))?;
+    let cli_verification = CliDataVerification {
+        url,
+        algorithm: config.hash_algorithm.to_string(),
+        expected: hex::encode(expected),
+        actual: hex::encode(actual),
+        verified: actual == expected,
+    };
+    if !cli_verification.verified {
+        println!("{}", config.output_format.formatted_string(&cli_verification));
+        return Err("Hash does not match".into());
+    }
+    Ok(config.output_format.formatted_string(&cli_verification))
 }
 
 /// Issues setup command
@@ -299,6 +773,13 @@ fn command_setup(
     recording_oracle_stake: u8,
     manifest_url: &str,
     manifest_hash: &Option<String>,
+    max_payout_per_recipient: &Option<String>,
+    min_payout: &Option<String>,
+    cancellation_fee: &Option<String>,
+    cancellation_fee_percent: u8,
+    cancel_notice: u64,
+    allow_list: &Option<Pubkey>,
+    verify: bool,
 ) -> CommandResult {
     // Validate parameters
     if reputation_oracle_stake > 100
@@ -307,15 +788,15 @@ fn command_setup(
     {
         return Err("Invalid stake values".into());
     }
+    if cancellation_fee_percent > 100 {
+        return Err("Invalid cancellation fee percent".into());
+    }
+    if cancellation_fee.is_some() && cancellation_fee_percent != 0 {
+        return Err("Cancellation fee can be either fixed or a percent, not both".into());
+    }
 
+    let manifest_hash = data_hash(config, manifest_url, manifest_hash, verify)?;
     let manifest_url: DataUrl = DataUrl::from_str(manifest_url).or(Err("URL too long"))?;
-    let manifest_hash: DataHash = match manifest_hash {
-        None => Default::default(),
-        Some(value) => {
-            let bytes = hex::decode(value).or(Err("Hash decoding error"))?;
-            DataHash::new_from_slice(&bytes).or(Err("Wrong hash size"))?
-        }
-    };
 
     let mut instructions: Vec<Instruction> = vec![];
     let token_account_balance = config
@@ -324,10 +805,34 @@ fn command_setup(
     let mut total_rent_free_balances = 0;
 
     let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+    let mut created_accounts = BTreeMap::new();
 
     // Read escrow state
-    let account_data = config.rpc_client.get_account_data(escrow)?;
-    let escrow_info: Escrow = Escrow::unpack_from_slice(account_data.as_slice())?;
+    let mut client = escrow_client(config);
+    let escrow_info = block_on(client.get_escrow(escrow))?;
+
+    // Convert payout limits to token units, zero maximum meaning no limit
+    let decimals = get_mint_decimals(config, &escrow_info.token_mint)?;
+    let max_payout_per_recipient = match max_payout_per_recipient {
+        Some(value) => ui_amount_to_amount(value, decimals)?,
+        None => 0,
+    };
+    let min_payout = match min_payout {
+        Some(value) => ui_amount_to_amount(value, decimals)?,
+        None => 0,
+    };
+    if max_payout_per_recipient != 0 && min_payout > max_payout_per_recipient {
+        return Err("Minimum payout cannot exceed maximum payout per recipient".into());
+    }
+    let cancellation_fee = match cancellation_fee {
+        Some(value) => ui_amount_to_amount(value, decimals)?,
+        None => 0,
+    };
+
+    // Make sure the allow-list exists before referencing it from the escrow
+    if let Some(allow_list) = allow_list {
+        get_allow_list(config, allow_list)?;
+    }
 
     // Unwrap optionals
     let reputation_oracle: Pubkey = reputation_oracle.unwrap_or(config.owner.pubkey());
@@ -336,9 +841,16 @@ fn command_setup(
     let reputation_oracle_token: Pubkey = match reputation_oracle_token {
         Some(value) => *value,
         None => {
-            println!(
-                "Creating reputation oracle token account {}",
-                reputation_oracle_token_account.pubkey()
+            println_display(
+                config,
+                format!(
+                    "Creating reputation oracle token account {}",
+                    reputation_oracle_token_account.pubkey()
+                ),
+            );
+            created_accounts.insert(
+                "reputationOracleTokenAccount",
+                reputation_oracle_token_account.pubkey().to_string(),
             );
 
             instructions.extend(vec![
@@ -370,9 +882,16 @@ fn command_setup(
     let recording_oracle_token: Pubkey = match recording_oracle_token {
         Some(value) => *value,
         None => {
-            println!(
-                "Creating recording oracle token account {}",
-                recording_oracle_token_account.pubkey()
+            println_display(
+                config,
+                format!(
+                    "Creating recording oracle token account {}",
+                    recording_oracle_token_account.pubkey()
+                ),
+            );
+            created_accounts.insert(
+                "recordingOracleTokenAccount",
+                recording_oracle_token_account.pubkey().to_string(),
             );
 
             instructions.extend(vec![
@@ -401,22 +920,24 @@ fn command_setup(
         }
     };
 
-    instructions.extend(vec![
-        // Add escrow setup instruction
-        setup_escrow(
-            &hmt_escrow::id(),
-            &escrow,
-            &config.owner.pubkey(),
-            &reputation_oracle,
-            &reputation_oracle_token,
-            reputation_oracle_stake,
-            &recording_oracle,
-            &recording_oracle_token,
-            recording_oracle_stake,
-            &manifest_url,
-            &manifest_hash,
-        )?,
-    ]);
+    instructions.push(block_on(client.setup_instruction(
+        escrow,
+        &config.owner.pubkey(),
+        &reputation_oracle,
+        &reputation_oracle_token,
+        reputation_oracle_stake,
+        &recording_oracle,
+        &recording_oracle_token,
+        recording_oracle_stake,
+        &manifest_url,
+        &manifest_hash,
+        max_payout_per_recipient,
+        min_payout,
+        cancellation_fee,
+        cancellation_fee_percent,
+        cancel_notice,
+        allow_list.as_ref(),
+    ))?);
 
     let mut transaction =
         Transaction::new_with_payer(&instructions, Some(&config.fee_payer.pubkey()));
@@ -428,7 +949,7 @@ fn command_setup(
     )?;
     unique_signers!(signers);
     transaction.sign(&signers, recent_blockhash);
-    Ok(Some(transaction))
+    send_transaction(config, transaction, created_accounts)
 }
 
 /// Issues store results command
@@ -439,16 +960,11 @@ fn command_store_results(
     recipients: u64,
     results_url: &str,
     results_hash: &Option<String>,
+    verify: bool,
 ) -> CommandResult {
     // Validate parameters
+    let results_hash = data_hash(config, results_url, results_hash, verify)?;
     let results_url: DataUrl = DataUrl::from_str(results_url).or(Err("URL too long"))?;
-    let results_hash: DataHash = match results_hash {
-        None => Default::default(),
-        Some(value) => {
-            let bytes = hex::decode(value).or(Err("Hash decoding error"))?;
-            DataHash::new_from_slice(&bytes).or(Err("Wrong hash size"))?
-        }
-    };
 
     // Read escrow state
     let account_data = config
@@ -468,6 +984,12 @@ fn command_store_results(
 
     let amount = spl_token::ui_amount_to_amount(amount, mint_info.decimals);
 
+    // Oracle stakes are locked if the amount reaches the config staking threshold
+    let reputation_oracle = escrow_info
+        .reputation_oracle
+        .ok_or("Escrow is not set up")?;
+    let recording_oracle = escrow_info.recording_oracle.ok_or("Escrow is not set up")?;
+
     let mut transaction = Transaction::new_with_payer(
         &[
             // Store results instruction
@@ -479,6 +1001,11 @@ fn command_store_results(
                 recipients,
                 &results_url,
                 &results_hash,
+                &EscrowProcessor::find_results_log_address(&hmt_escrow::id(), escrow).0,
+                &EscrowProcessor::find_config_address(&hmt_escrow::id()).0,
+                &EscrowProcessor::find_oracle_stake_address(&hmt_escrow::id(), &reputation_oracle)
+                    .0,
+                &EscrowProcessor::find_oracle_stake_address(&hmt_escrow::id(), &recording_oracle).0,
             )?,
         ],
         Some(&config.fee_payer.pubkey()),
@@ -489,43 +1016,522 @@ fn command_store_results(
     let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
     unique_signers!(signers);
     transaction.sign(&signers, recent_blockhash);
-    Ok(Some(transaction))
+    send_transaction(config, transaction, BTreeMap::new())
 }
 
-#[derive(Debug)]
-struct PayoutRecord {
-    recipient: Pubkey,
-    amount: f64,
-}
+/// Appends intermediate results to the escrow results log, owner must be the escrow recording oracle
+fn command_store_intermediate_results(
+    config: &Config,
+    escrow: &Pubkey,
+    results_url: &str,
+    results_hash: &Option<String>,
+    verify: bool,
+) -> CommandResult {
+    // Validate parameters
+    let results_hash = data_hash(config, results_url, results_hash, verify)?;
+    let results_url: DataUrl = DataUrl::from_str(results_url).or(Err("URL too long"))?;
 
-/// Creates transaction for payout from the escrow account
-fn command_payout(config: &Config, escrow: &Pubkey, file_name: &str) -> CommandResult {
-    // Read CSV file and validate its contents
+    let (results_log, _) = EscrowProcessor::find_results_log_address(&hmt_escrow::id(), escrow);
+
+    // Results log and its pages are created when needed and funded by the recording oracle
+    let mut required_balance = 0;
+    let mut created_accounts = BTreeMap::new();
+    let count = match config.rpc_client.get_account_data(&results_log) {
+        Ok(log_data) => {
+            ResultsLog::unpack_from_slice(log_data.as_slice())
+                .map_err(|_| format!("{} is not a valid results log address", results_log))?
+                .count
+        }
+        Err(_) => {
+            println_display(config, format!("Creating results log {}", results_log));
+            created_accounts.insert("resultsLog", results_log.to_string());
+            required_balance += config
+                .rpc_client
+                .get_minimum_balance_for_rent_exemption(ResultsLog::LEN)?;
+            0
+        }
+    };
+    let (results_log_page, _) = EscrowProcessor::find_results_log_page_address(
+        &hmt_escrow::id(),
+        escrow,
+        ResultsLog::page(count),
+    );
+    if config
+        .rpc_client
+        .get_account_data(&results_log_page)
+        .is_err()
+    {
+        println_display(
+            config,
+            format!("Creating results log page {}", results_log_page),
+        );
+        created_accounts.insert("resultsLogPage", results_log_page.to_string());
+        required_balance += config
+            .rpc_client
+            .get_minimum_balance_for_rent_exemption(ResultsLogPage::ACCOUNT_LEN)?;
+    }
+    let balance = config.rpc_client.get_balance(&config.owner.pubkey())?;
+    if balance < required_balance {
+        return Err(format!(
+            "Recording oracle, {}, has insufficient balance: {} required, {} available",
+            config.owner.pubkey(),
+            lamports_to_sol(required_balance),
+            lamports_to_sol(balance)
+        )
+        .into());
+    }
+
+    let mut transaction = Transaction::new_with_payer(
+        &[store_intermediate_results(
+            &hmt_escrow::id(),
+            escrow,
+            &results_log,
+            &results_log_page,
+            &config.owner.pubkey(),
+            &results_url,
+            &results_hash,
+        )?],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(&transaction.message()))?;
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+    unique_signers!(signers);
+    transaction.sign(&signers, recent_blockhash);
+    send_transaction(config, transaction, created_accounts)
+}
+
+/// Shows escrow results log and verifies its hash chain
+fn command_results_log(config: &Config, escrow: &Pubkey) -> CommandResult {
+    let account_data = config
+        .rpc_client
+        .get_account_data(escrow)
+        .or(Err("Cannot read escrow data"))?;
+    let escrow_info: Escrow = Escrow::unpack_from_slice(account_data.as_slice())
+        .map_err(|_| format!("{} is not a valid escrow address", escrow))?;
+
+    let (results_log_address, _) =
+        EscrowProcessor::find_results_log_address(&hmt_escrow::id(), escrow);
+    let log_data = config
+        .rpc_client
+        .get_account_data(&results_log_address)
+        .map_err(|_| format!("No intermediate results stored for {}", escrow))?;
+    let results_log = ResultsLog::unpack_from_slice(log_data.as_slice())
+        .map_err(|_| format!("{} is not a valid results log address", results_log_address))?;
+
+    // Every entry is kept in the log pages, the hash chain is checked from the start
+    let mut entries = vec![];
+    let mut head = Hash::default();
+    let mut committed_head = Hash::default();
+    for page in 0..results_log.page_count() {
+        let (page_address, _) =
+            EscrowProcessor::find_results_log_page_address(&hmt_escrow::id(), escrow, page);
+        let page_data = config
+            .rpc_client
+            .get_account_data(&page_address)
+            .map_err(|_| format!("Cannot read results log page {}", page_address))?;
+
+        let first = page as usize * RESULTS_LOG_PAGE_ENTRIES;
+        let last = (first + RESULTS_LOG_PAGE_ENTRIES).min(results_log.count as usize);
+        for index in first..last {
+            let entry = ResultsLogPage::unpack_entry(&page_data, index)?;
+            head = entry.next_head(&head);
+            if index as u64 + 1 == escrow_info.results_log_count {
+                committed_head = head;
+            }
+            entries.push(CliResultsLogEntry {
+                index,
+                timestamp: entry.timestamp,
+                url: str::from_utf8(entry.url.as_ref()).unwrap_or("").to_string(),
+                hash: hex::encode(entry.hash.as_ref()),
+                signer: entry.signer.to_string(),
+            });
+        }
+    }
+
+    if head != results_log.head {
+        return Err("Results log hash chain does not match its head".into());
+    }
+    if escrow_info.results_log_count != 0 && committed_head != escrow_info.results_log_head {
+        return Err("Final results commitment does not match the results log".into());
+    }
+
+    Ok(config.output_format.formatted_string(&CliResultsLog {
+        address: results_log_address.to_string(),
+        entries,
+        head: results_log.head.to_string(),
+        committed_count: escrow_info.results_log_count,
+    }))
+}
+
+#[derive(Debug)]
+struct PayoutRecord {
+    line: u64,
+    recipient: Pubkey,
+    amount: u64,
+}
+
+/// Maximum number of accounts fetched by a single `getMultipleAccounts` request
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Maximum number of payout transactions waiting for confirmation at once
+const PAYOUT_PARALLEL_TRANSACTIONS: usize = 8;
+
+/// Payout transaction recorded in the journal before it is sent
+struct SentPayout {
+    signature: Signature,
+    blockhash: Hash,
+    rows: Vec<usize>,
+}
+
+/// Append-only payout progress journal.
+///
+/// Each transaction is written as `sent <signature> <blockhash> <row>:<recipient>,...`
+/// before it is sent and as `confirmed <signature>` once it lands, so an interrupted
+/// run knows exactly which rows may have been paid.
+struct PayoutJournal {
+    file: File,
+}
+
+impl PayoutJournal {
+    fn append(&mut self, line: &str) -> Result<(), Error> {
+        writeln!(self.file, "{}", line)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    fn sent(&mut self, payout: &SentPayout, recipients: &[PayoutRecord]) -> Result<(), Error> {
+        let rows: Vec<String> = payout
+            .rows
+            .iter()
+            .map(|row| format!("{}:{}", row, recipients[*row].recipient))
+            .collect();
+        self.append(&format!(
+            "sent {} {} {}",
+            payout.signature,
+            payout.blockhash,
+            rows.join(",")
+        ))
+    }
+
+    fn confirmed(&mut self, signature: &Signature) -> Result<(), Error> {
+        self.append(&format!("confirmed {}", signature))
+    }
+}
+
+/// Reads the journal of an interrupted run, returns paid rows and unconfirmed transactions
+fn read_payout_journal(
+    file_name: &str,
+    escrow: &Pubkey,
+    recipients: &[PayoutRecord],
+) -> Result<(HashSet<usize>, Vec<SentPayout>), Error> {
+    let content = std::fs::read_to_string(file_name)
+        .map_err(|_| format!("Cannot read journal {}", file_name))?;
+    let invalid = || format!("Journal {} is corrupted", file_name);
+
+    let mut lines = content.lines();
+    if lines.next() != Some(format!("escrow {}", escrow).as_str()) {
+        return Err(format!("Journal {} belongs to another escrow", file_name).into());
+    }
+
+    let mut sent: Vec<SentPayout> = vec![];
+    let mut confirmed: HashSet<Signature> = HashSet::new();
+    for line in lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["sent", signature, blockhash, rows] => {
+                let rows = rows
+                    .split(',')
+                    .map(|row| {
+                        let mut parts = row.splitn(2, ':');
+                        let index = parts.next().and_then(|index| index.parse::<usize>().ok());
+                        let recipient = parts.next().and_then(|key| Pubkey::from_str(key).ok());
+                        match (index, recipient) {
+                            (Some(index), Some(recipient))
+                                if recipients.get(index).map(|record| record.recipient)
+                                    == Some(recipient) =>
+                            {
+                                Ok(index)
+                            }
+                            _ => Err(format!(
+                                "Journal {} does not match the payout file",
+                                file_name
+                            )),
+                        }
+                    })
+                    .collect::<Result<Vec<usize>, String>>()?;
+                sent.push(SentPayout {
+                    signature: Signature::from_str(signature).map_err(|_| invalid())?,
+                    blockhash: Hash::from_str(blockhash).map_err(|_| invalid())?,
+                    rows,
+                });
+            }
+            ["confirmed", signature] => {
+                confirmed.insert(Signature::from_str(signature).map_err(|_| invalid())?);
+            }
+            [] => {}
+            _ => return Err(invalid().into()),
+        }
+    }
+
+    let mut paid = HashSet::new();
+    let mut unconfirmed = vec![];
+    for payout in sent {
+        if confirmed.contains(&payout.signature) {
+            paid.extend(payout.rows);
+        } else {
+            unconfirmed.push(payout);
+        }
+    }
+    Ok((paid, unconfirmed))
+}
+
+/// Waits until every payout is confirmed or its blockhash expires,
+/// returns the confirmed and the expired ones.
+///
+/// A failed transaction aborts the run, its rows stay unpaid in the journal.
+fn confirm_payouts(
+    config: &Config,
+    journal: &mut PayoutJournal,
+    mut payouts: Vec<SentPayout>,
+) -> Result<(Vec<SentPayout>, Vec<SentPayout>), Error> {
+    let mut confirmed = vec![];
+    let mut expired = vec![];
+    while !payouts.is_empty() {
+        let mut pending = vec![];
+        for payout in payouts {
+            match config
+                .rpc_client
+                .get_signature_status_with_commitment_and_history(
+                    &payout.signature,
+                    config.commitment_config,
+                    true,
+                )? {
+                Some(Ok(())) => {
+                    journal.confirmed(&payout.signature)?;
+                    println_display(
+                        config,
+                        format!(
+                            "Paid {} recipients: {}",
+                            payout.rows.len(),
+                            payout.signature
+                        ),
+                    );
+                    confirmed.push(payout);
+                }
+                Some(Err(err)) => {
+                    return Err(
+                        format!("Payout transaction {} failed: {}", payout.signature, err).into(),
+                    );
+                }
+                None => {
+                    if config
+                        .rpc_client
+                        .get_fee_calculator_for_blockhash(&payout.blockhash)?
+                        .is_none()
+                    {
+                        expired.push(payout);
+                    } else {
+                        pending.push(payout);
+                    }
+                }
+            }
+        }
+        if !pending.is_empty() {
+            sleep(Duration::from_millis(500));
+        }
+        payouts = pending;
+    }
+    Ok((confirmed, expired))
+}
+
+/// Reads the payout CSV file, every row must hold a recipient token account and an amount
+fn read_payout_file(
+    file_name: &str,
+    decimals: u8,
+) -> Result<(Vec<PayoutRecord>, Vec<String>), Error> {
     let file = File::open(file_name).map_err(|_| format!("Cannot find file {}", file_name))?;
     let file_reader = BufReader::new(file);
     let mut csv_reader = csv::ReaderBuilder::new()
         .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
         .from_reader(file_reader);
 
-    let recipients: Vec<PayoutRecord> = csv_reader
-        .records()
-        .filter_map(|record| {
-            record.ok().and_then(|record| {
-                let recipient: Option<Pubkey> =
-                    Pubkey::from_str(record.get(0).unwrap_or_default()).ok();
-                let amount: Option<f64> = record.get(1).unwrap_or_default().parse::<f64>().ok();
-                match (recipient, amount) {
-                    (Some(recipient), Some(amount)) => Some(PayoutRecord { recipient, amount }),
-                    _ => None
+    let mut recipients: Vec<PayoutRecord> = vec![];
+    let mut problems: Vec<String> = vec![];
+    let mut first_lines: HashMap<Pubkey, u64> = HashMap::new();
+    for (index, record) in csv_reader.records().enumerate() {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                problems.push(format!("Row {}: {}", index + 1, err));
+                continue;
+            }
+        };
+        let line = record
+            .position()
+            .map_or(index as u64 + 1, |position| position.line());
+        if record.len() != 2 {
+            problems.push(format!(
+                "Line {}: expected <address>,<amount>, found {} fields",
+                line,
+                record.len()
+            ));
+            continue;
+        }
+        let recipient = match Pubkey::from_str(&record[0]) {
+            Ok(recipient) => recipient,
+            Err(_) => {
+                problems.push(format!("Line {}: invalid recipient {}", line, &record[0]));
+                continue;
+            }
+        };
+        let amount = match ui_amount_to_amount(&record[1], decimals) {
+            Ok(amount) => amount,
+            Err(err) => {
+                problems.push(format!("Line {}: {} {}", line, err, &record[1]));
+                continue;
+            }
+        };
+        if amount == 0 {
+            problems.push(format!("Line {}: zero amount for {}", line, recipient));
+        }
+        if let Some(first_line) = first_lines.insert(recipient, line) {
+            problems.push(format!(
+                "Line {}: duplicate recipient {}, first listed on line {}",
+                line, recipient, first_line
+            ));
+        }
+        recipients.push(PayoutRecord {
+            line,
+            recipient,
+            amount,
+        });
+    }
+    if recipients.is_empty() && problems.is_empty() {
+        problems.push("Cannot find anyone to sent tokens to".to_string());
+    }
+    Ok((recipients, problems))
+}
+
+/// Checks that every recipient is a token account of the escrow mint
+/// owned by one of the allowed owners, if the escrow has an allow-list
+fn validate_payout_recipients(
+    config: &Config,
+    token_mint: &Pubkey,
+    allowed_owners: Option<&[Pubkey]>,
+    records: &[&PayoutRecord],
+) -> Result<(Vec<String>, HashMap<Pubkey, Pubkey>), Error> {
+    let mut problems = vec![];
+    let mut owners = HashMap::new();
+    for chunk in records.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let keys: Vec<Pubkey> = chunk.iter().map(|record| record.recipient).collect();
+        let accounts = config.rpc_client.get_multiple_accounts(&keys)?;
+        for (record, account) in chunk.iter().zip(accounts) {
+            let account = match account {
+                Some(account) => account,
+                None => {
+                    problems.push(format!(
+                        "Line {}: recipient {} does not exist",
+                        record.line, record.recipient
+                    ));
+                    continue;
+                }
+            };
+            let token_account = if account.owner == spl_token::id() {
+                TokenAccount::unpack(&account.data).ok()
+            } else {
+                None
+            };
+            match token_account {
+                None => problems.push(format!(
+                    "Line {}: recipient {} is not a token account",
+                    record.line, record.recipient
+                )),
+                Some(token_account) if token_account.mint != *token_mint => {
+                    problems.push(format!(
+                        "Line {}: recipient {} holds mint {}, escrow mint is {}",
+                        record.line, record.recipient, token_account.mint, token_mint
+                    ))
+                }
+                Some(token_account) => {
+                    if let Some(owners) = allowed_owners {
+                        if owners.binary_search(&token_account.owner).is_err() {
+                            problems.push(format!(
+                                "Line {}: recipient {} owner {} is not in the escrow allow-list",
+                                record.line, record.recipient, token_account.owner
+                            ));
+                        }
+                    }
+                    owners.insert(record.recipient, token_account.owner);
                 }
+            }
+        }
+    }
+    Ok((problems, owners))
+}
+
+/// Checks the totals paid to every recipient owner, together with what the
+/// escrow already paid them, against the escrow maximum payout per recipient
+fn validate_recipient_payouts(
+    config: &Config,
+    escrow: &Pubkey,
+    max_payout_per_recipient: u64,
+    decimals: u8,
+    owners: &HashMap<Pubkey, Pubkey>,
+    records: &[&PayoutRecord],
+) -> Result<Vec<String>, Error> {
+    let mut totals: BTreeMap<Pubkey, u64> = BTreeMap::new();
+    for record in records.iter() {
+        if let Some(owner) = owners.get(&record.recipient) {
+            let total = totals.entry(*owner).or_insert(0);
+            *total = total.saturating_add(record.amount);
+        }
+    }
+
+    let mut problems = vec![];
+    let totals: Vec<(Pubkey, u64)> = totals.into_iter().collect();
+    for chunk in totals.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let keys: Vec<Pubkey> = chunk
+            .iter()
+            .map(|(owner, _)| {
+                EscrowProcessor::find_recipient_payout_address(&hmt_escrow::id(), escrow, owner).0
             })
-        })
-        .collect();
-    if recipients.is_empty() {
-        return Err("Cannot find anyone to sent tokens to".into());
+            .collect();
+        let accounts = config.rpc_client.get_multiple_accounts(&keys)?;
+        for ((owner, amount), account) in chunk.iter().zip(accounts) {
+            let paid = account
+                .filter(|account| account.owner == hmt_escrow::id())
+                .and_then(|account| RecipientPayout::unpack_from_slice(&account.data).ok())
+                .map_or(0, |recipient_payout| recipient_payout.amount);
+            if paid.saturating_add(*amount) > max_payout_per_recipient {
+                problems.push(format!(
+                    "Owner {}: {} on top of {} already paid is above escrow maximum payout per recipient {}",
+                    owner,
+                    amount_to_ui_amount(*amount, decimals),
+                    amount_to_ui_amount(paid, decimals),
+                    amount_to_ui_amount(max_payout_per_recipient, decimals)
+                ));
+            }
+        }
     }
-    let total_amount: f64 = recipients.iter().map(|x| x.amount).sum();
+    Ok(problems)
+}
 
+/// Pays from the escrow account to every CSV row, splitting rows into transactions
+/// that fit into a packet and recording progress in the journal.
+///
+/// Every row is validated first, any problem aborts the payout before signing.
+/// With `dry_run` the validation report is printed and nothing is sent.
+fn command_payout(
+    config: &Config,
+    escrow: &Pubkey,
+    file_name: &str,
+    resume: &Option<String>,
+    dry_run: bool,
+) -> CommandResult {
     // Read escrow state
     let account_data = config
         .rpc_client
@@ -542,16 +1548,16 @@ fn command_payout(config: &Config, escrow: &Pubkey, file_name: &str) -> CommandR
         .recording_oracle_token_account
         .ok_or::<Error>("Recording oracle token account not defined".into())?;
 
-    // Check token mint to convert amount to u64
+    // Check token mint to convert amounts to u64
     let account_data = config
         .rpc_client
         .get_account_data(&escrow_info.token_mint)
         .or(Err("Cannot read escrow mint data"))?;
     let mint_info: TokenMint = TokenMint::unpack_from_slice(account_data.as_slice())
         .map_err(|_| format!("{} is not a valid mint address", escrow_info.token_mint))?;
-    let total_amount = spl_token::ui_amount_to_amount(total_amount, mint_info.decimals);
+    let decimals = mint_info.decimals;
 
-    // Check escrow token account balance
+    // Read escrow token account balance
     let account_data = config
         .rpc_client
         .get_account_data(&escrow_info.token_account)
@@ -564,22 +1570,227 @@ fn command_payout(config: &Config, escrow: &Pubkey, file_name: &str) -> CommandR
             )
         })?;
 
+    let (recipients, mut problems) = read_payout_file(file_name, decimals)?;
+
+    // Read the journal, resolving transactions left unconfirmed by an interrupted run
+    let (mut paid, unconfirmed) = match resume {
+        Some(journal_name) => read_payout_journal(journal_name, escrow, &recipients)?,
+        None => (HashSet::new(), vec![]),
+    };
+    let mut newly_confirmed = vec![];
+    for payout in unconfirmed {
+        match config
+            .rpc_client
+            .get_signature_status_with_commitment_and_history(
+                &payout.signature,
+                config.commitment_config,
+                true,
+            )? {
+            Some(Ok(())) => {
+                newly_confirmed.push(payout.signature);
+                paid.extend(payout.rows);
+            }
+            Some(Err(_)) => {}
+            None => {
+                if config
+                    .rpc_client
+                    .get_fee_calculator_for_blockhash(&payout.blockhash)?
+                    .is_some()
+                {
+                    return Err(format!(
+                        "Transaction {} may still be processed, resume again later",
+                        payout.signature
+                    )
+                    .into());
+                }
+            }
+        }
+    }
+    let rows: Vec<usize> = (0..recipients.len())
+        .filter(|row| !paid.contains(row))
+        .collect();
+    if !paid.is_empty() {
+        println_display(
+            config,
+            format!(
+                "Resuming payout, {} of {} recipients already paid",
+                paid.len(),
+                recipients.len()
+            ),
+        );
+    }
+
+    // Validate recipients and totals against what is left in the escrow
+    let records: Vec<&PayoutRecord> = rows.iter().map(|row| &recipients[*row]).collect();
+    let allow_list = match escrow_info.allow_list {
+        COption::Some(allow_list) => Some(allow_list),
+        COption::None => None,
+    };
+    let allowed_owners = match allow_list {
+        Some(allow_list) => Some(get_allow_list(config, &allow_list)?.2),
+        None => None,
+    };
+    let (recipient_problems, owners) = validate_payout_recipients(
+        config,
+        &escrow_info.token_mint,
+        allowed_owners.as_deref(),
+        &records,
+    )?;
+    problems.extend(recipient_problems);
+    for record in records.iter().filter(|record| record.amount != 0) {
+        if record.amount < escrow_info.min_payout {
+            problems.push(format!(
+                "Line {}: amount {} below escrow minimum payout {}",
+                record.line,
+                amount_to_ui_amount(record.amount, decimals),
+                amount_to_ui_amount(escrow_info.min_payout, decimals)
+            ));
+        }
+    }
+    if escrow_info.max_payout_per_recipient != 0 {
+        problems.extend(validate_recipient_payouts(
+            config,
+            escrow,
+            escrow_info.max_payout_per_recipient,
+            decimals,
+            &owners,
+            &records,
+        )?);
+    }
+    let total_amount = records
+        .iter()
+        .try_fold(0u64, |total, record| total.checked_add(record.amount))
+        .ok_or("Total payout amount overflows")?;
+    let remaining_amount = escrow_info
+        .total_amount
+        .saturating_sub(escrow_info.sent_amount);
+    let remaining_recipients = escrow_info
+        .total_recipients
+        .saturating_sub(escrow_info.sent_recipients);
+    if total_amount > remaining_amount {
+        problems.push(format!(
+            "Total {} exceeds {} left to pay by the escrow",
+            amount_to_ui_amount(total_amount, decimals),
+            amount_to_ui_amount(remaining_amount, decimals)
+        ));
+    }
+    if records.len() as u64 > remaining_recipients {
+        problems.push(format!(
+            "{} recipients exceed {} left to pay by the escrow",
+            records.len(),
+            remaining_recipients
+        ));
+    }
     if total_amount > token_account_info.amount {
-        return Err(format!(
+        problems.push(format!(
             "{} tokens needed on escrow account, only {} found",
-            spl_token::amount_to_ui_amount(total_amount, mint_info.decimals),
-            spl_token::amount_to_ui_amount(token_account_info.amount, mint_info.decimals)
+            amount_to_ui_amount(total_amount, decimals),
+            amount_to_ui_amount(token_account_info.amount, decimals)
+        ));
+    }
+
+    // Fee breakdown, fees are calculated the same way as the program does
+    let fee = |amount: u64, stake: u8| amount.checked_mul(stake as u64).unwrap_or(0) / 100;
+    let mut total_reputation_fee = 0u64;
+    let mut total_recording_fee = 0u64;
+    let mut cli_recipients = vec![];
+    for record in records.iter() {
+        let reputation_fee = fee(record.amount, escrow_info.reputation_oracle_stake);
+        let recording_fee = fee(record.amount, escrow_info.recording_oracle_stake);
+        total_reputation_fee += reputation_fee;
+        total_recording_fee += recording_fee;
+        cli_recipients.push(CliPayoutRecipient {
+            line: record.line,
+            recipient: record.recipient.to_string(),
+            amount: amount_to_ui_amount(record.amount, decimals),
+            reputation_fee: amount_to_ui_amount(reputation_fee, decimals),
+            recording_fee: amount_to_ui_amount(recording_fee, decimals),
+            net: amount_to_ui_amount(
+                record.amount.saturating_sub(reputation_fee + recording_fee),
+                decimals,
+            ),
+            signature: None,
+        });
+    }
+    let mut cli_payout = CliPayout {
+        escrow: escrow.to_string(),
+        dry_run,
+        journal: None,
+        recipients: cli_recipients,
+        total_amount: amount_to_ui_amount(total_amount, decimals),
+        reputation_fee: amount_to_ui_amount(total_reputation_fee, decimals),
+        reputation_oracle_stake: escrow_info.reputation_oracle_stake,
+        recording_fee: amount_to_ui_amount(total_recording_fee, decimals),
+        recording_oracle_stake: escrow_info.recording_oracle_stake,
+        net: amount_to_ui_amount(
+            total_amount.saturating_sub(total_reputation_fee + total_recording_fee),
+            decimals,
+        ),
+        remaining_amount: amount_to_ui_amount(remaining_amount, decimals),
+        remaining_recipients,
+        problems,
+    };
+
+    if !cli_payout.problems.is_empty() {
+        println!("{}", config.output_format.formatted_string(&cli_payout));
+        for problem in cli_payout.problems.iter() {
+            eprintln!("{}", problem);
+        }
+        return Err(format!(
+            "{} problems found, nothing was signed",
+            cli_payout.problems.len()
         )
         .into());
     }
+    if dry_run || rows.is_empty() {
+        return Ok(config.output_format.formatted_string(&cli_payout));
+    }
+
+    // Open the journal only once the run is valid
+    let journal_name = resume
+        .clone()
+        .unwrap_or_else(|| format!("{}.journal", file_name));
+    let mut journal = PayoutJournal {
+        file: OpenOptions::new()
+            .append(true)
+            .create_new(resume.is_none())
+            .open(&journal_name)
+            .map_err(|_| {
+                format!(
+                    "Cannot create journal {}, use --resume to continue its run",
+                    journal_name
+                )
+            })?,
+    };
+    if resume.is_none() {
+        journal.append(&format!("escrow {}", escrow))?;
+    }
+    for signature in newly_confirmed.iter() {
+        journal.confirmed(signature)?;
+    }
 
     let authority =
         EscrowProcessor::authority_id(&hmt_escrow::id(), &escrow, escrow_info.bump_seed)?;
-    let mut instructions_ui_amount: f64 = 0.0;
-    let instructions: Vec<Instruction> = recipients
+    let fee_payer = config.fee_payer.pubkey();
+    let recipient_payouts: HashMap<Pubkey, Pubkey> = if escrow_info.max_payout_per_recipient != 0 {
+        owners
+            .iter()
+            .map(|(recipient, owner)| {
+                let (recipient_payout, _) = EscrowProcessor::find_recipient_payout_address(
+                    &hmt_escrow::id(),
+                    escrow,
+                    owner,
+                );
+                (*recipient, recipient_payout)
+            })
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    let instructions: Vec<Instruction> = records
         .iter()
-        .filter_map(|record| {
-            let instruction = payout(
+        .map(|record| {
+            payout(
                 &hmt_escrow::id(),
                 &escrow,
                 &config.owner.pubkey(),
@@ -589,59 +1800,169 @@ fn command_payout(config: &Config, escrow: &Pubkey, file_name: &str) -> CommandR
                 &reputation_oracle_token_account,
                 &recording_oracle_token_account,
                 &spl_token::id(),
-                spl_token::ui_amount_to_amount(record.amount, mint_info.decimals),
+                record.amount,
+                allow_list.as_ref(),
+                recipient_payouts
+                    .get(&record.recipient)
+                    .map(|recipient_payout| (recipient_payout, &fee_payer)),
             )
-            .ok();
+        })
+        .collect::<Result<_, _>>()?;
 
-            if instruction != None {
-                println!("{}: {}", record.recipient, record.amount);
-                instructions_ui_amount += record.amount;
-            }
+    // Group rows into transactions that fit into a packet
+    let mut queue: VecDeque<Vec<usize>> =
+        transaction_ranges(&instructions, &config.fee_payer.pubkey())
+            .into_iter()
+            .map(|range| rows[range].to_vec())
+            .collect();
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+    unique_signers!(signers);
+    let (_, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(
+        config,
+        queue.len() as u64 * signers.len() as u64 * fee_calculator.lamports_per_signature,
+    )?;
+    println_display(config, format!("Payout journal: {}", journal_name));
 
-            instruction
-        })
+    let row_positions: HashMap<usize, usize> = rows
+        .iter()
+        .enumerate()
+        .map(|(position, row)| (*row, position))
         .collect();
+    let row_instructions: HashMap<usize, Instruction> = rows.into_iter().zip(instructions).collect();
+    while !queue.is_empty() {
+        let wave = queue.len().min(PAYOUT_PARALLEL_TRANSACTIONS);
+        let (blockhash, _) = config.rpc_client.get_recent_blockhash()?;
+        let mut sent = vec![];
+        for rows in queue.drain(..wave) {
+            let instructions: Vec<Instruction> = rows
+                .iter()
+                .map(|row| row_instructions[row].clone())
+                .collect();
+            let mut transaction =
+                Transaction::new_with_payer(&instructions, Some(&config.fee_payer.pubkey()));
+            transaction.sign(&signers, blockhash);
+            let payout = SentPayout {
+                signature: transaction.signatures[0],
+                blockhash,
+                rows,
+            };
+            // Journal first, so a crash right after sending cannot lose the transaction
+            journal.sent(&payout, &recipients)?;
+            if let Err(err) = config.rpc_client.send_transaction(&transaction) {
+                eprintln!("Sending {} failed: {}", payout.signature, err);
+            }
+            sent.push(payout);
+        }
 
-    if !instructions.is_empty() {
-        let total_fees = escrow_info.reputation_oracle_stake + escrow_info.recording_oracle_stake;
-        if total_fees != 0 {
-            println!("Sending {} to {} recipients", instructions_ui_amount, instructions.len());
-            println!("{}% ({}) will be used to pay oracle fees", total_fees, total_fees as f64 * instructions_ui_amount / 100.0);
+        // Rows of expired transactions never landed and are safe to send again
+        let (confirmed, expired) = confirm_payouts(config, &mut journal, sent)?;
+        for payout in confirmed {
+            for row in payout.rows {
+                cli_payout.recipients[row_positions[&row]].signature =
+                    Some(payout.signature.to_string());
+            }
+        }
+        for payout in expired {
+            queue.push_back(payout.rows);
         }
+    }
 
-        let mut transaction =
-            Transaction::new_with_payer(&instructions, Some(&config.fee_payer.pubkey()));
+    cli_payout.journal = Some(journal_name);
+    Ok(config.output_format.formatted_string(&cli_payout))
+}
 
-        let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
-        check_fee_payer_balance(config, fee_calculator.calculate_fee(&transaction.message()))?;
-        let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
-        unique_signers!(signers);
-        transaction.sign(&signers, recent_blockhash);
-        Ok(Some(transaction))
-    } else {
-        Err("No payouts created".into())
+/// Creates transaction updating worker reputation, owner must be the escrow reputation oracle
+fn command_update_reputation(config: &Config, escrow: &Pubkey, file_name: &str) -> CommandResult {
+    // Read CSV file and validate its contents
+    let file = File::open(file_name).map_err(|_| format!("Cannot find file {}", file_name))?;
+    let file_reader = BufReader::new(file);
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(file_reader);
+
+    let updates: Vec<ReputationUpdate> = csv_reader
+        .records()
+        .filter_map(|record| {
+            record.ok().and_then(|record| {
+                let worker: Option<Pubkey> =
+                    Pubkey::from_str(record.get(0).unwrap_or_default()).ok();
+                let delta: Option<i64> = record.get(1).unwrap_or_default().parse::<i64>().ok();
+                match (worker, delta) {
+                    (Some(worker), Some(delta)) => Some(ReputationUpdate { worker, delta }),
+                    _ => None,
+                }
+            })
+        })
+        .collect();
+    if updates.is_empty() {
+        return Err("Cannot find any reputation updates".into());
+    }
+    if updates.len() > MAX_REPUTATION_UPDATES {
+        return Err(format!(
+            "No more than {} reputation updates supported in a single transaction",
+            MAX_REPUTATION_UPDATES
+        )
+        .into());
     }
-}
 
-fn command_cancel(config: &Config, escrow: &Pubkey) -> CommandResult {
-    let account_data = config.rpc_client.get_account_data(escrow)?;
-    let escrow_info: Escrow = Escrow::unpack_from_slice(account_data.as_slice())?;
+    let reputation_accounts: Vec<Pubkey> = updates
+        .iter()
+        .map(|update| {
+            println_display(config, format!("{}: {:+}", update.worker, update.delta));
+            EscrowProcessor::find_reputation_address(&hmt_escrow::id(), &update.worker).0
+        })
+        .collect();
+    let reputation_job_accounts: Vec<Pubkey> = updates
+        .iter()
+        .map(|update| {
+            EscrowProcessor::find_reputation_job_address(&hmt_escrow::id(), &update.worker, escrow)
+                .0
+        })
+        .collect();
 
-    let authority =
-        EscrowProcessor::authority_id(&hmt_escrow::id(), &escrow, escrow_info.bump_seed)?;
+    // New reputation and job accounts are funded by the reputation oracle
+    let reputation_balance = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(Reputation::LEN)?;
+    let reputation_job_balance = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(0)?;
+    let mut required_balance = 0;
+    for reputation_account in &reputation_accounts {
+        if config.rpc_client.get_account_data(reputation_account).is_err() {
+            required_balance += reputation_balance;
+        }
+    }
+    for reputation_job_account in &reputation_job_accounts {
+        if config
+            .rpc_client
+            .get_account_data(reputation_job_account)
+            .is_err()
+        {
+            required_balance += reputation_job_balance;
+        }
+    }
+    let balance = config.rpc_client.get_balance(&config.owner.pubkey())?;
+    if balance < required_balance {
+        return Err(format!(
+            "Reputation oracle, {}, has insufficient balance: {} required, {} available",
+            config.owner.pubkey(),
+            lamports_to_sol(required_balance),
+            lamports_to_sol(balance)
+        )
+        .into());
+    }
 
     let mut transaction = Transaction::new_with_payer(
-        &[
-            cancel_escrow(
-                &hmt_escrow::id(),
-                &escrow,
-                &config.owner.pubkey(),
-                &escrow_info.token_account,
-                &authority,
-                &escrow_info.canceler_token_account,
-                &spl_token::id(),
-            )?,
-        ],
+        &[update_reputation(
+            &hmt_escrow::id(),
+            escrow,
+            &config.owner.pubkey(),
+            &reputation_accounts,
+            &reputation_job_accounts,
+            &updates,
+        )?],
         Some(&config.fee_payer.pubkey()),
     );
 
@@ -650,116 +1971,2510 @@ fn command_cancel(config: &Config, escrow: &Pubkey) -> CommandResult {
     let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
     unique_signers!(signers);
     transaction.sign(&signers, recent_blockhash);
-    Ok(Some(transaction))
+    send_transaction(config, transaction, BTreeMap::new())
 }
 
-fn command_complete(config: &Config, escrow: &Pubkey) -> CommandResult {
-    let mut transaction = Transaction::new_with_payer(
-        &[
-            complete_escrow(
-                &hmt_escrow::id(),
-                &escrow,
-                &config.owner.pubkey(),
-            )?,
-        ],
-        Some(&config.fee_payer.pubkey()),
-    );
+fn command_reputation_info(config: &Config, worker: &Pubkey) -> CommandResult {
+    let (reputation_address, _) =
+        EscrowProcessor::find_reputation_address(&hmt_escrow::id(), worker);
+    let account_data = config
+        .rpc_client
+        .get_account_data(&reputation_address)
+        .map_err(|_| format!("No reputation found for {}", worker))?;
+    let reputation = Reputation::unpack_from_slice(account_data.as_slice())
+        .map_err(|_| format!("{} is not a valid reputation address", reputation_address))?;
+
+    Ok(config.output_format.formatted_string(&CliReputation {
+        address: reputation_address.to_string(),
+        worker: reputation.worker.to_string(),
+        score: reputation.score,
+        jobs_completed: reputation.jobs_completed,
+        last_escrow: reputation.last_escrow.to_string(),
+    }))
+}
+
+/// Creates recipient allow-list with the owner as its authority
+fn command_allow_list_create(config: &Config, capacity: usize) -> CommandResult {
+    if capacity == 0 {
+        return Err("Allow-list capacity must be positive".into());
+    }
+
+    let allow_list = Keypair::new();
+    println_display(
+        config,
+        format!("Creating allow-list {}", allow_list.pubkey()),
+    );
+    let mut created_accounts = BTreeMap::new();
+    created_accounts.insert("allowList", allow_list.pubkey().to_string());
+
+    let account_len = AllowList::account_len(capacity);
+    let allow_list_balance = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(account_len)?;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            // Account for the allow-list
+            system_instruction::create_account(
+                &config.fee_payer.pubkey(),
+                &allow_list.pubkey(),
+                allow_list_balance,
+                account_len as u64,
+                &hmt_escrow::id(),
+            ),
+            // Initialize allow-list account
+            initialize_allow_list(
+                &hmt_escrow::id(),
+                &allow_list.pubkey(),
+                &config.owner.pubkey(),
+            )?,
+        ],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(
+        config,
+        allow_list_balance + fee_calculator.calculate_fee(&transaction.message()),
+    )?;
+    let mut signers = vec![
+        config.fee_payer.as_ref(),
+        config.owner.as_ref(),
+        &allow_list,
+    ];
+    unique_signers!(signers);
+    transaction.sign(&signers, recent_blockhash);
+    send_transaction(config, transaction, created_accounts)
+}
+
+/// Adds and removes allow-list owners, must be signed by the allow-list authority
+fn command_allow_list_update(
+    config: &Config,
+    allow_list: &Pubkey,
+    add: &[Pubkey],
+    remove: &[Pubkey],
+) -> CommandResult {
+    if add.is_empty() && remove.is_empty() {
+        return Err("Nothing to add or remove".into());
+    }
+    if add.len() > MAX_ALLOW_LIST_UPDATES || remove.len() > MAX_ALLOW_LIST_UPDATES {
+        return Err(format!(
+            "No more than {} owners can be added or removed in a single transaction",
+            MAX_ALLOW_LIST_UPDATES
+        )
+        .into());
+    }
+
+    let (allow_list_info, capacity, owners) = get_allow_list(config, allow_list)?;
+    if allow_list_info.authority != config.owner.pubkey() {
+        return Err(format!(
+            "Allow-list authority is {}, not the owner",
+            allow_list_info.authority
+        )
+        .into());
+    }
+    let remaining = owners
+        .iter()
+        .filter(|owner| !remove.contains(owner))
+        .count();
+    let added = add
+        .iter()
+        .filter(|owner| owners.binary_search(owner).is_err() || remove.contains(owner))
+        .count();
+    if remaining + added > capacity {
+        return Err(format!("Allow-list capacity of {} owners exceeded", capacity).into());
+    }
+
+    let mut transaction = Transaction::new_with_payer(
+        &[update_allow_list(
+            &hmt_escrow::id(),
+            allow_list,
+            &config.owner.pubkey(),
+            add,
+            remove,
+        )?],
+        Some(&config.fee_payer.pubkey()),
+    );
 
     let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
     check_fee_payer_balance(config, fee_calculator.calculate_fee(&transaction.message()))?;
     let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
     unique_signers!(signers);
     transaction.sign(&signers, recent_blockhash);
-    Ok(Some(transaction))
+    send_transaction(config, transaction, BTreeMap::new())
 }
 
-/// Return an error if a hex cannot be parsed.
-pub fn is_hex<T>(string: T) -> Result<(), String>
-where
-    T: AsRef<str> + Display,
-{
-    match hex::decode(string.as_ref()) {
-        Ok(_) => Ok(()),
-        Err(err) => Err(format!("{}", err)),
+/// Shows allow-list authority and owners
+fn command_allow_list_info(config: &Config, allow_list: &Pubkey) -> CommandResult {
+    let (allow_list_info, capacity, owners) = get_allow_list(config, allow_list)?;
+
+    Ok(config.output_format.formatted_string(&CliAllowList {
+        address: allow_list.to_string(),
+        authority: allow_list_info.authority.to_string(),
+        capacity,
+        owners: owners.iter().map(|owner| owner.to_string()).collect(),
+    }))
+}
+
+/// Creates escrow template, the owner becomes the template authority
+#[allow(clippy::too_many_arguments)]
+fn command_template_create(
+    config: &Config,
+    mint: &Pubkey,
+    canceler: &Option<Pubkey>,
+    canceler_token: &Pubkey,
+    guardian: &Option<Pubkey>,
+    duration: u64,
+    reputation_oracle: &Option<Pubkey>,
+    reputation_oracle_token: &Pubkey,
+    reputation_oracle_stake: u8,
+    recording_oracle: &Option<Pubkey>,
+    recording_oracle_token: &Pubkey,
+    recording_oracle_stake: u8,
+    max_payout_per_recipient: &Option<String>,
+    min_payout: &Option<String>,
+    cancellation_fee: &Option<String>,
+    cancellation_fee_percent: u8,
+    cancel_notice: u64,
+    allow_list: &Option<Pubkey>,
+) -> CommandResult {
+    // Validate parameters
+    if reputation_oracle_stake > 100
+        || recording_oracle_stake > 100
+        || reputation_oracle_stake.saturating_add(recording_oracle_stake) > 100
+    {
+        return Err("Invalid stake values".into());
+    }
+    if cancellation_fee_percent > 100 {
+        return Err("Invalid cancellation fee percent".into());
     }
+    if cancellation_fee.is_some() && cancellation_fee_percent != 0 {
+        return Err("Cancellation fee can be either fixed or a percent, not both".into());
+    }
+
+    // Convert payout limits to token units, zero maximum meaning no limit
+    let decimals = get_mint_decimals(config, mint)?;
+    let max_payout_per_recipient = match max_payout_per_recipient {
+        Some(value) => ui_amount_to_amount(value, decimals)?,
+        None => 0,
+    };
+    let min_payout = match min_payout {
+        Some(value) => ui_amount_to_amount(value, decimals)?,
+        None => 0,
+    };
+    if max_payout_per_recipient != 0 && min_payout > max_payout_per_recipient {
+        return Err("Minimum payout cannot exceed maximum payout per recipient".into());
+    }
+    let cancellation_fee = match cancellation_fee {
+        Some(value) => ui_amount_to_amount(value, decimals)?,
+        None => 0,
+    };
+
+    // Make sure the allow-list exists before referencing it from the template
+    if let Some(allow_list) = allow_list {
+        get_allow_list(config, allow_list)?;
+    }
+
+    let template = EscrowTemplate {
+        is_initialized: true,
+        authority: config.owner.pubkey(),
+        token_mint: *mint,
+        canceler: canceler.unwrap_or(config.owner.pubkey()),
+        canceler_token_account: *canceler_token,
+        guardian: guardian.unwrap_or(config.owner.pubkey()),
+        duration,
+        reputation_oracle: reputation_oracle.unwrap_or(config.owner.pubkey()),
+        reputation_oracle_token_account: *reputation_oracle_token,
+        reputation_oracle_stake,
+        recording_oracle: recording_oracle.unwrap_or(config.owner.pubkey()),
+        recording_oracle_token_account: *recording_oracle_token,
+        recording_oracle_stake,
+        max_payout_per_recipient,
+        min_payout,
+        cancellation_fee,
+        cancellation_fee_percent,
+        cancel_notice,
+        allow_list: allow_list.map_or(COption::None, COption::Some),
+    };
+
+    let template_account = Keypair::new();
+    println_display(
+        config,
+        format!("Creating escrow template {}", template_account.pubkey()),
+    );
+    let mut created_accounts = BTreeMap::new();
+    created_accounts.insert("template", template_account.pubkey().to_string());
+
+    let template_balance = get_minimum_balance_for_rent_exemption(config, EscrowTemplate::LEN)?;
+    let instructions = vec![
+        // Account for the template
+        system_instruction::create_account(
+            &config.fee_payer.pubkey(),
+            &template_account.pubkey(),
+            template_balance,
+            EscrowTemplate::LEN as u64,
+            &hmt_escrow::id(),
+        ),
+        // Initialize template account
+        initialize_template(&hmt_escrow::id(), &template_account.pubkey(), &template)?,
+    ];
+
+    process_instructions(
+        config,
+        instructions,
+        vec![
+            config.fee_payer.as_ref(),
+            config.owner.as_ref(),
+            &template_account,
+        ],
+        template_balance,
+        created_accounts,
+    )
 }
 
-fn main() {
-    let matches = App::new(crate_name!())
-        .about(crate_description!())
-        .version(crate_version!())
-        .setting(AppSettings::SubcommandRequiredElseHelp)
-        .arg({
-            let arg = Arg::with_name("config_file")
-                .short("C")
-                .long("config")
-                .value_name("PATH")
-                .takes_value(true)
-                .global(true)
-                .help("Configuration file to use");
-            if let Some(ref config_file) = *solana_cli_config::CONFIG_FILE {
-                arg.default_value(&config_file)
-            } else {
-                arg
-            }
-        })
-        .arg(
-            Arg::with_name("verbose")
-                .long("verbose")
-                .short("v")
-                .takes_value(false)
-                .global(true)
-                .help("Show additional information"),
-        )
-        .arg(
-            Arg::with_name("json_rpc_url")
-                .long("url")
-                .value_name("URL")
-                .takes_value(true)
-                .validator(is_url)
-                .help("JSON RPC URL for the cluster.  Default from the configuration file."),
+/// Shows escrow template
+fn command_template_info(config: &Config, address: &Pubkey) -> CommandResult {
+    let template = get_template(config, address)?;
+    let decimals = get_mint_decimals(config, &template.token_mint)?;
+
+    Ok(config.output_format.formatted_string(&CliTemplate {
+        address: address.to_string(),
+        authority: template.authority.to_string(),
+        token_mint: template.token_mint.to_string(),
+        canceler: template.canceler.to_string(),
+        canceler_token_account: template.canceler_token_account.to_string(),
+        guardian: template.guardian.to_string(),
+        duration: template.duration,
+        reputation_oracle: CliOracle {
+            account: Some(template.reputation_oracle.to_string()),
+            token_account: Some(template.reputation_oracle_token_account.to_string()),
+            fee: template.reputation_oracle_stake,
+        },
+        recording_oracle: CliOracle {
+            account: Some(template.recording_oracle.to_string()),
+            token_account: Some(template.recording_oracle_token_account.to_string()),
+            fee: template.recording_oracle_stake,
+        },
+        max_payout_per_recipient: if template.max_payout_per_recipient == 0 {
+            "no".to_string()
+        } else {
+            amount_to_ui_amount(template.max_payout_per_recipient, decimals)
+        },
+        min_payout: amount_to_ui_amount(template.min_payout, decimals),
+        cancellation_fee: cancellation_fee_string(
+            template.cancellation_fee,
+            template.cancellation_fee_percent,
+            decimals,
+        ),
+        cancel_notice: template.cancel_notice,
+        allow_list: coption_key_string(&template.allow_list),
+    }))
+}
+
+/// Creates escrow from the template and sets it up with the manifest,
+/// the owner must be the template authority
+fn command_create_from_template(
+    config: &Config,
+    template_address: &Pubkey,
+    manifest_url: &str,
+    manifest_hash: &Option<String>,
+    verify: bool,
+) -> CommandResult {
+    let template = get_template(config, template_address)?;
+    if template.authority != config.owner.pubkey() {
+        return Err(format!(
+            "Template authority is {}, not the owner",
+            template.authority
         )
-        .arg(
-            Arg::with_name("owner")
-                .long("owner")
-                .value_name("KEYPAIR")
-                .validator(is_keypair)
-                .takes_value(true)
-                .help(
-                    "Specify the stake pool or stake account owner. \
-                     This may be a keypair file, the ASK keyword. \
-                     Defaults to the client keypair.",
+        .into());
+    }
+
+    let manifest_hash = data_hash(config, manifest_url, manifest_hash, verify)?;
+    let manifest_url: DataUrl = DataUrl::from_str(manifest_url).or(Err("URL too long"))?;
+
+    let escrow_account = Keypair::new();
+    let escrow_token_account = Keypair::new();
+    println_display(
+        config,
+        format!("Creating escrow {}", escrow_account.pubkey()),
+    );
+    let mut created_accounts = BTreeMap::new();
+    created_accounts.insert("escrow", escrow_account.pubkey().to_string());
+    created_accounts.insert(
+        "escrowTokenAccount",
+        escrow_token_account.pubkey().to_string(),
+    );
+
+    let token_account_balance = get_minimum_balance_for_rent_exemption(config, TokenAccount::LEN)?;
+    let escrow_account_balance = get_minimum_balance_for_rent_exemption(config, Escrow::LEN)?;
+    let (authority, _) =
+        EscrowProcessor::find_authority_bump_seed(&hmt_escrow::id(), &escrow_account.pubkey());
+    let (config_address, _) = EscrowProcessor::find_config_address(&hmt_escrow::id());
+    let (reputation_oracle_stake, _) =
+        EscrowProcessor::find_oracle_stake_address(&hmt_escrow::id(), &template.reputation_oracle);
+    let (recording_oracle_stake, _) =
+        EscrowProcessor::find_oracle_stake_address(&hmt_escrow::id(), &template.recording_oracle);
+
+    let instructions = vec![
+        // Account for the escrow tokens
+        system_instruction::create_account(
+            &config.fee_payer.pubkey(),
+            &escrow_token_account.pubkey(),
+            token_account_balance,
+            TokenAccount::LEN as u64,
+            &spl_token::id(),
+        ),
+        // Account for the escrow
+        system_instruction::create_account(
+            &config.fee_payer.pubkey(),
+            &escrow_account.pubkey(),
+            escrow_account_balance,
+            Escrow::LEN as u64,
+            &hmt_escrow::id(),
+        ),
+        // Initialize escrow token account
+        initialize_account(
+            &spl_token::id(),
+            &escrow_token_account.pubkey(),
+            &template.token_mint,
+            &authority,
+        )?,
+        // Initialize and set up escrow from the template
+        initialize_from_template(
+            &hmt_escrow::id(),
+            &escrow_account.pubkey(),
+            template_address,
+            &template,
+            &escrow_token_account.pubkey(),
+            &config_address,
+            &reputation_oracle_stake,
+            &recording_oracle_stake,
+            &manifest_url,
+            &manifest_hash,
+        )?,
+    ];
+
+    process_instructions(
+        config,
+        instructions,
+        vec![
+            config.fee_payer.as_ref(),
+            config.owner.as_ref(),
+            &escrow_account,
+            &escrow_token_account,
+        ],
+        token_account_balance + escrow_account_balance,
+        created_accounts,
+    )
+}
+
+/// Adds a vault of another token mint to the launched escrow, creates the vault
+/// token account and, when not given, the canceler token account of the mint
+fn command_vault_add(
+    config: &Config,
+    escrow: &Pubkey,
+    token_mint: &Pubkey,
+    canceler_token: &Option<Pubkey>,
+) -> CommandResult {
+    // Read escrow state
+    let account_data = config
+        .rpc_client
+        .get_account_data(escrow)
+        .or(Err("Cannot read escrow data"))?;
+    let escrow_info: Escrow = Escrow::unpack_from_slice(account_data.as_slice())
+        .map_err(|_| format!("{} is not a valid escrow address", escrow))?;
+    get_mint_decimals(config, token_mint)?;
+
+    let (vault, _) = EscrowProcessor::find_vault_address(&hmt_escrow::id(), escrow, token_mint);
+    let (authority, _) = EscrowProcessor::find_authority_bump_seed(&hmt_escrow::id(), escrow);
+    let token_account_balance = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(TokenAccount::LEN)?;
+    let vault_balance = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(EscrowVault::LEN)?;
+
+    let vault_token_account = Keypair::new();
+    println_display(
+        config,
+        format!(
+            "Creating vault token account {}",
+            vault_token_account.pubkey()
+        ),
+    );
+    let mut created_accounts = BTreeMap::new();
+    created_accounts.insert("vault", vault.to_string());
+    created_accounts.insert(
+        "vaultTokenAccount",
+        vault_token_account.pubkey().to_string(),
+    );
+    let mut instructions = vec![
+        // Account for the vault tokens
+        system_instruction::create_account(
+            &config.fee_payer.pubkey(),
+            &vault_token_account.pubkey(),
+            token_account_balance,
+            TokenAccount::LEN as u64,
+            &spl_token::id(),
+        ),
+        // Initialize vault token account owned by the escrow authority
+        initialize_account(
+            &spl_token::id(),
+            &vault_token_account.pubkey(),
+            token_mint,
+            &authority,
+        )?,
+    ];
+    let mut signers = vec![
+        config.fee_payer.as_ref(),
+        config.owner.as_ref(),
+        &vault_token_account,
+    ];
+    let mut token_accounts = 1;
+
+    let canceler_token_account = Keypair::new();
+    let canceler_token = match canceler_token {
+        Some(canceler_token) => *canceler_token,
+        None => {
+            println_display(
+                config,
+                format!(
+                    "Creating canceler token account {}",
+                    canceler_token_account.pubkey()
+                ),
+            );
+            created_accounts.insert(
+                "cancelerTokenAccount",
+                canceler_token_account.pubkey().to_string(),
+            );
+            instructions.extend(vec![
+                // Account for the canceler tokens
+                system_instruction::create_account(
+                    &config.fee_payer.pubkey(),
+                    &canceler_token_account.pubkey(),
+                    token_account_balance,
+                    TokenAccount::LEN as u64,
+                    &spl_token::id(),
                 ),
+                // Initialize canceler token account
+                initialize_account(
+                    &spl_token::id(),
+                    &canceler_token_account.pubkey(),
+                    token_mint,
+                    &escrow_info.canceler,
+                )?,
+            ]);
+            signers.push(&canceler_token_account);
+            token_accounts += 1;
+            canceler_token_account.pubkey()
+        }
+    };
+
+    // Vault account is created by the program and paid by the owner
+    let mut required_balance = token_account_balance * token_accounts;
+    if config.owner.pubkey() == config.fee_payer.pubkey() {
+        required_balance += vault_balance;
+    }
+    instructions.push(add_vault(
+        &hmt_escrow::id(),
+        escrow,
+        &config.owner.pubkey(),
+        &vault,
+        token_mint,
+        &vault_token_account.pubkey(),
+        &canceler_token,
+        &EscrowProcessor::find_config_address(&hmt_escrow::id()).0,
+    )?);
+
+    let mut transaction =
+        Transaction::new_with_payer(&instructions, Some(&config.fee_payer.pubkey()));
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(
+        config,
+        required_balance + fee_calculator.calculate_fee(&transaction.message()),
+    )?;
+    unique_signers!(signers);
+    transaction.sign(&signers, recent_blockhash);
+    send_transaction(config, transaction, created_accounts)
+}
+
+/// Stores the total amount the vault of the token mint pays out
+fn command_vault_store_results(
+    config: &Config,
+    escrow: &Pubkey,
+    token_mint: &Pubkey,
+    amount: f64,
+) -> CommandResult {
+    let (vault, _) = get_vault(config, escrow, token_mint)?;
+    let amount = spl_token::ui_amount_to_amount(amount, get_mint_decimals(config, token_mint)?);
+
+    let mut transaction = Transaction::new_with_payer(
+        &[store_vault_results(
+            &hmt_escrow::id(),
+            escrow,
+            &config.owner.pubkey(),
+            &vault,
+            amount,
+        )?],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(&transaction.message()))?;
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+    unique_signers!(signers);
+    transaction.sign(&signers, recent_blockhash);
+    send_transaction(config, transaction, BTreeMap::new())
+}
+
+/// Pays from the vault of the token mint to a single recipient, oracle fees go to
+/// the first oracle token accounts of the vault mint
+fn command_vault_payout(
+    config: &Config,
+    escrow: &Pubkey,
+    token_mint: &Pubkey,
+    recipient: &Pubkey,
+    amount: f64,
+) -> CommandResult {
+    // Read escrow state
+    let account_data = config
+        .rpc_client
+        .get_account_data(escrow)
+        .or(Err("Cannot read escrow data"))?;
+    let escrow_info: Escrow = Escrow::unpack_from_slice(account_data.as_slice())
+        .map_err(|_| format!("{} is not a valid escrow address", escrow))?;
+    let (vault, vault_info) = get_vault(config, escrow, token_mint)?;
+    let amount = spl_token::ui_amount_to_amount(amount, get_mint_decimals(config, token_mint)?);
+
+    // Oracle fee accounts of the vault mint
+    let mut oracle_token_accounts = vec![];
+    for (name, oracle) in [
+        ("Reputation", escrow_info.reputation_oracle),
+        ("Recording", escrow_info.recording_oracle),
+    ]
+    .iter()
+    {
+        let oracle = oracle.ok_or_else(|| format!("{} oracle not defined", name))?;
+        let token_account = find_token_accounts(&config.rpc_client, &oracle, token_mint)?
+            .into_iter()
+            .map(|(address, _)| address)
+            .next()
+            .ok_or_else(|| {
+                format!(
+                    "{} oracle {} has no {} token account",
+                    name, oracle, token_mint
+                )
+            })?;
+        oracle_token_accounts.push(token_account);
+    }
+
+    let authority =
+        EscrowProcessor::authority_id(&hmt_escrow::id(), &escrow, escrow_info.bump_seed)?;
+    let allow_list = match escrow_info.allow_list {
+        COption::Some(allow_list) => Some(allow_list),
+        COption::None => None,
+    };
+
+    let mut transaction = Transaction::new_with_payer(
+        &[vault_payout(
+            &hmt_escrow::id(),
+            escrow,
+            &config.owner.pubkey(),
+            &vault,
+            &vault_info.token_account,
+            &authority,
+            recipient,
+            &oracle_token_accounts[0],
+            &oracle_token_accounts[1],
+            &spl_token::id(),
+            amount,
+            allow_list.as_ref(),
+        )?],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(&transaction.message()))?;
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+    unique_signers!(signers);
+    transaction.sign(&signers, recent_blockhash);
+    send_transaction(config, transaction, BTreeMap::new())
+}
+
+/// Sets the Ethereum address of the key signing payout permits, the zero address
+/// stops accepting permits
+fn command_set_permit_signer(
+    config: &Config,
+    escrow: &Pubkey,
+    permit_signer: &[u8; ETH_ADDRESS_LEN],
+) -> CommandResult {
+    let mut transaction = Transaction::new_with_payer(
+        &[set_permit_signer(
+            &hmt_escrow::id(),
+            escrow,
+            &config.owner.pubkey(),
+            permit_signer,
+        )?],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(&transaction.message()))?;
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+    unique_signers!(signers);
+    transaction.sign(&signers, recent_blockhash);
+    send_transaction(config, transaction, BTreeMap::new())
+}
+
+/// Reads secp256k1 secret key stored as hex in the file
+fn read_permit_key(file_name: &str) -> Result<SecretKey, Error> {
+    let content = std::fs::read_to_string(file_name)
+        .map_err(|err| format!("Cannot read permit key {}: {}", file_name, err))?;
+    let bytes = hex::decode(content.trim()).or(Err("Permit key decoding error"))?;
+    SecretKey::parse_slice(&bytes).map_err(|_| "Invalid permit key".into())
+}
+
+/// Signs payout permit with the secp256k1 key, works without network access
+fn command_sign_permit(
+    config: &Config,
+    permit_key: &SecretKey,
+    escrow: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+    nonce: u64,
+    expires: i64,
+) -> CommandResult {
+    let message = payout_permit_message(escrow, recipient, amount, nonce, expires);
+
+    // Signature followed by the recovery id, as stored in the Secp256k1 program instruction
+    let instruction = solana_sdk::secp256k1::new_secp256k1_instruction(permit_key, &message);
+    let signer = &instruction.data[ETH_ADDRESS_OFFSET..ETH_ADDRESS_OFFSET + ETH_ADDRESS_LEN];
+    let signature = &instruction.data[SIGNATURE_OFFSET..SIGNATURE_OFFSET + SIGNATURE_LEN + 1];
+
+    Ok(config.output_format.formatted_string(&CliPermit {
+        signer: hex::encode(signer),
+        signature: hex::encode(signature),
+    }))
+}
+
+/// Creates transaction for payout authorized by the permit of the escrow permit signer
+#[allow(clippy::too_many_arguments)]
+fn command_payout_permit(
+    config: &Config,
+    escrow: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+    nonce: u64,
+    expires: i64,
+    signature: &[u8],
+) -> CommandResult {
+    if signature.len() != SIGNATURE_LEN + 1 {
+        return Err(format!(
+            "Permit signature must be {} bytes followed by the recovery id",
+            SIGNATURE_LEN
+        )
+        .into());
+    }
+
+    // Read escrow state
+    let account_data = config
+        .rpc_client
+        .get_account_data(escrow)
+        .or(Err("Cannot read escrow data"))?;
+    let escrow_info: Escrow = Escrow::unpack_from_slice(account_data.as_slice())
+        .map_err(|_| format!("{} is not a valid escrow address", escrow))?;
+
+    if escrow_info.permit_signer == [0; ETH_ADDRESS_LEN] {
+        return Err(format!("Escrow {} does not accept payout permits", escrow).into());
+    }
+    if nonce != escrow_info.payout_nonce {
+        return Err(format!(
+            "Permit nonce {} does not match escrow nonce {}",
+            nonce, escrow_info.payout_nonce
+        )
+        .into());
+    }
+
+    // Check oracle accounts
+    let reputation_oracle_token_account = escrow_info
+        .reputation_oracle_token_account
+        .ok_or::<Error>("Reputation oracle token account not defined".into())?;
+    let recording_oracle_token_account = escrow_info
+        .recording_oracle_token_account
+        .ok_or::<Error>("Recording oracle token account not defined".into())?;
+
+    // Signature is verified by the Secp256k1 program against the escrow permit signer
+    let message = payout_permit_message(escrow, recipient, amount, nonce, expires);
+    let mut signature_bytes = [0u8; SIGNATURE_LEN];
+    signature_bytes.copy_from_slice(&signature[..SIGNATURE_LEN]);
+
+    let authority =
+        EscrowProcessor::authority_id(&hmt_escrow::id(), &escrow, escrow_info.bump_seed)?;
+    let allow_list = match escrow_info.allow_list {
+        COption::Some(allow_list) => Some(allow_list),
+        COption::None => None,
+    };
+    let recipient_payout = get_recipient_payout_address(config, escrow, &escrow_info, recipient)?;
+    let fee_payer = config.fee_payer.pubkey();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            new_secp256k1_instruction(
+                &escrow_info.permit_signer,
+                &signature_bytes,
+                signature[SIGNATURE_LEN],
+                &message,
+                0,
+            ),
+            payout_with_permit(
+                &hmt_escrow::id(),
+                &escrow,
+                &escrow_info.token_account,
+                &authority,
+                recipient,
+                &reputation_oracle_token_account,
+                &recording_oracle_token_account,
+                &spl_token::id(),
+                amount,
+                nonce,
+                expires,
+                allow_list.as_ref(),
+                recipient_payout
+                    .as_ref()
+                    .map(|recipient_payout| (recipient_payout, &fee_payer)),
+            )?,
+        ],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(&transaction.message()))?;
+    let mut signers = vec![config.fee_payer.as_ref()];
+    unique_signers!(signers);
+    transaction.sign(&signers, recent_blockhash);
+    send_transaction(config, transaction, BTreeMap::new())
+}
+
+fn command_cancel(
+    config: &Config,
+    escrow: &Pubkey,
+    escrow_token_account: &Option<Pubkey>,
+    canceler_token: &Option<Pubkey>,
+    vault_mints: &[Pubkey],
+) -> CommandResult {
+    let instruction = match (escrow_token_account, canceler_token) {
+        (Some(token_account), Some(canceler_token)) if config.sign_only => {
+            let (authority, _) =
+                EscrowProcessor::find_authority_bump_seed(&hmt_escrow::id(), escrow);
+
+            // Every vault is refunded, in the order they were added
+            let mut vaults = vec![];
+            for token_mint in vault_mints {
+                let (vault, vault_info) = get_vault(config, escrow, token_mint)?;
+                vaults.push((
+                    vault_info.index,
+                    VaultRefund {
+                        vault,
+                        token_account: vault_info.token_account,
+                        canceler_token_account: vault_info.canceler_token_account,
+                    },
+                ));
+            }
+            vaults.sort_by_key(|(index, _)| *index);
+            let vaults: Vec<VaultRefund> = vaults.into_iter().map(|(_, vault)| vault).collect();
+
+            cancel_escrow(
+                &hmt_escrow::id(),
+                &escrow,
+                &config.owner.pubkey(),
+                token_account,
+                &authority,
+                canceler_token,
+                &spl_token::id(),
+                None,
+                &vaults,
+            )?
+        }
+        _ => block_on(escrow_client(config).cancel_instruction(
+            escrow,
+            &config.owner.pubkey(),
+            vault_mints,
+        ))?,
+    };
+
+    process_instructions(
+        config,
+        vec![instruction],
+        vec![config.fee_payer.as_ref(), config.owner.as_ref()],
+        0,
+        BTreeMap::new(),
+    )
+}
+
+fn command_complete(config: &Config, escrow: &Pubkey) -> CommandResult {
+    process_instructions(
+        config,
+        vec![escrow_client(config).complete_instruction(escrow, &config.owner.pubkey())?],
+        vec![config.fee_payer.as_ref(), config.owner.as_ref()],
+        0,
+        BTreeMap::new(),
+    )
+}
+
+fn command_set_paused(config: &Config, escrow: &Pubkey, paused: bool) -> CommandResult {
+    let instruction = if paused {
+        pause_escrow(&hmt_escrow::id(), &escrow, &config.owner.pubkey())?
+    } else {
+        unpause_escrow(&hmt_escrow::id(), &escrow, &config.owner.pubkey())?
+    };
+    process_instructions(
+        config,
+        vec![instruction],
+        vec![config.fee_payer.as_ref(), config.owner.as_ref()],
+        0,
+        BTreeMap::new(),
+    )
+}
+
+/// Escrow maintenance allowed by the keeper flags
+struct KeeperPolicy {
+    complete_paid: bool,
+    cancel_expired: bool,
+    /// Seconds to wait after the expiry before cancelling
+    cancel_grace: i64,
+    /// Transactions are only sent when set, otherwise actions are just logged
+    execute: bool,
+}
+
+/// Reads the token account balance, `account` is a token account of the escrow
+fn escrow_token_balance(config: &Config, escrow: &Pubkey, account: &Pubkey) -> Result<u64, Error> {
+    let account_data = config
+        .rpc_client
+        .get_account_data(account)
+        .or(Err("Cannot read escrow token account data"))?;
+    Ok(TokenAccount::unpack_from_slice(account_data.as_slice())
+        .map_err(|_| format!("Escrow {} has invalid token account", escrow))?
+        .amount)
+}
+
+/// Tokens left in the escrow token account and the vaults, nonzero when cancel refunds anything
+fn escrow_balance(config: &Config, escrow: &Pubkey, escrow_info: &Escrow) -> Result<u64, Error> {
+    let mut balance = escrow_token_balance(config, escrow, &escrow_info.token_account)?;
+    for (_, vault_info) in find_vaults(&config.rpc_client, &hmt_escrow::id(), escrow)? {
+        balance = balance.saturating_add(escrow_token_balance(
+            config,
+            escrow,
+            &vault_info.token_account,
+        )?);
+    }
+    Ok(balance)
+}
+
+/// Picks the keeper action for the escrow, `None` when there is nothing to do,
+/// otherwise the action and the reason it cannot be taken now, if any.
+///
+/// `balance` returns the tokens left in the escrow and its vaults,
+/// it is only called when a cancel depends on it
+fn keeper_action(
+    owner: &Pubkey,
+    policy: &KeeperPolicy,
+    escrow_info: &Escrow,
+    now: i64,
+    balance: impl FnOnce() -> Result<u64, Error>,
+) -> Result<Option<(&'static str, Option<String>)>, Error> {
+    let trusted = *owner == escrow_info.launcher || *owner == escrow_info.canceler;
+    match escrow_info.state {
+        EscrowState::Paid => {
+            let skipped = if !policy.complete_paid {
+                Some("--complete-paid is not set")
+            } else if !trusted {
+                Some("owner is neither the launcher nor the canceler")
+            } else if escrow_info.paused {
+                Some("escrow is paused")
+            } else if escrow_info.expires < now {
+                Some("escrow is expired")
+            } else {
+                None
+            };
+            Ok(Some(("complete", skipped.map(String::from))))
+        }
+        EscrowState::Launched | EscrowState::Pending | EscrowState::Partial
+            if escrow_info.expires.saturating_add(policy.cancel_grace) < now =>
+        {
+            let skipped = if !policy.cancel_expired {
+                Some("--cancel-expired is not set".to_string())
+            } else if !trusted {
+                Some("owner is neither the launcher nor the canceler".to_string())
+            } else if now < escrow_info.cancel_notice_end {
+                Some("cancel notice period is not over".to_string())
+            } else if balance()? == 0 {
+                Some("escrow and vault token accounts are empty".to_string())
+            } else {
+                None
+            };
+            Ok(Some(("cancel", skipped)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Sends the keeper action transaction, the owner signs as the trusted handler
+fn send_keeper_action(
+    config: &Config,
+    action: &str,
+    escrow: &Pubkey,
+    escrow_info: &Escrow,
+) -> Result<Signature, Error> {
+    let instruction = if action == "complete" {
+        complete_escrow(&hmt_escrow::id(), escrow, &config.owner.pubkey())?
+    } else {
+        // Cancellation fee goes to the oracle token accounts
+        let oracle_token_accounts = if escrow_info.has_cancellation_fee() {
+            match (
+                escrow_info.reputation_oracle_token_account,
+                escrow_info.recording_oracle_token_account,
+            ) {
+                (COption::Some(reputation), COption::Some(recording)) => {
+                    Some((reputation, recording))
+                }
+                _ => return Err("Escrow oracles are not set".into()),
+            }
+        } else {
+            None
+        };
+        let vaults: Vec<VaultRefund> = find_vaults(&config.rpc_client, &hmt_escrow::id(), escrow)?
+            .into_iter()
+            .map(|(vault, vault_info)| VaultRefund {
+                vault,
+                token_account: vault_info.token_account,
+                canceler_token_account: vault_info.canceler_token_account,
+            })
+            .collect();
+        if vaults.len() != escrow_info.vault_count as usize {
+            return Err("Cannot find all escrow vaults".into());
+        }
+        let (authority, _) = EscrowProcessor::find_authority_bump_seed(&hmt_escrow::id(), escrow);
+        cancel_escrow(
+            &hmt_escrow::id(),
+            escrow,
+            &config.owner.pubkey(),
+            &escrow_info.token_account,
+            &authority,
+            &escrow_info.canceler_token_account,
+            &spl_token::id(),
+            oracle_token_accounts
+                .as_ref()
+                .map(|(reputation, recording)| (reputation, recording)),
+            &vaults,
+        )?
+    };
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&config.fee_payer.pubkey()));
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(&transaction.message()))?;
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+    unique_signers!(signers);
+    transaction.sign(&signers, recent_blockhash);
+    Ok(config
+        .rpc_client
+        .send_and_confirm_transaction_with_spinner_and_commitment(
+            &transaction,
+            config.commitment_config,
+        )?)
+}
+
+/// Appends the keeper entry to the log as a JSON line and prints it
+fn log_keeper_entry(config: &Config, log: &mut File, entry: &CliKeeperEntry) -> Result<(), Error> {
+    writeln!(log, "{}", OutputFormat::JsonCompact.formatted_string(entry))?;
+    log.sync_data()?;
+    println!("{}", config.output_format.formatted_string(entry));
+    Ok(())
+}
+
+/// Completes paid escrows and cancels expired ones according to the policy.
+///
+/// Every action taken or skipped is printed and appended to the log as a JSON line,
+/// a skipped action is logged again only when the reason changes.
+/// RPC errors are logged and the next scan retries.
+/// Runs once, or keeps scanning every `interval` seconds when set.
+fn command_keeper(
+    config: &Config,
+    filters: &[EscrowFilter],
+    policy: &KeeperPolicy,
+    log_file: &str,
+    interval: Option<u64>,
+) -> CommandResult {
+    let mut log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_file)
+        .map_err(|_| format!("Cannot open keeper log {}", log_file))?;
+    let owner = config.owner.pubkey();
+    // Last skipped action logged per escrow
+    let mut skipped_logged: HashMap<Pubkey, (&'static str, String)> = HashMap::new();
+
+    loop {
+        let mut escrows = BTreeMap::new();
+        let mut scan_error = None;
+        for filter in filters {
+            match find_escrows(&config.rpc_client, &hmt_escrow::id(), filter) {
+                Ok(found) => escrows.extend(found),
+                Err(err) => {
+                    scan_error = Some(err.to_string());
+                    break;
+                }
+            }
+        }
+        if let Some(error) = scan_error {
+            escrows.clear();
+            let entry = CliKeeperEntry {
+                timestamp: Utc::now().timestamp(),
+                escrow: None,
+                state: None,
+                action: "scan".to_string(),
+                dry_run: !policy.execute,
+                skipped: None,
+                signature: None,
+                error: Some(error),
+            };
+            log_keeper_entry(config, &mut log, &entry)?;
+        } else {
+            skipped_logged.retain(|escrow, _| escrows.contains_key(escrow));
+        }
+
+        for (escrow, escrow_info) in &escrows {
+            let now = Utc::now().timestamp();
+            let action = keeper_action(&owner, policy, escrow_info, now, || {
+                escrow_balance(config, escrow, escrow_info)
+            });
+            let (action, skipped, signature, error) = match action {
+                Ok(None) => {
+                    skipped_logged.remove(escrow);
+                    continue;
+                }
+                Ok(Some((action, Some(reason)))) => {
+                    let skip = (action, reason);
+                    if skipped_logged.get(escrow) == Some(&skip) {
+                        continue;
+                    }
+                    skipped_logged.insert(*escrow, skip.clone());
+                    (action, Some(skip.1), None, None)
+                }
+                Ok(Some((action, None))) => {
+                    skipped_logged.remove(escrow);
+                    if policy.execute {
+                        match send_keeper_action(config, action, escrow, escrow_info) {
+                            Ok(signature) => (action, None, Some(signature.to_string()), None),
+                            Err(err) => (action, None, None, Some(err.to_string())),
+                        }
+                    } else {
+                        (action, None, None, None)
+                    }
+                }
+                // Only the balance read of a cancel can fail
+                Err(err) => {
+                    skipped_logged.remove(escrow);
+                    ("cancel", None, None, Some(err.to_string()))
+                }
+            };
+            let entry = CliKeeperEntry {
+                timestamp: now,
+                escrow: Some(escrow.to_string()),
+                state: Some(format!("{:?}", escrow_info.state)),
+                action: action.to_string(),
+                dry_run: !policy.execute,
+                skipped,
+                signature,
+                error,
+            };
+            log_keeper_entry(config, &mut log, &entry)?;
+        }
+
+        match interval {
+            Some(interval) => sleep(Duration::from_secs(interval)),
+            None => return Ok(String::new()),
+        }
+    }
+}
+
+fn command_init_config(
+    config: &Config,
+    max_oracle_stake: u8,
+    min_duration: u64,
+    max_duration: u64,
+    allowed_mints: &[Pubkey],
+) -> CommandResult {
+    if allowed_mints.len() > MAX_ALLOWED_MINTS {
+        return Err(format!("No more than {} allowed mints supported", MAX_ALLOWED_MINTS).into());
+    }
+
+    let (config_address, _) = EscrowProcessor::find_config_address(&hmt_escrow::id());
+    println_display(config, format!("Creating program config {}", config_address));
+    let mut created_accounts = BTreeMap::new();
+    created_accounts.insert("config", config_address.to_string());
+
+    let config_balance = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(EscrowConfig::LEN)?;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[initialize_config(
+            &hmt_escrow::id(),
+            &config_address,
+            &config.owner.pubkey(),
+            &EscrowProcessor::find_program_data_address(&hmt_escrow::id()),
+            &config.owner.pubkey(),
+            max_oracle_stake,
+            min_duration,
+            max_duration,
+            allowed_mints,
+        )?],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(&transaction.message()))?;
+    let balance = config.rpc_client.get_balance(&config.owner.pubkey())?;
+    if balance < config_balance {
+        return Err(format!(
+            "Admin, {}, has insufficient balance: {} required, {} available",
+            config.owner.pubkey(),
+            lamports_to_sol(config_balance),
+            lamports_to_sol(balance)
+        )
+        .into());
+    }
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+    unique_signers!(signers);
+    transaction.sign(&signers, recent_blockhash);
+    send_transaction(config, transaction, created_accounts)
+}
+
+fn get_escrow_config(config: &Config) -> Result<(Pubkey, EscrowConfig), Error> {
+    let (config_address, _) = EscrowProcessor::find_config_address(&hmt_escrow::id());
+    let account_data = config
+        .rpc_client
+        .get_account_data(&config_address)
+        .or(Err("Cannot read program config data"))?;
+    let escrow_config = EscrowConfig::unpack_from_slice(account_data.as_slice())
+        .map_err(|_| format!("{} is not a valid program config address", config_address))?;
+    Ok((config_address, escrow_config))
+}
+
+fn command_config_info(config: &Config) -> CommandResult {
+    let (config_address, escrow_config) = get_escrow_config(config)?;
+
+    let staking = match escrow_config.stake_mint {
+        COption::Some(stake_mint) => {
+            let decimals = get_mint_decimals(config, &stake_mint)?;
+            Some(CliStakingConfig {
+                stake_mint: stake_mint.to_string(),
+                min_oracle_stake: spl_token::amount_to_ui_amount(
+                    escrow_config.min_oracle_stake,
+                    decimals,
+                )
+                .to_string(),
+                stake_escrow_threshold: spl_token::amount_to_ui_amount(
+                    escrow_config.stake_escrow_threshold,
+                    decimals,
+                )
+                .to_string(),
+            })
+        }
+        COption::None => None,
+    };
+
+    Ok(config.output_format.formatted_string(&CliConfig {
+        address: config_address.to_string(),
+        admin: escrow_config.admin.to_string(),
+        paused: escrow_config.paused,
+        max_oracle_stake: escrow_config.max_oracle_stake,
+        min_duration: escrow_config.min_duration,
+        max_duration: escrow_config.max_duration,
+        allowed_mints: escrow_config
+            .allowed_mints()
+            .iter()
+            .map(|mint| mint.to_string())
+            .collect(),
+        staking,
+        arbiter: coption_key_string(&escrow_config.arbiter),
+    }))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn command_update_config(
+    config: &Config,
+    admin: &Option<Pubkey>,
+    paused: Option<bool>,
+    max_oracle_stake: Option<u8>,
+    min_duration: Option<u64>,
+    max_duration: Option<u64>,
+    allowed_mints: &Option<Vec<Pubkey>>,
+) -> CommandResult {
+    let (config_address, escrow_config) = get_escrow_config(config)?;
+
+    // Keep current values for all parameters not specified
+    let admin = admin.unwrap_or(escrow_config.admin);
+    let paused = paused.unwrap_or(escrow_config.paused);
+    let max_oracle_stake = max_oracle_stake.unwrap_or(escrow_config.max_oracle_stake);
+    let min_duration = min_duration.unwrap_or(escrow_config.min_duration);
+    let max_duration = max_duration.unwrap_or(escrow_config.max_duration);
+    let allowed_mints: Vec<Pubkey> = match allowed_mints {
+        Some(mints) => mints.clone(),
+        None => escrow_config.allowed_mints().to_vec(),
+    };
+    if allowed_mints.len() > MAX_ALLOWED_MINTS {
+        return Err(format!("No more than {} allowed mints supported", MAX_ALLOWED_MINTS).into());
+    }
+
+    let mut transaction = Transaction::new_with_payer(
+        &[update_config(
+            &hmt_escrow::id(),
+            &config_address,
+            &config.owner.pubkey(),
+            &admin,
+            paused,
+            max_oracle_stake,
+            min_duration,
+            max_duration,
+            &allowed_mints,
+        )?],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(&transaction.message()))?;
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+    unique_signers!(signers);
+    transaction.sign(&signers, recent_blockhash);
+    send_transaction(config, transaction, BTreeMap::new())
+}
+
+fn get_mint_decimals(config: &Config, mint: &Pubkey) -> Result<u8, Error> {
+    let account_data = config
+        .rpc_client
+        .get_account_data(mint)
+        .or(Err("Cannot read mint data"))?;
+    let mint_info: TokenMint = TokenMint::unpack_from_slice(account_data.as_slice())
+        .map_err(|_| format!("{} is not a valid mint address", mint))?;
+    Ok(mint_info.decimals)
+}
+
+fn get_vault(
+    config: &Config,
+    escrow: &Pubkey,
+    token_mint: &Pubkey,
+) -> Result<(Pubkey, EscrowVault), Error> {
+    let (vault, _) = EscrowProcessor::find_vault_address(&hmt_escrow::id(), escrow, token_mint);
+    let account_data = config
+        .rpc_client
+        .get_account_data(&vault)
+        .or_else(|_| Err(format!("Escrow has no vault of mint {}", token_mint)))?;
+    let vault_info = EscrowVault::unpack_from_slice(account_data.as_slice())
+        .map_err(|_| format!("{} is not a valid vault address", vault))?;
+    Ok((vault, vault_info))
+}
+
+fn get_allow_list(
+    config: &Config,
+    allow_list: &Pubkey,
+) -> Result<(AllowList, usize, Vec<Pubkey>), Error> {
+    let account_data = config
+        .rpc_client
+        .get_account_data(allow_list)
+        .or(Err("Cannot read allow-list data"))?;
+    let allow_list_info = AllowList::unpack_from_slice(account_data.as_slice())
+        .ok()
+        .filter(|allow_list_info| allow_list_info.is_initialized)
+        .ok_or_else(|| format!("{} is not a valid allow-list address", allow_list))?;
+    let capacity = AllowList::capacity(&account_data);
+    let owners = (0..allow_list_info.count as usize)
+        .map(|index| AllowList::unpack_owner(&account_data, index))
+        .collect::<Result<Vec<Pubkey>, _>>()?;
+    Ok((allow_list_info, capacity, owners))
+}
+
+/// Recipient payout address of the recipient token account owner, only if the
+/// escrow limits the payout per recipient
+fn get_recipient_payout_address(
+    config: &Config,
+    escrow: &Pubkey,
+    escrow_info: &Escrow,
+    recipient: &Pubkey,
+) -> Result<Option<Pubkey>, Error> {
+    if escrow_info.max_payout_per_recipient == 0 {
+        return Ok(None);
+    }
+    let account_data = config
+        .rpc_client
+        .get_account_data(recipient)
+        .or(Err("Cannot read recipient token account data"))?;
+    let token_account = TokenAccount::unpack_from_slice(account_data.as_slice())
+        .map_err(|_| format!("{} is not a valid token account address", recipient))?;
+    let (recipient_payout, _) = EscrowProcessor::find_recipient_payout_address(
+        &hmt_escrow::id(),
+        escrow,
+        &token_account.owner,
+    );
+    Ok(Some(recipient_payout))
+}
+
+fn get_template(config: &Config, template: &Pubkey) -> Result<EscrowTemplate, Error> {
+    let account_data = config
+        .rpc_client
+        .get_account_data(template)
+        .or(Err("Cannot read template data"))?;
+    EscrowTemplate::unpack_from_slice(account_data.as_slice())
+        .ok()
+        .filter(|template_info| template_info.is_initialized)
+        .ok_or_else(|| format!("{} is not a valid template address", template).into())
+}
+
+fn get_stake_mint(config: &Config) -> Result<(Pubkey, EscrowConfig, Pubkey), Error> {
+    let (config_address, escrow_config) = get_escrow_config(config)?;
+    let stake_mint = escrow_config
+        .stake_mint
+        .ok_or("Oracle staking is disabled in the program config")?;
+    Ok((config_address, escrow_config, stake_mint))
+}
+
+fn get_oracle_stake(config: &Config, oracle: &Pubkey) -> Result<(Pubkey, OracleStake), Error> {
+    let (oracle_stake_address, _) =
+        EscrowProcessor::find_oracle_stake_address(&hmt_escrow::id(), oracle);
+    let account_data = config
+        .rpc_client
+        .get_account_data(&oracle_stake_address)
+        .map_err(|_| format!("Cannot read oracle stake data for {}", oracle))?;
+    let oracle_stake = OracleStake::unpack_from_slice(account_data.as_slice())
+        .map_err(|_| format!("{} is not a valid oracle stake address", oracle_stake_address))?;
+    Ok((oracle_stake_address, oracle_stake))
+}
+
+fn command_update_staking_config(
+    config: &Config,
+    stake_mint: &Option<Pubkey>,
+    min_oracle_stake: f64,
+    stake_escrow_threshold: f64,
+    arbiter: &Option<Pubkey>,
+) -> CommandResult {
+    let (config_address, _) = get_escrow_config(config)?;
+
+    let to_coption = |key: &Option<Pubkey>| match key {
+        Some(key) => COption::Some(*key),
+        None => COption::None,
+    };
+    let (min_oracle_stake, stake_escrow_threshold) = match stake_mint {
+        Some(stake_mint) => {
+            let decimals = get_mint_decimals(config, stake_mint)?;
+            (
+                spl_token::ui_amount_to_amount(min_oracle_stake, decimals),
+                spl_token::ui_amount_to_amount(stake_escrow_threshold, decimals),
+            )
+        }
+        None => (0, 0),
+    };
+
+    let mut transaction = Transaction::new_with_payer(
+        &[update_staking_config(
+            &hmt_escrow::id(),
+            &config_address,
+            &config.owner.pubkey(),
+            to_coption(stake_mint),
+            min_oracle_stake,
+            stake_escrow_threshold,
+            to_coption(arbiter),
+        )?],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(&transaction.message()))?;
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+    unique_signers!(signers);
+    transaction.sign(&signers, recent_blockhash);
+    send_transaction(config, transaction, BTreeMap::new())
+}
+
+fn command_stake_create(config: &Config) -> CommandResult {
+    let (config_address, _, stake_mint) = get_stake_mint(config)?;
+
+    let oracle = config.owner.pubkey();
+    let (oracle_stake_address, _) =
+        EscrowProcessor::find_oracle_stake_address(&hmt_escrow::id(), &oracle);
+
+    let vault = Keypair::new();
+    println_display(config, format!("Creating oracle stake {}", oracle_stake_address));
+    println_display(
+        config,
+        format!("Creating stake vault token account {}", vault.pubkey()),
+    );
+    let mut created_accounts = BTreeMap::new();
+    created_accounts.insert("oracleStake", oracle_stake_address.to_string());
+    created_accounts.insert("vault", vault.pubkey().to_string());
+
+    let token_account_balance = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(TokenAccount::LEN)?;
+    let oracle_stake_balance = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(OracleStake::LEN)?;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            // Account for the locked tokens
+            system_instruction::create_account(
+                &config.fee_payer.pubkey(),
+                &vault.pubkey(),
+                token_account_balance,
+                TokenAccount::LEN as u64,
+                &spl_token::id(),
+            ),
+            // Initialize vault token account owned by the oracle stake
+            initialize_account(
+                &spl_token::id(),
+                &vault.pubkey(),
+                &stake_mint,
+                &oracle_stake_address,
+            )?,
+            // Initialize oracle stake
+            initialize_oracle_stake(
+                &hmt_escrow::id(),
+                &oracle_stake_address,
+                &oracle,
+                &vault.pubkey(),
+                &config_address,
+            )?,
+        ],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(
+        config,
+        token_account_balance + fee_calculator.calculate_fee(&transaction.message()),
+    )?;
+    let balance = config.rpc_client.get_balance(&oracle)?;
+    if balance < oracle_stake_balance {
+        return Err(format!(
+            "Oracle, {}, has insufficient balance: {} required, {} available",
+            oracle,
+            lamports_to_sol(oracle_stake_balance),
+            lamports_to_sol(balance)
+        )
+        .into());
+    }
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref(), &vault];
+    unique_signers!(signers);
+    transaction.sign(&signers, recent_blockhash);
+    send_transaction(config, transaction, created_accounts)
+}
+
+fn command_stake_info(config: &Config, oracle: &Option<Pubkey>) -> CommandResult {
+    let oracle = oracle.unwrap_or_else(|| config.owner.pubkey());
+    let (oracle_stake_address, oracle_stake) = get_oracle_stake(config, &oracle)?;
+    let vault_data = config
+        .rpc_client
+        .get_account_data(&oracle_stake.vault)
+        .or(Err("Cannot read stake vault data"))?;
+    let vault = TokenAccount::unpack_from_slice(vault_data.as_slice())
+        .map_err(|_| format!("{} is not a valid token account", oracle_stake.vault))?;
+    let decimals = get_mint_decimals(config, &vault.mint)?;
+
+    Ok(config.output_format.formatted_string(&CliOracleStake {
+        address: oracle_stake_address.to_string(),
+        oracle: oracle_stake.oracle.to_string(),
+        vault: oracle_stake.vault.to_string(),
+        amount: spl_token::amount_to_ui_amount(oracle_stake.amount, decimals).to_string(),
+        locked_until: oracle_stake.locked_until,
+    }))
+}
+
+fn command_stake_deposit(config: &Config, amount: f64, from: &Pubkey) -> CommandResult {
+    let (_, _, stake_mint) = get_stake_mint(config)?;
+    let (oracle_stake_address, oracle_stake) = get_oracle_stake(config, &config.owner.pubkey())?;
+    let amount = spl_token::ui_amount_to_amount(amount, get_mint_decimals(config, &stake_mint)?);
+
+    let mut transaction = Transaction::new_with_payer(
+        &[deposit_oracle_stake(
+            &hmt_escrow::id(),
+            &oracle_stake_address,
+            &oracle_stake.vault,
+            from,
+            &config.owner.pubkey(),
+            &spl_token::id(),
+            amount,
+        )?],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(&transaction.message()))?;
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+    unique_signers!(signers);
+    transaction.sign(&signers, recent_blockhash);
+    send_transaction(config, transaction, BTreeMap::new())
+}
+
+fn command_stake_withdraw(config: &Config, amount: f64, to: &Pubkey) -> CommandResult {
+    let (_, _, stake_mint) = get_stake_mint(config)?;
+    let (oracle_stake_address, oracle_stake) = get_oracle_stake(config, &config.owner.pubkey())?;
+    let amount = spl_token::ui_amount_to_amount(amount, get_mint_decimals(config, &stake_mint)?);
+
+    let mut transaction = Transaction::new_with_payer(
+        &[withdraw_oracle_stake(
+            &hmt_escrow::id(),
+            &oracle_stake_address,
+            &config.owner.pubkey(),
+            &oracle_stake.vault,
+            to,
+            &spl_token::id(),
+            amount,
+        )?],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(&transaction.message()))?;
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+    unique_signers!(signers);
+    transaction.sign(&signers, recent_blockhash);
+    send_transaction(config, transaction, BTreeMap::new())
+}
+
+fn command_slash(
+    config: &Config,
+    escrow: &Pubkey,
+    oracle: &Pubkey,
+    amount: f64,
+    to: &Option<Pubkey>,
+) -> CommandResult {
+    let (config_address, _, stake_mint) = get_stake_mint(config)?;
+    let (oracle_stake_address, oracle_stake) = get_oracle_stake(config, oracle)?;
+    let amount = spl_token::ui_amount_to_amount(amount, get_mint_decimals(config, &stake_mint)?);
+
+    let account_data = config
+        .rpc_client
+        .get_account_data(escrow)
+        .or(Err("Cannot read escrow data"))?;
+    let escrow_info: Escrow = Escrow::unpack_from_slice(account_data.as_slice())
+        .map_err(|_| format!("{} is not a valid escrow address", escrow))?;
+
+    // Canceler's escrow token account receives slashed tokens by default
+    let to = to.unwrap_or(escrow_info.canceler_token_account);
+
+    let mut transaction = Transaction::new_with_payer(
+        &[slash_oracle(
+            &hmt_escrow::id(),
+            &config_address,
+            &config.owner.pubkey(),
+            escrow,
+            &oracle_stake_address,
+            &oracle_stake.vault,
+            &to,
+            &spl_token::id(),
+            amount,
+        )?],
+        Some(&config.fee_payer.pubkey()),
+    );
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(&transaction.message()))?;
+    let mut signers = vec![config.fee_payer.as_ref(), config.owner.as_ref()];
+    unique_signers!(signers);
+    transaction.sign(&signers, recent_blockhash);
+    send_transaction(config, transaction, BTreeMap::new())
+}
+
+/// Return an error if a hex cannot be parsed.
+pub fn is_hex<T>(string: T) -> Result<(), String>
+where
+    T: AsRef<str> + Display,
+{
+    match hex::decode(string.as_ref()) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("{}", err)),
+    }
+}
+
+/// Return an error if a number of seconds cannot be parsed or is negative.
+pub fn is_non_negative_seconds<T>(string: T) -> Result<(), String>
+where
+    T: AsRef<str> + Display,
+{
+    match string.as_ref().parse::<i64>() {
+        Ok(seconds) if seconds >= 0 => Ok(()),
+        Ok(_) => Err(format!("Seconds cannot be negative: {}", string)),
+        Err(err) => Err(format!("Unable to parse seconds {}: {}", string, err)),
+    }
+}
+
+fn main() {
+    let matches = App::new(crate_name!())
+        .about(crate_description!())
+        .version(crate_version!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg({
+            let arg = Arg::with_name("config_file")
+                .short("C")
+                .long("config")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .help("Configuration file to use");
+            if let Some(ref config_file) = *solana_cli_config::CONFIG_FILE {
+                arg.default_value(&config_file)
+            } else {
+                arg
+            }
+        })
+        .arg(
+            Arg::with_name("verbose")
+                .long("verbose")
+                .short("v")
+                .takes_value(false)
+                .global(true)
+                .help("Show additional information"),
+        )
+        .arg(
+            Arg::with_name("output_format")
+                .long("output")
+                .value_name("FORMAT")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["json", "json-compact", "display"])
+                .help("Return information in specified output format [default: display]"),
+        )
+        .arg(
+            Arg::with_name("hash_algorithm")
+                .long("hash-algorithm")
+                .value_name("ALGORITHM")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["sha1", "ripemd160"])
+                .default_value("sha1")
+                .help("Algorithm of the 20-byte manifest and results hashes"),
+        )
+        .arg(
+            Arg::with_name("json_rpc_url")
+                .long("url")
+                .value_name("URL")
+                .takes_value(true)
+                .validator(is_url)
+                .help("JSON RPC URL for the cluster.  Default from the configuration file."),
+        )
+        .arg(
+            Arg::with_name("owner")
+                .long("owner")
+                .value_name("KEYPAIR")
+                .validator(is_valid_signer)
+                .takes_value(true)
+                .help(
+                    "Specify the stake pool or stake account owner. \
+                     This may be a keypair file, the ASK keyword. \
+                     Defaults to the client keypair.",
+                ),
+        )
+        .arg(
+            Arg::with_name("fee_payer")
+                .long("fee-payer")
+                .value_name("KEYPAIR")
+                .validator(is_valid_signer)
+                .takes_value(true)
+                .help(
+                    "Specify the fee-payer account. \
+                     This may be a keypair file, the ASK keyword. \
+                     Defaults to the client keypair.",
+                ),
+        )
+        .subcommand(SubCommand::with_name("init-config").about("Creates program config, owner must be the program upgrade authority and becomes the config admin")
+            .arg(
+                Arg::with_name("max_oracle_stake")
+                    .long("max-oracle-stake")
+                    .validator(is_parsable::<u8>)
+                    .value_name("PERCENT")
+                    .takes_value(true)
+                    .default_value("100")
+                    .help("Maximum combined oracle fee in payouts, from 0 to 100 percent"),
+            )
+            .arg(
+                Arg::with_name("min_duration")
+                    .long("min-duration")
+                    .validator(is_parsable::<u64>)
+                    .value_name("SECONDS")
+                    .takes_value(true)
+                    .default_value("1")
+                    .help("Minimum escrow duration in seconds"),
+            )
+            .arg(
+                Arg::with_name("max_duration")
+                    .long("max-duration")
+                    .validator(is_parsable::<u64>)
+                    .value_name("SECONDS")
+                    .takes_value(true)
+                    .default_value("31536000")
+                    .help("Maximum escrow duration in seconds"),
+            )
+            .arg(
+                Arg::with_name("allowed_mint")
+                    .long("allowed-mint")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Token mint accepted by escrows, may be specified multiple times [default: any mint]"),
+            )
+        )
+        .subcommand(SubCommand::with_name("update-config").about("Updates program config, must be signed by the config admin")
+            .arg(
+                Arg::with_name("admin")
+                    .long("admin")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("New config admin"),
+            )
+            .arg(
+                Arg::with_name("pause")
+                    .long("pause")
+                    .takes_value(false)
+                    .conflicts_with("unpause")
+                    .help("Stop initialization and setup of all escrows"),
+            )
+            .arg(
+                Arg::with_name("unpause")
+                    .long("unpause")
+                    .takes_value(false)
+                    .help("Resume initialization and setup of escrows"),
+            )
+            .arg(
+                Arg::with_name("max_oracle_stake")
+                    .long("max-oracle-stake")
+                    .validator(is_parsable::<u8>)
+                    .value_name("PERCENT")
+                    .takes_value(true)
+                    .help("Maximum combined oracle fee in payouts, from 0 to 100 percent"),
+            )
+            .arg(
+                Arg::with_name("min_duration")
+                    .long("min-duration")
+                    .validator(is_parsable::<u64>)
+                    .value_name("SECONDS")
+                    .takes_value(true)
+                    .help("Minimum escrow duration in seconds"),
+            )
+            .arg(
+                Arg::with_name("max_duration")
+                    .long("max-duration")
+                    .validator(is_parsable::<u64>)
+                    .value_name("SECONDS")
+                    .takes_value(true)
+                    .help("Maximum escrow duration in seconds"),
+            )
+            .arg(
+                Arg::with_name("allowed_mint")
+                    .long("allowed-mint")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .conflicts_with("any_mint")
+                    .help("Token mint accepted by escrows, replaces the whole allow-list, may be specified multiple times"),
+            )
+            .arg(
+                Arg::with_name("any_mint")
+                    .long("any-mint")
+                    .takes_value(false)
+                    .help("Clear the allow-list, escrows accept any mint"),
+            )
+        )
+        .subcommand(SubCommand::with_name("config-info").about("Shows program config"))
+        .subcommand(SubCommand::with_name("staking-config").about("Updates oracle staking config, must be signed by the config admin")
+            .arg(
+                Arg::with_name("stake_mint")
+                    .long("stake-mint")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .required_unless("disable")
+                    .help("Mint of the tokens locked by oracles"),
+            )
+            .arg(
+                Arg::with_name("disable")
+                    .long("disable")
+                    .takes_value(false)
+                    .conflicts_with("stake_mint")
+                    .help("Disable oracle staking requirement"),
+            )
+            .arg(
+                Arg::with_name("min_oracle_stake")
+                    .long("min-oracle-stake")
+                    .validator(is_amount)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .default_value("0")
+                    .help("Minimum stake each oracle has to lock"),
+            )
+            .arg(
+                Arg::with_name("threshold")
+                    .long("threshold")
+                    .validator(is_amount)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .default_value("0")
+                    .help("Escrows holding at least this amount of tokens require staked oracles"),
+            )
+            .arg(
+                Arg::with_name("arbiter")
+                    .long("arbiter")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Dispute arbiter allowed to slash oracle stakes [default: admin only]"),
+            )
+        )
+        .subcommand(SubCommand::with_name("stake-create").about("Creates oracle stake, owner is the oracle"))
+        .subcommand(SubCommand::with_name("stake-info").about("Shows oracle stake")
+            .arg(
+                Arg::with_name("oracle")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ORACLE_ADDRESS")
+                    .takes_value(true)
+                    .help("Oracle address [default: owner]"),
+            )
+        )
+        .subcommand(SubCommand::with_name("stake-deposit").about("Locks more tokens in the owner's oracle stake")
+            .arg(
+                Arg::with_name("amount")
+                    .validator(is_amount)
+                    .index(1)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Amount of tokens to deposit"),
+            )
+            .arg(
+                Arg::with_name("from")
+                    .long("from")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Owner's token account to deposit from"),
+            )
+        )
+        .subcommand(SubCommand::with_name("stake-withdraw").about("Withdraws tokens from the owner's oracle stake once it is unlocked")
+            .arg(
+                Arg::with_name("amount")
+                    .validator(is_amount)
+                    .index(1)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Amount of tokens to withdraw"),
+            )
+            .arg(
+                Arg::with_name("to")
+                    .long("to")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Token account to receive the tokens"),
+            )
+        )
+        .subcommand(SubCommand::with_name("slash").about("Slashes oracle stake in favor of the escrow canceler, owner must be the arbiter or the config admin")
+            .arg(
+                Arg::with_name("escrow")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ESCROW_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Escrow address"),
+            )
+            .arg(
+                Arg::with_name("oracle")
+                    .validator(is_pubkey)
+                    .index(2)
+                    .value_name("ORACLE_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Misbehaving oracle address"),
+            )
+            .arg(
+                Arg::with_name("amount")
+                    .validator(is_amount)
+                    .index(3)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Amount of tokens to slash"),
+            )
+            .arg(
+                Arg::with_name("to")
+                    .long("to")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Canceler's token account of the stake mint [default: escrow canceler token account]"),
+            )
+        )
+        .subcommand(SubCommand::with_name("create").about("Create a new escrow")
+            .arg(
+                Arg::with_name("mint")
+                    .long("mint")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Mint address for the token managed by this escrow"),
+            )
+            .arg(
+                Arg::with_name("launcher")
+                    .long("launcher")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Account which can manage the escrow [default: --owner]"),
+            )
+            .arg(
+                Arg::with_name("canceler")
+                    .long("canceler")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Account which is able to cancel this escrow [default: --owner]"),
+            )
+            .arg(
+                Arg::with_name("canceler_token")
+                    .long("canceler-receiver")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Token account which can receive tokens specified by the --mint parameter [default: new token account owned by the --canceler]"),
+            )
+            .arg(
+                Arg::with_name("guardian")
+                    .long("guardian")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Account which is able to pause and unpause this escrow [default: --owner]"),
+            )
+            .arg(
+                Arg::with_name("duration")
+                    .long("duration")
+                    .short("d")
+                    .validator(is_parsable::<u64>)
+                    .value_name("SECONDS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Escrow duration in seconds, once this time passes escrow contract is no longer operational"),
+            )
+            .arg(
+                Arg::with_name("escrow_keypair")
+                    .long("escrow-keypair")
+                    .validator(is_valid_signer)
+                    .value_name("KEYPAIR")
+                    .takes_value(true)
+                    .help("Escrow account keypair, needed to broadcast an escrow signed offline [default: new keypair]"),
+            )
+            .arg(
+                Arg::with_name("escrow_token_keypair")
+                    .long("escrow-token-keypair")
+                    .validator(is_valid_signer)
+                    .value_name("KEYPAIR")
+                    .takes_value(true)
+                    .help("Escrow token account keypair, needed to broadcast an escrow signed offline [default: new keypair]"),
+            )
+            .nonce_args(false)
+            .offline_args_config(&SignOnlyNeedsEscrowKeys {}),
+        )
+        .subcommand(SubCommand::with_name("fund").about("Transfers tokens from the owner's token account to the escrow")
+            .arg(
+                Arg::with_name("escrow")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ESCROW_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Escrow address"),
+            )
+            .arg(
+                Arg::with_name("amount")
+                    .long("amount")
+                    .validator(is_amount)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Amount of tokens to transfer to the escrow"),
+            )
+            .arg(
+                Arg::with_name("from")
+                    .long("from")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Source token account owned by --owner [default: owner's token account of the escrow mint with enough tokens]"),
+            )
+        )
+        .subcommand(SubCommand::with_name("status").about("Shows escrow balance, projected fees and the operations allowed now")
+            .arg(
+                Arg::with_name("escrow")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ESCROW_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Escrow address"),
+            )
+        )
+        .subcommand(SubCommand::with_name("info").about("Shows information about the escrow account")
+            .arg(
+                Arg::with_name("escrow")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ESCROW_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Escrow address"),
+            )
+        )
+        .subcommand(SubCommand::with_name("list").about("Lists escrows, optionally filtered by launcher, canceler, mint and state")
+            .arg(
+                Arg::with_name("launcher")
+                    .long("launcher")
+                    .validator(is_pubkey)
+                    .value_name("LAUNCHER_ADDRESS")
+                    .takes_value(true)
+                    .help("Only list escrows with this launcher"),
+            )
+            .arg(
+                Arg::with_name("canceler")
+                    .long("canceler")
+                    .validator(is_pubkey)
+                    .value_name("CANCELER_ADDRESS")
+                    .takes_value(true)
+                    .help("Only list escrows with this canceler"),
+            )
+            .arg(
+                Arg::with_name("mint")
+                    .long("mint")
+                    .validator(is_pubkey)
+                    .value_name("MINT_ADDRESS")
+                    .takes_value(true)
+                    .help("Only list escrows holding tokens of this mint"),
+            )
+            .arg(
+                Arg::with_name("state")
+                    .long("state")
+                    .value_name("STATE")
+                    .takes_value(true)
+                    .possible_values(&["launched", "pending", "partial", "paid", "complete", "cancelled"])
+                    .help("Only list escrows in this state"),
+            )
+        )
+        .subcommand(SubCommand::with_name("setup").about("Configures and launches escrow")
+            .arg(
+                Arg::with_name("escrow")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ESCROW_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Escrow address"),
+            )
+            .arg(
+                Arg::with_name("reputation_oracle")
+                    .long("reputation-oracle")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Escrow reputation oracle address [default: --owner]"),
+            )
+            .arg(
+                Arg::with_name("reputation_oracle_token")
+                    .long("reputation-oracle-token")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Reputation oracle token address [default: new token account owned by the --reputation-oracle]"),
+            )
+            .arg(
+                Arg::with_name("reputation_oracle_stake")
+                    .long("reputation-oracle-stake")
+                    .validator(is_parsable::<u8>)
+                    .value_name("PERCENT")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Reputation oracle fee in payouts, from 0 to 100 percent"),
+            )
+            .arg(
+                Arg::with_name("recording_oracle")
+                    .long("recording-oracle")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Escrow recording oracle address [default: --owner]"),
+            )
+            .arg(
+                Arg::with_name("recording_oracle_token")
+                    .long("recording-oracle-token")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Recording oracle token address [default: new token account owned by the --recording-oracle]"),
+            )
+            .arg(
+                Arg::with_name("recording_oracle_stake")
+                    .long("recording-oracle-stake")
+                    .validator(is_parsable::<u8>)
+                    .value_name("PERCENT")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Recording oracle fee in payouts, from 0 to 100 percent"),
+            )
+            .arg(
+                Arg::with_name("manifest_url")
+                    .long("manifest-url")
+                    .validator(is_url)
+                    .value_name("URL")
+                    .takes_value(true)
+                    .help("Job manifest URL [default: empty string]"),
+            )
+            .arg(
+                Arg::with_name("manifest_hash")
+                    .long("manifest-hash")
+                    .validator(is_hex)
+                    .value_name("HEX")
+                    .takes_value(true)
+                    .help("20-byte manifest SHA1 hash in hex format [default: 0-byte hash]"),
+            )
+            .arg(
+                Arg::with_name("max_payout")
+                    .long("max-payout")
+                    .validator(is_amount)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .help("Maximum amount of tokens a single recipient can be paid in total [default: no limit]"),
+            )
+            .arg(
+                Arg::with_name("min_payout")
+                    .long("min-payout")
+                    .validator(is_amount)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .help("Minimum amount of tokens a single payout must send [default: 0]"),
+            )
+            .arg(
+                Arg::with_name("cancellation_fee")
+                    .long("cancellation-fee")
+                    .validator(is_amount)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .conflicts_with("cancellation_fee_percent")
+                    .help("Fixed amount of tokens paid to the oracles on cancel [default: 0]"),
+            )
+            .arg(
+                Arg::with_name("cancellation_fee_percent")
+                    .long("cancellation-fee-percent")
+                    .validator(is_parsable::<u8>)
+                    .value_name("PERCENT")
+                    .takes_value(true)
+                    .help("Percent of the escrow balance paid to the oracles on cancel [default: 0]"),
+            )
+            .arg(
+                Arg::with_name("cancel_notice")
+                    .long("cancel-notice")
+                    .validator(is_parsable::<u64>)
+                    .value_name("SECONDS")
+                    .takes_value(true)
+                    .help("Seconds after setup before the escrow can be canceled [default: 0]"),
+            )
+            .arg(
+                Arg::with_name("verify")
+                    .long("verify")
+                    .takes_value(false)
+                    .help("Fetch the manifest and check it against --manifest-hash, or use its hash when no hash is given"),
+            )
+            .arg(
+                Arg::with_name("allow_list")
+                    .long("allow-list")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Allow-list of the payout recipient owners [default: any recipient]"),
+            )
+        )
+        .subcommand(SubCommand::with_name("store-results").about("Stores results in the escrow")
+            .arg(
+                Arg::with_name("escrow")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ESCROW_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Escrow address"),
+            )
+            .arg(
+                Arg::with_name("amount")
+                    .long("amount")
+                    .validator(is_amount)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Total amount to be sent out from the escrow."),
+            )
+            .arg(
+                Arg::with_name("recipients")
+                    .long("recipients")
+                    .validator(is_parsable::<u64>)
+                    .value_name("COUNT")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Number of recipients to receive tokens from the escrow."),
+            )
+            .arg(
+                Arg::with_name("results_url")
+                    .long("results-url")
+                    .validator(is_url)
+                    .value_name("URL")
+                    .takes_value(true)
+                    .help("Final results URL [default: empty string]"),
+            )
+            .arg(
+                Arg::with_name("results_hash")
+                    .long("results-hash")
+                    .validator(is_hex)
+                    .value_name("HEX")
+                    .takes_value(true)
+                    .help("20-byte results SHA1 hash in hex format [default: 0-byte hash]"),
+            )
+            .arg(
+                Arg::with_name("verify")
+                    .long("verify")
+                    .takes_value(false)
+                    .help("Fetch the results and check it against --results-hash, or use its hash when no hash is given"),
+            )
+        )
+        .subcommand(SubCommand::with_name("store-intermediate").about("Appends intermediate results to the escrow results log, owner must be the escrow recording oracle")
+            .arg(
+                Arg::with_name("escrow")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ESCROW_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Escrow address"),
+            )
+            .arg(
+                Arg::with_name("results_url")
+                    .long("results-url")
+                    .validator(is_url)
+                    .value_name("URL")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Intermediate results URL"),
+            )
+            .arg(
+                Arg::with_name("results_hash")
+                    .long("results-hash")
+                    .validator(is_hex)
+                    .value_name("HEX")
+                    .takes_value(true)
+                    .help("20-byte results SHA1 hash in hex format [default: 0-byte hash]"),
+            )
+            .arg(
+                Arg::with_name("verify")
+                    .long("verify")
+                    .takes_value(false)
+                    .help("Fetch the results and check it against --results-hash, or use its hash when no hash is given"),
+            )
+        )
+        .subcommand(SubCommand::with_name("verify").about("Fetches the escrow manifest or final results and checks them against the stored hash")
+            .arg(
+                Arg::with_name("escrow")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ESCROW_ADDRESS")
+                    .takes_value(true)
+                    .required_unless_all(&["url", "hash"])
+                    .help("Escrow address"),
+            )
+            .arg(
+                Arg::with_name("results")
+                    .long("results")
+                    .takes_value(false)
+                    .help("Verify the final results instead of the manifest"),
+            )
+            .arg(
+                Arg::with_name("url")
+                    .long("data-url")
+                    .value_name("URL")
+                    .takes_value(true)
+                    .help("Local file path, file:// or HTTP URL to fetch [default: escrow URL]"),
+            )
+            .arg(
+                Arg::with_name("hash")
+                    .long("hash")
+                    .validator(is_hex)
+                    .value_name("HEX")
+                    .takes_value(true)
+                    .help("20-byte expected hash in hex format [default: escrow hash]"),
+            )
+        )
+        .subcommand(SubCommand::with_name("hash").about("Computes the manifest or results hash of a local file or URL")
+            .arg(
+                Arg::with_name("source")
+                    .index(1)
+                    .value_name("PATH_OR_URL")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Local file path, file:// or HTTP URL"),
+            )
+        )
+        .subcommand(SubCommand::with_name("results-log").about("Shows the escrow results log and verifies it against the final results")
+            .arg(
+                Arg::with_name("escrow")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ESCROW_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Escrow address"),
+            )
+        )
+        .subcommand(SubCommand::with_name("payout").about("Pays tokens from the escrow account")
+            .arg(
+                Arg::with_name("escrow")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ESCROW_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Escrow address"),
+            )
+            .arg(
+                Arg::with_name("file_name")
+                    .validator(is_parsable::<String>)
+                    .index(2)
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .required(true)
+                    .help("CSV file with recipients and amounts, <address>,<amount> on each line"),
+            )
+            .arg(
+                Arg::with_name("resume")
+                    .long("resume")
+                    .validator(is_parsable::<String>)
+                    .value_name("JOURNAL")
+                    .takes_value(true)
+                    .help("Resume an interrupted payout from its journal, skipping recipients already paid [default: new journal FILE.journal]"),
+            )
+            .arg(
+                Arg::with_name("dry_run")
+                    .long("dry-run")
+                    .takes_value(false)
+                    .help("Validate the payout file and print the fee breakdown without signing anything"),
+            )
+        )
+        .subcommand(SubCommand::with_name("reputation").about("Updates reputation of the escrow workers, owner must be the escrow reputation oracle")
+            .arg(
+                Arg::with_name("escrow")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ESCROW_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Escrow address"),
+            )
+            .arg(
+                Arg::with_name("file_name")
+                    .validator(is_parsable::<String>)
+                    .index(2)
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .required(true)
+                    .help("CSV file with workers and score changes, <address>,<delta> on each line"),
+            )
+        )
+        .subcommand(SubCommand::with_name("reputation-info").about("Shows worker reputation")
+            .arg(
+                Arg::with_name("worker")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("WORKER_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Worker address"),
+            )
         )
-        .arg(
-            Arg::with_name("fee_payer")
-                .long("fee-payer")
-                .value_name("KEYPAIR")
-                .validator(is_keypair)
-                .takes_value(true)
-                .help(
-                    "Specify the fee-payer account. \
-                     This may be a keypair file, the ASK keyword. \
-                     Defaults to the client keypair.",
-                ),
+        .subcommand(SubCommand::with_name("allow-list-create").about("Creates payout recipient allow-list, the owner becomes its authority")
+            .arg(
+                Arg::with_name("capacity")
+                    .long("capacity")
+                    .validator(is_parsable::<usize>)
+                    .value_name("COUNT")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Maximum number of owners the allow-list can hold"),
+            )
         )
-        .subcommand(SubCommand::with_name("create").about("Create a new escrow")
+        .subcommand(SubCommand::with_name("allow-list-update").about("Adds and removes allow-list owners, owner must be the allow-list authority")
             .arg(
-                Arg::with_name("mint")
-                    .long("mint")
+                Arg::with_name("allow_list")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ALLOW_LIST_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Allow-list address"),
+            )
+            .arg(
+                Arg::with_name("add")
+                    .long("add")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Recipient owner to add, may be specified multiple times"),
+            )
+            .arg(
+                Arg::with_name("remove")
+                    .long("remove")
                     .validator(is_pubkey)
                     .value_name("ADDRESS")
                     .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Recipient owner to remove, may be specified multiple times"),
+            )
+        )
+        .subcommand(SubCommand::with_name("allow-list-info").about("Shows allow-list authority and owners")
+            .arg(
+                Arg::with_name("allow_list")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ALLOW_LIST_ADDRESS")
+                    .takes_value(true)
                     .required(true)
-                    .help("Mint address for the token managed by this escrow"),
+                    .help("Allow-list address"),
             )
+        )
+        .subcommand(SubCommand::with_name("template-create").about("Creates escrow template for launching escrows in one step, the owner becomes the template authority")
             .arg(
-                Arg::with_name("launcher")
-                    .long("launcher")
+                Arg::with_name("mint")
+                    .long("mint")
                     .validator(is_pubkey)
                     .value_name("ADDRESS")
                     .takes_value(true)
-                    .help("Account which can manage the escrow [default: --owner]"),
+                    .required(true)
+                    .help("Mint address for the token managed by escrows created from the template"),
             )
             .arg(
                 Arg::with_name("canceler")
@@ -767,7 +4482,7 @@ fn main() {
                     .validator(is_pubkey)
                     .value_name("ADDRESS")
                     .takes_value(true)
-                    .help("Account which is able to cancel this escrow [default: --owner]"),
+                    .help("Account which is able to cancel escrows [default: --owner]"),
             )
             .arg(
                 Arg::with_name("canceler_token")
@@ -775,20 +4490,174 @@ fn main() {
                     .validator(is_pubkey)
                     .value_name("ADDRESS")
                     .takes_value(true)
-                    .help("Token account which can receive tokens specified by the --mint parameter [default: new token account owned by the --canceler]"),
+                    .required(true)
+                    .help("Token account which can receive tokens specified by the --mint parameter"),
+            )
+            .arg(
+                Arg::with_name("guardian")
+                    .long("guardian")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Account which is able to pause and unpause escrows [default: --owner]"),
             )
             .arg(
                 Arg::with_name("duration")
                     .long("duration")
+                    .validator(is_parsable::<u64>)
+                    .value_name("SECONDS")
+                    .takes_value(true)
+                    .required(true)
                     .short("d")
+                    .help("Escrow duration in seconds, counted from the escrow creation"),
+            )
+            .arg(
+                Arg::with_name("reputation_oracle")
+                    .long("reputation-oracle")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Escrow reputation oracle address [default: --owner]"),
+            )
+            .arg(
+                Arg::with_name("reputation_oracle_token")
+                    .long("reputation-oracle-token")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Reputation oracle token address"),
+            )
+            .arg(
+                Arg::with_name("reputation_oracle_stake")
+                    .long("reputation-oracle-stake")
+                    .validator(is_parsable::<u8>)
+                    .value_name("PERCENT")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Reputation oracle fee in payouts, from 0 to 100 percent"),
+            )
+            .arg(
+                Arg::with_name("recording_oracle")
+                    .long("recording-oracle")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Escrow recording oracle address [default: --owner]"),
+            )
+            .arg(
+                Arg::with_name("recording_oracle_token")
+                    .long("recording-oracle-token")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Recording oracle token address"),
+            )
+            .arg(
+                Arg::with_name("recording_oracle_stake")
+                    .long("recording-oracle-stake")
+                    .validator(is_parsable::<u8>)
+                    .value_name("PERCENT")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Recording oracle fee in payouts, from 0 to 100 percent"),
+            )
+            .arg(
+                Arg::with_name("max_payout")
+                    .long("max-payout")
+                    .validator(is_amount)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .help("Maximum amount of tokens a single recipient can be paid in total [default: no limit]"),
+            )
+            .arg(
+                Arg::with_name("min_payout")
+                    .long("min-payout")
+                    .validator(is_amount)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .help("Minimum amount of tokens a single payout must send [default: 0]"),
+            )
+            .arg(
+                Arg::with_name("cancellation_fee")
+                    .long("cancellation-fee")
+                    .validator(is_amount)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .conflicts_with("cancellation_fee_percent")
+                    .help("Fixed amount of tokens paid to the oracles on cancel [default: 0]"),
+            )
+            .arg(
+                Arg::with_name("cancellation_fee_percent")
+                    .long("cancellation-fee-percent")
+                    .validator(is_parsable::<u8>)
+                    .value_name("PERCENT")
+                    .takes_value(true)
+                    .help("Percent of the escrow balance paid to the oracles on cancel [default: 0]"),
+            )
+            .arg(
+                Arg::with_name("cancel_notice")
+                    .long("cancel-notice")
                     .validator(is_parsable::<u64>)
                     .value_name("SECONDS")
                     .takes_value(true)
+                    .help("Seconds after setup before the escrow can be canceled [default: 0]"),
+            )
+            .arg(
+                Arg::with_name("allow_list")
+                    .long("allow-list")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Allow-list of the payout recipient owners [default: any recipient]"),
+            )
+        )
+        .subcommand(SubCommand::with_name("template-info").about("Shows escrow template")
+            .arg(
+                Arg::with_name("template")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("TEMPLATE_ADDRESS")
+                    .takes_value(true)
                     .required(true)
-                    .help("Escrow duration in seconds, once this time passes escrow contract is no longer operational"),
+                    .help("Template address"),
             )
         )
-        .subcommand(SubCommand::with_name("info").about("Shows information about the escrow account")
+        .subcommand(SubCommand::with_name("create-from-template").about("Creates and sets up escrow from the template, owner must be the template authority")
+            .arg(
+                Arg::with_name("template")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("TEMPLATE_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Template address"),
+            )
+            .arg(
+                Arg::with_name("manifest_url")
+                    .long("manifest-url")
+                    .validator(is_url)
+                    .value_name("URL")
+                    .takes_value(true)
+                    .help("Job manifest URL [default: empty string]"),
+            )
+            .arg(
+                Arg::with_name("manifest_hash")
+                    .long("manifest-hash")
+                    .validator(is_hex)
+                    .value_name("HEX")
+                    .takes_value(true)
+                    .help("20-byte manifest SHA1 hash in hex format [default: 0-byte hash]"),
+            )
+            .arg(
+                Arg::with_name("verify")
+                    .long("verify")
+                    .takes_value(false)
+                    .help("Fetch the manifest and check it against --manifest-hash, or use its hash when no hash is given"),
+            )
+        )
+        .subcommand(SubCommand::with_name("vault-add").about("Adds a vault of another token mint to the launched escrow, owner must be the launcher or the canceler and pays for the vault account")
             .arg(
                 Arg::with_name("escrow")
                     .validator(is_pubkey)
@@ -798,8 +4667,25 @@ fn main() {
                     .required(true)
                     .help("Escrow address"),
             )
+            .arg(
+                Arg::with_name("token_mint")
+                    .long("mint")
+                    .validator(is_pubkey)
+                    .value_name("MINT_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Vault token mint"),
+            )
+            .arg(
+                Arg::with_name("canceler_token")
+                    .long("canceler-receiver")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Canceler token account of the vault mint [default: new account owned by the escrow canceler]"),
+            )
         )
-        .subcommand(SubCommand::with_name("setup").about("Configures and launches escrow")
+        .subcommand(SubCommand::with_name("vault-store-results").about("Stores the total amount to pay from the escrow vault, owner must be the launcher or the canceler")
             .arg(
                 Arg::with_name("escrow")
                     .validator(is_pubkey)
@@ -810,73 +4696,139 @@ fn main() {
                     .help("Escrow address"),
             )
             .arg(
-                Arg::with_name("reputation_oracle")
-                    .long("reputation-oracle")
+                Arg::with_name("token_mint")
+                    .long("mint")
                     .validator(is_pubkey)
-                    .value_name("ADDRESS")
+                    .value_name("MINT_ADDRESS")
                     .takes_value(true)
-                    .help("Escrow reputation oracle address [default: --owner]"),
+                    .required(true)
+                    .help("Vault token mint"),
             )
             .arg(
-                Arg::with_name("reputation_oracle_token")
-                    .long("reputation-oracle-token")
+                Arg::with_name("amount")
+                    .validator(is_amount)
+                    .index(2)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Total amount of vault tokens to pay"),
+            )
+        )
+        .subcommand(SubCommand::with_name("vault-payout").about("Pays vault tokens to a single recipient, oracle fees go to the oracles' token accounts of the vault mint")
+            .arg(
+                Arg::with_name("escrow")
                     .validator(is_pubkey)
-                    .value_name("ADDRESS")
+                    .index(1)
+                    .value_name("ESCROW_ADDRESS")
                     .takes_value(true)
-                    .help("Reputation oracle token address [default: new token account owned by the --reputation-oracle]"),
+                    .required(true)
+                    .help("Escrow address"),
             )
             .arg(
-                Arg::with_name("reputation_oracle_stake")
-                    .long("reputation-oracle-stake")
-                    .validator(is_parsable::<u8>)
-                    .value_name("PERCENT")
+                Arg::with_name("token_mint")
+                    .long("mint")
+                    .validator(is_pubkey)
+                    .value_name("MINT_ADDRESS")
                     .takes_value(true)
                     .required(true)
-                    .help("Reputation oracle fee in payouts, from 0 to 100 percent"),
+                    .help("Vault token mint"),
             )
             .arg(
-                Arg::with_name("recording_oracle")
-                    .long("recording-oracle")
+                Arg::with_name("recipient")
                     .validator(is_pubkey)
-                    .value_name("ADDRESS")
+                    .index(2)
+                    .value_name("TOKEN_ACCOUNT")
                     .takes_value(true)
-                    .help("Escrow recording oracle address [default: --owner]"),
+                    .required(true)
+                    .help("Recipient token account of the vault mint"),
+            )
+            .arg(
+                Arg::with_name("amount")
+                    .validator(is_amount)
+                    .index(3)
+                    .value_name("AMOUNT")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Amount of vault tokens to pay, including oracle fees"),
+            )
+        )
+        .subcommand(SubCommand::with_name("set-permit-signer").about("Sets the key signing payout permits of the escrow, the owner must be a trusted handler")
+            .arg(
+                Arg::with_name("escrow")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ESCROW_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Escrow address"),
+            )
+            .arg(
+                Arg::with_name("permit_signer")
+                    .validator(is_hex)
+                    .index(2)
+                    .value_name("ETH_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Ethereum address of the permit signing key in hex, as printed by the sign-permit command. \
+                        All zeros stop accepting permits"),
+            )
+        )
+        .subcommand(SubCommand::with_name("sign-permit").about("Signs payout permit with the secp256k1 key of the escrow permit signer")
+            .arg(
+                Arg::with_name("permit_key")
+                    .long("permit-key")
+                    .value_name("KEY_FILE")
+                    .takes_value(true)
+                    .required(true)
+                    .help("File with the secp256k1 secret key in hex"),
+            )
+            .arg(
+                Arg::with_name("escrow")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ESCROW_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Escrow address"),
             )
             .arg(
-                Arg::with_name("recording_oracle_token")
-                    .long("recording-oracle-token")
+                Arg::with_name("recipient")
                     .validator(is_pubkey)
-                    .value_name("ADDRESS")
+                    .index(2)
+                    .value_name("RECIPIENT_ADDRESS")
                     .takes_value(true)
-                    .help("Recording oracle token address [default: new token account owned by the --recording-oracle]"),
+                    .required(true)
+                    .help("Token account to receive the payout"),
             )
             .arg(
-                Arg::with_name("recording_oracle_stake")
-                    .long("recording-oracle-stake")
-                    .validator(is_parsable::<u8>)
-                    .value_name("PERCENT")
+                Arg::with_name("amount")
+                    .long("amount")
+                    .validator(is_parsable::<u64>)
+                    .value_name("AMOUNT")
                     .takes_value(true)
                     .required(true)
-                    .help("Recording oracle fee in payouts, from 0 to 100 percent"),
+                    .help("Amount to pay in the smallest token units, including oracle fees"),
             )
             .arg(
-                Arg::with_name("manifest_url")
-                    .long("manifest-url")
-                    .validator(is_url)
-                    .value_name("URL")
+                Arg::with_name("nonce")
+                    .long("nonce")
+                    .validator(is_parsable::<u64>)
+                    .value_name("NONCE")
                     .takes_value(true)
-                    .help("Job manifest URL [default: empty string]"),
+                    .required(true)
+                    .help("Escrow payout nonce, each permit must use the next nonce"),
             )
             .arg(
-                Arg::with_name("manifest_hash")
-                    .long("manifest-hash")
-                    .validator(is_hex)
-                    .value_name("HEX")
+                Arg::with_name("expires")
+                    .long("expires")
+                    .validator(is_parsable::<i64>)
+                    .value_name("TIMESTAMP")
                     .takes_value(true)
-                    .help("20-byte manifest SHA1 hash in hex format [default: 0-byte hash]"),
+                    .required(true)
+                    .help("Unix timestamp after which the permit can no longer be used"),
             )
         )
-        .subcommand(SubCommand::with_name("store-results").about("Stores results in the escrow")
+        .subcommand(SubCommand::with_name("payout-permit").about("Pays tokens from the escrow account using permit signed by the escrow permit signer")
             .arg(
                 Arg::with_name("escrow")
                     .validator(is_pubkey)
@@ -886,42 +4838,53 @@ fn main() {
                     .required(true)
                     .help("Escrow address"),
             )
+            .arg(
+                Arg::with_name("recipient")
+                    .validator(is_pubkey)
+                    .index(2)
+                    .value_name("RECIPIENT_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Token account to receive the payout"),
+            )
             .arg(
                 Arg::with_name("amount")
                     .long("amount")
-                    .validator(is_amount)
+                    .validator(is_parsable::<u64>)
                     .value_name("AMOUNT")
                     .takes_value(true)
                     .required(true)
-                    .help("Total amount to be sent out from the escrow."),
+                    .help("Amount to pay in the smallest token units, including oracle fees"),
             )
             .arg(
-                Arg::with_name("recipients")
-                    .long("recipients")
+                Arg::with_name("nonce")
+                    .long("nonce")
                     .validator(is_parsable::<u64>)
-                    .value_name("COUNT")
+                    .value_name("NONCE")
                     .takes_value(true)
                     .required(true)
-                    .help("Number of recipients to receive tokens from the escrow."),
+                    .help("Escrow payout nonce used in the permit"),
             )
             .arg(
-                Arg::with_name("results_url")
-                    .long("results-url")
-                    .validator(is_url)
-                    .value_name("URL")
+                Arg::with_name("expires")
+                    .long("expires")
+                    .validator(is_parsable::<i64>)
+                    .value_name("TIMESTAMP")
                     .takes_value(true)
-                    .help("Final results URL [default: empty string]"),
+                    .required(true)
+                    .help("Permit expiration timestamp"),
             )
             .arg(
-                Arg::with_name("results_hash")
-                    .long("results-hash")
+                Arg::with_name("signature")
+                    .long("signature")
                     .validator(is_hex)
-                    .value_name("HEX")
+                    .value_name("SIGNATURE")
                     .takes_value(true)
-                    .help("20-byte results SHA1 hash in hex format [default: 0-byte hash]"),
+                    .required(true)
+                    .help("Permit signature produced by the sign-permit command"),
             )
         )
-        .subcommand(SubCommand::with_name("payout").about("Pays tokens from the escrow account")
+        .subcommand(SubCommand::with_name("cancel").about("Cancels escrow, all remaining funds are returned to the canceler's token account")
             .arg(
                 Arg::with_name("escrow")
                     .validator(is_pubkey)
@@ -932,16 +4895,48 @@ fn main() {
                     .help("Escrow address"),
             )
             .arg(
-                Arg::with_name("file_name")
-                    .validator(is_parsable::<String>)
-                    .index(2)
-                    .value_name("FILE")
+                Arg::with_name("escrow_token_account")
+                    .long("escrow-token-account")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Escrow token account, used with --sign-only instead of reading the escrow"),
+            )
+            .arg(
+                Arg::with_name("canceler_token")
+                    .long("canceler-receiver")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Canceler token account, used with --sign-only instead of reading the escrow"),
+            )
+            .arg(
+                Arg::with_name("vault_mint")
+                    .long("vault-mint")
+                    .validator(is_pubkey)
+                    .value_name("MINT_ADDRESS")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Mint of an escrow vault to refund, repeat for every vault of the escrow"),
+            )
+            .nonce_args(false)
+            .offline_args_config(&SignOnlyNeedsEscrowAccounts {}),
+        )
+        .subcommand(SubCommand::with_name("pause").about("Pauses escrow, only unpause and cancel are allowed until it is unpaused")
+            .arg(
+                Arg::with_name("escrow")
+                    .validator(is_pubkey)
+                    .index(1)
+                    .value_name("ESCROW_ADDRESS")
                     .takes_value(true)
                     .required(true)
-                    .help("CSV file with recipients and amounts, <address>,<amount> on each line"),
+                    .help("Escrow address"),
             )
+            .nonce_args(false)
+            .offline_args(),
         )
-        .subcommand(SubCommand::with_name("cancel").about("Cancels escrow, all remaining funds are returned to the canceler's token account")
+        .subcommand(SubCommand::with_name("unpause").about("Unpauses escrow")
             .arg(
                 Arg::with_name("escrow")
                     .validator(is_pubkey)
@@ -951,6 +4946,8 @@ fn main() {
                     .required(true)
                     .help("Escrow address"),
             )
+            .nonce_args(false)
+            .offline_args(),
         )
         .subcommand(SubCommand::with_name("complete").about("Completes escrow")
             .arg(
@@ -962,6 +4959,68 @@ fn main() {
                     .required(true)
                     .help("Escrow address"),
             )
+            .nonce_args(false)
+            .offline_args(),
+        )
+        .subcommand(SubCommand::with_name("keeper").about("Completes paid escrows and cancels expired ones, owner must be their launcher or canceler. Only logs the actions unless --execute is set")
+            .arg(
+                Arg::with_name("launcher")
+                    .long("launcher")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Only keep escrows with this launcher [default: escrows launched or cancelable by --owner]"),
+            )
+            .arg(
+                Arg::with_name("canceler")
+                    .long("canceler")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Only keep escrows with this canceler [default: escrows launched or cancelable by --owner]"),
+            )
+            .arg(
+                Arg::with_name("complete_paid")
+                    .long("complete-paid")
+                    .takes_value(false)
+                    .help("Complete fully paid escrows"),
+            )
+            .arg(
+                Arg::with_name("cancel_expired")
+                    .long("cancel-expired")
+                    .takes_value(false)
+                    .help("Cancel expired escrows which are not fully paid, returning funds to the canceler"),
+            )
+            .arg(
+                Arg::with_name("cancel_grace")
+                    .long("cancel-grace")
+                    .validator(is_non_negative_seconds)
+                    .value_name("SECONDS")
+                    .takes_value(true)
+                    .help("Wait this long after the expiry before cancelling [default: 0]"),
+            )
+            .arg(
+                Arg::with_name("execute")
+                    .long("execute")
+                    .takes_value(false)
+                    .help("Send the transactions, without it the keeper only logs what it would do"),
+            )
+            .arg(
+                Arg::with_name("log")
+                    .long("log")
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .default_value("keeper-log.jsonl")
+                    .help("Action log, every action taken or skipped is appended as a JSON line"),
+            )
+            .arg(
+                Arg::with_name("interval")
+                    .long("interval")
+                    .validator(is_parsable::<u64>)
+                    .value_name("SECONDS")
+                    .takes_value(true)
+                    .help("Keep running and scan escrows with this interval [default: run once]"),
+            )
         )
         .get_matches();
 
@@ -975,9 +5034,13 @@ fn main() {
         let json_rpc_url = value_t!(matches, "json_rpc_url", String)
             .unwrap_or_else(|_| cli_config.json_rpc_url.clone());
 
+        // Offline signing arguments belong to the subcommand, so do presigned `--signer` values
+        let (_, sub_matches) = matches.subcommand();
+        let sub_matches = sub_matches.unwrap();
+
         let owner = signer_from_path(
-            &matches,
-            &cli_config.keypair_path,
+            sub_matches,
+            matches.value_of("owner").unwrap_or(&cli_config.keypair_path),
             "owner",
             &mut wallet_manager,
         )
@@ -986,8 +5049,10 @@ fn main() {
             exit(1);
         });
         let fee_payer = signer_from_path(
-            &matches,
-            &cli_config.keypair_path,
+            sub_matches,
+            matches
+                .value_of("fee_payer")
+                .unwrap_or(&cli_config.keypair_path),
             "fee_payer",
             &mut wallet_manager,
         )
@@ -995,7 +5060,26 @@ fn main() {
             eprintln!("error: {}", e);
             exit(1);
         });
+        let nonce_account = pubkey_of_signer(sub_matches, NONCE_ARG.name, &mut wallet_manager)
+            .unwrap_or_else(|e| {
+                eprintln!("error: {}", e);
+                exit(1);
+            });
+        let (nonce_authority, _) =
+            signer_of(sub_matches, NONCE_AUTHORITY_ARG.name, &mut wallet_manager)
+                .unwrap_or_else(|e| {
+                    eprintln!("error: {}", e);
+                    exit(1);
+                });
+        let blockhash_query = BlockhashQuery::new_from_matches(sub_matches);
+        let sign_only = sub_matches.is_present(SIGN_ONLY_ARG.name);
         let verbose = matches.is_present("verbose");
+        let hash_algorithm = value_t_or_exit!(matches, "hash_algorithm", HashAlgorithm);
+        let output_format = match matches.value_of("output_format") {
+            Some("json") => OutputFormat::Json,
+            Some("json-compact") => OutputFormat::JsonCompact,
+            _ => OutputFormat::Display,
+        };
 
         Config {
             rpc_client: RpcClient::new(json_rpc_url),
@@ -1003,31 +5087,157 @@ fn main() {
             owner,
             fee_payer,
             commitment_config: CommitmentConfig::single(),
+            output_format,
+            nonce_account,
+            nonce_authority,
+            blockhash_query,
+            sign_only,
+            hash_algorithm,
         }
     };
 
     solana_logger::setup_with_default("solana=info");
 
     let _ = match matches.subcommand() {
+        ("init-config", Some(arg_matches)) => {
+            let max_oracle_stake = value_t_or_exit!(arg_matches, "max_oracle_stake", u8);
+            let min_duration = value_t_or_exit!(arg_matches, "min_duration", u64);
+            let max_duration = value_t_or_exit!(arg_matches, "max_duration", u64);
+            let allowed_mints: Vec<Pubkey> =
+                pubkeys_of(arg_matches, "allowed_mint").unwrap_or_default();
+            command_init_config(
+                &config,
+                max_oracle_stake,
+                min_duration,
+                max_duration,
+                &allowed_mints,
+            )
+        }
+        ("update-config", Some(arg_matches)) => {
+            let admin: Option<Pubkey> = pubkey_of(arg_matches, "admin");
+            let paused = if arg_matches.is_present("pause") {
+                Some(true)
+            } else if arg_matches.is_present("unpause") {
+                Some(false)
+            } else {
+                None
+            };
+            let max_oracle_stake: Option<u8> = value_of(arg_matches, "max_oracle_stake");
+            let min_duration: Option<u64> = value_of(arg_matches, "min_duration");
+            let max_duration: Option<u64> = value_of(arg_matches, "max_duration");
+            let allowed_mints: Option<Vec<Pubkey>> = if arg_matches.is_present("any_mint") {
+                Some(vec![])
+            } else {
+                pubkeys_of(arg_matches, "allowed_mint")
+            };
+            command_update_config(
+                &config,
+                &admin,
+                paused,
+                max_oracle_stake,
+                min_duration,
+                max_duration,
+                &allowed_mints,
+            )
+        }
+        ("config-info", Some(_arg_matches)) => command_config_info(&config),
+        ("staking-config", Some(arg_matches)) => {
+            let stake_mint: Option<Pubkey> = pubkey_of(arg_matches, "stake_mint");
+            let min_oracle_stake = value_t_or_exit!(arg_matches, "min_oracle_stake", f64);
+            let threshold = value_t_or_exit!(arg_matches, "threshold", f64);
+            let arbiter: Option<Pubkey> = pubkey_of(arg_matches, "arbiter");
+            command_update_staking_config(
+                &config,
+                &stake_mint,
+                min_oracle_stake,
+                threshold,
+                &arbiter,
+            )
+        }
+        ("stake-create", Some(_arg_matches)) => command_stake_create(&config),
+        ("stake-info", Some(arg_matches)) => {
+            let oracle: Option<Pubkey> = pubkey_of(arg_matches, "oracle");
+            command_stake_info(&config, &oracle)
+        }
+        ("stake-deposit", Some(arg_matches)) => {
+            let amount = value_t_or_exit!(arg_matches, "amount", f64);
+            let from: Pubkey = pubkey_of(arg_matches, "from").unwrap();
+            command_stake_deposit(&config, amount, &from)
+        }
+        ("stake-withdraw", Some(arg_matches)) => {
+            let amount = value_t_or_exit!(arg_matches, "amount", f64);
+            let to: Pubkey = pubkey_of(arg_matches, "to").unwrap();
+            command_stake_withdraw(&config, amount, &to)
+        }
+        ("slash", Some(arg_matches)) => {
+            let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
+            let oracle: Pubkey = pubkey_of(arg_matches, "oracle").unwrap();
+            let amount = value_t_or_exit!(arg_matches, "amount", f64);
+            let to: Option<Pubkey> = pubkey_of(arg_matches, "to");
+            command_slash(&config, &escrow, &oracle, amount, &to)
+        }
         ("create", Some(arg_matches)) => {
             let mint: Pubkey = pubkey_of(arg_matches, "mint").unwrap();
             let launcher: Option<Pubkey> = pubkey_of(arg_matches, "launcher");
             let canceler: Option<Pubkey> = pubkey_of(arg_matches, "canceler");
             let canceler_token: Option<Pubkey> = pubkey_of(arg_matches, "canceler_token");
+            let guardian: Option<Pubkey> = pubkey_of(arg_matches, "guardian");
             let duration = value_t_or_exit!(arg_matches, "duration", u64);
+            let mut signer_or_new = |name: &str| {
+                signer_of(arg_matches, name, &mut wallet_manager)
+                    .unwrap_or_else(|e| {
+                        eprintln!("error: {}", e);
+                        exit(1);
+                    })
+                    .0
+                    .unwrap_or_else(|| Box::new(Keypair::new()) as Box<dyn Signer>)
+            };
+            let escrow_account = signer_or_new("escrow_keypair");
+            let escrow_token_account = signer_or_new("escrow_token_keypair");
             command_create(
                 &config,
+                escrow_account,
+                escrow_token_account,
                 &mint,
                 &launcher,
                 &canceler,
                 &canceler_token,
+                &guardian,
                 duration,
             )
         }
+        ("fund", Some(arg_matches)) => {
+            let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
+            let amount = value_t_or_exit!(arg_matches, "amount", String);
+            let from: Option<Pubkey> = pubkey_of(arg_matches, "from");
+            command_fund(&config, &escrow, &amount, &from)
+        }
+        ("status", Some(arg_matches)) => {
+            let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
+            command_status(&config, &escrow)
+        }
         ("info", Some(arg_matches)) => {
             let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
             command_info(&config, &escrow)
         }
+        ("list", Some(arg_matches)) => {
+            let state = match arg_matches.value_of("state") {
+                Some("launched") => Some(EscrowState::Launched),
+                Some("pending") => Some(EscrowState::Pending),
+                Some("partial") => Some(EscrowState::Partial),
+                Some("paid") => Some(EscrowState::Paid),
+                Some("complete") => Some(EscrowState::Complete),
+                Some("cancelled") => Some(EscrowState::Cancelled),
+                _ => None,
+            };
+            let filter = EscrowFilter {
+                launcher: pubkey_of(arg_matches, "launcher"),
+                canceler: pubkey_of(arg_matches, "canceler"),
+                token_mint: pubkey_of(arg_matches, "mint"),
+                state,
+            };
+            command_list(&config, &filter)
+        }
         ("setup", Some(arg_matches)) => {
             let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
             let reputation_oracle: Option<Pubkey> = pubkey_of(arg_matches, "reputation_oracle");
@@ -1042,6 +5252,14 @@ fn main() {
                 value_t_or_exit!(arg_matches, "recording_oracle_stake", u8);
             let manifest_url: String = value_of(arg_matches, "manifest_url").unwrap_or_default();
             let manifest_hash: Option<String> = value_of(arg_matches, "manifest_hash");
+            let max_payout: Option<String> = value_of(arg_matches, "max_payout");
+            let min_payout: Option<String> = value_of(arg_matches, "min_payout");
+            let cancellation_fee: Option<String> = value_of(arg_matches, "cancellation_fee");
+            let cancellation_fee_percent: u8 =
+                value_of(arg_matches, "cancellation_fee_percent").unwrap_or(0);
+            let cancel_notice: u64 = value_of(arg_matches, "cancel_notice").unwrap_or(0);
+            let allow_list: Option<Pubkey> = pubkey_of(arg_matches, "allow_list");
+            let verify = arg_matches.is_present("verify");
             command_setup(
                 &config,
                 &escrow,
@@ -1053,6 +5271,13 @@ fn main() {
                 recording_oracle_stake,
                 &manifest_url,
                 &manifest_hash,
+                &max_payout,
+                &min_payout,
+                &cancellation_fee,
+                cancellation_fee_percent,
+                cancel_notice,
+                &allow_list,
+                verify,
             )
         }
         ("store-results", Some(arg_matches)) => {
@@ -1061,6 +5286,7 @@ fn main() {
             let recipients = value_t_or_exit!(arg_matches, "recipients", u64);
             let results_url: String = value_of(arg_matches, "results_url").unwrap_or_default();
             let results_hash: Option<String> = value_of(arg_matches, "results_hash");
+            let verify = arg_matches.is_present("verify");
             command_store_results(
                 &config,
                 &escrow,
@@ -1068,40 +5294,403 @@ fn main() {
                 recipients,
                 &results_url,
                 &results_hash,
+                verify,
             )
         }
+        ("store-intermediate", Some(arg_matches)) => {
+            let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
+            let results_url = value_t_or_exit!(arg_matches, "results_url", String);
+            let results_hash: Option<String> = value_of(arg_matches, "results_hash");
+            let verify = arg_matches.is_present("verify");
+            command_store_intermediate_results(
+                &config,
+                &escrow,
+                &results_url,
+                &results_hash,
+                verify,
+            )
+        }
+        ("verify", Some(arg_matches)) => {
+            let escrow: Option<Pubkey> = pubkey_of(arg_matches, "escrow");
+            let results = arg_matches.is_present("results");
+            let url: Option<String> = value_of(arg_matches, "url");
+            let hash: Option<String> = value_of(arg_matches, "hash");
+            command_verify(&config, &escrow, results, &url, &hash)
+        }
+        ("hash", Some(arg_matches)) => {
+            let source = value_t_or_exit!(arg_matches, "source", String);
+            command_hash(&config, &source)
+        }
+        ("results-log", Some(arg_matches)) => {
+            let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
+            command_results_log(&config, &escrow)
+        }
         ("payout", Some(arg_matches)) => {
             let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
             let file_name = value_t_or_exit!(arg_matches, "file_name", String);
-            command_payout(&config, &escrow, &file_name)
+            let resume: Option<String> = value_of(arg_matches, "resume");
+            let dry_run = arg_matches.is_present("dry_run");
+            command_payout(&config, &escrow, &file_name, &resume, dry_run)
+        }
+        ("reputation", Some(arg_matches)) => {
+            let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
+            let file_name = value_t_or_exit!(arg_matches, "file_name", String);
+            command_update_reputation(&config, &escrow, &file_name)
+        }
+        ("reputation-info", Some(arg_matches)) => {
+            let worker: Pubkey = pubkey_of(arg_matches, "worker").unwrap();
+            command_reputation_info(&config, &worker)
+        }
+        ("allow-list-create", Some(arg_matches)) => {
+            let capacity = value_t_or_exit!(arg_matches, "capacity", usize);
+            command_allow_list_create(&config, capacity)
+        }
+        ("allow-list-update", Some(arg_matches)) => {
+            let allow_list: Pubkey = pubkey_of(arg_matches, "allow_list").unwrap();
+            let add: Vec<Pubkey> = pubkeys_of(arg_matches, "add").unwrap_or_default();
+            let remove: Vec<Pubkey> = pubkeys_of(arg_matches, "remove").unwrap_or_default();
+            command_allow_list_update(&config, &allow_list, &add, &remove)
+        }
+        ("allow-list-info", Some(arg_matches)) => {
+            let allow_list: Pubkey = pubkey_of(arg_matches, "allow_list").unwrap();
+            command_allow_list_info(&config, &allow_list)
+        }
+        ("template-create", Some(arg_matches)) => {
+            let mint: Pubkey = pubkey_of(arg_matches, "mint").unwrap();
+            let canceler: Option<Pubkey> = pubkey_of(arg_matches, "canceler");
+            let canceler_token: Pubkey = pubkey_of(arg_matches, "canceler_token").unwrap();
+            let guardian: Option<Pubkey> = pubkey_of(arg_matches, "guardian");
+            let duration = value_t_or_exit!(arg_matches, "duration", u64);
+            let reputation_oracle: Option<Pubkey> = pubkey_of(arg_matches, "reputation_oracle");
+            let reputation_oracle_token: Pubkey =
+                pubkey_of(arg_matches, "reputation_oracle_token").unwrap();
+            let reputation_oracle_stake =
+                value_t_or_exit!(arg_matches, "reputation_oracle_stake", u8);
+            let recording_oracle: Option<Pubkey> = pubkey_of(arg_matches, "recording_oracle");
+            let recording_oracle_token: Pubkey =
+                pubkey_of(arg_matches, "recording_oracle_token").unwrap();
+            let recording_oracle_stake =
+                value_t_or_exit!(arg_matches, "recording_oracle_stake", u8);
+            let max_payout: Option<String> = value_of(arg_matches, "max_payout");
+            let min_payout: Option<String> = value_of(arg_matches, "min_payout");
+            let cancellation_fee: Option<String> = value_of(arg_matches, "cancellation_fee");
+            let cancellation_fee_percent: u8 =
+                value_of(arg_matches, "cancellation_fee_percent").unwrap_or(0);
+            let cancel_notice: u64 = value_of(arg_matches, "cancel_notice").unwrap_or(0);
+            let allow_list: Option<Pubkey> = pubkey_of(arg_matches, "allow_list");
+            command_template_create(
+                &config,
+                &mint,
+                &canceler,
+                &canceler_token,
+                &guardian,
+                duration,
+                &reputation_oracle,
+                &reputation_oracle_token,
+                reputation_oracle_stake,
+                &recording_oracle,
+                &recording_oracle_token,
+                recording_oracle_stake,
+                &max_payout,
+                &min_payout,
+                &cancellation_fee,
+                cancellation_fee_percent,
+                cancel_notice,
+                &allow_list,
+            )
+        }
+        ("template-info", Some(arg_matches)) => {
+            let template: Pubkey = pubkey_of(arg_matches, "template").unwrap();
+            command_template_info(&config, &template)
+        }
+        ("create-from-template", Some(arg_matches)) => {
+            let template: Pubkey = pubkey_of(arg_matches, "template").unwrap();
+            let manifest_url: String = value_of(arg_matches, "manifest_url").unwrap_or_default();
+            let manifest_hash: Option<String> = value_of(arg_matches, "manifest_hash");
+            let verify = arg_matches.is_present("verify");
+            command_create_from_template(&config, &template, &manifest_url, &manifest_hash, verify)
+        }
+        ("vault-add", Some(arg_matches)) => {
+            let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
+            let token_mint: Pubkey = pubkey_of(arg_matches, "token_mint").unwrap();
+            let canceler_token: Option<Pubkey> = pubkey_of(arg_matches, "canceler_token");
+            command_vault_add(&config, &escrow, &token_mint, &canceler_token)
+        }
+        ("vault-store-results", Some(arg_matches)) => {
+            let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
+            let token_mint: Pubkey = pubkey_of(arg_matches, "token_mint").unwrap();
+            let amount = value_t_or_exit!(arg_matches, "amount", f64);
+            command_vault_store_results(&config, &escrow, &token_mint, amount)
+        }
+        ("vault-payout", Some(arg_matches)) => {
+            let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
+            let token_mint: Pubkey = pubkey_of(arg_matches, "token_mint").unwrap();
+            let recipient: Pubkey = pubkey_of(arg_matches, "recipient").unwrap();
+            let amount = value_t_or_exit!(arg_matches, "amount", f64);
+            command_vault_payout(&config, &escrow, &token_mint, &recipient, amount)
+        }
+        ("set-permit-signer", Some(arg_matches)) => {
+            let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
+            let permit_signer =
+                hex::decode(arg_matches.value_of("permit_signer").unwrap()).unwrap();
+            if permit_signer.len() != ETH_ADDRESS_LEN {
+                eprintln!("error: Ethereum address must be {} bytes", ETH_ADDRESS_LEN);
+                exit(1);
+            }
+            let mut eth_address = [0; ETH_ADDRESS_LEN];
+            eth_address.copy_from_slice(&permit_signer);
+            command_set_permit_signer(&config, &escrow, &eth_address)
+        }
+        ("sign-permit", Some(arg_matches)) => {
+            let permit_key = read_permit_key(arg_matches.value_of("permit_key").unwrap())
+                .unwrap_or_else(|err| {
+                    eprintln!("error: {}", err);
+                    exit(1);
+                });
+            let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
+            let recipient: Pubkey = pubkey_of(arg_matches, "recipient").unwrap();
+            let amount = value_t_or_exit!(arg_matches, "amount", u64);
+            let nonce = value_t_or_exit!(arg_matches, "nonce", u64);
+            let expires = value_t_or_exit!(arg_matches, "expires", i64);
+            command_sign_permit(
+                &config,
+                &permit_key,
+                &escrow,
+                &recipient,
+                amount,
+                nonce,
+                expires,
+            )
+        }
+        ("payout-permit", Some(arg_matches)) => {
+            let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
+            let recipient: Pubkey = pubkey_of(arg_matches, "recipient").unwrap();
+            let amount = value_t_or_exit!(arg_matches, "amount", u64);
+            let nonce = value_t_or_exit!(arg_matches, "nonce", u64);
+            let expires = value_t_or_exit!(arg_matches, "expires", i64);
+            let signature = hex::decode(arg_matches.value_of("signature").unwrap()).unwrap();
+            command_payout_permit(
+                &config, &escrow, &recipient, amount, nonce, expires, &signature,
+            )
         }
         ("cancel", Some(arg_matches)) => {
             let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
-            command_cancel(&config, &escrow)
+            let escrow_token_account: Option<Pubkey> =
+                pubkey_of(arg_matches, "escrow_token_account");
+            let canceler_token: Option<Pubkey> = pubkey_of(arg_matches, "canceler_token");
+            let vault_mints: Vec<Pubkey> =
+                pubkeys_of(arg_matches, "vault_mint").unwrap_or_default();
+            command_cancel(
+                &config,
+                &escrow,
+                &escrow_token_account,
+                &canceler_token,
+                &vault_mints,
+            )
+        }
+        ("pause", Some(arg_matches)) => {
+            let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
+            command_set_paused(&config, &escrow, true)
+        }
+        ("unpause", Some(arg_matches)) => {
+            let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
+            command_set_paused(&config, &escrow, false)
         }
         ("complete", Some(arg_matches)) => {
             let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
             command_complete(&config, &escrow)
         }
+        ("keeper", Some(arg_matches)) => {
+            let launcher: Option<Pubkey> = pubkey_of(arg_matches, "launcher");
+            let canceler: Option<Pubkey> = pubkey_of(arg_matches, "canceler");
+            // Owner can complete and cancel escrows it launched and escrows it cancels
+            let filters = if launcher.is_none() && canceler.is_none() {
+                vec![
+                    EscrowFilter {
+                        launcher: Some(config.owner.pubkey()),
+                        ..EscrowFilter::default()
+                    },
+                    EscrowFilter {
+                        canceler: Some(config.owner.pubkey()),
+                        ..EscrowFilter::default()
+                    },
+                ]
+            } else {
+                vec![EscrowFilter {
+                    launcher,
+                    canceler,
+                    ..EscrowFilter::default()
+                }]
+            };
+            let policy = KeeperPolicy {
+                complete_paid: arg_matches.is_present("complete_paid"),
+                cancel_expired: arg_matches.is_present("cancel_expired"),
+                cancel_grace: value_of::<i64>(arg_matches, "cancel_grace").unwrap_or(0),
+                execute: arg_matches.is_present("execute"),
+            };
+            let log_file = value_t_or_exit!(arg_matches, "log", String);
+            let interval: Option<u64> = value_of(arg_matches, "interval");
+            command_keeper(&config, &filters, &policy, &log_file, interval)
+        }
         _ => unreachable!(),
     }
-    .and_then(|transaction| {
-        if let Some(transaction) = transaction {
-            // TODO: Upgrade to solana-client 1.3 and
-            // `send_and_confirm_transaction_with_spinner_and_commitment()` with single
-            // confirmation by default for better UX
-            let signature = config
-                .rpc_client
-                .send_and_confirm_transaction_with_spinner_and_commitment(
-                    &transaction,
-                    config.commitment_config,
-                )?;
-            println!("Signature: {}", signature);
+    .map(|output| {
+        if !output.is_empty() {
+            println!("{}", output);
         }
-        Ok(())
     })
     .map_err(|err| {
         eprintln!("{}", err);
         exit(1);
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_000_000;
+
+    fn policy() -> KeeperPolicy {
+        KeeperPolicy {
+            complete_paid: true,
+            cancel_expired: true,
+            cancel_grace: 60,
+            execute: true,
+        }
+    }
+
+    fn escrow(owner: &Pubkey, state: EscrowState, expires: i64) -> Escrow {
+        Escrow {
+            state,
+            expires,
+            launcher: *owner,
+            canceler: Pubkey::new_unique(),
+            ..Escrow::default()
+        }
+    }
+
+    fn no_balance() -> Result<u64, Error> {
+        panic!("balance must not be read")
+    }
+
+    #[test]
+    fn test_keeper_complete_paid() {
+        let owner = Pubkey::new_unique();
+        let paid = escrow(&owner, EscrowState::Paid, NOW + 10);
+        assert_eq!(
+            keeper_action(&owner, &policy(), &paid, NOW, no_balance).unwrap(),
+            Some(("complete", None))
+        );
+
+        let skipped = |policy: &KeeperPolicy, owner: &Pubkey, escrow_info: &Escrow| {
+            keeper_action(owner, policy, escrow_info, NOW, no_balance)
+                .unwrap()
+                .and_then(|(_, skipped)| skipped)
+        };
+        let mut manual = policy();
+        manual.complete_paid = false;
+        assert_eq!(
+            skipped(&manual, &owner, &paid).as_deref(),
+            Some("--complete-paid is not set")
+        );
+        assert_eq!(
+            skipped(&policy(), &Pubkey::new_unique(), &paid).as_deref(),
+            Some("owner is neither the launcher nor the canceler")
+        );
+        let paused = Escrow {
+            paused: true,
+            ..paid
+        };
+        assert_eq!(
+            skipped(&policy(), &owner, &paused).as_deref(),
+            Some("escrow is paused")
+        );
+        let expired = escrow(&owner, EscrowState::Paid, NOW - 10);
+        assert_eq!(
+            skipped(&policy(), &owner, &expired).as_deref(),
+            Some("escrow is expired")
+        );
+    }
+
+    #[test]
+    fn test_keeper_cancel_expired() {
+        let owner = Pubkey::new_unique();
+        let expired = escrow(&owner, EscrowState::Pending, NOW - 100);
+        assert_eq!(
+            keeper_action(&owner, &policy(), &expired, NOW, || Ok(10)).unwrap(),
+            Some(("cancel", None))
+        );
+
+        // Still within the grace period
+        let grace = escrow(&owner, EscrowState::Partial, NOW - 30);
+        assert_eq!(
+            keeper_action(&owner, &policy(), &grace, NOW, no_balance).unwrap(),
+            None
+        );
+
+        let skipped =
+            |policy: &KeeperPolicy, owner: &Pubkey, escrow_info: &Escrow, balance: u64| {
+                keeper_action(owner, policy, escrow_info, NOW, || Ok(balance))
+                    .unwrap()
+                    .and_then(|(_, skipped)| skipped)
+            };
+        let mut manual = policy();
+        manual.cancel_expired = false;
+        assert_eq!(
+            skipped(&manual, &owner, &expired, 10).as_deref(),
+            Some("--cancel-expired is not set")
+        );
+        assert_eq!(
+            skipped(&policy(), &Pubkey::new_unique(), &expired, 10).as_deref(),
+            Some("owner is neither the launcher nor the canceler")
+        );
+        let notice = Escrow {
+            cancel_notice_end: NOW + 10,
+            ..expired
+        };
+        assert_eq!(
+            skipped(&policy(), &owner, &notice, 10).as_deref(),
+            Some("cancel notice period is not over")
+        );
+        assert_eq!(
+            skipped(&policy(), &owner, &expired, 0).as_deref(),
+            Some("escrow and vault token accounts are empty")
+        );
+
+        // The canceler is trusted too
+        let canceler = expired.canceler;
+        assert_eq!(skipped(&policy(), &canceler, &expired, 10), None);
+    }
+
+    #[test]
+    fn test_keeper_nothing_to_do() {
+        let owner = Pubkey::new_unique();
+        for state in &[
+            EscrowState::Launched,
+            EscrowState::Pending,
+            EscrowState::Partial,
+        ] {
+            let active = escrow(&owner, *state, NOW + 10);
+            assert_eq!(
+                keeper_action(&owner, &policy(), &active, NOW, no_balance).unwrap(),
+                None
+            );
+        }
+        for state in &[EscrowState::Complete, EscrowState::Cancelled] {
+            let done = escrow(&owner, *state, NOW - 100);
+            assert_eq!(
+                keeper_action(&owner, &policy(), &done, NOW, no_balance).unwrap(),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_non_negative_seconds() {
+        assert!(is_non_negative_seconds("0").is_ok());
+        assert!(is_non_negative_seconds("3600").is_ok());
+        assert!(is_non_negative_seconds("-1").is_err());
+        assert!(is_non_negative_seconds("soon").is_err());
+    }
+}
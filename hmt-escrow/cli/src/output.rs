@@ -0,0 +1,716 @@
+//! Structured command results, printed as text or JSON depending on `--output`
+
+use chrono::prelude::*;
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt};
+
+fn format_timestamp(timestamp: i64) -> String {
+    NaiveDateTime::from_timestamp(timestamp, 0)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+fn format_duration(seconds: i64) -> String {
+    let seconds = seconds.abs();
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m {}s", minutes, seconds % 60)
+    }
+}
+
+fn format_optional(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or("None")
+}
+
+/// Signature of the sent transaction and the accounts it created
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliTransaction {
+    /// Created accounts by their role, e.g. `escrow` or `escrowTokenAccount`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub created_accounts: BTreeMap<&'static str, String>,
+    pub signature: String,
+}
+
+impl fmt::Display for CliTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Created accounts are already reported while the transaction is built
+        write!(f, "Signature: {}", self.signature)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliOracle {
+    pub account: Option<String>,
+    pub token_account: Option<String>,
+    pub fee: u8,
+}
+
+impl CliOracle {
+    fn write(&self, f: &mut fmt::Formatter, name: &str) -> fmt::Result {
+        writeln!(f, "{}", name)?;
+        writeln!(f, "{}", "=".repeat(name.len()))?;
+        writeln!(f, "Account: {}", format_optional(&self.account))?;
+        writeln!(f, "Token account: {}", format_optional(&self.token_account))?;
+        writeln!(f, "Fee: {}%", self.fee)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliEscrow {
+    pub address: String,
+    pub state: String,
+    pub expires: i64,
+    pub token_mint: String,
+    pub token_account: String,
+    pub launcher: String,
+    pub canceler: String,
+    pub canceler_token_account: String,
+    pub guardian: String,
+    pub paused: bool,
+    pub reputation_oracle: CliOracle,
+    pub recording_oracle: CliOracle,
+    pub manifest_url: String,
+    pub manifest_hash: String,
+    pub final_results_url: String,
+    pub final_results_hash: String,
+    pub results_log_count: u64,
+    pub results_log_head: String,
+    pub total_amount: String,
+    pub sent_amount: String,
+    pub total_recipients: u64,
+    pub sent_recipients: u64,
+    pub payout_nonce: u64,
+    /// Ethereum address of the permit signing key, none when permits are not accepted
+    pub permit_signer: Option<String>,
+    pub max_payout_per_recipient: String,
+    pub min_payout: String,
+    pub allow_list: Option<String>,
+    pub vault_count: u8,
+    pub unpaid_vaults: u8,
+    pub cancellation_fee: String,
+    pub cancel_notice_end: Option<i64>,
+}
+
+impl fmt::Display for CliEscrow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Escrow information")?;
+        writeln!(f, "==================")?;
+        writeln!(f, "State: {}", self.state)?;
+        writeln!(f, "Expires: {}", format_timestamp(self.expires))?;
+        writeln!(f, "Token mint: {}", self.token_mint)?;
+        writeln!(f, "Token account: {}", self.token_account)?;
+        writeln!(f, "Launcher: {}", self.launcher)?;
+        writeln!(f, "Canceler: {}", self.canceler)?;
+        writeln!(f, "Canceler token account: {}", self.canceler_token_account)?;
+        writeln!(f, "Guardian: {}", self.guardian)?;
+        writeln!(f, "Paused: {}", self.paused)?;
+        writeln!(f)?;
+        self.reputation_oracle.write(f, "Reputation oracle")?;
+        writeln!(f)?;
+        self.recording_oracle.write(f, "Recording oracle")?;
+        writeln!(f)?;
+        writeln!(f, "Data")?;
+        writeln!(f, "====")?;
+        writeln!(f, "Job manifest URL: {}", self.manifest_url)?;
+        writeln!(f, "Job manifest hash: {}", self.manifest_hash)?;
+        writeln!(f, "Final results URL: {}", self.final_results_url)?;
+        writeln!(f, "Final results hash: {}", self.final_results_hash)?;
+        writeln!(
+            f,
+            "Intermediate results: {} (log head {})",
+            self.results_log_count, self.results_log_head
+        )?;
+        writeln!(f)?;
+        writeln!(f, "Amounts and recipients")?;
+        writeln!(f, "======================")?;
+        writeln!(
+            f,
+            "Amount: {} ({} sent)",
+            self.total_amount, self.sent_amount
+        )?;
+        writeln!(
+            f,
+            "Recipients: {} ({} sent)",
+            self.total_recipients, self.sent_recipients
+        )?;
+        writeln!(
+            f,
+            "Payout per recipient: {} minimum, {} maximum",
+            self.min_payout, self.max_payout_per_recipient
+        )?;
+        writeln!(
+            f,
+            "Recipient allow-list: {}",
+            format_optional(&self.allow_list)
+        )?;
+        writeln!(
+            f,
+            "Vaults: {} ({} unpaid)",
+            self.vault_count, self.unpaid_vaults
+        )?;
+        writeln!(f, "Cancellation fee: {}", self.cancellation_fee)?;
+        if let Some(cancel_notice_end) = self.cancel_notice_end {
+            writeln!(
+                f,
+                "Cancel notice ends: {}",
+                format_timestamp(cancel_notice_end)
+            )?;
+        }
+        writeln!(
+            f,
+            "Payout permit signer: {}",
+            format_optional(&self.permit_signer)
+        )?;
+        write!(f, "Payout permit nonce: {}", self.payout_nonce)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliEscrowSummary {
+    pub address: String,
+    pub state: String,
+    pub token_mint: String,
+    pub launcher: String,
+    pub expires: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliEscrowList {
+    pub escrows: Vec<CliEscrowSummary>,
+}
+
+impl fmt::Display for CliEscrowList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.escrows.is_empty() {
+            return write!(f, "No escrows found");
+        }
+        writeln!(
+            f,
+            "{:<44} {:<10} {:<44} {:<44} Expires",
+            "Escrow", "State", "Token mint", "Launcher"
+        )?;
+        for escrow in self.escrows.iter() {
+            writeln!(
+                f,
+                "{:<44} {:<10} {:<44} {:<44} {}",
+                escrow.address,
+                escrow.state,
+                escrow.token_mint,
+                escrow.launcher,
+                format_timestamp(escrow.expires)
+            )?;
+        }
+        write!(f, "Found {} escrows", self.escrows.len())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliResultsLogEntry {
+    pub index: usize,
+    pub timestamp: i64,
+    pub url: String,
+    pub hash: String,
+    pub signer: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliResultsLog {
+    pub address: String,
+    pub entries: Vec<CliResultsLogEntry>,
+    pub head: String,
+    /// Number of entries committed by the final results, zero before they are stored
+    pub committed_count: u64,
+}
+
+impl fmt::Display for CliResultsLog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Results log {}", self.address)?;
+        writeln!(f, "==============")?;
+        for entry in self.entries.iter() {
+            writeln!(
+                f,
+                "{}: {} {} {} by {}",
+                entry.index,
+                format_timestamp(entry.timestamp),
+                entry.url,
+                entry.hash,
+                entry.signer,
+            )?;
+        }
+        write!(f, "Head: {}", self.head)?;
+        if self.committed_count != 0 {
+            write!(
+                f,
+                "\nFinal results commit to the first {} entries",
+                self.committed_count
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliPayoutRecipient {
+    pub line: u64,
+    pub recipient: String,
+    pub amount: String,
+    pub reputation_fee: String,
+    pub recording_fee: String,
+    pub net: String,
+    /// Signature of the confirmed payout transaction, none until paid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliPayout {
+    pub escrow: String,
+    pub dry_run: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub journal: Option<String>,
+    pub recipients: Vec<CliPayoutRecipient>,
+    pub total_amount: String,
+    pub reputation_fee: String,
+    pub reputation_oracle_stake: u8,
+    pub recording_fee: String,
+    pub recording_oracle_stake: u8,
+    pub net: String,
+    pub remaining_amount: String,
+    pub remaining_recipients: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for CliPayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.recipients.is_empty() {
+            return write!(f, "No recipients left to pay");
+        }
+        writeln!(
+            f,
+            "{:<6} {:<44} {:>20} {:>20} {:>20} {:>20}",
+            "Line", "Recipient", "Amount", "Reputation fee", "Recording fee", "Net"
+        )?;
+        for recipient in self.recipients.iter() {
+            writeln!(
+                f,
+                "{:<6} {:<44} {:>20} {:>20} {:>20} {:>20}",
+                recipient.line,
+                recipient.recipient,
+                recipient.amount,
+                recipient.reputation_fee,
+                recipient.recording_fee,
+                recipient.net,
+            )?;
+        }
+        writeln!(
+            f,
+            "Sending {} to {} recipients, {} left in the escrow for {} recipients",
+            self.total_amount,
+            self.recipients.len(),
+            self.remaining_amount,
+            self.remaining_recipients
+        )?;
+        writeln!(
+            f,
+            "Reputation oracle fee: {} ({}%)",
+            self.reputation_fee, self.reputation_oracle_stake
+        )?;
+        writeln!(
+            f,
+            "Recording oracle fee: {} ({}%)",
+            self.recording_fee, self.recording_oracle_stake
+        )?;
+        write!(f, "Recipients receive: {}", self.net)?;
+        if let Some(journal) = &self.journal {
+            write!(f, "\nPayout journal: {}", journal)?;
+        }
+        if self.dry_run && self.problems.is_empty() {
+            write!(f, "\nDry run passed, nothing was signed")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliReputation {
+    pub address: String,
+    pub worker: String,
+    pub score: i64,
+    pub jobs_completed: u64,
+    pub last_escrow: String,
+}
+
+impl fmt::Display for CliReputation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Reputation {}", self.address)?;
+        writeln!(f, "==============")?;
+        writeln!(f, "Worker: {}", self.worker)?;
+        writeln!(f, "Score: {}", self.score)?;
+        writeln!(f, "Jobs completed: {}", self.jobs_completed)?;
+        write!(f, "Last escrow: {}", self.last_escrow)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliPermit {
+    pub signer: String,
+    pub signature: String,
+}
+
+impl fmt::Display for CliPermit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Signer: {}", self.signer)?;
+        write!(f, "Permit signature: {}", self.signature)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliStakingConfig {
+    pub stake_mint: String,
+    pub min_oracle_stake: String,
+    pub stake_escrow_threshold: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliConfig {
+    pub address: String,
+    pub admin: String,
+    pub paused: bool,
+    pub max_oracle_stake: u8,
+    pub min_duration: u64,
+    pub max_duration: u64,
+    /// Empty when any mint is allowed
+    pub allowed_mints: Vec<String>,
+    /// None when oracle staking is disabled
+    pub staking: Option<CliStakingConfig>,
+    pub arbiter: Option<String>,
+}
+
+impl fmt::Display for CliConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Program config {}", self.address)?;
+        writeln!(f, "==============")?;
+        writeln!(f, "Admin: {}", self.admin)?;
+        writeln!(f, "Paused: {}", self.paused)?;
+        writeln!(f, "Max oracle stake: {}%", self.max_oracle_stake)?;
+        writeln!(f, "Min duration: {} seconds", self.min_duration)?;
+        writeln!(f, "Max duration: {} seconds", self.max_duration)?;
+        if self.allowed_mints.is_empty() {
+            writeln!(f, "Allowed mints: any")?;
+        } else {
+            writeln!(f, "Allowed mints:")?;
+            for mint in self.allowed_mints.iter() {
+                writeln!(f, "  {}", mint)?;
+            }
+        }
+        match &self.staking {
+            Some(staking) => {
+                writeln!(f, "Stake mint: {}", staking.stake_mint)?;
+                writeln!(f, "Min oracle stake: {}", staking.min_oracle_stake)?;
+                writeln!(
+                    f,
+                    "Stake escrow threshold: {}",
+                    staking.stake_escrow_threshold
+                )?;
+            }
+            None => writeln!(f, "Oracle staking: disabled")?,
+        }
+        write!(f, "Arbiter: {}", format_optional(&self.arbiter))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliOracleStake {
+    pub address: String,
+    pub oracle: String,
+    pub vault: String,
+    pub amount: String,
+    /// Unix timestamp, zero when the stake is not locked
+    pub locked_until: i64,
+}
+
+impl fmt::Display for CliOracleStake {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Oracle stake {}", self.address)?;
+        writeln!(f, "==============")?;
+        writeln!(f, "Oracle: {}", self.oracle)?;
+        writeln!(f, "Vault: {}", self.vault)?;
+        write!(f, "Amount: {}", self.amount)?;
+        if self.locked_until != 0 {
+            write!(f, "\nLocked until: {}", format_timestamp(self.locked_until))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliAllowList {
+    pub address: String,
+    pub authority: String,
+    pub capacity: usize,
+    pub owners: Vec<String>,
+}
+
+impl fmt::Display for CliAllowList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Allow-list {}", self.address)?;
+        writeln!(f, "==========")?;
+        writeln!(f, "Authority: {}", self.authority)?;
+        write!(f, "Owners: {} of {}", self.owners.len(), self.capacity)?;
+        for owner in &self.owners {
+            write!(f, "\n  {}", owner)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliTemplate {
+    pub address: String,
+    pub authority: String,
+    pub token_mint: String,
+    pub canceler: String,
+    pub canceler_token_account: String,
+    pub guardian: String,
+    pub duration: u64,
+    pub reputation_oracle: CliOracle,
+    pub recording_oracle: CliOracle,
+    pub max_payout_per_recipient: String,
+    pub min_payout: String,
+    pub cancellation_fee: String,
+    pub cancel_notice: u64,
+    pub allow_list: Option<String>,
+}
+
+impl fmt::Display for CliTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Escrow template {}", self.address)?;
+        writeln!(f, "===============")?;
+        writeln!(f, "Authority: {}", self.authority)?;
+        writeln!(f, "Token mint: {}", self.token_mint)?;
+        writeln!(f, "Canceler: {}", self.canceler)?;
+        writeln!(f, "Canceler token account: {}", self.canceler_token_account)?;
+        writeln!(f, "Guardian: {}", self.guardian)?;
+        writeln!(f, "Duration: {}", format_duration(self.duration as i64))?;
+        writeln!(f)?;
+        self.reputation_oracle.write(f, "Reputation oracle")?;
+        writeln!(f)?;
+        self.recording_oracle.write(f, "Recording oracle")?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Payout per recipient: {} minimum, {} maximum",
+            self.min_payout, self.max_payout_per_recipient
+        )?;
+        writeln!(
+            f,
+            "Recipient allow-list: {}",
+            format_optional(&self.allow_list)
+        )?;
+        writeln!(f, "Cancellation fee: {}", self.cancellation_fee)?;
+        write!(
+            f,
+            "Cancel notice: {}",
+            format_duration(self.cancel_notice as i64)
+        )
+    }
+}
+
+/// Keeper action log entry, written as a JSON line for every action taken or skipped
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliKeeperEntry {
+    pub timestamp: i64,
+    /// Not set for `scan` entries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub escrow: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    /// `complete`, `cancel`, or `scan` when listing the escrows failed
+    pub action: String,
+    pub dry_run: bool,
+    /// Reason the action was not taken
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl fmt::Display for CliKeeperEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", format_timestamp(self.timestamp), self.action)?;
+        if let (Some(escrow), Some(state)) = (&self.escrow, &self.state) {
+            write!(f, " {} ({})", escrow, state)?;
+        }
+        write!(f, ": ")?;
+        match (&self.skipped, &self.error, &self.signature) {
+            (Some(reason), _, _) => write!(f, "skipped, {}", reason),
+            (None, Some(error), _) => write!(f, "failed, {}", error),
+            (None, None, Some(signature)) => write!(f, "sent {}", signature),
+            (None, None, None) => write!(f, "dry run"),
+        }
+    }
+}
+
+/// Whether an escrow operation is currently allowed, and why not if it is not
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliEscrowAction {
+    pub allowed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl CliEscrowAction {
+    /// Allowed unless one of the `(blocked, reason)` checks holds, the first one wins
+    pub fn new(checks: &[(bool, &str)]) -> Self {
+        let reason = checks
+            .iter()
+            .find(|(blocked, _)| *blocked)
+            .map(|(_, reason)| reason.to_string());
+        Self {
+            allowed: reason.is_none(),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for CliEscrowAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.reason {
+            None => write!(f, "allowed"),
+            Some(reason) => write!(f, "not allowed, {}", reason),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliEscrowStatus {
+    pub address: String,
+    pub state: String,
+    pub paused: bool,
+    pub expires: i64,
+    /// Seconds until expiry by the local clock, negative once expired
+    pub expires_in: i64,
+    pub balance: String,
+    /// Amount still to be paid, `total_amount - sent_amount`
+    pub remaining_amount: String,
+    pub remaining_recipients: u64,
+    /// Balance above the remaining amount, negative when the escrow is underfunded
+    pub surplus: String,
+    pub reputation_fee: String,
+    pub reputation_oracle_stake: u8,
+    pub recording_fee: String,
+    pub recording_oracle_stake: u8,
+    pub payout: CliEscrowAction,
+    pub cancel: CliEscrowAction,
+    pub complete: CliEscrowAction,
+}
+
+impl fmt::Display for CliEscrowStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Escrow status {}", self.address)?;
+        writeln!(f, "==============")?;
+        if self.paused {
+            writeln!(f, "State: {} (paused)", self.state)?;
+        } else {
+            writeln!(f, "State: {}", self.state)?;
+        }
+        if self.expires_in >= 0 {
+            writeln!(
+                f,
+                "Expires: {} (in {})",
+                format_timestamp(self.expires),
+                format_duration(self.expires_in)
+            )?;
+        } else {
+            writeln!(
+                f,
+                "Expired: {} ({} ago)",
+                format_timestamp(self.expires),
+                format_duration(self.expires_in)
+            )?;
+        }
+        writeln!(f, "Balance: {}", self.balance)?;
+        writeln!(
+            f,
+            "Left to pay: {} to {} recipients",
+            self.remaining_amount, self.remaining_recipients
+        )?;
+        writeln!(f, "Surplus: {}", self.surplus)?;
+        writeln!(
+            f,
+            "Projected reputation oracle fee: {} ({}%)",
+            self.reputation_fee, self.reputation_oracle_stake
+        )?;
+        writeln!(
+            f,
+            "Projected recording oracle fee: {} ({}%)",
+            self.recording_fee, self.recording_oracle_stake
+        )?;
+        writeln!(f, "Payout: {}", self.payout)?;
+        writeln!(f, "Cancel: {}", self.cancel)?;
+        write!(f, "Complete: {}", self.complete)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliDataHash {
+    pub source: String,
+    pub algorithm: String,
+    pub hash: String,
+}
+
+impl fmt::Display for CliDataHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.hash)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliDataVerification {
+    pub url: String,
+    pub algorithm: String,
+    pub expected: String,
+    pub actual: String,
+    pub verified: bool,
+}
+
+impl fmt::Display for CliDataVerification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "URL: {}", self.url)?;
+        writeln!(f, "Expected {} hash: {}", self.algorithm, self.expected)?;
+        writeln!(f, "Actual {} hash: {}", self.algorithm, self.actual)?;
+        if self.verified {
+            write!(f, "Hash matches")
+        } else {
+            write!(f, "Hash MISMATCH")
+        }
+    }
+}
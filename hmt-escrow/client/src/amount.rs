@@ -0,0 +1,118 @@
+//! Exact conversion between decimal token amounts and base units
+
+use thiserror::Error;
+
+/// Errors of the decimal token amount parsing
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum AmountError {
+    /// Amount is not a non-negative decimal number
+    #[error("Invalid amount")]
+    Invalid,
+
+    /// Amount has more decimal places than the mint, it would be rounded
+    #[error("Amount has more than {0} decimal places and would be rounded")]
+    Precision(u8),
+
+    /// Amount does not fit into base units
+    #[error("Amount is too large")]
+    Overflow,
+}
+
+/// Parses a decimal token amount into base units, rejecting any precision loss
+pub fn ui_amount_to_amount(value: &str, decimals: u8) -> Result<u64, AmountError> {
+    let value = value.trim();
+    let mut parts = value.splitn(2, '.');
+    let whole = parts.next().unwrap_or_default();
+    let fraction = parts.next().unwrap_or_default();
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+        return Err(AmountError::Invalid);
+    }
+
+    // Trailing zeros past the mint decimals do not lose anything
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(AmountError::Precision(decimals));
+    }
+
+    let scale = 10u64
+        .checked_pow(decimals as u32)
+        .ok_or(AmountError::Overflow)?;
+    let whole = if whole.is_empty() {
+        0
+    } else {
+        whole.parse::<u64>().map_err(|_| AmountError::Overflow)?
+    };
+    let fraction = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<u64>().map_err(|_| AmountError::Overflow)?
+            * 10u64.pow((decimals as usize - fraction.len()) as u32)
+    };
+    whole
+        .checked_mul(scale)
+        .and_then(|amount| amount.checked_add(fraction))
+        .ok_or(AmountError::Overflow)
+}
+
+/// Formats base units as a decimal token amount without floating point rounding
+pub fn amount_to_ui_amount(amount: u64, decimals: u8) -> String {
+    let decimals = decimals as usize;
+    let digits = format!("{:0>width$}", amount, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ui_amount_to_amount() {
+        assert_eq!(ui_amount_to_amount("1", 9), Ok(1_000_000_000));
+        assert_eq!(ui_amount_to_amount("0.1", 9), Ok(100_000_000));
+        assert_eq!(ui_amount_to_amount(".5", 2), Ok(50));
+        assert_eq!(ui_amount_to_amount("2.", 2), Ok(200));
+        assert_eq!(ui_amount_to_amount(" 1.230000000000 ", 2), Ok(123));
+        assert_eq!(ui_amount_to_amount("0.000000001", 9), Ok(1));
+        assert_eq!(ui_amount_to_amount("5", 0), Ok(5));
+
+        assert_eq!(
+            ui_amount_to_amount("0.0000000001", 9),
+            Err(AmountError::Precision(9))
+        );
+        assert_eq!(
+            ui_amount_to_amount("1.5", 0),
+            Err(AmountError::Precision(0))
+        );
+        assert_eq!(ui_amount_to_amount("", 9), Err(AmountError::Invalid));
+        assert_eq!(ui_amount_to_amount(".", 9), Err(AmountError::Invalid));
+        assert_eq!(ui_amount_to_amount("-1", 9), Err(AmountError::Invalid));
+        assert_eq!(ui_amount_to_amount("1e9", 9), Err(AmountError::Invalid));
+        assert_eq!(ui_amount_to_amount("1.2.3", 9), Err(AmountError::Invalid));
+        assert_eq!(
+            ui_amount_to_amount("18446744073.709551616", 9),
+            Err(AmountError::Overflow)
+        );
+        assert_eq!(
+            ui_amount_to_amount("18446744073.709551615", 9),
+            Ok(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_amount_to_ui_amount() {
+        assert_eq!(amount_to_ui_amount(1_000_000_000, 9), "1");
+        assert_eq!(amount_to_ui_amount(1, 9), "0.000000001");
+        assert_eq!(amount_to_ui_amount(123, 2), "1.23");
+        assert_eq!(amount_to_ui_amount(120, 2), "1.2");
+        assert_eq!(amount_to_ui_amount(0, 2), "0");
+        assert_eq!(amount_to_ui_amount(7, 0), "7");
+        assert_eq!(amount_to_ui_amount(u64::MAX, 9), "18446744073.709551615");
+    }
+}
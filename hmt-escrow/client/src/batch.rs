@@ -0,0 +1,68 @@
+//! Splitting of instruction lists into transactions that fit into a packet
+
+use solana_program::{instruction::Instruction, message::Message, pubkey::Pubkey};
+use solana_sdk::{packet::PACKET_DATA_SIZE, signature::Signature};
+use std::{mem, ops::Range};
+
+/// Serialized size of the signed transaction built from `instructions`
+pub fn transaction_size(instructions: &[Instruction], payer: &Pubkey) -> usize {
+    let message = Message::new(instructions, Some(payer));
+    let signatures = message.header.num_required_signatures as usize;
+    // Signature count is a short vector length, a single byte below 128 signatures
+    1 + signatures * mem::size_of::<Signature>() + message.serialize().len()
+}
+
+/// Splits `instructions` into consecutive ranges that each fit into one transaction.
+///
+/// An instruction too large for a transaction on its own still gets its own range,
+/// so the error surfaces when the transaction is sent.
+pub fn transaction_ranges(instructions: &[Instruction], payer: &Pubkey) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut start = 0;
+    while start < instructions.len() {
+        let mut end = start + 1;
+        while end < instructions.len()
+            && transaction_size(&instructions[start..=end], payer) <= PACKET_DATA_SIZE
+        {
+            end += 1;
+        }
+        ranges.push(start..end);
+        start = end;
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::instruction::AccountMeta;
+
+    #[test]
+    fn test_transaction_ranges() {
+        let payer = Pubkey::new_from_array([1; 32]);
+        let program_id = Pubkey::new_from_array([2; 32]);
+        let instructions: Vec<Instruction> = (0..40u8)
+            .map(|i| Instruction {
+                program_id,
+                accounts: vec![
+                    AccountMeta::new(Pubkey::new_from_array([3; 32]), false),
+                    AccountMeta::new(Pubkey::new_from_array([i + 10; 32]), false),
+                ],
+                data: vec![i; 9],
+            })
+            .collect();
+
+        let ranges = transaction_ranges(&instructions, &payer);
+        assert!(ranges.len() > 1);
+        assert_eq!(ranges.first().unwrap().start, 0);
+        assert_eq!(ranges.last().unwrap().end, instructions.len());
+        for pair in ranges.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+        for range in ranges {
+            assert!(transaction_size(&instructions[range], &payer) <= PACKET_DATA_SIZE);
+        }
+
+        assert!(transaction_ranges(&[], &payer).is_empty());
+    }
+}
@@ -0,0 +1,45 @@
+//! Error types
+
+use hmt_escrow::error::EscrowError;
+use solana_client::client_error::ClientError as RpcClientError;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_sdk::{signature::SignerError, transport::TransportError};
+use thiserror::Error;
+
+/// Errors that may be returned by the escrow client
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// Transaction failed to process
+    #[error("Transport error: {0}")]
+    Transport(#[from] TransportError),
+
+    /// RPC request failed
+    #[error("RPC error: {0}")]
+    Rpc(#[from] RpcClientError),
+
+    /// Banks request failed
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Instruction could not be created or account data could not be decoded
+    #[error("Program error: {0}")]
+    Program(#[from] ProgramError),
+
+    /// Transaction could not be signed
+    #[error("Signer error: {0}")]
+    Signer(#[from] SignerError),
+
+    /// Requested account does not exist
+    #[error("Account {0} not found")]
+    AccountNotFound(Pubkey),
+
+    /// Requested account is not owned by the expected program
+    #[error("Account {0} has the wrong owner")]
+    InvalidAccountOwner(Pubkey),
+}
+
+impl From<EscrowError> for ClientError {
+    fn from(e: EscrowError) -> Self {
+        ClientError::Program(e.into())
+    }
+}
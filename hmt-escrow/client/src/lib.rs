@@ -0,0 +1,833 @@
+#![deny(missing_docs)]
+
+//! Human Protocol Escrow Contract client library
+
+pub mod amount;
+pub mod batch;
+pub mod error;
+pub mod manifest;
+pub mod query;
+pub mod transport;
+
+pub use error::ClientError;
+pub use transport::Transport;
+
+use hmt_escrow::{
+    error::EscrowError,
+    instruction::{self, VaultRefund},
+    processor::Processor as EscrowProcessor,
+    state::{
+        AllowList, DataHash, DataUrl, Escrow, EscrowConfig, EscrowTemplate, EscrowVault,
+        OracleStake, RecipientPayout, Reputation,
+    },
+};
+use solana_program::{
+    instruction::Instruction, program_option::COption, program_pack::Pack, pubkey::Pubkey,
+    system_instruction,
+};
+use solana_sdk::{
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+use spl_token::state::Account as TokenAccount;
+
+/// Escrow program client, builds, signs and sends transactions through a `Transport`
+pub struct EscrowClient<T> {
+    transport: T,
+    program_id: Pubkey,
+    fee_payer: Box<dyn Signer>,
+}
+
+impl<T: Transport> EscrowClient<T> {
+    /// Creates new client, `fee_payer` pays for all transactions and new accounts
+    pub fn new(transport: T, program_id: Pubkey, fee_payer: Box<dyn Signer>) -> Self {
+        Self {
+            transport,
+            program_id,
+            fee_payer,
+        }
+    }
+
+    /// Underlying transport
+    pub fn transport(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Escrow program id
+    pub fn program_id(&self) -> &Pubkey {
+        &self.program_id
+    }
+
+    /// Fee payer pubkey
+    pub fn fee_payer(&self) -> Pubkey {
+        self.fee_payer.pubkey()
+    }
+
+    /// Escrow authority, owner of the escrow token account
+    pub fn authority_address(&self, escrow: &Pubkey) -> Pubkey {
+        EscrowProcessor::find_authority_bump_seed(&self.program_id, escrow).0
+    }
+
+    /// Program config address
+    pub fn config_address(&self) -> Pubkey {
+        EscrowProcessor::find_config_address(&self.program_id).0
+    }
+
+    /// Oracle stake address
+    pub fn oracle_stake_address(&self, oracle: &Pubkey) -> Pubkey {
+        EscrowProcessor::find_oracle_stake_address(&self.program_id, oracle).0
+    }
+
+    /// Worker reputation address
+    pub fn reputation_address(&self, worker: &Pubkey) -> Pubkey {
+        EscrowProcessor::find_reputation_address(&self.program_id, worker).0
+    }
+
+    /// Escrow results log address
+    pub fn results_log_address(&self, escrow: &Pubkey) -> Pubkey {
+        EscrowProcessor::find_results_log_address(&self.program_id, escrow).0
+    }
+
+    /// Fetches account data, fails if the account does not exist
+    pub async fn get_account_data(&mut self, address: &Pubkey) -> Result<Vec<u8>, ClientError> {
+        self.transport
+            .get_account(address)
+            .await?
+            .map(|account| account.data)
+            .ok_or(ClientError::AccountNotFound(*address))
+    }
+
+    /// Escrow vault address for the token mint
+    pub fn vault_address(&self, escrow: &Pubkey, token_mint: &Pubkey) -> Pubkey {
+        EscrowProcessor::find_vault_address(&self.program_id, escrow, token_mint).0
+    }
+
+    /// Address of the total paid by the escrow to the recipient token account owner
+    pub fn recipient_payout_address(&self, escrow: &Pubkey, owner: &Pubkey) -> Pubkey {
+        EscrowProcessor::find_recipient_payout_address(&self.program_id, escrow, owner).0
+    }
+
+    /// Fetches and decodes a packed account
+    pub async fn get_packed<P: Pack>(&mut self, address: &Pubkey) -> Result<P, ClientError> {
+        let data = self.get_account_data(address).await?;
+        Ok(P::unpack_from_slice(&data)?)
+    }
+
+    /// Fetches and decodes a program owned account
+    async fn get_program_account<P: Pack>(&mut self, address: &Pubkey) -> Result<P, ClientError> {
+        let account = self
+            .transport
+            .get_account(address)
+            .await?
+            .ok_or(ClientError::AccountNotFound(*address))?;
+        if account.owner != self.program_id {
+            return Err(ClientError::InvalidAccountOwner(*address));
+        }
+        Ok(P::unpack_from_slice(&account.data)?)
+    }
+
+    /// Fetches the total paid by the escrow to the recipient token account owner,
+    /// fails if the owner has not been paid yet
+    pub async fn get_recipient_payout(
+        &mut self,
+        escrow: &Pubkey,
+        owner: &Pubkey,
+    ) -> Result<RecipientPayout, ClientError> {
+        let address = self.recipient_payout_address(escrow, owner);
+        self.get_program_account(&address).await
+    }
+
+    /// Fetches the escrow
+    pub async fn get_escrow(&mut self, escrow: &Pubkey) -> Result<Escrow, ClientError> {
+        self.get_program_account(escrow).await
+    }
+
+    /// Fetches the escrow vault of the token mint
+    pub async fn get_vault(
+        &mut self,
+        escrow: &Pubkey,
+        token_mint: &Pubkey,
+    ) -> Result<EscrowVault, ClientError> {
+        let address = self.vault_address(escrow, token_mint);
+        self.get_program_account(&address).await
+    }
+
+    /// Fetches the program config
+    pub async fn get_config(&mut self) -> Result<EscrowConfig, ClientError> {
+        let address = self.config_address();
+        self.get_program_account(&address).await
+    }
+
+    /// Fetches the oracle stake
+    pub async fn get_oracle_stake(&mut self, oracle: &Pubkey) -> Result<OracleStake, ClientError> {
+        let address = self.oracle_stake_address(oracle);
+        self.get_program_account(&address).await
+    }
+
+    /// Fetches the worker reputation
+    pub async fn get_reputation(&mut self, worker: &Pubkey) -> Result<Reputation, ClientError> {
+        let address = self.reputation_address(worker);
+        self.get_program_account(&address).await
+    }
+
+    /// Fetches the recipient allow-list
+    pub async fn get_allow_list(&mut self, allow_list: &Pubkey) -> Result<AllowList, ClientError> {
+        self.get_program_account(allow_list).await
+    }
+
+    /// Fetches the escrow template
+    pub async fn get_template(&mut self, template: &Pubkey) -> Result<EscrowTemplate, ClientError> {
+        self.get_program_account(template).await
+    }
+
+    /// Signs instructions with the fee payer and `signers`, then sends them in one transaction
+    pub async fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&dyn Signer],
+    ) -> Result<Signature, ClientError> {
+        let recent_blockhash = self.transport.get_recent_blockhash().await?;
+        let mut transaction =
+            Transaction::new_with_payer(instructions, Some(&self.fee_payer.pubkey()));
+        let mut all_signers = vec![self.fee_payer.as_ref()];
+        all_signers.extend_from_slice(signers);
+        all_signers.sort_by_key(|signer| signer.pubkey());
+        all_signers.dedup();
+        transaction.try_sign(&all_signers, recent_blockhash)?;
+
+        let signature = transaction.signatures[0];
+        self.transport.process_transaction(transaction).await?;
+        Ok(signature)
+    }
+
+    /// Creates the escrow token account and the escrow, returns the new escrow address
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create(
+        &mut self,
+        token_mint: &Pubkey,
+        launcher: &Pubkey,
+        canceler: &Pubkey,
+        canceler_token_account: &Pubkey,
+        guardian: &Pubkey,
+        duration: u64,
+    ) -> Result<Pubkey, ClientError> {
+        let escrow = Keypair::new();
+        let escrow_token_account = Keypair::new();
+        let instructions = self
+            .create_instructions(
+                &escrow.pubkey(),
+                &escrow_token_account.pubkey(),
+                token_mint,
+                launcher,
+                canceler,
+                canceler_token_account,
+                guardian,
+                duration,
+            )
+            .await?;
+        self.send(&instructions, &[&escrow, &escrow_token_account])
+            .await?;
+        Ok(escrow.pubkey())
+    }
+
+    /// Instructions creating the escrow token account and the escrow, the fee payer
+    /// funds both accounts, `escrow` and `escrow_token_account` must sign
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_instructions(
+        &mut self,
+        escrow: &Pubkey,
+        escrow_token_account: &Pubkey,
+        token_mint: &Pubkey,
+        launcher: &Pubkey,
+        canceler: &Pubkey,
+        canceler_token_account: &Pubkey,
+        guardian: &Pubkey,
+        duration: u64,
+    ) -> Result<Vec<Instruction>, ClientError> {
+        let token_account_balance = self
+            .transport
+            .get_minimum_balance_for_rent_exemption(TokenAccount::LEN)
+            .await?;
+        let escrow_balance = self
+            .transport
+            .get_minimum_balance_for_rent_exemption(Escrow::LEN)
+            .await?;
+        let authority = self.authority_address(escrow);
+
+        Ok(vec![
+            system_instruction::create_account(
+                &self.fee_payer.pubkey(),
+                escrow_token_account,
+                token_account_balance,
+                TokenAccount::LEN as u64,
+                &spl_token::id(),
+            ),
+            system_instruction::create_account(
+                &self.fee_payer.pubkey(),
+                escrow,
+                escrow_balance,
+                Escrow::LEN as u64,
+                &self.program_id,
+            ),
+            spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                escrow_token_account,
+                token_mint,
+                &authority,
+            )?,
+            instruction::initialize(
+                &self.program_id,
+                escrow,
+                token_mint,
+                escrow_token_account,
+                launcher,
+                canceler,
+                canceler_token_account,
+                guardian,
+                &self.config_address(),
+                duration,
+            )?,
+        ])
+    }
+
+    /// Sets the escrow oracles and manifest, `trusted_handler` is the launcher or the canceler
+    #[allow(clippy::too_many_arguments)]
+    pub async fn setup(
+        &mut self,
+        escrow: &Pubkey,
+        trusted_handler: &dyn Signer,
+        reputation_oracle: &Pubkey,
+        reputation_oracle_token_account: &Pubkey,
+        reputation_oracle_stake: u8,
+        recording_oracle: &Pubkey,
+        recording_oracle_token_account: &Pubkey,
+        recording_oracle_stake: u8,
+        manifest_url: &DataUrl,
+        manifest_hash: &DataHash,
+        max_payout_per_recipient: u64,
+        min_payout: u64,
+        cancellation_fee: u64,
+        cancellation_fee_percent: u8,
+        cancel_notice: u64,
+        allow_list: Option<&Pubkey>,
+    ) -> Result<Signature, ClientError> {
+        let instruction = self
+            .setup_instruction(
+                escrow,
+                &trusted_handler.pubkey(),
+                reputation_oracle,
+                reputation_oracle_token_account,
+                reputation_oracle_stake,
+                recording_oracle,
+                recording_oracle_token_account,
+                recording_oracle_stake,
+                manifest_url,
+                manifest_hash,
+                max_payout_per_recipient,
+                min_payout,
+                cancellation_fee,
+                cancellation_fee_percent,
+                cancel_notice,
+                allow_list,
+            )
+            .await?;
+        self.send(&[instruction], &[trusted_handler]).await
+    }
+
+    /// Setup instruction of the escrow, signed by `trusted_handler`
+    #[allow(clippy::too_many_arguments)]
+    pub async fn setup_instruction(
+        &mut self,
+        escrow: &Pubkey,
+        trusted_handler: &Pubkey,
+        reputation_oracle: &Pubkey,
+        reputation_oracle_token_account: &Pubkey,
+        reputation_oracle_stake: u8,
+        recording_oracle: &Pubkey,
+        recording_oracle_token_account: &Pubkey,
+        recording_oracle_stake: u8,
+        manifest_url: &DataUrl,
+        manifest_hash: &DataHash,
+        max_payout_per_recipient: u64,
+        min_payout: u64,
+        cancellation_fee: u64,
+        cancellation_fee_percent: u8,
+        cancel_notice: u64,
+        allow_list: Option<&Pubkey>,
+    ) -> Result<Instruction, ClientError> {
+        let escrow_info = self.get_escrow(escrow).await?;
+        Ok(instruction::setup(
+            &self.program_id,
+            escrow,
+            trusted_handler,
+            reputation_oracle,
+            reputation_oracle_token_account,
+            reputation_oracle_stake,
+            recording_oracle,
+            recording_oracle_token_account,
+            recording_oracle_stake,
+            manifest_url,
+            manifest_hash,
+            max_payout_per_recipient,
+            min_payout,
+            cancellation_fee,
+            cancellation_fee_percent,
+            cancel_notice,
+            &self.config_address(),
+            &escrow_info.token_account,
+            &self.oracle_stake_address(reputation_oracle),
+            &self.oracle_stake_address(recording_oracle),
+            allow_list,
+        )?)
+    }
+
+    /// Stores the final results, committing the current results log head
+    pub async fn store_results(
+        &mut self,
+        escrow: &Pubkey,
+        trusted_handler: &dyn Signer,
+        total_amount: u64,
+        total_recipients: u64,
+        final_results_url: &DataUrl,
+        final_results_hash: &DataHash,
+    ) -> Result<Signature, ClientError> {
+        let escrow_info = self.get_escrow(escrow).await?;
+        let reputation_oracle = escrow_info
+            .reputation_oracle
+            .ok_or(EscrowError::OracleNotInitialized)?;
+        let recording_oracle = escrow_info
+            .recording_oracle
+            .ok_or(EscrowError::OracleNotInitialized)?;
+        let instruction = instruction::store_results(
+            &self.program_id,
+            escrow,
+            &trusted_handler.pubkey(),
+            total_amount,
+            total_recipients,
+            final_results_url,
+            final_results_hash,
+            &self.results_log_address(escrow),
+            &self.config_address(),
+            &self.oracle_stake_address(&reputation_oracle),
+            &self.oracle_stake_address(&recording_oracle),
+        )?;
+        self.send(&[instruction], &[trusted_handler]).await
+    }
+
+    /// Pays every `(recipient token account, amount)` pair, splitting payouts into
+    /// as many transactions as needed and sending them one after another
+    pub async fn payout(
+        &mut self,
+        escrow: &Pubkey,
+        trusted_handler: &dyn Signer,
+        payouts: &[(Pubkey, u64)],
+    ) -> Result<Vec<Signature>, ClientError> {
+        let escrow_info = self.get_escrow(escrow).await?;
+        let reputation_oracle_token_account = escrow_info
+            .reputation_oracle_token_account
+            .ok_or(EscrowError::OracleNotInitialized)?;
+        let recording_oracle_token_account = escrow_info
+            .recording_oracle_token_account
+            .ok_or(EscrowError::OracleNotInitialized)?;
+        let authority =
+            EscrowProcessor::authority_id(&self.program_id, escrow, escrow_info.bump_seed)?;
+        let allow_list = match escrow_info.allow_list {
+            COption::Some(allow_list) => Some(allow_list),
+            COption::None => None,
+        };
+
+        // Payouts of the escrow limiting the payout per recipient add up the
+        // total paid to the owner of the recipient token account
+        let mut recipient_payouts = vec![];
+        if escrow_info.max_payout_per_recipient != 0 {
+            for (recipient, _) in payouts {
+                let owner = self.get_packed::<TokenAccount>(recipient).await?.owner;
+                recipient_payouts.push(self.recipient_payout_address(escrow, &owner));
+            }
+        }
+        let fee_payer = self.fee_payer.pubkey();
+
+        let instructions = payouts
+            .iter()
+            .enumerate()
+            .map(|(index, (recipient, amount))| {
+                instruction::payout(
+                    &self.program_id,
+                    escrow,
+                    &trusted_handler.pubkey(),
+                    &escrow_info.token_account,
+                    &authority,
+                    recipient,
+                    &reputation_oracle_token_account,
+                    &recording_oracle_token_account,
+                    &spl_token::id(),
+                    *amount,
+                    allow_list.as_ref(),
+                    recipient_payouts
+                        .get(index)
+                        .map(|recipient_payout| (recipient_payout, &fee_payer)),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut signatures = vec![];
+        for range in batch::transaction_ranges(&instructions, &self.fee_payer.pubkey()) {
+            signatures.push(self.send(&instructions[range], &[trusted_handler]).await?);
+        }
+        Ok(signatures)
+    }
+
+    /// Cancels the escrow, returning its tokens to the canceler token account
+    /// less the cancellation fee paid to the oracles.
+    /// `vault_mints` lists the mints of every escrow vault, vault tokens are
+    /// returned to the canceler token accounts recorded in the vaults
+    pub async fn cancel(
+        &mut self,
+        escrow: &Pubkey,
+        trusted_handler: &dyn Signer,
+        vault_mints: &[Pubkey],
+    ) -> Result<Signature, ClientError> {
+        let instruction = self
+            .cancel_instruction(escrow, &trusted_handler.pubkey(), vault_mints)
+            .await?;
+        self.send(&[instruction], &[trusted_handler]).await
+    }
+
+    /// Cancel instruction of the escrow, signed by `trusted_handler`.
+    /// `vault_mints` lists the mints of every escrow vault
+    pub async fn cancel_instruction(
+        &mut self,
+        escrow: &Pubkey,
+        trusted_handler: &Pubkey,
+        vault_mints: &[Pubkey],
+    ) -> Result<Instruction, ClientError> {
+        let escrow_info = self.get_escrow(escrow).await?;
+        let authority =
+            EscrowProcessor::authority_id(&self.program_id, escrow, escrow_info.bump_seed)?;
+
+        if vault_mints.len() != escrow_info.vault_count as usize {
+            return Err(EscrowError::InvalidVault.into());
+        }
+        let mut vaults = vec![];
+        for token_mint in vault_mints {
+            let vault = self.get_vault(escrow, token_mint).await?;
+            let refund = VaultRefund {
+                vault: self.vault_address(escrow, token_mint),
+                token_account: vault.token_account,
+                canceler_token_account: vault.canceler_token_account,
+            };
+            vaults.push((vault.index, refund));
+        }
+        vaults.sort_by_key(|(index, _)| *index);
+        let refunds: Vec<VaultRefund> = vaults.into_iter().map(|(_, refund)| refund).collect();
+        let oracle_token_accounts = if escrow_info.has_cancellation_fee() {
+            Some((
+                escrow_info
+                    .reputation_oracle_token_account
+                    .ok_or(EscrowError::OracleNotInitialized)?,
+                escrow_info
+                    .recording_oracle_token_account
+                    .ok_or(EscrowError::OracleNotInitialized)?,
+            ))
+        } else {
+            None
+        };
+
+        Ok(instruction::cancel(
+            &self.program_id,
+            escrow,
+            trusted_handler,
+            &escrow_info.token_account,
+            &authority,
+            &escrow_info.canceler_token_account,
+            &spl_token::id(),
+            oracle_token_accounts
+                .as_ref()
+                .map(|(reputation, recording)| (reputation, recording)),
+            &refunds,
+        )?)
+    }
+
+    /// Adds a vault of another token mint to the launched escrow, `trusted_handler`
+    /// pays for the vault account. Creates the vault token account owned by the escrow
+    /// authority, returns the vault address
+    pub async fn add_vault(
+        &mut self,
+        escrow: &Pubkey,
+        trusted_handler: &dyn Signer,
+        token_mint: &Pubkey,
+        canceler_token_account: &Pubkey,
+    ) -> Result<Pubkey, ClientError> {
+        let vault_token_account = Keypair::new();
+        let balance = self
+            .transport
+            .get_minimum_balance_for_rent_exemption(TokenAccount::LEN)
+            .await?;
+        let vault = self.vault_address(escrow, token_mint);
+
+        let instructions = [
+            system_instruction::create_account(
+                &self.fee_payer.pubkey(),
+                &vault_token_account.pubkey(),
+                balance,
+                TokenAccount::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                &vault_token_account.pubkey(),
+                token_mint,
+                &self.authority_address(escrow),
+            )?,
+            instruction::add_vault(
+                &self.program_id,
+                escrow,
+                &trusted_handler.pubkey(),
+                &vault,
+                token_mint,
+                &vault_token_account.pubkey(),
+                canceler_token_account,
+                &self.config_address(),
+            )?,
+        ];
+        self.send(&instructions, &[&vault_token_account, trusted_handler])
+            .await?;
+        Ok(vault)
+    }
+
+    /// Stores the total amount to pay from the vault of the token mint
+    pub async fn store_vault_results(
+        &mut self,
+        escrow: &Pubkey,
+        trusted_handler: &dyn Signer,
+        token_mint: &Pubkey,
+        total_amount: u64,
+    ) -> Result<Signature, ClientError> {
+        let instruction = instruction::store_vault_results(
+            &self.program_id,
+            escrow,
+            &trusted_handler.pubkey(),
+            &self.vault_address(escrow, token_mint),
+            total_amount,
+        )?;
+        self.send(&[instruction], &[trusted_handler]).await
+    }
+
+    /// Pays every `(recipient token account, amount)` pair from the vault of the token
+    /// mint, oracle fees go to the oracle token accounts of the same mint
+    pub async fn vault_payout(
+        &mut self,
+        escrow: &Pubkey,
+        trusted_handler: &dyn Signer,
+        token_mint: &Pubkey,
+        reputation_oracle_token_account: &Pubkey,
+        recording_oracle_token_account: &Pubkey,
+        payouts: &[(Pubkey, u64)],
+    ) -> Result<Vec<Signature>, ClientError> {
+        let escrow_info = self.get_escrow(escrow).await?;
+        let vault = self.vault_address(escrow, token_mint);
+        let vault_info = self.get_vault(escrow, token_mint).await?;
+        let authority =
+            EscrowProcessor::authority_id(&self.program_id, escrow, escrow_info.bump_seed)?;
+        let allow_list = match escrow_info.allow_list {
+            COption::Some(allow_list) => Some(allow_list),
+            COption::None => None,
+        };
+
+        let instructions = payouts
+            .iter()
+            .map(|(recipient, amount)| {
+                instruction::vault_payout(
+                    &self.program_id,
+                    escrow,
+                    &trusted_handler.pubkey(),
+                    &vault,
+                    &vault_info.token_account,
+                    &authority,
+                    recipient,
+                    reputation_oracle_token_account,
+                    recording_oracle_token_account,
+                    &spl_token::id(),
+                    *amount,
+                    allow_list.as_ref(),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut signatures = vec![];
+        for range in batch::transaction_ranges(&instructions, &self.fee_payer.pubkey()) {
+            signatures.push(self.send(&instructions[range], &[trusted_handler]).await?);
+        }
+        Ok(signatures)
+    }
+
+    /// Completes the fully paid escrow
+    pub async fn complete(
+        &mut self,
+        escrow: &Pubkey,
+        trusted_handler: &dyn Signer,
+    ) -> Result<Signature, ClientError> {
+        let instruction = self.complete_instruction(escrow, &trusted_handler.pubkey())?;
+        self.send(&[instruction], &[trusted_handler]).await
+    }
+
+    /// Complete instruction of the escrow, signed by `trusted_handler`
+    pub fn complete_instruction(
+        &self,
+        escrow: &Pubkey,
+        trusted_handler: &Pubkey,
+    ) -> Result<Instruction, ClientError> {
+        Ok(instruction::complete(
+            &self.program_id,
+            escrow,
+            trusted_handler,
+        )?)
+    }
+
+    /// Creates a recipient allow-list holding up to `capacity` owners, returns its address
+    pub async fn create_allow_list(
+        &mut self,
+        authority: &dyn Signer,
+        capacity: usize,
+    ) -> Result<Pubkey, ClientError> {
+        let allow_list = Keypair::new();
+        let account_len = AllowList::account_len(capacity);
+        let balance = self
+            .transport
+            .get_minimum_balance_for_rent_exemption(account_len)
+            .await?;
+
+        let instructions = [
+            system_instruction::create_account(
+                &self.fee_payer.pubkey(),
+                &allow_list.pubkey(),
+                balance,
+                account_len as u64,
+                &self.program_id,
+            ),
+            instruction::initialize_allow_list(
+                &self.program_id,
+                &allow_list.pubkey(),
+                &authority.pubkey(),
+            )?,
+        ];
+        self.send(&instructions, &[&allow_list, authority]).await?;
+        Ok(allow_list.pubkey())
+    }
+
+    /// Adds and removes allow-list owners, at most `MAX_ALLOW_LIST_UPDATES` of each
+    pub async fn update_allow_list(
+        &mut self,
+        allow_list: &Pubkey,
+        authority: &dyn Signer,
+        add: &[Pubkey],
+        remove: &[Pubkey],
+    ) -> Result<Signature, ClientError> {
+        let instruction = instruction::update_allow_list(
+            &self.program_id,
+            allow_list,
+            &authority.pubkey(),
+            add,
+            remove,
+        )?;
+        self.send(&[instruction], &[authority]).await
+    }
+
+    /// Creates an escrow template with the `template` values, `authority` becomes the
+    /// template authority. Returns the template address
+    pub async fn create_template(
+        &mut self,
+        authority: &dyn Signer,
+        template: &EscrowTemplate,
+    ) -> Result<Pubkey, ClientError> {
+        let template_account = Keypair::new();
+        let balance = self
+            .transport
+            .get_minimum_balance_for_rent_exemption(EscrowTemplate::LEN)
+            .await?;
+        let template = EscrowTemplate {
+            authority: authority.pubkey(),
+            ..*template
+        };
+
+        let instructions = [
+            system_instruction::create_account(
+                &self.fee_payer.pubkey(),
+                &template_account.pubkey(),
+                balance,
+                EscrowTemplate::LEN as u64,
+                &self.program_id,
+            ),
+            instruction::initialize_template(
+                &self.program_id,
+                &template_account.pubkey(),
+                &template,
+            )?,
+        ];
+        self.send(&instructions, &[&template_account, authority])
+            .await?;
+        Ok(template_account.pubkey())
+    }
+
+    /// Creates the escrow token account and the escrow, then sets it up with the
+    /// template values and the manifest. `launcher` must be the template authority.
+    /// Returns the new escrow address
+    pub async fn create_from_template(
+        &mut self,
+        template: &Pubkey,
+        launcher: &dyn Signer,
+        manifest_url: &DataUrl,
+        manifest_hash: &DataHash,
+    ) -> Result<Pubkey, ClientError> {
+        let template_info = self.get_template(template).await?;
+        let escrow = Keypair::new();
+        let escrow_token_account = Keypair::new();
+        let token_account_balance = self
+            .transport
+            .get_minimum_balance_for_rent_exemption(TokenAccount::LEN)
+            .await?;
+        let escrow_balance = self
+            .transport
+            .get_minimum_balance_for_rent_exemption(Escrow::LEN)
+            .await?;
+        let authority = self.authority_address(&escrow.pubkey());
+
+        let instructions = [
+            system_instruction::create_account(
+                &self.fee_payer.pubkey(),
+                &escrow_token_account.pubkey(),
+                token_account_balance,
+                TokenAccount::LEN as u64,
+                &spl_token::id(),
+            ),
+            system_instruction::create_account(
+                &self.fee_payer.pubkey(),
+                &escrow.pubkey(),
+                escrow_balance,
+                Escrow::LEN as u64,
+                &self.program_id,
+            ),
+            spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                &escrow_token_account.pubkey(),
+                &template_info.token_mint,
+                &authority,
+            )?,
+            instruction::initialize_from_template(
+                &self.program_id,
+                &escrow.pubkey(),
+                template,
+                &template_info,
+                &escrow_token_account.pubkey(),
+                &self.config_address(),
+                &self.oracle_stake_address(&template_info.reputation_oracle),
+                &self.oracle_stake_address(&template_info.recording_oracle),
+                manifest_url,
+                manifest_hash,
+            )?,
+        ];
+        self.send(&instructions, &[&escrow, &escrow_token_account, launcher])
+            .await?;
+        Ok(escrow.pubkey())
+    }
+}
@@ -0,0 +1,172 @@
+//! Fetching job manifests and results and checking them against `DataHash`
+
+use hmt_escrow::state::DataHash;
+use ripemd160::Ripemd160;
+use sha1::{Digest, Sha1};
+use std::{fmt, fs, str::FromStr};
+use thiserror::Error;
+
+/// Errors of fetching and hashing manifests and results
+#[derive(Debug, Error)]
+pub enum ManifestError {
+    /// Nothing to fetch, the URL is empty
+    #[error("URL is empty")]
+    EmptyUrl,
+
+    /// URL scheme other than `file`, `http` or `https`
+    #[error("Unsupported URL scheme: {0}")]
+    UnsupportedScheme(String),
+
+    /// Local file could not be read
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// HTTP request failed or returned an error status
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// Hash algorithm name is not known
+    #[error("Unknown hash algorithm: {0}")]
+    UnknownAlgorithm(String),
+}
+
+/// Algorithm of the 20-byte manifest and results hashes
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HashAlgorithm {
+    /// SHA-1, used by the Human Protocol manifests
+    Sha1,
+    /// RIPEMD-160
+    Ripemd160,
+}
+
+impl Default for HashAlgorithm {
+    fn default() -> Self {
+        HashAlgorithm::Sha1
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = ManifestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sha1" => Ok(HashAlgorithm::Sha1),
+            "ripemd160" => Ok(HashAlgorithm::Ripemd160),
+            _ => Err(ManifestError::UnknownAlgorithm(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HashAlgorithm::Sha1 => write!(f, "sha1"),
+            HashAlgorithm::Ripemd160 => write!(f, "ripemd160"),
+        }
+    }
+}
+
+impl HashAlgorithm {
+    /// Hashes data into the on-chain hash
+    pub fn hash(&self, data: &[u8]) -> DataHash {
+        let mut hash = [0u8; 20];
+        match self {
+            HashAlgorithm::Sha1 => hash.copy_from_slice(&Sha1::digest(data)),
+            HashAlgorithm::Ripemd160 => hash.copy_from_slice(&Ripemd160::digest(data)),
+        }
+        DataHash::new_from_array(hash)
+    }
+}
+
+/// Reads data from a local file path, a `file://` URL or over HTTP(S)
+pub fn fetch(url: &str) -> Result<Vec<u8>, ManifestError> {
+    if url.is_empty() {
+        return Err(ManifestError::EmptyUrl);
+    }
+    if let Some(path) = url.strip_prefix("file://") {
+        return Ok(fs::read(path)?);
+    }
+    if url.starts_with("http://") || url.starts_with("https://") {
+        let response = reqwest::blocking::get(url)?.error_for_status()?;
+        return Ok(response.bytes()?.to_vec());
+    }
+    match url.find("://") {
+        Some(end) => Err(ManifestError::UnsupportedScheme(url[..end].to_string())),
+        None => Ok(fs::read(url)?),
+    }
+}
+
+/// Fetches data from `url` and hashes it
+pub fn fetch_hash(url: &str, algorithm: HashAlgorithm) -> Result<DataHash, ManifestError> {
+    Ok(algorithm.hash(&fetch(url)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    const DATA: &[u8] = b"abc";
+    const SHA1: &str = "a9993e364706816aba3e25717850c26c9cd0d89d";
+    const RIPEMD160: &str = "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc";
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(hex::encode(HashAlgorithm::Sha1.hash(DATA)), SHA1);
+        assert_eq!(hex::encode(HashAlgorithm::Ripemd160.hash(DATA)), RIPEMD160);
+        assert_eq!(
+            "SHA1".parse::<HashAlgorithm>().unwrap(),
+            HashAlgorithm::Sha1
+        );
+        assert!("md5".parse::<HashAlgorithm>().is_err());
+    }
+
+    #[test]
+    fn test_fetch_file() {
+        let path = std::env::temp_dir().join(format!("hmt-manifest-{}.json", std::process::id()));
+        fs::write(&path, DATA).unwrap();
+        let path = path.to_str().unwrap();
+
+        assert_eq!(fetch(path).unwrap(), DATA);
+        assert_eq!(
+            hex::encode(fetch_hash(&format!("file://{}", path), HashAlgorithm::Sha1).unwrap()),
+            SHA1
+        );
+        fs::remove_file(path).unwrap();
+
+        assert!(matches!(fetch(""), Err(ManifestError::EmptyUrl)));
+        assert!(matches!(fetch(path), Err(ManifestError::Io(_))));
+        assert!(matches!(
+            fetch("ipfs://manifest"),
+            Err(ManifestError::UnsupportedScheme(scheme)) if scheme == "ipfs"
+        ));
+    }
+
+    #[test]
+    fn test_fetch_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/manifest.json", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                DATA.len()
+            )
+            .unwrap();
+            stream.write_all(DATA).unwrap();
+        });
+
+        assert_eq!(
+            hex::encode(fetch_hash(&url, HashAlgorithm::Sha1).unwrap()),
+            SHA1
+        );
+        server.join().unwrap();
+    }
+}
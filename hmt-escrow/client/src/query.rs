@@ -0,0 +1,224 @@
+//! Escrow and token account discovery through `getProgramAccounts`
+
+use crate::error::ClientError;
+use hmt_escrow::state::{
+    Escrow, EscrowState, EscrowVault, ESCROW_CANCELER_OFFSET, ESCROW_LAUNCHER_OFFSET,
+    ESCROW_STATE_OFFSET, ESCROW_TOKEN_MINT_OFFSET, ESCROW_VAULT_ESCROW_OFFSET,
+};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+};
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::Account as TokenAccount;
+
+/// Offset of the mint in the packed token account
+const TOKEN_ACCOUNT_MINT_OFFSET: usize = 0;
+/// Offset of the owner in the packed token account
+const TOKEN_ACCOUNT_OWNER_OFFSET: usize = 32;
+
+/// Escrow selection criteria, unset fields match any escrow
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EscrowFilter {
+    /// Escrow launcher
+    pub launcher: Option<Pubkey>,
+    /// Escrow canceler
+    pub canceler: Option<Pubkey>,
+    /// Mint of the escrow token
+    pub token_mint: Option<Pubkey>,
+    /// Escrow state
+    pub state: Option<EscrowState>,
+}
+
+fn memcmp(offset: usize, bytes: &[u8]) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp {
+        offset,
+        bytes: MemcmpEncodedBytes::Binary(bs58::encode(bytes).into_string()),
+        encoding: None,
+    })
+}
+
+impl EscrowFilter {
+    /// RPC filters selecting the escrow accounts matching this filter
+    pub fn rpc_filters(&self) -> Vec<RpcFilterType> {
+        let mut filters = vec![RpcFilterType::DataSize(Escrow::LEN as u64)];
+        if let Some(launcher) = self.launcher {
+            filters.push(memcmp(ESCROW_LAUNCHER_OFFSET, launcher.as_ref()));
+        }
+        if let Some(canceler) = self.canceler {
+            filters.push(memcmp(ESCROW_CANCELER_OFFSET, canceler.as_ref()));
+        }
+        if let Some(token_mint) = self.token_mint {
+            filters.push(memcmp(ESCROW_TOKEN_MINT_OFFSET, token_mint.as_ref()));
+        }
+        if let Some(state) = self.state {
+            filters.push(memcmp(ESCROW_STATE_OFFSET, &[state as u8]));
+        }
+        filters
+    }
+
+    /// Checks if the decoded escrow matches this filter
+    pub fn matches(&self, escrow: &Escrow) -> bool {
+        self.launcher.map_or(true, |key| key == escrow.launcher)
+            && self.canceler.map_or(true, |key| key == escrow.canceler)
+            && self.token_mint.map_or(true, |key| key == escrow.token_mint)
+            && self.state.map_or(true, |state| state == escrow.state)
+    }
+}
+
+fn program_accounts(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    filters: Vec<RpcFilterType>,
+) -> Result<Vec<(Pubkey, Vec<u8>)>, ClientError> {
+    let accounts = rpc_client.get_program_accounts_with_config(
+        program_id,
+        RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+        },
+    )?;
+    Ok(accounts
+        .into_iter()
+        .map(|(address, account)| (address, account.data))
+        .collect())
+}
+
+/// Fetches all escrows of the program matching the filter
+pub fn find_escrows(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    filter: &EscrowFilter,
+) -> Result<Vec<(Pubkey, Escrow)>, ClientError> {
+    let accounts = program_accounts(rpc_client, program_id, filter.rpc_filters())?;
+
+    // Skip accounts that do not decode, filters only guarantee the data size
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(address, data)| {
+            Escrow::unpack_from_slice(&data)
+                .ok()
+                .filter(|escrow| filter.matches(escrow))
+                .map(|escrow| (address, escrow))
+        })
+        .collect())
+}
+
+/// Fetches all vaults of the escrow, in the order they were added
+pub fn find_vaults(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    escrow: &Pubkey,
+) -> Result<Vec<(Pubkey, EscrowVault)>, ClientError> {
+    let filters = vec![
+        RpcFilterType::DataSize(EscrowVault::LEN as u64),
+        memcmp(ESCROW_VAULT_ESCROW_OFFSET, escrow.as_ref()),
+    ];
+    let accounts = program_accounts(rpc_client, program_id, filters)?;
+
+    let mut vaults: Vec<(Pubkey, EscrowVault)> = accounts
+        .into_iter()
+        .filter_map(|(address, data)| {
+            EscrowVault::unpack(&data)
+                .ok()
+                .filter(|vault| vault.escrow == *escrow)
+                .map(|vault| (address, vault))
+        })
+        .collect();
+    vaults.sort_by_key(|(_, vault)| vault.index);
+    Ok(vaults)
+}
+
+/// Fetches all token accounts of `mint` owned by `owner`
+pub fn find_token_accounts(
+    rpc_client: &RpcClient,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Result<Vec<(Pubkey, TokenAccount)>, ClientError> {
+    let filters = vec![
+        RpcFilterType::DataSize(TokenAccount::LEN as u64),
+        memcmp(TOKEN_ACCOUNT_MINT_OFFSET, mint.as_ref()),
+        memcmp(TOKEN_ACCOUNT_OWNER_OFFSET, owner.as_ref()),
+    ];
+    let accounts = program_accounts(rpc_client, &spl_token::id(), filters)?;
+
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(address, data)| {
+            TokenAccount::unpack(&data)
+                .ok()
+                .map(|account| (address, account))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filters_match_packed_escrow() {
+        let escrow = Escrow {
+            state: EscrowState::Pending,
+            token_mint: Pubkey::new_from_array([1; 32]),
+            launcher: Pubkey::new_from_array([2; 32]),
+            canceler: Pubkey::new_from_array([3; 32]),
+            ..Escrow::default()
+        };
+        let mut data = vec![0; Escrow::LEN];
+        Escrow::pack(escrow, &mut data).unwrap();
+
+        let filter = EscrowFilter {
+            launcher: Some(escrow.launcher),
+            canceler: Some(escrow.canceler),
+            token_mint: Some(escrow.token_mint),
+            state: Some(escrow.state),
+        };
+        assert!(filter.matches(&escrow));
+
+        let filters = filter.rpc_filters();
+        assert_eq!(filters.len(), 5);
+        for rpc_filter in filters {
+            match rpc_filter {
+                RpcFilterType::DataSize(size) => assert_eq!(size as usize, data.len()),
+                RpcFilterType::Memcmp(memcmp) => {
+                    let MemcmpEncodedBytes::Binary(bytes) = memcmp.bytes;
+                    let bytes = bs58::decode(bytes).into_vec().unwrap();
+                    let offset = memcmp.offset;
+                    assert_eq!(data[offset..offset + bytes.len()], bytes[..]);
+                }
+            }
+        }
+
+        let other = EscrowFilter {
+            state: Some(EscrowState::Cancelled),
+            ..filter
+        };
+        assert!(!other.matches(&escrow));
+    }
+
+    #[test]
+    fn test_token_account_offsets() {
+        let account = TokenAccount {
+            mint: Pubkey::new_from_array([1; 32]),
+            owner: Pubkey::new_from_array([2; 32]),
+            ..TokenAccount::default()
+        };
+        let mut data = vec![0; TokenAccount::LEN];
+        TokenAccount::pack(account, &mut data).unwrap();
+
+        assert_eq!(
+            data[TOKEN_ACCOUNT_MINT_OFFSET..TOKEN_ACCOUNT_MINT_OFFSET + 32],
+            account.mint.as_ref()[..]
+        );
+        assert_eq!(
+            data[TOKEN_ACCOUNT_OWNER_OFFSET..TOKEN_ACCOUNT_OWNER_OFFSET + 32],
+            account.owner.as_ref()[..]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Blockchain access used by the escrow client
+
+use crate::error::ClientError;
+use async_trait::async_trait;
+use solana_banks_client::BanksClient;
+use solana_client::rpc_client::RpcClient;
+use solana_program::{hash::Hash, pubkey::Pubkey};
+use solana_sdk::{account::Account, transaction::Transaction};
+
+/// Minimal set of requests the escrow client sends to a cluster
+#[async_trait(?Send)]
+pub trait Transport {
+    /// Fetches the account, `None` if it does not exist
+    async fn get_account(&mut self, address: &Pubkey) -> Result<Option<Account>, ClientError>;
+
+    /// Returns the minimum balance for a rent exempt account of `data_len` bytes
+    async fn get_minimum_balance_for_rent_exemption(
+        &mut self,
+        data_len: usize,
+    ) -> Result<u64, ClientError>;
+
+    /// Returns a recent blockhash to sign transactions with
+    async fn get_recent_blockhash(&mut self) -> Result<Hash, ClientError>;
+
+    /// Sends the signed transaction and waits for its confirmation
+    async fn process_transaction(&mut self, transaction: Transaction) -> Result<(), ClientError>;
+}
+
+/// `RpcClient` requests are blocking, every call holds the thread until the node replies.
+/// Drive it with a blocking executor such as `futures::executor::block_on` or from
+/// `tokio::task::spawn_blocking`, never directly on a shared async runtime thread
+#[async_trait(?Send)]
+impl Transport for RpcClient {
+    async fn get_account(&mut self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        Ok(self
+            .get_account_with_commitment(address, self.commitment())?
+            .value)
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(
+        &mut self,
+        data_len: usize,
+    ) -> Result<u64, ClientError> {
+        Ok(RpcClient::get_minimum_balance_for_rent_exemption(
+            self, data_len,
+        )?)
+    }
+
+    async fn get_recent_blockhash(&mut self) -> Result<Hash, ClientError> {
+        let (recent_blockhash, _) = RpcClient::get_recent_blockhash(self)?;
+        Ok(recent_blockhash)
+    }
+
+    async fn process_transaction(&mut self, transaction: Transaction) -> Result<(), ClientError> {
+        self.send_and_confirm_transaction(&transaction)?;
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Transport for BanksClient {
+    async fn get_account(&mut self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        Ok(BanksClient::get_account(self, *address).await?)
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(
+        &mut self,
+        data_len: usize,
+    ) -> Result<u64, ClientError> {
+        let rent = self.get_rent().await?;
+        Ok(rent.minimum_balance(data_len))
+    }
+
+    async fn get_recent_blockhash(&mut self) -> Result<Hash, ClientError> {
+        Ok(BanksClient::get_recent_blockhash(self).await?)
+    }
+
+    async fn process_transaction(&mut self, transaction: Transaction) -> Result<(), ClientError> {
+        Ok(BanksClient::process_transaction(self, transaction).await?)
+    }
+}
@@ -0,0 +1,415 @@
+#![cfg(feature = "test-bpf")]
+
+use hmt_escrow::{
+    id, instruction, processor,
+    state::{DataHash, DataUrl, EscrowState, EscrowTemplate},
+};
+use hmt_escrow_client::{ClientError, EscrowClient};
+use solana_program::{
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_instruction,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    signature::{keypair_from_seed, Keypair, Signer},
+};
+use spl_token::state::{Account as TokenAccount, Mint};
+use std::str::FromStr;
+
+const DECIMALS: u8 = 9;
+
+fn program_test() -> ProgramTest {
+    let mut pc = ProgramTest::new(
+        "hmt_escrow",
+        id(),
+        processor!(processor::Processor::process),
+    );
+
+    // Add SPL Token program
+    pc.add_program(
+        "spl_token",
+        spl_token::id(),
+        processor!(spl_token::processor::Processor::process),
+    );
+
+    // Program data of the upgradeable escrow program, its upgrade authority
+    // initializes the config
+    pc.add_account(
+        processor::Processor::find_program_data_address(&id()),
+        Account {
+            lamports: 1_000_000_000,
+            data: bincode::serialize(&UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(upgrade_authority().pubkey()),
+            })
+            .unwrap(),
+            owner: bpf_loader_upgradeable::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    pc
+}
+
+fn upgrade_authority() -> Keypair {
+    keypair_from_seed(&[7; 32]).unwrap()
+}
+
+async fn create_mint(client: &mut EscrowClient<BanksClient>, mint: &Keypair, owner: &Pubkey) {
+    let rent = client
+        .transport()
+        .get_rent()
+        .await
+        .unwrap()
+        .minimum_balance(Mint::LEN);
+    client
+        .send(
+            &[
+                system_instruction::create_account(
+                    &client.fee_payer(),
+                    &mint.pubkey(),
+                    rent,
+                    Mint::LEN as u64,
+                    &spl_token::id(),
+                ),
+                spl_token::instruction::initialize_mint(
+                    &spl_token::id(),
+                    &mint.pubkey(),
+                    owner,
+                    None,
+                    DECIMALS,
+                )
+                .unwrap(),
+            ],
+            &[mint],
+        )
+        .await
+        .unwrap();
+}
+
+async fn create_token_account(
+    client: &mut EscrowClient<BanksClient>,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Pubkey {
+    let account = Keypair::new();
+    let rent = client
+        .transport()
+        .get_rent()
+        .await
+        .unwrap()
+        .minimum_balance(TokenAccount::LEN);
+    client
+        .send(
+            &[
+                system_instruction::create_account(
+                    &client.fee_payer(),
+                    &account.pubkey(),
+                    rent,
+                    TokenAccount::LEN as u64,
+                    &spl_token::id(),
+                ),
+                spl_token::instruction::initialize_account(
+                    &spl_token::id(),
+                    &account.pubkey(),
+                    mint,
+                    owner,
+                )
+                .unwrap(),
+            ],
+            &[&account],
+        )
+        .await
+        .unwrap();
+    account.pubkey()
+}
+
+async fn mint_to(
+    client: &mut EscrowClient<BanksClient>,
+    mint: &Pubkey,
+    mint_authority: &Keypair,
+    account: &Pubkey,
+    amount: u64,
+) {
+    client
+        .send(
+            &[spl_token::instruction::mint_to(
+                &spl_token::id(),
+                mint,
+                account,
+                &mint_authority.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap()],
+            &[mint_authority],
+        )
+        .await
+        .unwrap();
+}
+
+async fn token_balance(client: &mut EscrowClient<BanksClient>, account: &Pubkey) -> u64 {
+    client
+        .get_packed::<TokenAccount>(account)
+        .await
+        .unwrap()
+        .amount
+}
+
+#[tokio::test]
+async fn test_escrow_client_lifecycle() {
+    let (banks_client, payer, _) = program_test().start().await;
+    let admin = payer.pubkey();
+    let mut client = EscrowClient::new(banks_client, id(), Box::new(payer));
+
+    let mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    let handler = Keypair::new();
+    create_mint(&mut client, &mint, &mint_authority.pubkey()).await;
+
+    let config_address = client.config_address();
+    client
+        .send(
+            &[instruction::initialize_config(
+                &id(),
+                &config_address,
+                &admin,
+                &processor::Processor::find_program_data_address(&id()),
+                &upgrade_authority().pubkey(),
+                50,
+                60,
+                2592000,
+                &[mint.pubkey()],
+            )
+            .unwrap()],
+            &[&upgrade_authority()],
+        )
+        .await
+        .unwrap();
+    assert!(client
+        .get_config()
+        .await
+        .unwrap()
+        .is_mint_allowed(&mint.pubkey()));
+
+    let canceler_token_account =
+        create_token_account(&mut client, &mint.pubkey(), &handler.pubkey()).await;
+    let escrow = client
+        .create(
+            &mint.pubkey(),
+            &handler.pubkey(),
+            &handler.pubkey(),
+            &canceler_token_account,
+            &handler.pubkey(),
+            3600,
+        )
+        .await
+        .unwrap();
+    let escrow_info = client.get_escrow(&escrow).await.unwrap();
+    assert_eq!(escrow_info.state, EscrowState::Launched);
+    assert_eq!(escrow_info.token_mint, mint.pubkey());
+    mint_to(
+        &mut client,
+        &mint.pubkey(),
+        &mint_authority,
+        &escrow_info.token_account,
+        1000,
+    )
+    .await;
+
+    let oracle = Keypair::new();
+    let oracle_token_account =
+        create_token_account(&mut client, &mint.pubkey(), &oracle.pubkey()).await;
+    let worker = Keypair::new();
+    let allow_list = client.create_allow_list(&handler, 4).await.unwrap();
+    client
+        .update_allow_list(&allow_list, &handler, &[worker.pubkey()], &[])
+        .await
+        .unwrap();
+    assert_eq!(client.get_allow_list(&allow_list).await.unwrap().count, 1);
+    client
+        .setup(
+            &escrow,
+            &handler,
+            &oracle.pubkey(),
+            &oracle_token_account,
+            10,
+            &oracle.pubkey(),
+            &oracle_token_account,
+            10,
+            &DataUrl::from_str("https://example.com/manifest").unwrap(),
+            &DataHash::new_from_array([1; 20]),
+            1000,
+            1,
+            0,
+            0,
+            0,
+            Some(&allow_list),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        client.get_escrow(&escrow).await.unwrap().state,
+        EscrowState::Pending
+    );
+
+    client
+        .store_results(
+            &escrow,
+            &handler,
+            1000,
+            2,
+            &DataUrl::from_str("https://example.com/results").unwrap(),
+            &DataHash::new_from_array([2; 20]),
+        )
+        .await
+        .unwrap();
+
+    let recipient = create_token_account(&mut client, &mint.pubkey(), &worker.pubkey()).await;
+    client
+        .payout(&escrow, &handler, &[(recipient, 600), (recipient, 400)])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut client, &recipient).await, 800);
+    assert_eq!(token_balance(&mut client, &oracle_token_account).await, 200);
+    let escrow_info = client.get_escrow(&escrow).await.unwrap();
+    assert_eq!(escrow_info.state, EscrowState::Paid);
+    assert_eq!(escrow_info.sent_amount, 1000);
+    assert_eq!(
+        client
+            .get_recipient_payout(&escrow, &worker.pubkey())
+            .await
+            .unwrap()
+            .amount,
+        1000
+    );
+
+    client.complete(&escrow, &handler).await.unwrap();
+    assert_eq!(
+        client.get_escrow(&escrow).await.unwrap().state,
+        EscrowState::Complete
+    );
+
+    // Repeat job is created from a template with the same configuration
+    let template = client
+        .create_template(
+            &handler,
+            &EscrowTemplate {
+                token_mint: mint.pubkey(),
+                canceler: handler.pubkey(),
+                canceler_token_account,
+                guardian: handler.pubkey(),
+                duration: 3600,
+                reputation_oracle: oracle.pubkey(),
+                reputation_oracle_token_account: oracle_token_account,
+                reputation_oracle_stake: 10,
+                recording_oracle: oracle.pubkey(),
+                recording_oracle_token_account: oracle_token_account,
+                recording_oracle_stake: 10,
+                max_payout_per_recipient: 600,
+                min_payout: 1,
+                allow_list: COption::Some(allow_list),
+                ..EscrowTemplate::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        client.get_template(&template).await.unwrap().authority,
+        handler.pubkey()
+    );
+    let escrow = client
+        .create_from_template(
+            &template,
+            &handler,
+            &DataUrl::from_str("https://example.com/manifest-2").unwrap(),
+            &DataHash::new_from_array([3; 20]),
+        )
+        .await
+        .unwrap();
+    let escrow_info = client.get_escrow(&escrow).await.unwrap();
+    assert_eq!(escrow_info.state, EscrowState::Pending);
+    assert_eq!(escrow_info.launcher, handler.pubkey());
+    assert_eq!(escrow_info.allow_list, COption::Some(allow_list));
+    assert_eq!(escrow_info.max_payout_per_recipient, 600);
+}
+
+#[tokio::test]
+async fn test_escrow_client_cancel() {
+    let (banks_client, payer, _) = program_test().start().await;
+    let admin = payer.pubkey();
+    let mut client = EscrowClient::new(banks_client, id(), Box::new(payer));
+
+    let mint = Keypair::new();
+    let handler = Keypair::new();
+    create_mint(&mut client, &mint, &handler.pubkey()).await;
+    let config_address = client.config_address();
+    client
+        .send(
+            &[instruction::initialize_config(
+                &id(),
+                &config_address,
+                &admin,
+                &processor::Processor::find_program_data_address(&id()),
+                &upgrade_authority().pubkey(),
+                50,
+                60,
+                2592000,
+                &[],
+            )
+            .unwrap()],
+            &[&upgrade_authority()],
+        )
+        .await
+        .unwrap();
+
+    let canceler_token_account =
+        create_token_account(&mut client, &mint.pubkey(), &handler.pubkey()).await;
+    let escrow = client
+        .create(
+            &mint.pubkey(),
+            &handler.pubkey(),
+            &handler.pubkey(),
+            &canceler_token_account,
+            &handler.pubkey(),
+            3600,
+        )
+        .await
+        .unwrap();
+    let escrow_info = client.get_escrow(&escrow).await.unwrap();
+    mint_to(
+        &mut client,
+        &mint.pubkey(),
+        &handler,
+        &escrow_info.token_account,
+        500,
+    )
+    .await;
+
+    // Only the launcher or the canceler may cancel
+    let stranger = Keypair::new();
+    assert!(client.cancel(&escrow, &stranger, &[]).await.is_err());
+
+    client.cancel(&escrow, &handler, &[]).await.unwrap();
+    assert_eq!(
+        client.get_escrow(&escrow).await.unwrap().state,
+        EscrowState::Cancelled
+    );
+    assert_eq!(
+        token_balance(&mut client, &canceler_token_account).await,
+        500
+    );
+
+    // Non-program accounts are not decoded as escrows
+    match client.get_escrow(&canceler_token_account).await {
+        Err(ClientError::InvalidAccountOwner(address)) => {
+            assert_eq!(address, canceler_token_account)
+        }
+        _ => panic!("token account decoded as an escrow"),
+    }
+}
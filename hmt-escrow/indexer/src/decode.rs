@@ -0,0 +1,360 @@
+//! Escrow events decoded from the program instructions
+
+use hmt_escrow::instruction::EscrowInstruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::message::Message;
+
+/// Escrow state change or payout made by a single instruction
+#[derive(Clone, Debug, PartialEq)]
+pub enum EscrowEvent {
+    /// Escrow initialized directly or from a template
+    Initialized {
+        /// Escrow account
+        escrow: Pubkey,
+        /// Escrow launcher
+        launcher: Pubkey,
+        /// Escrow canceler
+        canceler: Pubkey,
+        /// Mint of the escrow token
+        token_mint: Pubkey,
+        /// Escrow duration, unknown for escrows created from a template
+        duration: Option<u64>,
+        /// Template the escrow was created from
+        template: Option<Pubkey>,
+    },
+    /// Escrow set up and waiting for the results
+    SetUp {
+        /// Escrow account
+        escrow: Pubkey,
+        /// Job manifest URL
+        manifest_url: String,
+    },
+    /// Final results stored
+    ResultsStored {
+        /// Escrow account
+        escrow: Pubkey,
+        /// Total amount to pay
+        total_amount: u64,
+        /// Total number of recipients
+        total_recipients: u64,
+    },
+    /// Tokens paid to a recipient, oracle fees included
+    Paid {
+        /// Escrow account
+        escrow: Pubkey,
+        /// Vault the tokens were paid from, `None` for the escrow token
+        vault: Option<Pubkey>,
+        /// Recipient token account
+        recipient: Pubkey,
+        /// Amount of tokens
+        amount: u64,
+    },
+    /// Escrow cancelled and refunded
+    Cancelled {
+        /// Escrow account
+        escrow: Pubkey,
+    },
+    /// Escrow marked complete
+    Completed {
+        /// Escrow account
+        escrow: Pubkey,
+    },
+    /// Escrow paused by the guardian
+    Paused {
+        /// Escrow account
+        escrow: Pubkey,
+    },
+    /// Escrow unpaused by the guardian
+    Unpaused {
+        /// Escrow account
+        escrow: Pubkey,
+    },
+    /// Vault of another mint added to the escrow
+    VaultAdded {
+        /// Escrow account
+        escrow: Pubkey,
+        /// Vault account
+        vault: Pubkey,
+        /// Mint of the vault token
+        token_mint: Pubkey,
+    },
+    /// Vault payout total stored
+    VaultResultsStored {
+        /// Escrow account
+        escrow: Pubkey,
+        /// Vault account
+        vault: Pubkey,
+        /// Total amount to pay from the vault
+        total_amount: u64,
+    },
+    /// Escrow template created, kept to resolve durations of escrows created from it
+    TemplateInitialized {
+        /// Template account
+        template: Pubkey,
+        /// Escrow duration
+        duration: u64,
+    },
+}
+
+impl EscrowEvent {
+    /// Escrow the event belongs to, `None` for template events
+    pub fn escrow(&self) -> Option<&Pubkey> {
+        match self {
+            EscrowEvent::Initialized { escrow, .. }
+            | EscrowEvent::SetUp { escrow, .. }
+            | EscrowEvent::ResultsStored { escrow, .. }
+            | EscrowEvent::Paid { escrow, .. }
+            | EscrowEvent::Cancelled { escrow }
+            | EscrowEvent::Completed { escrow }
+            | EscrowEvent::Paused { escrow }
+            | EscrowEvent::Unpaused { escrow }
+            | EscrowEvent::VaultAdded { escrow, .. }
+            | EscrowEvent::VaultResultsStored { escrow, .. } => Some(escrow),
+            EscrowEvent::TemplateInitialized { .. } => None,
+        }
+    }
+
+    /// Short event name stored in the timeline
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EscrowEvent::Initialized { .. } => "initialized",
+            EscrowEvent::SetUp { .. } => "setup",
+            EscrowEvent::ResultsStored { .. } => "results",
+            EscrowEvent::Paid { .. } => "payout",
+            EscrowEvent::Cancelled { .. } => "cancelled",
+            EscrowEvent::Completed { .. } => "completed",
+            EscrowEvent::Paused { .. } => "paused",
+            EscrowEvent::Unpaused { .. } => "unpaused",
+            EscrowEvent::VaultAdded { .. } => "vault-added",
+            EscrowEvent::VaultResultsStored { .. } => "vault-results",
+            EscrowEvent::TemplateInitialized { .. } => "template",
+        }
+    }
+}
+
+/// Decodes a single escrow instruction, returns `None` for instructions
+/// which do not change the escrow timeline or do not unpack
+pub fn decode_instruction(data: &[u8], accounts: &[Pubkey]) -> Option<EscrowEvent> {
+    let key = |index: usize| accounts.get(index).copied();
+    let event = match EscrowInstruction::unpack(data).ok()? {
+        EscrowInstruction::Initialize { duration } => EscrowEvent::Initialized {
+            escrow: key(0)?,
+            token_mint: key(2)?,
+            launcher: key(4)?,
+            canceler: key(5)?,
+            duration: Some(duration),
+            template: None,
+        },
+        EscrowInstruction::InitializeFromTemplate { .. } => EscrowEvent::Initialized {
+            escrow: key(0)?,
+            launcher: key(1)?,
+            template: Some(key(3)?),
+            token_mint: key(4)?,
+            canceler: key(6)?,
+            duration: None,
+        },
+        EscrowInstruction::Setup { manifest_url, .. } => EscrowEvent::SetUp {
+            escrow: key(0)?,
+            manifest_url: manifest_url.as_str().unwrap_or_default().to_string(),
+        },
+        EscrowInstruction::StoreResults {
+            total_amount,
+            total_recipients,
+            ..
+        } => EscrowEvent::ResultsStored {
+            escrow: key(0)?,
+            total_amount,
+            total_recipients,
+        },
+        EscrowInstruction::Payout { amount }
+        | EscrowInstruction::PayoutWithPermit { amount, .. } => EscrowEvent::Paid {
+            escrow: key(0)?,
+            vault: None,
+            recipient: key(5)?,
+            amount,
+        },
+        EscrowInstruction::VaultPayout { amount } => EscrowEvent::Paid {
+            escrow: key(0)?,
+            vault: Some(key(3)?),
+            recipient: key(6)?,
+            amount,
+        },
+        EscrowInstruction::Cancel => EscrowEvent::Cancelled { escrow: key(0)? },
+        EscrowInstruction::Complete => EscrowEvent::Completed { escrow: key(0)? },
+        EscrowInstruction::Pause => EscrowEvent::Paused { escrow: key(0)? },
+        EscrowInstruction::Unpause => EscrowEvent::Unpaused { escrow: key(0)? },
+        EscrowInstruction::AddVault => EscrowEvent::VaultAdded {
+            escrow: key(0)?,
+            vault: key(3)?,
+            token_mint: key(4)?,
+        },
+        EscrowInstruction::StoreVaultResults { total_amount } => EscrowEvent::VaultResultsStored {
+            escrow: key(0)?,
+            vault: key(3)?,
+            total_amount,
+        },
+        EscrowInstruction::InitializeTemplate { duration, .. } => {
+            EscrowEvent::TemplateInitialized {
+                template: key(0)?,
+                duration,
+            }
+        }
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// Decodes the escrow events of all top level instructions of `program_id`
+/// in the transaction message, in the instruction order
+pub fn decode_message(program_id: &Pubkey, message: &Message) -> Vec<EscrowEvent> {
+    message
+        .instructions
+        .iter()
+        .filter(|instruction| {
+            message
+                .account_keys
+                .get(instruction.program_id_index as usize)
+                == Some(program_id)
+        })
+        .filter_map(|instruction| {
+            let accounts: Vec<Pubkey> = instruction
+                .accounts
+                .iter()
+                .filter_map(|index| message.account_keys.get(*index as usize).copied())
+                .collect();
+            decode_instruction(&instruction.data, &accounts)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hmt_escrow::{
+        instruction::{cancel, initialize, payout, store_results},
+        state::{DataHash, DataUrl},
+    };
+    use solana_program::instruction::Instruction;
+
+    fn decode(program_id: &Pubkey, instructions: &[Instruction]) -> Vec<EscrowEvent> {
+        let message = Message::new(instructions, Some(&Pubkey::new_unique()));
+        decode_message(program_id, &message)
+    }
+
+    #[test]
+    fn test_decode_escrow_lifecycle() {
+        let program_id = Pubkey::new_unique();
+        let escrow = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let token_account = Pubkey::new_unique();
+        let launcher = Pubkey::new_unique();
+        let canceler = Pubkey::new_unique();
+        let canceler_token = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let oracle_token = Pubkey::new_unique();
+
+        let events = decode(
+            &program_id,
+            &[
+                initialize(
+                    &program_id,
+                    &escrow,
+                    &mint,
+                    &token_account,
+                    &launcher,
+                    &canceler,
+                    &canceler_token,
+                    &launcher,
+                    &Pubkey::new_unique(),
+                    3600,
+                )
+                .unwrap(),
+                store_results(
+                    &program_id,
+                    &escrow,
+                    &launcher,
+                    100,
+                    2,
+                    &DataUrl::default(),
+                    &DataHash::default(),
+                    &Pubkey::new_unique(),
+                    &Pubkey::new_unique(),
+                    &Pubkey::new_unique(),
+                    &Pubkey::new_unique(),
+                )
+                .unwrap(),
+                payout(
+                    &program_id,
+                    &escrow,
+                    &launcher,
+                    &token_account,
+                    &Pubkey::new_unique(),
+                    &recipient,
+                    &oracle_token,
+                    &oracle_token,
+                    &Pubkey::new_unique(),
+                    40,
+                    None,
+                    None,
+                )
+                .unwrap(),
+            ],
+        );
+        assert_eq!(
+            events,
+            vec![
+                EscrowEvent::Initialized {
+                    escrow,
+                    launcher,
+                    canceler,
+                    token_mint: mint,
+                    duration: Some(3600),
+                    template: None,
+                },
+                EscrowEvent::ResultsStored {
+                    escrow,
+                    total_amount: 100,
+                    total_recipients: 2,
+                },
+                EscrowEvent::Paid {
+                    escrow,
+                    vault: None,
+                    recipient,
+                    amount: 40,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_skips_other_programs() {
+        let program_id = Pubkey::new_unique();
+        let other_program_id = Pubkey::new_unique();
+        let escrow = Pubkey::new_unique();
+        let instruction = cancel(
+            &other_program_id,
+            &escrow,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            None,
+            &[],
+        )
+        .unwrap();
+        assert!(decode(&program_id, &[instruction.clone()]).is_empty());
+        assert_eq!(
+            decode(&other_program_id, &[instruction]),
+            vec![EscrowEvent::Cancelled { escrow }]
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid_data() {
+        assert_eq!(decode_instruction(&[], &[Pubkey::new_unique()]), None);
+        assert_eq!(decode_instruction(&[255], &[Pubkey::new_unique()]), None);
+        // Complete with missing accounts
+        assert_eq!(decode_instruction(&[6], &[]), None);
+    }
+}
@@ -0,0 +1,313 @@
+//! Indexes escrow program transactions into SQLite and answers history queries
+
+mod decode;
+mod store;
+mod sync;
+
+use chrono::prelude::*;
+use clap::{
+    crate_description, crate_name, crate_version, value_t, value_t_or_exit, App, AppSettings, Arg,
+    SubCommand,
+};
+use solana_clap_utils::{
+    input_parsers::pubkey_of,
+    input_validators::{is_parsable, is_pubkey, is_url},
+};
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use std::{process::exit, thread::sleep, time::Duration};
+use store::Store;
+
+struct Config {
+    rpc_client: RpcClient,
+    program_id: Pubkey,
+    store: Store,
+}
+
+type Error = Box<dyn std::error::Error>;
+type CommandResult = Result<String, Error>;
+
+fn format_timestamp(timestamp: Option<i64>) -> String {
+    match timestamp {
+        Some(timestamp) => NaiveDateTime::from_timestamp(timestamp, 0)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+        None => "unknown time".to_string(),
+    }
+}
+
+/// Indexes new transactions once, or keeps polling every `interval` seconds when set
+fn command_sync(config: &mut Config, page_size: usize, interval: Option<u64>) -> CommandResult {
+    loop {
+        let indexed = sync::sync(
+            &config.rpc_client,
+            &mut config.store,
+            &config.program_id,
+            page_size,
+        )?;
+        match interval {
+            Some(interval) => {
+                if indexed > 0 {
+                    println!("Indexed {} transactions", indexed);
+                }
+                sleep(Duration::from_secs(interval));
+            }
+            None => return Ok(format!("Indexed {} transactions", indexed)),
+        }
+    }
+}
+
+fn command_timeline(config: &Config, escrow: &Pubkey) -> CommandResult {
+    let timeline = config.store.timeline(escrow)?;
+    if timeline.is_empty() {
+        return Err(format!("No indexed events of escrow {}", escrow).into());
+    }
+    Ok(timeline
+        .iter()
+        .map(|entry| {
+            format!(
+                "{} slot {} {:<13} {:<9} {} {}",
+                format_timestamp(entry.block_time),
+                entry.slot,
+                entry.kind,
+                entry.state.as_deref().unwrap_or("-"),
+                entry.detail,
+                entry.signature,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn command_launcher_totals(config: &Config, launcher: &Option<Pubkey>) -> CommandResult {
+    let totals = config.store.launcher_totals(launcher.as_ref())?;
+    Ok(totals
+        .iter()
+        .map(|totals| {
+            format!(
+                "{}: {} escrows, {} complete, {} cancelled, total {}, paid {}",
+                totals.launcher,
+                totals.escrows,
+                totals.completed,
+                totals.cancelled,
+                totals.total_amount,
+                totals.sent_amount,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn command_recipient_payouts(config: &Config, recipient: &Option<Pubkey>) -> CommandResult {
+    let payouts = config.store.recipient_payouts(recipient.as_ref())?;
+    Ok(payouts
+        .iter()
+        .map(|payouts| {
+            format!(
+                "{}: {} in {} payouts from {} escrows",
+                payouts.recipient, payouts.amount, payouts.payouts, payouts.escrows,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn command_expiring(config: &Config, within: u64) -> CommandResult {
+    let now = Utc::now().timestamp();
+    let escrows = config
+        .store
+        .expiring(now, now.saturating_add(within as i64))?;
+    Ok(escrows
+        .iter()
+        .map(|escrow| {
+            format!(
+                "{} expires {} ({}), launcher {}, paid {} of {}",
+                escrow.address,
+                format_timestamp(Some(escrow.expires)),
+                escrow.state,
+                escrow.launcher,
+                escrow.sent_amount,
+                escrow.total_amount,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn main() {
+    let matches = App::new(crate_name!())
+        .about(crate_description!())
+        .version(crate_version!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg({
+            let arg = Arg::with_name("config_file")
+                .short("C")
+                .long("config")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .help("Configuration file to use");
+            if let Some(ref config_file) = *solana_cli_config::CONFIG_FILE {
+                arg.default_value(&config_file)
+            } else {
+                arg
+            }
+        })
+        .arg(
+            Arg::with_name("json_rpc_url")
+                .long("url")
+                .value_name("URL")
+                .takes_value(true)
+                .global(true)
+                .validator(is_url)
+                .help("JSON RPC URL for the cluster.  Default from the configuration file."),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .long("program-id")
+                .value_name("ADDRESS")
+                .takes_value(true)
+                .global(true)
+                .validator(is_pubkey)
+                .help("Escrow program to index [default: hmt-escrow program id]"),
+        )
+        .arg(
+            Arg::with_name("database")
+                .long("db")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .default_value("hmt-escrow-index.sqlite")
+                .help("SQLite database file with the indexed history"),
+        )
+        .subcommand(
+            SubCommand::with_name("sync")
+                .about("Indexes program transactions confirmed since the last sync")
+                .arg(
+                    Arg::with_name("page_size")
+                        .long("page-size")
+                        .validator(is_parsable::<usize>)
+                        .value_name("COUNT")
+                        .takes_value(true)
+                        .default_value("1000")
+                        .help("Number of signatures requested from the node at once"),
+                )
+                .arg(
+                    Arg::with_name("follow")
+                        .long("follow")
+                        .validator(is_parsable::<u64>)
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .help(
+                            "Keep indexing new transactions, polling the node with this interval",
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("timeline")
+                .about("Shows indexed state changes and payouts of the escrow")
+                .arg(
+                    Arg::with_name("escrow")
+                        .validator(is_pubkey)
+                        .index(1)
+                        .value_name("ESCROW_ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Escrow address"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("launcher-totals")
+                .about("Shows escrow totals by launcher")
+                .arg(
+                    Arg::with_name("launcher")
+                        .long("launcher")
+                        .validator(is_pubkey)
+                        .value_name("ADDRESS")
+                        .takes_value(true)
+                        .help("Only show totals of this launcher"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("recipient-payouts")
+                .about("Shows payout totals by recipient token account")
+                .arg(
+                    Arg::with_name("recipient")
+                        .long("recipient")
+                        .validator(is_pubkey)
+                        .value_name("ADDRESS")
+                        .takes_value(true)
+                        .help("Only show payouts to this token account"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("expiring")
+                .about("Lists open escrows nearing expiry, soonest first")
+                .arg(
+                    Arg::with_name("within")
+                        .long("within")
+                        .validator(is_parsable::<u64>)
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .default_value("86400")
+                        .help("Only list escrows expiring within this many seconds from now"),
+                ),
+        )
+        .get_matches();
+
+    let mut config = {
+        let cli_config = if let Some(config_file) = matches.value_of("config_file") {
+            solana_cli_config::Config::load(config_file).unwrap_or_default()
+        } else {
+            solana_cli_config::Config::default()
+        };
+        let json_rpc_url = value_t!(matches, "json_rpc_url", String)
+            .unwrap_or_else(|_| cli_config.json_rpc_url.clone());
+        let program_id = pubkey_of(&matches, "program_id").unwrap_or_else(hmt_escrow::id);
+        let database = value_t_or_exit!(matches, "database", String);
+        let store = Store::open(&database).unwrap_or_else(|e| {
+            eprintln!("error: cannot open {}: {}", database, e);
+            exit(1);
+        });
+
+        Config {
+            rpc_client: RpcClient::new(json_rpc_url),
+            program_id,
+            store,
+        }
+    };
+
+    let _ = match matches.subcommand() {
+        ("sync", Some(arg_matches)) => {
+            let page_size = value_t_or_exit!(arg_matches, "page_size", usize);
+            let interval: Option<u64> = value_t!(arg_matches, "follow", u64).ok();
+            command_sync(&mut config, page_size, interval)
+        }
+        ("timeline", Some(arg_matches)) => {
+            let escrow: Pubkey = pubkey_of(arg_matches, "escrow").unwrap();
+            command_timeline(&config, &escrow)
+        }
+        ("launcher-totals", Some(arg_matches)) => {
+            let launcher: Option<Pubkey> = pubkey_of(arg_matches, "launcher");
+            command_launcher_totals(&config, &launcher)
+        }
+        ("recipient-payouts", Some(arg_matches)) => {
+            let recipient: Option<Pubkey> = pubkey_of(arg_matches, "recipient");
+            command_recipient_payouts(&config, &recipient)
+        }
+        ("expiring", Some(arg_matches)) => {
+            let within = value_t_or_exit!(arg_matches, "within", u64);
+            command_expiring(&config, within)
+        }
+        _ => unreachable!(),
+    }
+    .map(|output| {
+        if !output.is_empty() {
+            println!("{}", output);
+        }
+    })
+    .map_err(|err| {
+        eprintln!("{}", err);
+        exit(1);
+    });
+}
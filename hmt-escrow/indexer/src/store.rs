@@ -0,0 +1,666 @@
+//! SQLite storage of the indexed escrow timelines
+
+use crate::decode::EscrowEvent;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use solana_program::{clock::UnixTimestamp, pubkey::Pubkey};
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS escrows (
+    address TEXT PRIMARY KEY,
+    launcher TEXT NOT NULL,
+    canceler TEXT NOT NULL,
+    token_mint TEXT NOT NULL,
+    template TEXT,
+    state TEXT NOT NULL,
+    expires INTEGER,
+    total_amount INTEGER NOT NULL DEFAULT 0,
+    total_recipients INTEGER NOT NULL DEFAULT 0,
+    sent_amount INTEGER NOT NULL DEFAULT 0,
+    sent_recipients INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS escrows_launcher ON escrows (launcher);
+CREATE TABLE IF NOT EXISTS events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    escrow TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    signature TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    state TEXT,
+    detail TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS events_escrow ON events (escrow);
+CREATE TABLE IF NOT EXISTS payouts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    escrow TEXT NOT NULL,
+    vault TEXT,
+    recipient TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    signature TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS payouts_recipient ON payouts (recipient);
+CREATE TABLE IF NOT EXISTS templates (
+    address TEXT PRIMARY KEY,
+    duration INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS cursor (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL
+);
+";
+
+/// Escrow states recorded by the indexer, named after `EscrowState`
+const STATE_LAUNCHED: &str = "launched";
+const STATE_PENDING: &str = "pending";
+const STATE_PARTIAL: &str = "partial";
+const STATE_PAID: &str = "paid";
+const STATE_COMPLETE: &str = "complete";
+const STATE_CANCELLED: &str = "cancelled";
+
+/// Confirmed transaction of the escrow program
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexedTransaction {
+    /// Transaction signature
+    pub signature: String,
+    /// Slot the transaction was confirmed in
+    pub slot: u64,
+    /// Estimated production time of the slot, if the node knows it
+    pub block_time: Option<UnixTimestamp>,
+    /// Decoded escrow events, empty for failed transactions
+    pub events: Vec<EscrowEvent>,
+}
+
+/// Single entry of the escrow timeline
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimelineEntry {
+    /// Slot of the transaction
+    pub slot: u64,
+    /// Block time of the transaction
+    pub block_time: Option<UnixTimestamp>,
+    /// Transaction signature
+    pub signature: String,
+    /// Event name
+    pub kind: String,
+    /// Escrow state after the event, `None` when the event does not change it
+    pub state: Option<String>,
+    /// Event details
+    pub detail: String,
+}
+
+/// Escrow totals of a single launcher
+#[derive(Clone, Debug, PartialEq)]
+pub struct LauncherTotals {
+    /// Launcher account
+    pub launcher: String,
+    /// Number of escrows launched
+    pub escrows: u64,
+    /// Number of escrows completed
+    pub completed: u64,
+    /// Number of escrows cancelled
+    pub cancelled: u64,
+    /// Sum of the stored escrow totals
+    pub total_amount: u64,
+    /// Sum of the escrow token payouts
+    pub sent_amount: u64,
+}
+
+/// Payout totals of a single recipient token account
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecipientPayouts {
+    /// Recipient token account
+    pub recipient: String,
+    /// Number of payouts
+    pub payouts: u64,
+    /// Number of escrows paying the recipient
+    pub escrows: u64,
+    /// Sum of the payouts
+    pub amount: u64,
+}
+
+/// Open escrow with a known expiration time
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpiringEscrow {
+    /// Escrow account
+    pub address: String,
+    /// Escrow launcher
+    pub launcher: String,
+    /// Escrow state
+    pub state: String,
+    /// Expiration timestamp
+    pub expires: UnixTimestamp,
+    /// Stored escrow total
+    pub total_amount: u64,
+    /// Escrow token paid so far
+    pub sent_amount: u64,
+}
+
+/// Indexed escrow history
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    /// Opens the database file, creating the tables when missing
+    pub fn open<P: AsRef<Path>>(path: P) -> rusqlite::Result<Self> {
+        Self::new(Connection::open(path)?)
+    }
+
+    /// Opens a database kept in memory
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::new(Connection::open_in_memory()?)
+    }
+
+    fn new(connection: Connection) -> rusqlite::Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// Signature of the newest indexed transaction
+    pub fn last_signature(&self) -> rusqlite::Result<Option<String>> {
+        self.connection
+            .query_row(
+                "SELECT signature FROM cursor WHERE id = 0",
+                params![],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    /// Applies the transaction events and moves the cursor to it in one database transaction,
+    /// so the interrupted sync resumes without applying anything twice
+    pub fn apply(&mut self, transaction: &IndexedTransaction) -> rusqlite::Result<()> {
+        let db = self.connection.transaction()?;
+        for event in &transaction.events {
+            apply_event(&db, transaction, event)?;
+        }
+        db.execute(
+            "INSERT OR REPLACE INTO cursor (id, signature, slot) VALUES (0, ?1, ?2)",
+            params![transaction.signature, transaction.slot as i64],
+        )?;
+        db.commit()
+    }
+
+    /// Escrow events in the order they happened
+    pub fn timeline(&self, escrow: &Pubkey) -> rusqlite::Result<Vec<TimelineEntry>> {
+        let mut statement = self.connection.prepare(
+            "SELECT slot, block_time, signature, kind, state, detail FROM events
+             WHERE escrow = ?1 ORDER BY id",
+        )?;
+        let rows = statement.query_map(params![escrow.to_string()], |row| {
+            Ok(TimelineEntry {
+                slot: row.get::<_, i64>(0)? as u64,
+                block_time: row.get(1)?,
+                signature: row.get(2)?,
+                kind: row.get(3)?,
+                state: row.get(4)?,
+                detail: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Escrow totals grouped by launcher, optionally for a single launcher
+    pub fn launcher_totals(
+        &self,
+        launcher: Option<&Pubkey>,
+    ) -> rusqlite::Result<Vec<LauncherTotals>> {
+        let mut statement = self.connection.prepare(
+            "SELECT launcher, COUNT(*),
+                 SUM(state = ?1), SUM(state = ?2),
+                 SUM(total_amount), SUM(sent_amount)
+             FROM escrows WHERE ?3 IS NULL OR launcher = ?3
+             GROUP BY launcher ORDER BY launcher",
+        )?;
+        let rows = statement.query_map(
+            params![
+                STATE_COMPLETE,
+                STATE_CANCELLED,
+                launcher.map(|key| key.to_string())
+            ],
+            |row| {
+                Ok(LauncherTotals {
+                    launcher: row.get(0)?,
+                    escrows: row.get::<_, i64>(1)? as u64,
+                    completed: row.get::<_, i64>(2)? as u64,
+                    cancelled: row.get::<_, i64>(3)? as u64,
+                    total_amount: row.get::<_, i64>(4)? as u64,
+                    sent_amount: row.get::<_, i64>(5)? as u64,
+                })
+            },
+        )?;
+        rows.collect()
+    }
+
+    /// Payout totals grouped by recipient token account, optionally for a single recipient
+    pub fn recipient_payouts(
+        &self,
+        recipient: Option<&Pubkey>,
+    ) -> rusqlite::Result<Vec<RecipientPayouts>> {
+        let mut statement = self.connection.prepare(
+            "SELECT recipient, COUNT(*), COUNT(DISTINCT escrow), SUM(amount)
+             FROM payouts WHERE ?1 IS NULL OR recipient = ?1
+             GROUP BY recipient ORDER BY SUM(amount) DESC, recipient",
+        )?;
+        let rows = statement.query_map(params![recipient.map(|key| key.to_string())], |row| {
+            Ok(RecipientPayouts {
+                recipient: row.get(0)?,
+                payouts: row.get::<_, i64>(1)? as u64,
+                escrows: row.get::<_, i64>(2)? as u64,
+                amount: row.get::<_, i64>(3)? as u64,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Open escrows expiring after `now` but no later than `until`, soonest first
+    pub fn expiring(
+        &self,
+        now: UnixTimestamp,
+        until: UnixTimestamp,
+    ) -> rusqlite::Result<Vec<ExpiringEscrow>> {
+        let mut statement = self.connection.prepare(
+            "SELECT address, launcher, state, expires, total_amount, sent_amount
+             FROM escrows
+             WHERE expires > ?1 AND expires <= ?2 AND state NOT IN (?3, ?4)
+             ORDER BY expires, address",
+        )?;
+        let rows = statement.query_map(
+            params![now, until, STATE_COMPLETE, STATE_CANCELLED],
+            |row| {
+                Ok(ExpiringEscrow {
+                    address: row.get(0)?,
+                    launcher: row.get(1)?,
+                    state: row.get(2)?,
+                    expires: row.get(3)?,
+                    total_amount: row.get::<_, i64>(4)? as u64,
+                    sent_amount: row.get::<_, i64>(5)? as u64,
+                })
+            },
+        )?;
+        rows.collect()
+    }
+}
+
+fn insert_event(
+    db: &Transaction,
+    transaction: &IndexedTransaction,
+    escrow: &Pubkey,
+    kind: &str,
+    state: Option<&str>,
+    detail: String,
+) -> rusqlite::Result<()> {
+    db.execute(
+        "INSERT INTO events (escrow, slot, block_time, signature, kind, state, detail)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            escrow.to_string(),
+            transaction.slot as i64,
+            transaction.block_time,
+            transaction.signature,
+            kind,
+            state,
+            detail
+        ],
+    )?;
+    Ok(())
+}
+
+fn set_state(db: &Transaction, escrow: &Pubkey, state: &str) -> rusqlite::Result<()> {
+    db.execute(
+        "UPDATE escrows SET state = ?2 WHERE address = ?1",
+        params![escrow.to_string(), state],
+    )?;
+    Ok(())
+}
+
+fn apply_event(
+    db: &Transaction,
+    transaction: &IndexedTransaction,
+    event: &EscrowEvent,
+) -> rusqlite::Result<()> {
+    let (escrow, state, detail) = match event {
+        EscrowEvent::Initialized {
+            escrow,
+            launcher,
+            canceler,
+            token_mint,
+            duration,
+            template,
+        } => {
+            // Escrows created from a template take the template duration
+            let duration: Option<i64> = match (duration, template) {
+                (Some(duration), _) => Some(*duration as i64),
+                (None, Some(template)) => db
+                    .query_row(
+                        "SELECT duration FROM templates WHERE address = ?1",
+                        params![template.to_string()],
+                        |row| row.get(0),
+                    )
+                    .optional()?,
+                (None, None) => None,
+            };
+            let expires = match (transaction.block_time, duration) {
+                (Some(block_time), Some(duration)) => Some(block_time.saturating_add(duration)),
+                _ => None,
+            };
+            // Escrow created from a template is set up by the same instruction
+            let state = if template.is_some() {
+                STATE_PENDING
+            } else {
+                STATE_LAUNCHED
+            };
+            db.execute(
+                "INSERT OR REPLACE INTO escrows
+                     (address, launcher, canceler, token_mint, template, state, expires)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    escrow.to_string(),
+                    launcher.to_string(),
+                    canceler.to_string(),
+                    token_mint.to_string(),
+                    template.map(|key| key.to_string()),
+                    state,
+                    expires
+                ],
+            )?;
+            (
+                escrow,
+                Some(state),
+                format!("launcher {}, mint {}", launcher, token_mint),
+            )
+        }
+        EscrowEvent::SetUp {
+            escrow,
+            manifest_url,
+        } => {
+            set_state(db, escrow, STATE_PENDING)?;
+            (
+                escrow,
+                Some(STATE_PENDING),
+                format!("manifest {}", manifest_url),
+            )
+        }
+        EscrowEvent::ResultsStored {
+            escrow,
+            total_amount,
+            total_recipients,
+        } => {
+            db.execute(
+                "UPDATE escrows SET total_amount = ?2, total_recipients = ?3 WHERE address = ?1",
+                params![
+                    escrow.to_string(),
+                    *total_amount as i64,
+                    *total_recipients as i64
+                ],
+            )?;
+            (
+                escrow,
+                None,
+                format!("total {} to {} recipients", total_amount, total_recipients),
+            )
+        }
+        EscrowEvent::Paid {
+            escrow,
+            vault,
+            recipient,
+            amount,
+        } => {
+            db.execute(
+                "INSERT INTO payouts (escrow, vault, recipient, amount, slot, block_time, signature)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    escrow.to_string(),
+                    vault.map(|key| key.to_string()),
+                    recipient.to_string(),
+                    *amount as i64,
+                    transaction.slot as i64,
+                    transaction.block_time,
+                    transaction.signature
+                ],
+            )?;
+            // Payout state only follows the escrow token, vault totals are not tracked
+            let state = if vault.is_none() {
+                db.execute(
+                    "UPDATE escrows SET sent_amount = sent_amount + ?2,
+                         sent_recipients = sent_recipients + 1
+                     WHERE address = ?1",
+                    params![escrow.to_string(), *amount as i64],
+                )?;
+                let paid: Option<bool> = db
+                    .query_row(
+                        "SELECT sent_amount = total_amount AND sent_recipients = total_recipients
+                         FROM escrows WHERE address = ?1",
+                        params![escrow.to_string()],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                let state = if paid.unwrap_or(false) {
+                    STATE_PAID
+                } else {
+                    STATE_PARTIAL
+                };
+                set_state(db, escrow, state)?;
+                Some(state)
+            } else {
+                None
+            };
+            let detail = match vault {
+                Some(vault) => format!("{} from vault {} to {}", amount, vault, recipient),
+                None => format!("{} to {}", amount, recipient),
+            };
+            (escrow, state, detail)
+        }
+        EscrowEvent::Cancelled { escrow } => {
+            set_state(db, escrow, STATE_CANCELLED)?;
+            (escrow, Some(STATE_CANCELLED), String::new())
+        }
+        EscrowEvent::Completed { escrow } => {
+            set_state(db, escrow, STATE_COMPLETE)?;
+            (escrow, Some(STATE_COMPLETE), String::new())
+        }
+        EscrowEvent::Paused { escrow } | EscrowEvent::Unpaused { escrow } => {
+            (escrow, None, String::new())
+        }
+        EscrowEvent::VaultAdded {
+            escrow,
+            vault,
+            token_mint,
+        } => (
+            escrow,
+            None,
+            format!("vault {}, mint {}", vault, token_mint),
+        ),
+        EscrowEvent::VaultResultsStored {
+            escrow,
+            vault,
+            total_amount,
+        } => (
+            escrow,
+            None,
+            format!("vault {} total {}", vault, total_amount),
+        ),
+        EscrowEvent::TemplateInitialized { template, duration } => {
+            db.execute(
+                "INSERT OR REPLACE INTO templates (address, duration) VALUES (?1, ?2)",
+                params![template.to_string(), *duration as i64],
+            )?;
+            return Ok(());
+        }
+    };
+    insert_event(db, transaction, escrow, event.kind(), state, detail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(slot: u64, block_time: i64, events: Vec<EscrowEvent>) -> IndexedTransaction {
+        IndexedTransaction {
+            signature: format!("signature{}", slot),
+            slot,
+            block_time: Some(block_time),
+            events,
+        }
+    }
+
+    fn initialized(escrow: Pubkey, launcher: Pubkey, duration: u64) -> EscrowEvent {
+        EscrowEvent::Initialized {
+            escrow,
+            launcher,
+            canceler: launcher,
+            token_mint: Pubkey::new_unique(),
+            duration: Some(duration),
+            template: None,
+        }
+    }
+
+    #[test]
+    fn test_escrow_timeline() {
+        let mut store = Store::open_in_memory().unwrap();
+        assert_eq!(store.last_signature().unwrap(), None);
+
+        let escrow = Pubkey::new_unique();
+        let launcher = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let events = vec![
+            transaction(1, 1000, vec![initialized(escrow, launcher, 600)]),
+            transaction(
+                2,
+                1010,
+                vec![
+                    EscrowEvent::SetUp {
+                        escrow,
+                        manifest_url: "https://example.com/manifest".to_string(),
+                    },
+                    EscrowEvent::ResultsStored {
+                        escrow,
+                        total_amount: 100,
+                        total_recipients: 2,
+                    },
+                ],
+            ),
+            transaction(
+                3,
+                1020,
+                vec![EscrowEvent::Paid {
+                    escrow,
+                    vault: None,
+                    recipient,
+                    amount: 60,
+                }],
+            ),
+            transaction(
+                4,
+                1030,
+                vec![EscrowEvent::Paid {
+                    escrow,
+                    vault: None,
+                    recipient,
+                    amount: 40,
+                }],
+            ),
+        ];
+        for transaction in &events {
+            store.apply(transaction).unwrap();
+        }
+        assert_eq!(
+            store.last_signature().unwrap(),
+            Some("signature4".to_string())
+        );
+
+        let timeline = store.timeline(&escrow).unwrap();
+        let states: Vec<(&str, Option<&str>)> = timeline
+            .iter()
+            .map(|entry| (entry.kind.as_str(), entry.state.as_deref()))
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                ("initialized", Some(STATE_LAUNCHED)),
+                ("setup", Some(STATE_PENDING)),
+                ("results", None),
+                ("payout", Some(STATE_PARTIAL)),
+                ("payout", Some(STATE_PAID)),
+            ]
+        );
+
+        assert_eq!(
+            store.recipient_payouts(Some(&recipient)).unwrap(),
+            vec![RecipientPayouts {
+                recipient: recipient.to_string(),
+                payouts: 2,
+                escrows: 1,
+                amount: 100,
+            }]
+        );
+        assert_eq!(
+            store.launcher_totals(None).unwrap(),
+            vec![LauncherTotals {
+                launcher: launcher.to_string(),
+                escrows: 1,
+                completed: 0,
+                cancelled: 0,
+                total_amount: 100,
+                sent_amount: 100,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_expiring_escrows() {
+        let mut store = Store::open_in_memory().unwrap();
+        let launcher = Pubkey::new_unique();
+        let soon = Pubkey::new_unique();
+        let later = Pubkey::new_unique();
+        let cancelled = Pubkey::new_unique();
+        let template = Pubkey::new_unique();
+        let from_template = Pubkey::new_unique();
+        store
+            .apply(&transaction(
+                1,
+                1000,
+                vec![
+                    initialized(soon, launcher, 100),
+                    initialized(later, launcher, 10_000),
+                    initialized(cancelled, launcher, 100),
+                    EscrowEvent::Cancelled { escrow: cancelled },
+                    EscrowEvent::TemplateInitialized {
+                        template,
+                        duration: 200,
+                    },
+                    EscrowEvent::Initialized {
+                        escrow: from_template,
+                        launcher,
+                        canceler: launcher,
+                        token_mint: Pubkey::new_unique(),
+                        duration: None,
+                        template: Some(template),
+                    },
+                ],
+            ))
+            .unwrap();
+
+        let expiring: Vec<(String, String, i64)> = store
+            .expiring(1000, 1500)
+            .unwrap()
+            .into_iter()
+            .map(|escrow| (escrow.address, escrow.state, escrow.expires))
+            .collect();
+        assert_eq!(
+            expiring,
+            vec![
+                (soon.to_string(), STATE_LAUNCHED.to_string(), 1100),
+                (from_template.to_string(), STATE_PENDING.to_string(), 1200),
+            ]
+        );
+        assert!(store.expiring(1200, 1500).unwrap().is_empty());
+
+        let totals = store.launcher_totals(Some(&launcher)).unwrap();
+        assert_eq!(totals[0].escrows, 4);
+        assert_eq!(totals[0].cancelled, 1);
+    }
+}
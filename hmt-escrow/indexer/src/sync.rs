@@ -0,0 +1,242 @@
+//! Walks the confirmed transactions of the escrow program into the store
+
+use crate::{
+    decode::decode_message,
+    store::{IndexedTransaction, Store},
+    Error,
+};
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_program::{clock::UnixTimestamp, pubkey::Pubkey};
+use solana_sdk::{message::Message, signature::Signature};
+use solana_transaction_status::UiTransactionEncoding;
+use std::str::FromStr;
+
+/// Signature of a confirmed transaction mentioning the program
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignatureInfo {
+    /// Transaction signature
+    pub signature: String,
+    /// Slot the transaction was confirmed in
+    pub slot: u64,
+    /// Transaction failed, its instructions changed nothing
+    pub failed: bool,
+}
+
+/// Source of the confirmed program transactions
+pub trait TransactionSource {
+    /// Up to `limit` signatures older than `before` and newer than `until`, newest first
+    fn signatures(
+        &self,
+        program_id: &Pubkey,
+        before: Option<&str>,
+        until: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<SignatureInfo>, Error>;
+
+    /// Message of the confirmed transaction
+    fn message(&self, signature: &str) -> Result<Option<Message>, Error>;
+
+    /// Estimated production time of the slot
+    fn block_time(&self, slot: u64) -> Option<UnixTimestamp>;
+}
+
+impl TransactionSource for RpcClient {
+    fn signatures(
+        &self,
+        program_id: &Pubkey,
+        before: Option<&str>,
+        until: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<SignatureInfo>, Error> {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before: before.map(Signature::from_str).transpose()?,
+            until: until.map(Signature::from_str).transpose()?,
+            limit: Some(limit),
+        };
+        Ok(self
+            .get_confirmed_signatures_for_address2_with_config(program_id, config)?
+            .into_iter()
+            .map(|status| SignatureInfo {
+                signature: status.signature,
+                slot: status.slot,
+                failed: status.err.is_some(),
+            })
+            .collect())
+    }
+
+    fn message(&self, signature: &str) -> Result<Option<Message>, Error> {
+        let transaction = self.get_confirmed_transaction(
+            &Signature::from_str(signature)?,
+            UiTransactionEncoding::Base64,
+        )?;
+        Ok(transaction
+            .transaction
+            .transaction
+            .decode()
+            .map(|transaction| transaction.message))
+    }
+
+    fn block_time(&self, slot: u64) -> Option<UnixTimestamp> {
+        // Nodes without the block time of old slots still index the events
+        self.get_block_time(slot).ok()
+    }
+}
+
+/// Indexes program transactions confirmed after the last indexed one, oldest first.
+/// Signatures are requested in pages of `page_size`, returns the number of indexed transactions.
+pub fn sync(
+    source: &dyn TransactionSource,
+    store: &mut Store,
+    program_id: &Pubkey,
+    page_size: usize,
+) -> Result<usize, Error> {
+    let until = store.last_signature()?;
+
+    // Signatures come newest first, collect them all before applying
+    let mut pending: Vec<SignatureInfo> = vec![];
+    loop {
+        let before = pending.last().map(|info| info.signature.as_str());
+        let page = source.signatures(program_id, before, until.as_deref(), page_size)?;
+        let done = page.len() < page_size;
+        pending.extend(page);
+        if done {
+            break;
+        }
+    }
+
+    for info in pending.iter().rev() {
+        let events = if info.failed {
+            vec![]
+        } else {
+            source
+                .message(&info.signature)?
+                .map(|message| decode_message(program_id, &message))
+                .unwrap_or_default()
+        };
+        store.apply(&IndexedTransaction {
+            signature: info.signature.clone(),
+            slot: info.slot,
+            block_time: source.block_time(info.slot),
+            events,
+        })?;
+    }
+    Ok(pending.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hmt_escrow::instruction::{complete, initialize, pause};
+    use solana_program::instruction::Instruction;
+
+    /// Transactions kept in memory, oldest first
+    struct MemorySource {
+        transactions: Vec<(SignatureInfo, Message)>,
+    }
+
+    impl TransactionSource for MemorySource {
+        fn signatures(
+            &self,
+            _program_id: &Pubkey,
+            before: Option<&str>,
+            until: Option<&str>,
+            limit: usize,
+        ) -> Result<Vec<SignatureInfo>, Error> {
+            Ok(self
+                .transactions
+                .iter()
+                .rev()
+                .map(|(info, _)| info)
+                .skip_while(|info| before.map_or(false, |before| info.signature != before))
+                .skip(if before.is_some() { 1 } else { 0 })
+                .take_while(|info| until.map_or(true, |until| info.signature != until))
+                .take(limit)
+                .cloned()
+                .collect())
+        }
+
+        fn message(&self, signature: &str) -> Result<Option<Message>, Error> {
+            Ok(self
+                .transactions
+                .iter()
+                .find(|(info, _)| info.signature == signature)
+                .map(|(_, message)| message.clone()))
+        }
+
+        fn block_time(&self, slot: u64) -> Option<UnixTimestamp> {
+            Some(1000 + slot as UnixTimestamp)
+        }
+    }
+
+    #[test]
+    fn test_sync_resumes_after_last_signature() {
+        let program_id = Pubkey::new_unique();
+        let escrow = Pubkey::new_unique();
+        let launcher = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let initialize = initialize(
+            &program_id,
+            &escrow,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &launcher,
+            &launcher,
+            &Pubkey::new_unique(),
+            &launcher,
+            &Pubkey::new_unique(),
+            600,
+        )
+        .unwrap();
+        let pause = pause(&program_id, &escrow, &launcher).unwrap();
+        let complete = complete(&program_id, &escrow, &launcher).unwrap();
+        let transaction = |slot: u64, failed: bool, instruction: Instruction| {
+            (
+                SignatureInfo {
+                    signature: format!("signature{}", slot),
+                    slot,
+                    failed,
+                },
+                Message::new(&[instruction], Some(&payer)),
+            )
+        };
+
+        let mut source = MemorySource {
+            transactions: vec![
+                transaction(1, false, initialize),
+                transaction(2, true, complete.clone()),
+                transaction(3, false, pause),
+            ],
+        };
+        let mut store = Store::open_in_memory().unwrap();
+        assert_eq!(sync(&source, &mut store, &program_id, 2).unwrap(), 3);
+        assert_eq!(sync(&source, &mut store, &program_id, 2).unwrap(), 0);
+
+        source.transactions.push(transaction(4, false, complete));
+        assert_eq!(sync(&source, &mut store, &program_id, 2).unwrap(), 1);
+        assert_eq!(
+            store.last_signature().unwrap(),
+            Some("signature4".to_string())
+        );
+
+        // Failed complete is not in the timeline
+        let timeline: Vec<(String, u64, Option<UnixTimestamp>)> = store
+            .timeline(&escrow)
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.kind, entry.slot, entry.block_time))
+            .collect();
+        assert_eq!(
+            timeline,
+            vec![
+                ("initialized".to_string(), 1, Some(1001)),
+                ("paused".to_string(), 3, Some(1003)),
+                ("completed".to_string(), 4, Some(1004)),
+            ]
+        );
+        assert_eq!(
+            store.expiring(1001, 1601).unwrap().len(),
+            0,
+            "completed escrow is not expiring"
+        );
+    }
+}
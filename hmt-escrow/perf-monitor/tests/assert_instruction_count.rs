@@ -0,0 +1,1143 @@
+#![cfg(feature = "test-bpf")]
+
+use hmt_escrow::{
+    id,
+    instruction::{self, ReputationUpdate, VaultRefund},
+    processor::Processor,
+    secp256k1::{ETH_ADDRESS_LEN, ETH_ADDRESS_OFFSET},
+    state::{
+        AllowList, DataHash, DataUrl, Escrow, EscrowTemplate, MAX_ALLOW_LIST_UPDATES,
+        MAX_REPUTATION_UPDATES,
+    },
+};
+use secp256k1::SecretKey;
+use solana_program::{
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    instruction::Instruction,
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program, sysvar,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    secp256k1::new_secp256k1_instruction,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+    transport::TransportError,
+};
+use spl_token::state::{Account as TokenAccount, Mint};
+use std::{collections::BTreeMap, str::FromStr};
+
+const DECIMALS: u8 = 9;
+const TOKENS: u64 = 1_000_000_000;
+const DURATION: u64 = 86400;
+const LAMPORTS: u64 = 10_000_000_000;
+const ALLOW_LIST_CAPACITY: usize = 256;
+
+// Compute units consumed by each instruction, token program calls included.
+// The suite fails when an instruction needs more or when it leaves more than
+// BASELINE_SLACK_PERCENT of the baseline unused, update them to the measured units.
+const BASELINE_SLACK_PERCENT: u64 = 10;
+const BASELINE_INITIALIZE_CONFIG: u64 = 40_000;
+const BASELINE_UPDATE_CONFIG: u64 = 20_000;
+const BASELINE_UPDATE_STAKING_CONFIG: u64 = 15_000;
+const BASELINE_INITIALIZE_ORACLE_STAKE: u64 = 50_000;
+const BASELINE_DEPOSIT_ORACLE_STAKE: u64 = 40_000;
+const BASELINE_WITHDRAW_ORACLE_STAKE: u64 = 40_000;
+const BASELINE_INITIALIZE: u64 = 40_000;
+const BASELINE_PAUSE: u64 = 10_000;
+const BASELINE_SETUP: u64 = 60_000;
+const BASELINE_STORE_INTERMEDIATE_RESULTS: u64 = 60_000;
+const BASELINE_STORE_RESULTS: u64 = 30_000;
+const BASELINE_PAYOUT: u64 = 130_000;
+const BASELINE_SET_PERMIT_SIGNER: u64 = 10_000;
+const BASELINE_PAYOUT_WITH_PERMIT: u64 = 140_000;
+const BASELINE_UPDATE_REPUTATION: u64 = 200_000;
+const BASELINE_SLASH_ORACLE: u64 = 40_000;
+const BASELINE_COMPLETE: u64 = 15_000;
+const BASELINE_CANCEL: u64 = 40_000;
+const BASELINE_INITIALIZE_ALLOW_LIST: u64 = 20_000;
+const BASELINE_UPDATE_ALLOW_LIST: u64 = 150_000;
+const BASELINE_ADD_VAULT: u64 = 50_000;
+const BASELINE_STORE_VAULT_RESULTS: u64 = 20_000;
+const BASELINE_VAULT_PAYOUT: u64 = 100_000;
+const BASELINE_CANCEL_VAULT: u64 = 60_000;
+const BASELINE_INITIALIZE_TEMPLATE: u64 = 30_000;
+const BASELINE_INITIALIZE_FROM_TEMPLATE: u64 = 100_000;
+
+/// Accounts carried over from one bank to the next. Every transaction runs in a fresh
+/// bank, so the compute budget is applied to the measured instruction alone.
+#[derive(Default)]
+struct Monitor {
+    accounts: BTreeMap<Pubkey, Account>,
+}
+
+impl Monitor {
+    fn fund(&mut self, key: &Pubkey) {
+        self.accounts
+            .insert(*key, Account::new(LAMPORTS, 0, &system_program::id()));
+    }
+
+    async fn process(
+        &mut self,
+        budget: Option<u64>,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), TransportError> {
+        let mut program_test = ProgramTest::new("hmt_escrow", id(), None);
+        program_test.add_program("spl_token", spl_token::id(), None);
+        if let Some(budget) = budget {
+            program_test.set_bpf_compute_max_units(budget);
+        }
+        for (key, account) in self.accounts.iter() {
+            program_test.add_account(*key, account.clone());
+        }
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+        let mut all_signers: Vec<&dyn Signer> = vec![&payer];
+        for signer in signers {
+            all_signers.push(*signer);
+        }
+        transaction.sign(&all_signers, recent_blockhash);
+        banks_client.process_transaction(transaction).await?;
+
+        let keys = instructions
+            .iter()
+            .flat_map(|instruction| instruction.accounts.iter())
+            .map(|meta| meta.pubkey)
+            .filter(|key| *key != payer.pubkey());
+        for key in keys {
+            match banks_client.get_account(key).await? {
+                Some(account) if account.executable || account.owner == sysvar::id() => {}
+                Some(account) => {
+                    self.accounts.insert(key, account);
+                }
+                None => {
+                    self.accounts.remove(&key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn prepare(&mut self, instructions: &[Instruction], signers: &[&Keypair]) {
+        self.process(None, instructions, signers).await.unwrap();
+    }
+
+    /// Finds the compute units the instructions consume by bisecting the budget,
+    /// every attempt starts from the same accounts and the smallest budget they
+    /// succeed with is the consumed units. Accounts are carried over from the run
+    /// within the baseline.
+    async fn measure(
+        &mut self,
+        name: &str,
+        baseline: u64,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) {
+        let accounts = self.accounts.clone();
+        if let Err(error) = self.process(Some(baseline), instructions, signers).await {
+            panic!("{} does not fit into {} units: {:?}", name, baseline, error);
+        }
+        let processed_accounts = std::mem::replace(&mut self.accounts, accounts.clone());
+
+        let (mut failed, mut consumed) = (0, baseline);
+        while consumed - failed > 1 {
+            let budget = failed + (consumed - failed) / 2;
+            self.accounts = accounts.clone();
+            let result = self.process(Some(budget), instructions, signers).await;
+            if result.is_ok() {
+                consumed = budget;
+            } else {
+                failed = budget;
+            }
+        }
+        self.accounts = processed_accounts;
+
+        println!("  {:<40}: {:?}", name, consumed);
+        assert!(
+            consumed * 100 >= baseline * (100 - BASELINE_SLACK_PERCENT),
+            "{} consumes {} units, lower its baseline {}",
+            name,
+            consumed,
+            baseline
+        );
+    }
+}
+
+fn create_account(funder: &Keypair, account: &Keypair, len: usize, owner: &Pubkey) -> Instruction {
+    system_instruction::create_account(
+        &funder.pubkey(),
+        &account.pubkey(),
+        Rent::default().minimum_balance(len),
+        len as u64,
+        owner,
+    )
+}
+
+async fn create_mint(monitor: &mut Monitor, funder: &Keypair, mint: &Keypair, owner: &Pubkey) {
+    monitor
+        .prepare(
+            &[
+                create_account(funder, mint, Mint::LEN, &spl_token::id()),
+                spl_token::instruction::initialize_mint(
+                    &spl_token::id(),
+                    &mint.pubkey(),
+                    owner,
+                    None,
+                    DECIMALS,
+                )
+                .unwrap(),
+            ],
+            &[funder, mint],
+        )
+        .await;
+}
+
+async fn create_token_account(
+    monitor: &mut Monitor,
+    funder: &Keypair,
+    account: &Keypair,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) {
+    monitor
+        .prepare(
+            &[
+                create_account(funder, account, TokenAccount::LEN, &spl_token::id()),
+                spl_token::instruction::initialize_account(
+                    &spl_token::id(),
+                    &account.pubkey(),
+                    mint,
+                    owner,
+                )
+                .unwrap(),
+            ],
+            &[funder, account],
+        )
+        .await;
+}
+
+async fn mint_to(
+    monitor: &mut Monitor,
+    mint: &Pubkey,
+    account: &Pubkey,
+    owner: &Keypair,
+    amount: u64,
+) {
+    monitor
+        .prepare(
+            &[spl_token::instruction::mint_to(
+                &spl_token::id(),
+                mint,
+                account,
+                &owner.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap()],
+            &[owner],
+        )
+        .await;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn initialize_escrow(
+    funder: &Keypair,
+    escrow: &Keypair,
+    token_mint: &Pubkey,
+    token_account: &Pubkey,
+    launcher: &Pubkey,
+    canceler: &Pubkey,
+    canceler_token_account: &Pubkey,
+    guardian: &Pubkey,
+) -> Vec<Instruction> {
+    vec![
+        create_account(funder, escrow, Escrow::LEN, &id()),
+        instruction::initialize(
+            &id(),
+            &escrow.pubkey(),
+            token_mint,
+            token_account,
+            launcher,
+            canceler,
+            canceler_token_account,
+            guardian,
+            &Processor::find_config_address(&id()).0,
+            DURATION,
+        )
+        .unwrap(),
+    ]
+}
+
+#[tokio::test]
+async fn assert_instruction_count() {
+    let mut monitor = Monitor::default();
+    let funder = Keypair::new();
+    let admin = Keypair::new();
+    let mint = Keypair::new();
+    let bonus_mint = Keypair::new();
+    let mint_authority = Keypair::new();
+    let launcher = Keypair::new();
+    let canceler = Keypair::new();
+    let guardian = Keypair::new();
+    let reputation_oracle = Keypair::new();
+    let recording_oracle = Keypair::new();
+    for key in [
+        &funder,
+        &admin,
+        &launcher,
+        &reputation_oracle,
+        &recording_oracle,
+    ]
+    .iter()
+    {
+        monitor.fund(&key.pubkey());
+    }
+
+    // The admin is the upgrade authority of the escrow program
+    monitor.accounts.insert(
+        Processor::find_program_data_address(&id()),
+        Account {
+            lamports: LAMPORTS,
+            data: bincode::serialize(&UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(admin.pubkey()),
+            })
+            .unwrap(),
+            owner: bpf_loader_upgradeable::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    println!("BPF compute units budget");
+
+    // Program config with staking enabled for all escrows
+    let config = Processor::find_config_address(&id()).0;
+    monitor
+        .measure(
+            "InitializeConfig",
+            BASELINE_INITIALIZE_CONFIG,
+            &[instruction::initialize_config(
+                &id(),
+                &config,
+                &admin.pubkey(),
+                &Processor::find_program_data_address(&id()),
+                &admin.pubkey(),
+                50,
+                60,
+                2592000,
+                &[mint.pubkey()],
+            )
+            .unwrap()],
+            &[&admin],
+        )
+        .await;
+    monitor
+        .measure(
+            "UpdateConfig",
+            BASELINE_UPDATE_CONFIG,
+            &[instruction::update_config(
+                &id(),
+                &config,
+                &admin.pubkey(),
+                &admin.pubkey(),
+                false,
+                50,
+                60,
+                2592000,
+                &[mint.pubkey(), bonus_mint.pubkey()],
+            )
+            .unwrap()],
+            &[&admin],
+        )
+        .await;
+    monitor
+        .measure(
+            "UpdateStakingConfig",
+            BASELINE_UPDATE_STAKING_CONFIG,
+            &[instruction::update_staking_config(
+                &id(),
+                &config,
+                &admin.pubkey(),
+                COption::Some(mint.pubkey()),
+                100 * TOKENS,
+                TOKENS,
+                COption::Some(admin.pubkey()),
+            )
+            .unwrap()],
+            &[&admin],
+        )
+        .await;
+
+    create_mint(&mut monitor, &funder, &mint, &mint_authority.pubkey()).await;
+    create_mint(&mut monitor, &funder, &bonus_mint, &mint_authority.pubkey()).await;
+
+    // Oracle stakes, the escrow below is large enough to lock them
+    let mut oracle_stakes = vec![];
+    for oracle in [&reputation_oracle, &recording_oracle].iter() {
+        let oracle_stake = Processor::find_oracle_stake_address(&id(), &oracle.pubkey()).0;
+        let vault = Keypair::new();
+        let source = Keypair::new();
+        create_token_account(&mut monitor, &funder, &vault, &mint.pubkey(), &oracle_stake).await;
+        create_token_account(
+            &mut monitor,
+            &funder,
+            &source,
+            &mint.pubkey(),
+            &oracle.pubkey(),
+        )
+        .await;
+        mint_to(
+            &mut monitor,
+            &mint.pubkey(),
+            &source.pubkey(),
+            &mint_authority,
+            200 * TOKENS,
+        )
+        .await;
+
+        monitor
+            .measure(
+                "InitializeOracleStake",
+                BASELINE_INITIALIZE_ORACLE_STAKE,
+                &[instruction::initialize_oracle_stake(
+                    &id(),
+                    &oracle_stake,
+                    &oracle.pubkey(),
+                    &vault.pubkey(),
+                    &config,
+                )
+                .unwrap()],
+                &[*oracle],
+            )
+            .await;
+        monitor
+            .measure(
+                "DepositOracleStake",
+                BASELINE_DEPOSIT_ORACLE_STAKE,
+                &[instruction::deposit_oracle_stake(
+                    &id(),
+                    &oracle_stake,
+                    &vault.pubkey(),
+                    &source.pubkey(),
+                    &oracle.pubkey(),
+                    &spl_token::id(),
+                    200 * TOKENS,
+                )
+                .unwrap()],
+                &[*oracle],
+            )
+            .await;
+        monitor
+            .measure(
+                "WithdrawOracleStake",
+                BASELINE_WITHDRAW_ORACLE_STAKE,
+                &[instruction::withdraw_oracle_stake(
+                    &id(),
+                    &oracle_stake,
+                    &oracle.pubkey(),
+                    &vault.pubkey(),
+                    &source.pubkey(),
+                    &spl_token::id(),
+                    50 * TOKENS,
+                )
+                .unwrap()],
+                &[*oracle],
+            )
+            .await;
+        oracle_stakes.push((oracle_stake, vault));
+    }
+
+    // Nearly full allow-list, measured update inserts in front of every owner
+    let allow_list = Keypair::new();
+    let compliance_authority = Keypair::new();
+    monitor
+        .measure(
+            "InitializeAllowList",
+            BASELINE_INITIALIZE_ALLOW_LIST,
+            &[
+                create_account(
+                    &funder,
+                    &allow_list,
+                    AllowList::account_len(ALLOW_LIST_CAPACITY),
+                    &id(),
+                ),
+                instruction::initialize_allow_list(
+                    &id(),
+                    &allow_list.pubkey(),
+                    &compliance_authority.pubkey(),
+                )
+                .unwrap(),
+            ],
+            &[&funder, &allow_list, &compliance_authority],
+        )
+        .await;
+    let recipient_owners: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let mut owners = recipient_owners.clone();
+    owners.resize_with(ALLOW_LIST_CAPACITY - MAX_ALLOW_LIST_UPDATES, || {
+        let mut key = Pubkey::new_unique().to_bytes();
+        key[0] |= 0x80;
+        Pubkey::new_from_array(key)
+    });
+    for batch in owners.chunks(MAX_ALLOW_LIST_UPDATES) {
+        monitor
+            .prepare(
+                &[instruction::update_allow_list(
+                    &id(),
+                    &allow_list.pubkey(),
+                    &compliance_authority.pubkey(),
+                    batch,
+                    &[],
+                )
+                .unwrap()],
+                &[&compliance_authority],
+            )
+            .await;
+    }
+    let first_owners: Vec<Pubkey> = (0..MAX_ALLOW_LIST_UPDATES)
+        .map(|index| Pubkey::new_from_array([index as u8; 32]))
+        .collect();
+    monitor
+        .measure(
+            "UpdateAllowList",
+            BASELINE_UPDATE_ALLOW_LIST,
+            &[instruction::update_allow_list(
+                &id(),
+                &allow_list.pubkey(),
+                &compliance_authority.pubkey(),
+                &first_owners,
+                &[],
+            )
+            .unwrap()],
+            &[&compliance_authority],
+        )
+        .await;
+
+    // Escrow and the token accounts it pays to
+    let escrow = Keypair::new();
+    let escrow_token_account = Keypair::new();
+    let escrow_authority = Processor::find_authority_bump_seed(&id(), &escrow.pubkey()).0;
+    let canceler_token_account = Keypair::new();
+    let reputation_oracle_token_account = Keypair::new();
+    let recording_oracle_token_account = Keypair::new();
+    for (account, owner) in [
+        (&escrow_token_account, escrow_authority),
+        (&canceler_token_account, canceler.pubkey()),
+        (&reputation_oracle_token_account, reputation_oracle.pubkey()),
+        (&recording_oracle_token_account, recording_oracle.pubkey()),
+    ]
+    .iter()
+    {
+        create_token_account(&mut monitor, &funder, account, &mint.pubkey(), owner).await;
+    }
+    mint_to(
+        &mut monitor,
+        &mint.pubkey(),
+        &escrow_token_account.pubkey(),
+        &mint_authority,
+        1000 * TOKENS,
+    )
+    .await;
+
+    monitor
+        .measure(
+            "Initialize",
+            BASELINE_INITIALIZE,
+            &initialize_escrow(
+                &funder,
+                &escrow,
+                &mint.pubkey(),
+                &escrow_token_account.pubkey(),
+                &launcher.pubkey(),
+                &canceler.pubkey(),
+                &canceler_token_account.pubkey(),
+                &guardian.pubkey(),
+            ),
+            &[&funder, &escrow],
+        )
+        .await;
+    monitor
+        .measure(
+            "Pause",
+            BASELINE_PAUSE,
+            &[instruction::pause(&id(), &escrow.pubkey(), &guardian.pubkey()).unwrap()],
+            &[&guardian],
+        )
+        .await;
+    monitor
+        .measure(
+            "Unpause",
+            BASELINE_PAUSE,
+            &[instruction::unpause(&id(), &escrow.pubkey(), &guardian.pubkey()).unwrap()],
+            &[&guardian],
+        )
+        .await;
+
+    // Bonus vault funded and paid out alongside the escrow
+    let vault = Processor::find_vault_address(&id(), &escrow.pubkey(), &bonus_mint.pubkey()).0;
+    let vault_token_account = Keypair::new();
+    let canceler_bonus_account = Keypair::new();
+    let reputation_oracle_bonus_account = Keypair::new();
+    let recording_oracle_bonus_account = Keypair::new();
+    for (account, owner) in [
+        (&vault_token_account, escrow_authority),
+        (&canceler_bonus_account, canceler.pubkey()),
+        (&reputation_oracle_bonus_account, reputation_oracle.pubkey()),
+        (&recording_oracle_bonus_account, recording_oracle.pubkey()),
+    ]
+    .iter()
+    {
+        create_token_account(&mut monitor, &funder, account, &bonus_mint.pubkey(), owner).await;
+    }
+    mint_to(
+        &mut monitor,
+        &bonus_mint.pubkey(),
+        &vault_token_account.pubkey(),
+        &mint_authority,
+        100 * TOKENS,
+    )
+    .await;
+    monitor
+        .measure(
+            "AddVault",
+            BASELINE_ADD_VAULT,
+            &[instruction::add_vault(
+                &id(),
+                &escrow.pubkey(),
+                &launcher.pubkey(),
+                &vault,
+                &bonus_mint.pubkey(),
+                &vault_token_account.pubkey(),
+                &canceler_bonus_account.pubkey(),
+                &config,
+            )
+            .unwrap()],
+            &[&launcher],
+        )
+        .await;
+
+    monitor
+        .measure(
+            "Setup (locks oracle stakes)",
+            BASELINE_SETUP,
+            &[instruction::setup(
+                &id(),
+                &escrow.pubkey(),
+                &launcher.pubkey(),
+                &reputation_oracle.pubkey(),
+                &reputation_oracle_token_account.pubkey(),
+                10,
+                &recording_oracle.pubkey(),
+                &recording_oracle_token_account.pubkey(),
+                15,
+                &DataUrl::from_str("http://manifest.example.com/manifest.json").unwrap(),
+                &DataHash::new_from_array([1; 20]),
+                100 * TOKENS,
+                TOKENS,
+                0,
+                0,
+                0,
+                &config,
+                &escrow_token_account.pubkey(),
+                &oracle_stakes[0].0,
+                &oracle_stakes[1].0,
+                Some(&allow_list.pubkey()),
+            )
+            .unwrap()],
+            &[&launcher],
+        )
+        .await;
+
+    // First intermediate results also create the results log and its first page
+    let results_log = Processor::find_results_log_address(&id(), &escrow.pubkey()).0;
+    let results_log_page = Processor::find_results_log_page_address(&id(), &escrow.pubkey(), 0).0;
+    for name in [
+        "StoreIntermediateResults (new log)",
+        "StoreIntermediateResults",
+    ]
+    .iter()
+    {
+        monitor
+            .measure(
+                name,
+                BASELINE_STORE_INTERMEDIATE_RESULTS,
+                &[instruction::store_intermediate_results(
+                    &id(),
+                    &escrow.pubkey(),
+                    &results_log,
+                    &results_log_page,
+                    &recording_oracle.pubkey(),
+                    &DataUrl::from_str("http://results.example.com/intermediate.json").unwrap(),
+                    &DataHash::new_from_array([2; 20]),
+                )
+                .unwrap()],
+                &[&recording_oracle],
+            )
+            .await;
+    }
+
+    monitor
+        .measure(
+            "StoreResults",
+            BASELINE_STORE_RESULTS,
+            &[instruction::store_results(
+                &id(),
+                &escrow.pubkey(),
+                &launcher.pubkey(),
+                300 * TOKENS,
+                3,
+                &DataUrl::from_str("http://results.example.com/final.json").unwrap(),
+                &DataHash::new_from_array([3; 20]),
+                &results_log,
+                &config,
+                &Processor::find_oracle_stake_address(&id(), &reputation_oracle.pubkey()).0,
+                &Processor::find_oracle_stake_address(&id(), &recording_oracle.pubkey()).0,
+            )
+            .unwrap()],
+            &[&launcher],
+        )
+        .await;
+
+    monitor
+        .measure(
+            "StoreVaultResults",
+            BASELINE_STORE_VAULT_RESULTS,
+            &[instruction::store_vault_results(
+                &id(),
+                &escrow.pubkey(),
+                &launcher.pubkey(),
+                &vault,
+                100 * TOKENS,
+            )
+            .unwrap()],
+            &[&launcher],
+        )
+        .await;
+
+    // Payouts with both oracle fees, three token transfers each, and the first
+    // payout to the owner creating its recipient payout account
+    let mut recipients = vec![];
+    let mut recipient_payouts = vec![];
+    for owner in recipient_owners.iter() {
+        let recipient = Keypair::new();
+        create_token_account(&mut monitor, &funder, &recipient, &mint.pubkey(), owner).await;
+        recipients.push(recipient.pubkey());
+        recipient_payouts
+            .push(Processor::find_recipient_payout_address(&id(), &escrow.pubkey(), owner).0);
+    }
+    let payout = |index: usize| {
+        instruction::payout(
+            &id(),
+            &escrow.pubkey(),
+            &launcher.pubkey(),
+            &escrow_token_account.pubkey(),
+            &escrow_authority,
+            &recipients[index],
+            &reputation_oracle_token_account.pubkey(),
+            &recording_oracle_token_account.pubkey(),
+            &spl_token::id(),
+            100 * TOKENS,
+            Some(&allow_list.pubkey()),
+            Some((&recipient_payouts[index], &launcher.pubkey())),
+        )
+        .unwrap()
+    };
+    monitor
+        .measure("Payout", BASELINE_PAYOUT, &[payout(0)], &[&launcher])
+        .await;
+
+    // Permit signed by the reputation oracle secp256k1 key
+    let permit_key = SecretKey::parse(&[5; 32]).unwrap();
+    let mut permit_signer = [0; ETH_ADDRESS_LEN];
+    permit_signer.copy_from_slice(
+        &new_secp256k1_instruction(&permit_key, &[]).data
+            [ETH_ADDRESS_OFFSET..ETH_ADDRESS_OFFSET + ETH_ADDRESS_LEN],
+    );
+    monitor
+        .measure(
+            "SetPermitSigner",
+            BASELINE_SET_PERMIT_SIGNER,
+            &[instruction::set_permit_signer(
+                &id(),
+                &escrow.pubkey(),
+                &launcher.pubkey(),
+                &permit_signer,
+            )
+            .unwrap()],
+            &[&launcher],
+        )
+        .await;
+
+    let message = instruction::payout_permit_message(
+        &escrow.pubkey(),
+        &recipients[1],
+        100 * TOKENS,
+        0,
+        i64::MAX,
+    );
+    monitor
+        .measure(
+            "PayoutWithPermit",
+            BASELINE_PAYOUT_WITH_PERMIT,
+            &[
+                new_secp256k1_instruction(&permit_key, &message),
+                instruction::payout_with_permit(
+                    &id(),
+                    &escrow.pubkey(),
+                    &escrow_token_account.pubkey(),
+                    &escrow_authority,
+                    &recipients[1],
+                    &reputation_oracle_token_account.pubkey(),
+                    &recording_oracle_token_account.pubkey(),
+                    &spl_token::id(),
+                    100 * TOKENS,
+                    0,
+                    i64::MAX,
+                    Some(&allow_list.pubkey()),
+                    Some((&recipient_payouts[1], &funder.pubkey())),
+                )
+                .unwrap(),
+            ],
+            &[&funder],
+        )
+        .await;
+
+    let bonus_recipient = Keypair::new();
+    create_token_account(
+        &mut monitor,
+        &funder,
+        &bonus_recipient,
+        &bonus_mint.pubkey(),
+        &recipient_owners[0],
+    )
+    .await;
+    monitor
+        .measure(
+            "VaultPayout",
+            BASELINE_VAULT_PAYOUT,
+            &[instruction::vault_payout(
+                &id(),
+                &escrow.pubkey(),
+                &launcher.pubkey(),
+                &vault,
+                &vault_token_account.pubkey(),
+                &escrow_authority,
+                &bonus_recipient.pubkey(),
+                &reputation_oracle_bonus_account.pubkey(),
+                &recording_oracle_bonus_account.pubkey(),
+                &spl_token::id(),
+                100 * TOKENS,
+                Some(&allow_list.pubkey()),
+            )
+            .unwrap()],
+            &[&launcher],
+        )
+        .await;
+
+    // Largest reputation update, every worker account is created
+    let updates: Vec<ReputationUpdate> = (0..MAX_REPUTATION_UPDATES)
+        .map(|_| ReputationUpdate {
+            worker: Pubkey::new_unique(),
+            delta: 1,
+        })
+        .collect();
+    let reputation_accounts: Vec<Pubkey> = updates
+        .iter()
+        .map(|update| Processor::find_reputation_address(&id(), &update.worker).0)
+        .collect();
+    let reputation_job_accounts: Vec<Pubkey> = updates
+        .iter()
+        .map(|update| {
+            Processor::find_reputation_job_address(&id(), &update.worker, &escrow.pubkey()).0
+        })
+        .collect();
+    monitor
+        .measure(
+            "UpdateReputation (new accounts)",
+            BASELINE_UPDATE_REPUTATION,
+            &[instruction::update_reputation(
+                &id(),
+                &escrow.pubkey(),
+                &reputation_oracle.pubkey(),
+                &reputation_accounts,
+                &reputation_job_accounts,
+                &updates,
+            )
+            .unwrap()],
+            &[&reputation_oracle],
+        )
+        .await;
+
+    monitor
+        .measure(
+            "SlashOracle",
+            BASELINE_SLASH_ORACLE,
+            &[instruction::slash_oracle(
+                &id(),
+                &config,
+                &admin.pubkey(),
+                &escrow.pubkey(),
+                &oracle_stakes[1].0,
+                &oracle_stakes[1].1.pubkey(),
+                &canceler_token_account.pubkey(),
+                &spl_token::id(),
+                TOKENS,
+            )
+            .unwrap()],
+            &[&admin],
+        )
+        .await;
+
+    monitor
+        .measure("Payout (last)", BASELINE_PAYOUT, &[payout(2)], &[&launcher])
+        .await;
+    monitor
+        .measure(
+            "Complete",
+            BASELINE_COMPLETE,
+            &[instruction::complete(&id(), &escrow.pubkey(), &launcher.pubkey()).unwrap()],
+            &[&launcher],
+        )
+        .await;
+
+    // Cancel returns the whole balance of another escrow
+    let escrow = Keypair::new();
+    let escrow_token_account = Keypair::new();
+    let escrow_authority = Processor::find_authority_bump_seed(&id(), &escrow.pubkey()).0;
+    create_token_account(
+        &mut monitor,
+        &funder,
+        &escrow_token_account,
+        &mint.pubkey(),
+        &escrow_authority,
+    )
+    .await;
+    mint_to(
+        &mut monitor,
+        &mint.pubkey(),
+        &escrow_token_account.pubkey(),
+        &mint_authority,
+        10 * TOKENS,
+    )
+    .await;
+    monitor
+        .prepare(
+            &initialize_escrow(
+                &funder,
+                &escrow,
+                &mint.pubkey(),
+                &escrow_token_account.pubkey(),
+                &launcher.pubkey(),
+                &canceler.pubkey(),
+                &canceler_token_account.pubkey(),
+                &guardian.pubkey(),
+            ),
+            &[&funder, &escrow],
+        )
+        .await;
+    monitor
+        .measure(
+            "Cancel",
+            BASELINE_CANCEL,
+            &[instruction::cancel(
+                &id(),
+                &escrow.pubkey(),
+                &launcher.pubkey(),
+                &escrow_token_account.pubkey(),
+                &escrow_authority,
+                &canceler_token_account.pubkey(),
+                &spl_token::id(),
+                None,
+                &[],
+            )
+            .unwrap()],
+            &[&launcher],
+        )
+        .await;
+
+    // Cancel also refunds a vault of the bonus mint
+    let escrow = Keypair::new();
+    let escrow_token_account = Keypair::new();
+    let escrow_authority = Processor::find_authority_bump_seed(&id(), &escrow.pubkey()).0;
+    let vault = Processor::find_vault_address(&id(), &escrow.pubkey(), &bonus_mint.pubkey()).0;
+    let vault_token_account = Keypair::new();
+    create_token_account(
+        &mut monitor,
+        &funder,
+        &escrow_token_account,
+        &mint.pubkey(),
+        &escrow_authority,
+    )
+    .await;
+    create_token_account(
+        &mut monitor,
+        &funder,
+        &vault_token_account,
+        &bonus_mint.pubkey(),
+        &escrow_authority,
+    )
+    .await;
+    mint_to(
+        &mut monitor,
+        &mint.pubkey(),
+        &escrow_token_account.pubkey(),
+        &mint_authority,
+        10 * TOKENS,
+    )
+    .await;
+    mint_to(
+        &mut monitor,
+        &bonus_mint.pubkey(),
+        &vault_token_account.pubkey(),
+        &mint_authority,
+        10 * TOKENS,
+    )
+    .await;
+    let mut instructions = initialize_escrow(
+        &funder,
+        &escrow,
+        &mint.pubkey(),
+        &escrow_token_account.pubkey(),
+        &launcher.pubkey(),
+        &canceler.pubkey(),
+        &canceler_token_account.pubkey(),
+        &guardian.pubkey(),
+    );
+    instructions.push(
+        instruction::add_vault(
+            &id(),
+            &escrow.pubkey(),
+            &launcher.pubkey(),
+            &vault,
+            &bonus_mint.pubkey(),
+            &vault_token_account.pubkey(),
+            &canceler_bonus_account.pubkey(),
+            &config,
+        )
+        .unwrap(),
+    );
+    monitor
+        .prepare(&instructions, &[&funder, &escrow, &launcher])
+        .await;
+    monitor
+        .measure(
+            "Cancel (one vault)",
+            BASELINE_CANCEL_VAULT,
+            &[instruction::cancel(
+                &id(),
+                &escrow.pubkey(),
+                &launcher.pubkey(),
+                &escrow_token_account.pubkey(),
+                &escrow_authority,
+                &canceler_token_account.pubkey(),
+                &spl_token::id(),
+                None,
+                &[VaultRefund {
+                    vault,
+                    token_account: vault_token_account.pubkey(),
+                    canceler_token_account: canceler_bonus_account.pubkey(),
+                }],
+            )
+            .unwrap()],
+            &[&launcher],
+        )
+        .await;
+
+    // Escrow created and set up from a template in one step
+    let template_account = Keypair::new();
+    let template = EscrowTemplate {
+        is_initialized: true,
+        authority: launcher.pubkey(),
+        token_mint: mint.pubkey(),
+        canceler: canceler.pubkey(),
+        canceler_token_account: canceler_token_account.pubkey(),
+        guardian: guardian.pubkey(),
+        duration: DURATION,
+        reputation_oracle: reputation_oracle.pubkey(),
+        reputation_oracle_token_account: reputation_oracle_token_account.pubkey(),
+        reputation_oracle_stake: 10,
+        recording_oracle: recording_oracle.pubkey(),
+        recording_oracle_token_account: recording_oracle_token_account.pubkey(),
+        recording_oracle_stake: 15,
+        max_payout_per_recipient: 100 * TOKENS,
+        min_payout: TOKENS,
+        cancellation_fee: 0,
+        cancellation_fee_percent: 5,
+        cancel_notice: 0,
+        allow_list: COption::Some(allow_list.pubkey()),
+    };
+    monitor
+        .prepare(
+            &[create_account(
+                &funder,
+                &template_account,
+                EscrowTemplate::LEN,
+                &id(),
+            )],
+            &[&funder, &template_account],
+        )
+        .await;
+    monitor
+        .measure(
+            "InitializeTemplate",
+            BASELINE_INITIALIZE_TEMPLATE,
+            &[
+                instruction::initialize_template(&id(), &template_account.pubkey(), &template)
+                    .unwrap(),
+            ],
+            &[&launcher],
+        )
+        .await;
+
+    let escrow = Keypair::new();
+    let escrow_token_account = Keypair::new();
+    let escrow_authority = Processor::find_authority_bump_seed(&id(), &escrow.pubkey()).0;
+    create_token_account(
+        &mut monitor,
+        &funder,
+        &escrow_token_account,
+        &mint.pubkey(),
+        &escrow_authority,
+    )
+    .await;
+    mint_to(
+        &mut monitor,
+        &mint.pubkey(),
+        &escrow_token_account.pubkey(),
+        &mint_authority,
+        1000 * TOKENS,
+    )
+    .await;
+    monitor
+        .prepare(
+            &[create_account(&funder, &escrow, Escrow::LEN, &id())],
+            &[&funder, &escrow],
+        )
+        .await;
+    monitor
+        .measure(
+            "InitializeFromTemplate (locks oracle stakes)",
+            BASELINE_INITIALIZE_FROM_TEMPLATE,
+            &[instruction::initialize_from_template(
+                &id(),
+                &escrow.pubkey(),
+                &template_account.pubkey(),
+                &template,
+                &escrow_token_account.pubkey(),
+                &config,
+                &oracle_stakes[0].0,
+                &oracle_stakes[1].0,
+                &DataUrl::from_str("http://manifest.example.com/manifest.json").unwrap(),
+                &DataHash::new_from_array([1; 20]),
+            )
+            .unwrap()],
+            &[&launcher],
+        )
+        .await;
+}
@@ -0,0 +1,19 @@
+use hmt_escrow::instruction::EscrowInstruction;
+use honggfuzz::fuzz;
+
+fn main() {
+    loop {
+        fuzz!(|data: &[u8]| {
+            run_fuzz(data);
+        });
+    }
+}
+
+/// Whatever unpacks must pack back into exactly the same bytes
+fn run_fuzz(data: &[u8]) {
+    if let Ok(instruction) = EscrowInstruction::unpack(data) {
+        let packed = instruction.pack();
+        assert_eq!(packed, data);
+        assert_eq!(EscrowInstruction::unpack(&packed), Ok(instruction));
+    }
+}
@@ -0,0 +1,4 @@
+//! Native harness for fuzzing the escrow program outside of the runtime
+
+pub mod native_account_data;
+pub mod native_processor;
@@ -0,0 +1,36 @@
+//! Account data owned by the harness, lent to the program as `AccountInfo`
+
+use solana_program::{account_info::AccountInfo, clock::Epoch, pubkey::Pubkey};
+
+/// Account kept by the harness, signer and writable flags come from the instruction
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NativeAccountData {
+    pub key: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+}
+
+impl NativeAccountData {
+    pub fn new(key: Pubkey, owner: Pubkey, lamports: u64, data: Vec<u8>) -> Self {
+        Self {
+            key,
+            lamports,
+            data,
+            owner,
+        }
+    }
+
+    pub fn as_account_info(&mut self) -> AccountInfo {
+        AccountInfo::new(
+            &self.key,
+            false,
+            false,
+            &mut self.lamports,
+            &mut self.data[..],
+            &self.owner,
+            false,
+            Epoch::default(),
+        )
+    }
+}
@@ -0,0 +1,90 @@
+//! Runs the escrow processor natively, token program calls are processed in place
+
+use crate::native_account_data::NativeAccountData;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, instruction::Instruction,
+    program_error::ProgramError, program_stubs, pubkey::Pubkey,
+};
+use std::sync::Once;
+
+struct NativeSyscallStubs;
+
+impl program_stubs::SyscallStubs for NativeSyscallStubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        // Only the token program is available, system program calls fail like a missing account
+        if instruction.program_id != spl_token::id()
+            || !account_infos
+                .iter()
+                .any(|info| *info.key == spl_token::id())
+        {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        let signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &hmt_escrow::id()))
+            .collect::<Result<Vec<_>, _>>()
+            .or(Err(ProgramError::InvalidSeeds))?;
+        let mut new_account_infos = vec![];
+        for meta in instruction.accounts.iter() {
+            let account_info = account_infos
+                .iter()
+                .find(|info| *info.key == meta.pubkey)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let mut new_account_info = account_info.clone();
+            if signers.contains(account_info.key) {
+                new_account_info.is_signer = true;
+            }
+            new_account_infos.push(new_account_info);
+        }
+
+        spl_token::processor::Processor::process(
+            &instruction.program_id,
+            &new_account_infos,
+            &instruction.data,
+        )
+    }
+}
+
+fn native_syscall_stubs() {
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        program_stubs::set_syscall_stubs(Box::new(NativeSyscallStubs));
+    });
+}
+
+/// Processes the escrow instruction over `accounts`, repeated keys share one account
+/// like they do in the runtime
+pub fn do_process_instruction(
+    instruction: &Instruction,
+    accounts: &mut [NativeAccountData],
+) -> ProgramResult {
+    native_syscall_stubs();
+
+    let account_infos: Vec<AccountInfo> =
+        accounts.iter_mut().map(|a| a.as_account_info()).collect();
+    let mut instruction_infos = vec![];
+    for meta in instruction.accounts.iter() {
+        let mut account_info = account_infos
+            .iter()
+            .find(|info| *info.key == meta.pubkey)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?
+            .clone();
+        account_info.is_signer = meta.is_signer;
+        account_info.is_writable = meta.is_writable;
+        instruction_infos.push(account_info);
+    }
+
+    hmt_escrow::processor::Processor::process(
+        &instruction.program_id,
+        &instruction_infos,
+        &instruction.data,
+    )
+}
@@ -0,0 +1,235 @@
+use arbitrary::Arbitrary;
+use hmt_escrow::{
+    processor::Processor,
+    state::{
+        AllowList, Escrow, EscrowConfig, EscrowState, EscrowTemplate, EscrowVault, OracleStake,
+        RecipientPayout, Reputation, ResultsLog, ResultsLogPage,
+    },
+};
+use hmt_escrow_fuzz::{
+    native_account_data::NativeAccountData, native_processor::do_process_instruction,
+};
+use honggfuzz::fuzz;
+use solana_program::{
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+    sysvar::{self, Sysvar},
+};
+use spl_token::state::{Account as TokenAccount, Mint};
+
+/// Number of plain keys in the key pool, each comes with its program derived addresses
+const KEY_SEEDS: u8 = 8;
+
+/// Layout the account data is resized to, so fuzzed bytes reach the field checks
+#[derive(Clone, Copy, Debug, Arbitrary)]
+enum Layout {
+    Raw,
+    Escrow,
+    Config,
+    OracleStake,
+    Reputation,
+    ResultsLog,
+    ResultsLogPage,
+    RecipientPayout,
+    AllowList,
+    Vault,
+    Template,
+    TokenAccount,
+    Mint,
+    Clock,
+    Rent,
+}
+
+impl Layout {
+    fn len(self, raw_len: usize) -> usize {
+        match self {
+            Layout::Raw => raw_len,
+            Layout::Escrow => Escrow::LEN,
+            Layout::Config => EscrowConfig::LEN,
+            Layout::OracleStake => OracleStake::LEN,
+            Layout::Reputation => Reputation::LEN,
+            Layout::ResultsLog => ResultsLog::LEN,
+            Layout::ResultsLogPage => ResultsLogPage::ACCOUNT_LEN,
+            Layout::RecipientPayout => RecipientPayout::LEN,
+            Layout::AllowList => AllowList::account_len(4),
+            Layout::Vault => EscrowVault::LEN,
+            Layout::Template => EscrowTemplate::LEN,
+            Layout::TokenAccount => TokenAccount::LEN,
+            Layout::Mint => Mint::LEN,
+            Layout::Clock => Clock::size_of(),
+            Layout::Rent => Rent::size_of(),
+        }
+    }
+}
+
+#[derive(Debug, Arbitrary)]
+struct FuzzAccount {
+    key: u8,
+    owner: u8,
+    lamports: u64,
+    layout: Layout,
+    data: Vec<u8>,
+}
+
+#[derive(Debug, Arbitrary)]
+struct FuzzMeta {
+    key: u8,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+#[derive(Debug, Arbitrary)]
+struct FuzzData {
+    instruction: Vec<u8>,
+    accounts: Vec<FuzzAccount>,
+    metas: Vec<FuzzMeta>,
+}
+
+/// Keys the fuzzer picks by index: program and sysvar ids, a few plain keys and the
+/// addresses the program derives from them, so address checks can pass
+struct KeyPool(Vec<Pubkey>);
+
+impl KeyPool {
+    fn new() -> Self {
+        let program_id = hmt_escrow::id();
+        let mut keys = vec![
+            program_id,
+            spl_token::id(),
+            system_program::id(),
+            sysvar::clock::id(),
+            sysvar::rent::id(),
+            sysvar::instructions::id(),
+            Processor::find_config_address(&program_id).0,
+        ];
+        for seed in 1..=KEY_SEEDS {
+            let key = Pubkey::new_from_array([seed; 32]);
+            keys.push(key);
+            keys.push(Processor::find_authority_bump_seed(&program_id, &key).0);
+            keys.push(Processor::find_oracle_stake_address(&program_id, &key).0);
+            keys.push(Processor::find_reputation_address(&program_id, &key).0);
+            keys.push(Processor::find_results_log_address(&program_id, &key).0);
+            keys.push(Processor::find_results_log_page_address(&program_id, &key, 0).0);
+            let other = Pubkey::new_from_array([seed % KEY_SEEDS + 1; 32]);
+            keys.push(Processor::find_vault_address(&program_id, &key, &other).0);
+            keys.push(Processor::find_recipient_payout_address(&program_id, &key, &other).0);
+        }
+        Self(keys)
+    }
+
+    fn get(&self, index: u8) -> Pubkey {
+        self.0[index as usize % self.0.len()]
+    }
+}
+
+fn main() {
+    let keys = KeyPool::new();
+    loop {
+        fuzz!(|fuzz_data: FuzzData| {
+            run_fuzz(&keys, fuzz_data);
+        });
+    }
+}
+
+fn run_fuzz(keys: &KeyPool, fuzz_data: FuzzData) {
+    let mut accounts: Vec<NativeAccountData> = vec![];
+    for account in fuzz_data.accounts {
+        let key = keys.get(account.key);
+        if accounts.iter().any(|a| a.key == key) {
+            continue;
+        }
+        let mut data = account.data;
+        data.resize(account.layout.len(data.len()), 0);
+        accounts.push(NativeAccountData::new(
+            key,
+            keys.get(account.owner),
+            account.lamports,
+            data,
+        ));
+    }
+
+    let metas: Vec<AccountMeta> = fuzz_data
+        .metas
+        .iter()
+        .map(|meta| {
+            let pubkey = keys.get(meta.key);
+            if meta.is_writable {
+                AccountMeta::new(pubkey, meta.is_signer)
+            } else {
+                AccountMeta::new_readonly(pubkey, meta.is_signer)
+            }
+        })
+        .collect();
+    for meta in metas.iter() {
+        if !accounts.iter().any(|a| a.key == meta.pubkey) {
+            accounts.push(NativeAccountData::new(
+                meta.pubkey,
+                system_program::id(),
+                0,
+                vec![],
+            ));
+        }
+    }
+
+    let instruction = Instruction {
+        program_id: hmt_escrow::id(),
+        accounts: metas,
+        data: fuzz_data.instruction,
+    };
+    let before = accounts.clone();
+    if do_process_instruction(&instruction, &mut accounts).is_ok() {
+        check_accepted(&instruction, &before, &accounts);
+    }
+}
+
+/// Checks the changes of a successful instruction the runtime would let through
+fn check_accepted(
+    instruction: &Instruction,
+    before: &[NativeAccountData],
+    after: &[NativeAccountData],
+) {
+    // The runtime rejects changes to read-only accounts and data changes by other programs
+    for (old, new) in before.iter().zip(after) {
+        let is_writable = instruction
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey == new.key && meta.is_writable);
+        let can_change_data = new.owner == hmt_escrow::id() || new.owner == spl_token::id();
+        if (old != new && !is_writable) || (old.data != new.data && !can_change_data) {
+            return;
+        }
+    }
+
+    let lamports = |accounts: &[NativeAccountData]| {
+        accounts
+            .iter()
+            .map(|account| account.lamports as u128)
+            .sum::<u128>()
+    };
+    assert_eq!(lamports(before), lamports(after));
+
+    for (old, new) in before.iter().zip(after) {
+        if old.data == new.data || new.owner != hmt_escrow::id() || new.data.len() != Escrow::LEN {
+            continue;
+        }
+        let new_escrow = Escrow::unpack_unchecked(&new.data).expect("escrow cannot be read back");
+        if let Ok(old_escrow) = Escrow::unpack_unchecked(&old.data) {
+            assert!(
+                old_escrow.state != EscrowState::Complete
+                    && old_escrow.state != EscrowState::Cancelled,
+                "finished escrow changed: {:?} -> {:?}",
+                old_escrow,
+                new_escrow
+            );
+            if old_escrow.sent_amount <= old_escrow.total_amount {
+                assert!(new_escrow.sent_amount <= new_escrow.total_amount);
+            }
+            if old_escrow.sent_recipients <= old_escrow.total_recipients {
+                assert!(new_escrow.sent_recipients <= new_escrow.total_recipients);
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use hmt_escrow::state::{
+    AllowList, Escrow, EscrowConfig, EscrowTemplate, EscrowVault, OracleStake, RecipientPayout,
+    Reputation, ResultsLog, ResultsLogEntry, ResultsLogPage,
+};
+use honggfuzz::fuzz;
+use solana_program::program_pack::Pack;
+use std::fmt::Debug;
+
+fn main() {
+    loop {
+        fuzz!(|data: &[u8]| {
+            run_fuzz(data);
+        });
+    }
+}
+
+/// Arbitrary account data of any length must not panic, whatever unpacks must survive
+/// a pack and unpack round trip, including packing over the original bytes
+fn round_trip<T: Pack + Copy + Debug + PartialEq>(data: &[u8]) {
+    if let Ok(value) = T::unpack_from_slice(data) {
+        let mut packed = vec![0; T::LEN];
+        value.pack_into_slice(&mut packed);
+        assert_eq!(T::unpack_from_slice(&packed), Ok(value));
+
+        let mut overwritten = data[..T::LEN].to_vec();
+        value.pack_into_slice(&mut overwritten);
+        assert_eq!(T::unpack_from_slice(&overwritten), Ok(value));
+    }
+}
+
+fn run_fuzz(data: &[u8]) {
+    round_trip::<Escrow>(data);
+    round_trip::<EscrowConfig>(data);
+    round_trip::<OracleStake>(data);
+    round_trip::<Reputation>(data);
+    round_trip::<ResultsLog>(data);
+    round_trip::<ResultsLogPage>(data);
+    round_trip::<ResultsLogEntry>(data);
+    round_trip::<RecipientPayout>(data);
+    round_trip::<AllowList>(data);
+    round_trip::<EscrowVault>(data);
+    round_trip::<EscrowTemplate>(data);
+    let _ = ResultsLogPage::unpack_entry(data, data.first().copied().unwrap_or(0) as usize);
+    if let Ok(allow_list) = AllowList::unpack_from_slice(data) {
+        let _ = allow_list.contains(data, &Default::default());
+    }
+}
@@ -42,6 +42,94 @@ pub enum EscrowError {
     /// Too many payouts
     #[error("Too many payouts")]
     TooManyPayouts,
+
+    /// Payout permit is not signed by the reputation oracle
+    #[error("Invalid permit signature")]
+    InvalidPermitSignature,
+
+    /// Payout permit expired
+    #[error("Permit expired")]
+    PermitExpired,
+
+    /// Payout permit nonce does not match the escrow nonce
+    #[error("Invalid permit nonce")]
+    InvalidPermitNonce,
+
+    /// Escrow is paused by the guardian
+    #[error("Escrow paused")]
+    Paused,
+
+    /// All escrows are paused by the program config admin
+    #[error("Program paused")]
+    ProgramPaused,
+
+    /// Escrow duration is out of the program config bounds
+    #[error("Duration out of bounds")]
+    DurationOutOfBounds,
+
+    /// Token mint is not in the program config allow-list
+    #[error("Mint not allowed")]
+    MintNotAllowed,
+
+    /// Program config values are not valid
+    #[error("Invalid config")]
+    InvalidConfig,
+
+    /// Oracle has not locked enough stake to set up this escrow
+    #[error("Insufficient oracle stake")]
+    InsufficientOracleStake,
+
+    /// Oracle stake is locked by escrows not yet expired
+    #[error("Oracle stake locked")]
+    OracleStakeLocked,
+
+    /// Payout amount is above the escrow limit for a single recipient
+    #[error("Payout above maximum")]
+    PayoutAboveMaximum,
+
+    /// Payout amount is zero or below the escrow minimum
+    #[error("Payout below minimum")]
+    PayoutBelowMinimum,
+
+    /// Escrow minimum payout is above the maximum payout
+    #[error("Invalid payout limits")]
+    InvalidPayoutLimits,
+
+    /// No more owners can be added to the allow-list
+    #[error("Allow-list full")]
+    AllowListFull,
+
+    /// Allow-list account is missing or is not the one referenced by the escrow
+    #[error("Invalid allow-list")]
+    InvalidAllowList,
+
+    /// Payout recipient owner is not in the escrow allow-list
+    #[error("Recipient not allowed")]
+    RecipientNotAllowed,
+
+    /// Escrow already has the maximum number of vaults
+    #[error("Too many vaults")]
+    TooManyVaults,
+
+    /// Vault account does not belong to the escrow or is out of order
+    #[error("Invalid vault")]
+    InvalidVault,
+
+    /// Both fixed and percent cancellation fees are set, or the percent is above 100
+    #[error("Invalid cancellation fee")]
+    InvalidCancellationFee,
+
+    /// Escrow cannot be cancelled until its notice period ends
+    #[error("Cancel notice period")]
+    CancelNoticePeriod,
+
+    /// Template account is not initialized or accounts do not match the template
+    #[error("Invalid template")]
+    InvalidTemplate,
+
+    /// Cancel notice period would end after the escrow expires
+    #[error("Cancel notice too long")]
+    CancelNoticeTooLong,
 }
 
 const BASE_ERROR_CODE: u32 = 0x100;
@@ -1,14 +1,43 @@
 //! Instruction types
 #![allow(clippy::too_many_arguments)]
 
-use crate::state::{DataHash, DataUrl, URL_LEN};
+use crate::secp256k1::ETH_ADDRESS_LEN;
+use crate::state::{
+    DataHash, DataUrl, EscrowTemplate, MAX_ALLOWED_MINTS, MAX_ALLOW_LIST_UPDATES,
+    MAX_REPUTATION_UPDATES, URL_LEN,
+};
 use solana_program::{
+    clock::UnixTimestamp,
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
+    program_option::COption,
     pubkey::Pubkey,
-    sysvar,
+    system_program, sysvar,
 };
 use std::{convert::TryInto, mem::size_of};
+
+/// Reputation score change of a single worker
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReputationUpdate {
+    /// Worker account
+    pub worker: Pubkey,
+    /// Score change, negative values decrease the reputation
+    pub delta: i64,
+}
+
+/// Vault accounts refunded by `Cancel`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VaultRefund {
+    /// Escrow vault account
+    pub vault: Pubkey,
+    /// Vault token account holding the funds
+    pub token_account: Pubkey,
+    /// Canceler token account of the vault mint to receive funds
+    pub canceler_token_account: Pubkey,
+}
+
 /// Instructions supported by the escrow program
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq)]
@@ -28,6 +57,8 @@ pub enum EscrowInstruction {
     /// 4. [] Escrow launcher account
     /// 5. [] Escrow canceler account
     /// 6. [] Canceler's token account to receive escrow funds
+    /// 7. [] Escrow guardian account, able to pause the escrow
+    /// 8. [] Program config account
     Initialize {
         /// Escrow duration in seconds, escrow can only be canceled after its duration expires
         duration: u64,
@@ -47,6 +78,28 @@ pub enum EscrowInstruction {
     /// 4. [] Reputation oracle's token account to receive fees
     /// 5. [] Signer account for the recording oracle for this escrow
     /// 6. [] Recording oracle's token account to receive fees
+    /// 7. [] Program config account
+    /// 8. [] Token account where escrow funds are stored
+    /// 9. [w] Reputation oracle stake account
+    /// 10. [w] Recording oracle stake account
+    /// 11. [] Optional recipient allow-list account, payouts are then limited
+    ///     to the token accounts of the owners in the list
+    ///
+    /// If the escrow holds at least the config staking threshold, both oracles
+    /// must have enough stake locked, and their stakes stay locked until the
+    /// escrow expires.
+    ///
+    /// Every payout of the escrow must be at least `min_payout` tokens. Unless
+    /// `max_payout_per_recipient` is zero, the total paid to the owner of the
+    /// recipient token accounts, oracle fees included, cannot exceed it. The total
+    /// is kept in a recipient payout account of the escrow and the owner,
+    /// created by the first payout to the owner.
+    ///
+    /// On cancel the oracles are paid either a fixed `cancellation_fee` or
+    /// `cancellation_fee_percent` of the remaining balance, split in proportion to
+    /// their fees. At most one of them can be set. Cancel is blocked for
+    /// `cancel_notice` seconds after setup, the notice cannot end after the
+    /// escrow expires.
     Setup {
         /// Reputation oracle fee in percents
         reputation_oracle_stake: u8,
@@ -59,19 +112,42 @@ pub enum EscrowInstruction {
 
         /// Manifest hash
         manifest_hash: DataHash,
+
+        /// Maximum amount of tokens a single recipient can be paid in total, zero for no limit
+        max_payout_per_recipient: u64,
+
+        /// Minimum amount of tokens in a single payout
+        min_payout: u64,
+
+        /// Fixed amount of tokens paid to the oracles on cancel
+        cancellation_fee: u64,
+
+        /// Percent of the remaining balance paid to the oracles on cancel
+        cancellation_fee_percent: u8,
+
+        /// Period in seconds after setup during which cancel is blocked
+        cancel_notice: u64,
     },
 
     /// Store job results
     ///
     /// When the job is over save total amount of tokens, number of recepients and
     /// final results URL and hash. Must be signed by one of the trusted
-    /// handlers.
+    /// handlers. Totals cannot be lower than the amount and recipients already paid.
     ///
     /// Accounts expected by this instruction:
     ///
     /// 0. [w] Escrow account
     /// 1. [s] Trusted handler signing this transaction
     /// 2. [] Clock sysvar
+    /// 3. [] Escrow results log account, its head is committed into the escrow
+    /// 4. [] Program config account
+    /// 5. [w] Reputation oracle stake account
+    /// 6. [w] Recording oracle stake account
+    ///
+    /// If the total amount is at least the config staking threshold, both oracles
+    /// must have enough stake locked, and their stakes stay locked until the
+    /// escrow expires.
     StoreResults {
         /// Total amount to pay
         total_amount: u64,
@@ -102,6 +178,15 @@ pub enum EscrowInstruction {
     /// 6. [w] Reputation oracle's token account to receive fees
     /// 7. [w] Recording oracle's token account to receive fees
     /// 8. [] Token contract program
+    /// 9. [] Escrow recipient allow-list account, only if the escrow has one
+    /// 10. [w] Recipient payout account at the address derived from the escrow
+    ///     and the recipient owner, only if the escrow limits the payout per recipient
+    /// 11. [ws] Funder of the recipient payout account created by the first payout
+    /// 12. [] Rent sysvar
+    /// 13. [] System program
+    ///
+    /// Recipient payout accounts directly follow the token program if the escrow
+    /// has no allow-list.
     Payout {
         /// Amount of tokens to pay
         amount: u64,
@@ -110,16 +195,28 @@ pub enum EscrowInstruction {
     ///
     /// Before escrow is finalized it is possible to cancel it and send all funds to
     /// the canceler token account. Must be signed by one of the trusted
-    /// handlers.
+    /// handlers. Cancel is blocked until the notice period set by `Setup` ends,
+    /// unless the escrow is paused by its guardian.
     ///
     /// Accounts expected by this instruction:
     ///
     /// 0. [w] Escrow account
     /// 1. [s] Trusted handler signing this transaction
-    /// 2. [w] Escrow token sending account
-    /// 3. [] Escrow signing authority (token sending account's owner)
-    /// 4. [w] Canceler token account to receive funds
-    /// 5. [] Token contract program
+    /// 2. [] Clock sysvar
+    /// 3. [w] Escrow token sending account
+    /// 4. [] Escrow signing authority (token sending account's owner)
+    /// 5. [w] Canceler token account to receive funds
+    /// 6. [] Token contract program
+    /// 7. [w] Reputation oracle token account, only if the escrow has a cancellation fee
+    /// 8. [w] Recording oracle token account, only if the escrow has a cancellation fee
+    /// 9. ..9+3*N For every escrow vault, in the order they were added:
+    ///    [w] vault account, [w] vault token account and
+    ///    [w] canceler token account of the vault mint to receive funds
+    ///
+    /// The cancellation fee is taken from the escrow token account only, the rest
+    /// goes to the canceler. Every vault must be refunded, vaults with no tokens
+    /// left are skipped. Fails if neither the escrow token account nor any vault
+    /// holds tokens.
     Cancel,
 
     /// Complete escrow
@@ -134,6 +231,454 @@ pub enum EscrowInstruction {
     /// 1. [s] Trusted handler signing this transaction
     /// 2. [] Clock sysvar
     Complete,
+
+    /// Do a single payout authorized by the reputation oracle off-chain
+    ///
+    /// Reputation oracle signs a message created with `payout_permit_message`
+    /// with the secp256k1 key set by `SetPermitSigner` and anyone can relay it.
+    /// Instruction right before this one in the same transaction must be the
+    /// Secp256k1 program instruction verifying this signature. Each permit can
+    /// only be used once, its nonce must match the escrow nonce.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Escrow account
+    /// 1. [] Clock sysvar
+    /// 2. [] Instructions sysvar
+    /// 3. [w] Escrow token sending account
+    /// 4. [] Escrow signing authority (token sending account's owner)
+    /// 5. [w] Payment recipient
+    /// 6. [w] Reputation oracle's token account to receive fees
+    /// 7. [w] Recording oracle's token account to receive fees
+    /// 8. [] Token contract program
+    /// 9. [] Escrow recipient allow-list account, only if the escrow has one
+    /// 10. [w] Recipient payout account at the address derived from the escrow
+    ///     and the recipient owner, only if the escrow limits the payout per recipient
+    /// 11. [ws] Funder of the recipient payout account created by the first payout
+    /// 12. [] Rent sysvar
+    /// 13. [] System program
+    ///
+    /// Recipient payout accounts directly follow the token program if the escrow
+    /// has no allow-list.
+    PayoutWithPermit {
+        /// Amount of tokens to pay
+        amount: u64,
+
+        /// Permit nonce, must be equal to the escrow payout nonce
+        nonce: u64,
+
+        /// Permit expiration timestamp
+        expires: UnixTimestamp,
+    },
+
+    /// Pause escrow
+    ///
+    /// Freezes the escrow during incident investigation. While paused all
+    /// instructions except `Unpause` and `Cancel` fail. Must be signed by the
+    /// escrow guardian.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Escrow account
+    /// 1. [s] Escrow guardian
+    Pause,
+
+    /// Unpause escrow
+    ///
+    /// Resumes paused escrow. Must be signed by the escrow guardian.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Escrow account
+    /// 1. [s] Escrow guardian
+    Unpause,
+
+    /// Initializes program config
+    ///
+    /// Creates singleton program config account at the address derived from
+    /// `CONFIG_SEED`, signer becomes the config admin. Config limits are
+    /// checked by `Initialize` and `Setup` instructions of every escrow.
+    /// Must be signed by the upgrade authority of the escrow program, so the
+    /// first caller cannot take over the config.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Program config account
+    /// 1. [ws] Config admin, funds the config account
+    /// 2. [] Rent sysvar
+    /// 3. [] System program
+    /// 4. [] Program data account of the escrow program
+    /// 5. [s] Upgrade authority of the escrow program
+    InitializeConfig {
+        /// Maximum combined oracle stake in percents
+        max_oracle_stake: u8,
+
+        /// Minimum escrow duration in seconds
+        min_duration: u64,
+
+        /// Maximum escrow duration in seconds
+        max_duration: u64,
+
+        /// Token mints accepted by escrows, empty list accepts any mint
+        allowed_mints: Vec<Pubkey>,
+    },
+
+    /// Updates program config
+    ///
+    /// Replaces all config values, must be signed by the config admin.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Program config account
+    /// 1. [s] Config admin
+    UpdateConfig {
+        /// New config admin
+        admin: Pubkey,
+
+        /// Global pause flag
+        paused: bool,
+
+        /// Maximum combined oracle stake in percents
+        max_oracle_stake: u8,
+
+        /// Minimum escrow duration in seconds
+        min_duration: u64,
+
+        /// Maximum escrow duration in seconds
+        max_duration: u64,
+
+        /// Token mints accepted by escrows, empty list accepts any mint
+        allowed_mints: Vec<Pubkey>,
+    },
+
+    /// Updates oracle staking part of the program config
+    ///
+    /// Must be signed by the config admin. Setting no stake mint disables
+    /// oracle staking requirement.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Program config account
+    /// 1. [s] Config admin
+    UpdateStakingConfig {
+        /// Mint of the tokens locked by oracles
+        stake_mint: COption<Pubkey>,
+
+        /// Minimum stake each oracle has to lock
+        min_oracle_stake: u64,
+
+        /// Escrows holding at least this amount of tokens require staked oracles
+        stake_escrow_threshold: u64,
+
+        /// Dispute arbiter allowed to slash oracle stakes
+        arbiter: COption<Pubkey>,
+    },
+
+    /// Creates oracle stake account at the address derived from
+    /// `ORACLE_STAKE_SEED` and the oracle key.
+    ///
+    /// Vault token account must be created beforehand with the stake account
+    /// as its owner and the config stake mint.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Oracle stake account
+    /// 1. [ws] Oracle, funds the stake account
+    /// 2. [] Vault token account
+    /// 3. [] Program config account
+    /// 4. [] Rent sysvar
+    /// 5. [] System program
+    InitializeOracleStake,
+
+    /// Locks more tokens in the oracle stake vault
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Oracle stake account
+    /// 1. [w] Vault token account
+    /// 2. [w] Source token account
+    /// 3. [s] Source token account owner
+    /// 4. [] Token program
+    DepositOracleStake {
+        /// Amount of tokens to deposit
+        amount: u64,
+    },
+
+    /// Withdraws tokens from the oracle stake vault
+    ///
+    /// Must be signed by the oracle, fails while the stake is locked by escrows.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Oracle stake account
+    /// 1. [s] Oracle
+    /// 2. [] Clock sysvar
+    /// 3. [w] Vault token account
+    /// 4. [w] Destination token account
+    /// 5. [] Token program
+    WithdrawOracleStake {
+        /// Amount of tokens to withdraw
+        amount: u64,
+    },
+
+    /// Slashes misbehaving oracle, moving part of its stake to the escrow canceler
+    ///
+    /// Must be signed by the dispute arbiter or the config admin. Oracle must be
+    /// one of the escrow oracles.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [] Program config account
+    /// 1. [s] Dispute arbiter or config admin
+    /// 2. [] Escrow account
+    /// 3. [w] Oracle stake account
+    /// 4. [w] Vault token account
+    /// 5. [w] Escrow canceler's token account of the stake mint
+    /// 6. [] Token program
+    SlashOracle {
+        /// Amount of tokens to slash
+        amount: u64,
+    },
+
+    /// Updates reputation of the workers paid from the escrow
+    ///
+    /// Must be signed by the escrow reputation oracle while the escrow is in
+    /// `Partial` or `Paid` state. Missing reputation accounts are created at
+    /// the addresses derived from `REPUTATION_SEED` and the worker keys.
+    /// The first update of a worker for the escrow creates an empty reputation
+    /// job account derived from `REPUTATION_JOB_SEED`, the worker and the escrow
+    /// keys, and counts the completed job.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [] Escrow account
+    /// 1. [ws] Reputation oracle, funds new reputation and job accounts
+    /// 2. [] Clock sysvar
+    /// 3. [] Rent sysvar
+    /// 4. [] System program
+    /// 5. ..5+2N [w] Reputation account and reputation job account of every
+    ///    update, in the same order as the updates
+    UpdateReputation {
+        /// Reputation changes, up to `MAX_REPUTATION_UPDATES`
+        updates: Vec<ReputationUpdate>,
+    },
+
+    /// Appends intermediate results to the escrow results log
+    ///
+    /// Must be signed by the escrow recording oracle while the escrow is in
+    /// `Pending` or `Partial` state. Results log is created on the first call
+    /// at the address derived from `RESULTS_LOG_SEED` and the escrow key.
+    /// Entries are stored in pages of `RESULTS_LOG_PAGE_ENTRIES`, the page holding
+    /// the next entry is created by its first entry. All entries are kept.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [] Escrow account
+    /// 1. [w] Escrow results log account
+    /// 2. [w] Escrow results log page holding the next entry, derived from
+    ///    `RESULTS_LOG_PAGE_SEED`, the escrow key and the page number
+    /// 3. [ws] Recording oracle, funds the results log and page accounts
+    /// 4. [] Clock sysvar
+    /// 5. [] Rent sysvar
+    /// 6. [] System program
+    StoreIntermediateResults {
+        /// Intermediate results URL
+        url: DataUrl,
+
+        /// Intermediate results hash
+        hash: DataHash,
+    },
+
+    /// Initializes a new recipient allow-list
+    ///
+    /// Allow-list account must be created beforehand, owned by this program and
+    /// rent exempt, its size sets the list capacity. Any number of escrows can
+    /// reference the same allow-list at setup.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Allow-list account
+    /// 1. [s] Compliance authority, able to update the list
+    /// 2. [] Rent sysvar
+    InitializeAllowList,
+
+    /// Adds and removes recipient owners from the allow-list
+    ///
+    /// Must be signed by the allow-list authority. Owners are removed first,
+    /// adding owners already in the list or removing missing ones is a no-op.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Allow-list account
+    /// 1. [s] Compliance authority
+    UpdateAllowList {
+        /// Owners to add, up to `MAX_ALLOW_LIST_UPDATES`
+        add: Vec<Pubkey>,
+
+        /// Owners to remove, up to `MAX_ALLOW_LIST_UPDATES`
+        remove: Vec<Pubkey>,
+    },
+
+    /// Adds token vault to the escrow
+    ///
+    /// Launched escrow can hold tokens of up to `MAX_ESCROW_VAULTS` additional
+    /// mints, for example a bonus paid on top of the base reward. Vault account
+    /// is created at the address derived from `VAULT_SEED`, the escrow key and
+    /// the mint key. Must be signed by one of the trusted handlers.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Escrow account
+    /// 1. [ws] Trusted handler signing this transaction, funds the vault account
+    /// 2. [] Clock sysvar
+    /// 3. [w] Escrow vault account
+    /// 4. [] Mint account for token held by the vault
+    /// 5. [] Token account where vault funds will be stored
+    /// 6. [] Canceler's token account to receive vault funds
+    /// 7. [] Program config account
+    /// 8. [] Rent sysvar
+    /// 9. [] System program
+    AddVault,
+
+    /// Store vault payout total
+    ///
+    /// Sets the total amount of the vault tokens to pay out, the escrow
+    /// cannot be paid until the totals of all its vaults are stored. Must be
+    /// signed by one of the trusted handlers. Total cannot be lower than the
+    /// amount already paid from the vault.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Escrow account
+    /// 1. [s] Trusted handler signing this transaction
+    /// 2. [] Clock sysvar
+    /// 3. [w] Escrow vault account
+    StoreVaultResults {
+        /// Total amount to pay from the vault
+        total_amount: u64,
+    },
+
+    /// Do a single payout from the escrow vault
+    ///
+    /// Same as `Payout` for the vault tokens, oracle fees are paid to the
+    /// oracle token accounts of the vault mint. Escrow payout limits are in
+    /// escrow token units and, like the recipient count, only apply to the
+    /// escrow token. Must be signed by one of the trusted handlers.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Escrow account
+    /// 1. [s] Trusted handler signing this transaction
+    /// 2. [] Clock sysvar
+    /// 3. [w] Escrow vault account
+    /// 4. [w] Vault token sending account
+    /// 5. [] Escrow signing authority (token sending account's owner)
+    /// 6. [w] Payment recipient
+    /// 7. [w] Reputation oracle's token account of the vault mint to receive fees
+    /// 8. [w] Recording oracle's token account of the vault mint to receive fees
+    /// 9. [] Token contract program
+    /// 10. [] Escrow recipient allow-list account, only if the escrow has one
+    VaultPayout {
+        /// Amount of tokens to pay
+        amount: u64,
+    },
+
+    /// Initializes a new escrow template
+    ///
+    /// Template keeps the escrow configuration of repeat jobs. Template account
+    /// must be created beforehand, owned by this program and rent exempt.
+    /// Values are checked the same way as by `Initialize` and `Setup`, program
+    /// config limits are checked when escrows are created from the template.
+    /// The cancel notice cannot be longer than the escrow duration.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Template account
+    /// 1. [s] Template authority, the only launcher allowed to use the template
+    /// 2. [] Rent sysvar
+    /// 3. [] Mint account for token managed by the escrows
+    /// 4. [] Escrow canceler account
+    /// 5. [] Canceler's token account to receive escrow funds
+    /// 6. [] Escrow guardian account, able to pause the escrows
+    /// 7. [] Reputation oracle account
+    /// 8. [] Reputation oracle's token account to receive fees
+    /// 9. [] Recording oracle account
+    /// 10. [] Recording oracle's token account to receive fees
+    /// 11. [] Optional recipient allow-list account
+    InitializeTemplate {
+        /// Escrow duration in seconds
+        duration: u64,
+
+        /// Reputation oracle fee in percents
+        reputation_oracle_stake: u8,
+
+        /// Recording oracle fee in percents
+        recording_oracle_stake: u8,
+
+        /// Maximum amount of tokens a single recipient can be paid in total, zero for no limit
+        max_payout_per_recipient: u64,
+
+        /// Minimum amount of tokens in a single payout
+        min_payout: u64,
+
+        /// Fixed amount of tokens paid to the oracles on cancel
+        cancellation_fee: u64,
+
+        /// Percent of the remaining balance paid to the oracles on cancel
+        cancellation_fee_percent: u8,
+
+        /// Period in seconds after setup during which cancel is blocked
+        cancel_notice: u64,
+    },
+
+    /// Initializes a new escrow from the template and sets it up
+    ///
+    /// Same as `Initialize` followed by `Setup` with the template values, only
+    /// the job manifest is passed. Must be signed by the template authority,
+    /// which becomes the escrow launcher. Accounts must match the template.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Account for the new escrow
+    /// 1. [s] Template authority, escrow launcher
+    /// 2. [] Clock sysvar
+    /// 3. [] Template account
+    /// 4. [] Mint account for token managed by this escrow
+    /// 5. [] Token account where escrow funds will be stored
+    /// 6. [] Escrow canceler account
+    /// 7. [] Canceler's token account to receive escrow funds
+    /// 8. [] Escrow guardian account
+    /// 9. [] Program config account
+    /// 10. [] Reputation oracle account
+    /// 11. [] Reputation oracle's token account to receive fees
+    /// 12. [] Recording oracle account
+    /// 13. [] Recording oracle's token account to receive fees
+    /// 14. [w] Reputation oracle stake account
+    /// 15. [w] Recording oracle stake account
+    /// 16. [] Recipient allow-list account, only if the template has one
+    InitializeFromTemplate {
+        /// Manifest URL
+        manifest_url: DataUrl,
+
+        /// Manifest hash
+        manifest_hash: DataHash,
+    },
+
+    /// Sets the key signing payout permits
+    ///
+    /// Reputation oracle signs permits off-chain with a secp256k1 key, the
+    /// escrow keeps its Ethereum address. Zero address disables permits.
+    /// Must be signed by one of the trusted handlers.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. [w] Escrow account
+    /// 1. [s] Trusted handler signing this transaction
+    /// 2. [] Clock sysvar
+    SetPermitSigner {
+        /// Ethereum address of the permit signing key
+        permit_signer: [u8; ETH_ADDRESS_LEN],
+    },
 }
 
 impl EscrowInstruction {
@@ -142,43 +687,204 @@ impl EscrowInstruction {
         let (&tag, rest) = input
             .split_first()
             .ok_or(ProgramError::InvalidInstructionData)?;
-        Ok(match tag {
+        let (instruction, rest) = match tag {
             1 => {
-                let (duration, _rest) = Self::unpack_u64(rest)?;
-                Self::Initialize { duration }
+                let (duration, rest) = Self::unpack_u64(rest)?;
+                (Self::Initialize { duration }, rest)
             }
             2 => {
                 let (reputation_oracle_stake, rest) = Self::unpack_u8(rest)?;
                 let (recording_oracle_stake, rest) = Self::unpack_u8(rest)?;
                 let (manifest_url, rest) = Self::unpack_url(rest)?;
-                let (manifest_hash, _rest) = Self::unpack_hash(rest)?;
-                Self::Setup {
-                    reputation_oracle_stake,
-                    recording_oracle_stake,
-                    manifest_url,
-                    manifest_hash,
-                }
+                let (manifest_hash, rest) = Self::unpack_hash(rest)?;
+                let (max_payout_per_recipient, rest) = Self::unpack_u64(rest)?;
+                let (min_payout, rest) = Self::unpack_u64(rest)?;
+                let (cancellation_fee, rest) = Self::unpack_u64(rest)?;
+                let (cancellation_fee_percent, rest) = Self::unpack_u8(rest)?;
+                let (cancel_notice, rest) = Self::unpack_u64(rest)?;
+                (
+                    Self::Setup {
+                        reputation_oracle_stake,
+                        recording_oracle_stake,
+                        manifest_url,
+                        manifest_hash,
+                        max_payout_per_recipient,
+                        min_payout,
+                        cancellation_fee,
+                        cancellation_fee_percent,
+                        cancel_notice,
+                    },
+                    rest,
+                )
             }
             3 => {
                 let (total_amount, rest) = Self::unpack_u64(rest)?;
                 let (total_recipients, rest) = Self::unpack_u64(rest)?;
                 let (final_results_url, rest) = Self::unpack_url(rest)?;
-                let (final_results_hash, _rest) = Self::unpack_hash(rest)?;
-                Self::StoreResults {
-                    total_amount,
-                    total_recipients,
-                    final_results_url,
-                    final_results_hash,
-                }
+                let (final_results_hash, rest) = Self::unpack_hash(rest)?;
+                (
+                    Self::StoreResults {
+                        total_amount,
+                        total_recipients,
+                        final_results_url,
+                        final_results_hash,
+                    },
+                    rest,
+                )
             }
             4 => {
-                let (amount, _rest) = Self::unpack_u64(rest)?;
-                Self::Payout { amount }
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                (Self::Payout { amount }, rest)
+            }
+            5 => (Self::Cancel, rest),
+            6 => (Self::Complete, rest),
+            7 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                let (nonce, rest) = Self::unpack_u64(rest)?;
+                let (expires, rest) = Self::unpack_i64(rest)?;
+                (
+                    Self::PayoutWithPermit {
+                        amount,
+                        nonce,
+                        expires,
+                    },
+                    rest,
+                )
+            }
+            8 => (Self::Pause, rest),
+            9 => (Self::Unpause, rest),
+            10 => {
+                let (max_oracle_stake, rest) = Self::unpack_u8(rest)?;
+                let (min_duration, rest) = Self::unpack_u64(rest)?;
+                let (max_duration, rest) = Self::unpack_u64(rest)?;
+                let (allowed_mints, rest) = Self::unpack_mints(rest)?;
+                (
+                    Self::InitializeConfig {
+                        max_oracle_stake,
+                        min_duration,
+                        max_duration,
+                        allowed_mints,
+                    },
+                    rest,
+                )
+            }
+            11 => {
+                let (admin, rest) = Self::unpack_pubkey(rest)?;
+                let (paused, rest) = Self::unpack_bool(rest)?;
+                let (max_oracle_stake, rest) = Self::unpack_u8(rest)?;
+                let (min_duration, rest) = Self::unpack_u64(rest)?;
+                let (max_duration, rest) = Self::unpack_u64(rest)?;
+                let (allowed_mints, rest) = Self::unpack_mints(rest)?;
+                (
+                    Self::UpdateConfig {
+                        admin,
+                        paused,
+                        max_oracle_stake,
+                        min_duration,
+                        max_duration,
+                        allowed_mints,
+                    },
+                    rest,
+                )
+            }
+            12 => {
+                let (stake_mint, rest) = Self::unpack_pubkey_option(rest)?;
+                let (min_oracle_stake, rest) = Self::unpack_u64(rest)?;
+                let (stake_escrow_threshold, rest) = Self::unpack_u64(rest)?;
+                let (arbiter, rest) = Self::unpack_pubkey_option(rest)?;
+                (
+                    Self::UpdateStakingConfig {
+                        stake_mint,
+                        min_oracle_stake,
+                        stake_escrow_threshold,
+                        arbiter,
+                    },
+                    rest,
+                )
+            }
+            13 => (Self::InitializeOracleStake, rest),
+            14 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                (Self::DepositOracleStake { amount }, rest)
+            }
+            15 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                (Self::WithdrawOracleStake { amount }, rest)
+            }
+            16 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                (Self::SlashOracle { amount }, rest)
+            }
+            17 => {
+                let (updates, rest) = Self::unpack_reputation_updates(rest)?;
+                (Self::UpdateReputation { updates }, rest)
+            }
+            18 => {
+                let (url, rest) = Self::unpack_url(rest)?;
+                let (hash, rest) = Self::unpack_hash(rest)?;
+                (Self::StoreIntermediateResults { url, hash }, rest)
+            }
+            19 => (Self::InitializeAllowList, rest),
+            20 => {
+                let (add, rest) = Self::unpack_owners(rest)?;
+                let (remove, rest) = Self::unpack_owners(rest)?;
+                (Self::UpdateAllowList { add, remove }, rest)
+            }
+            21 => (Self::AddVault, rest),
+            22 => {
+                let (total_amount, rest) = Self::unpack_u64(rest)?;
+                (Self::StoreVaultResults { total_amount }, rest)
+            }
+            23 => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                (Self::VaultPayout { amount }, rest)
+            }
+            24 => {
+                let (duration, rest) = Self::unpack_u64(rest)?;
+                let (reputation_oracle_stake, rest) = Self::unpack_u8(rest)?;
+                let (recording_oracle_stake, rest) = Self::unpack_u8(rest)?;
+                let (max_payout_per_recipient, rest) = Self::unpack_u64(rest)?;
+                let (min_payout, rest) = Self::unpack_u64(rest)?;
+                let (cancellation_fee, rest) = Self::unpack_u64(rest)?;
+                let (cancellation_fee_percent, rest) = Self::unpack_u8(rest)?;
+                let (cancel_notice, rest) = Self::unpack_u64(rest)?;
+                (
+                    Self::InitializeTemplate {
+                        duration,
+                        reputation_oracle_stake,
+                        recording_oracle_stake,
+                        max_payout_per_recipient,
+                        min_payout,
+                        cancellation_fee,
+                        cancellation_fee_percent,
+                        cancel_notice,
+                    },
+                    rest,
+                )
+            }
+            25 => {
+                let (manifest_url, rest) = Self::unpack_url(rest)?;
+                let (manifest_hash, rest) = Self::unpack_hash(rest)?;
+                (
+                    Self::InitializeFromTemplate {
+                        manifest_url,
+                        manifest_hash,
+                    },
+                    rest,
+                )
+            }
+            26 => {
+                let (permit_signer, rest) = Self::unpack_eth_address(rest)?;
+                (Self::SetPermitSigner { permit_signer }, rest)
             }
-            5 => Self::Cancel,
-            6 => Self::Complete,
             _ => return Err(ProgramError::InvalidInstructionData),
-        })
+        };
+
+        // Trailing bytes mean the data was not packed for this instruction
+        if !rest.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(instruction)
     }
 
     /// Packs a [EscrowInstruction](enum.EscrowInstruction.html) into a byte buffer.
@@ -194,12 +900,22 @@ impl EscrowInstruction {
                 recording_oracle_stake,
                 manifest_url,
                 manifest_hash,
+                max_payout_per_recipient,
+                min_payout,
+                cancellation_fee,
+                cancellation_fee_percent,
+                cancel_notice,
             } => {
                 buf.push(2);
                 buf.push(reputation_oracle_stake);
                 buf.push(recording_oracle_stake);
                 buf.extend(manifest_url.as_ref());
                 buf.extend(manifest_hash.as_ref());
+                buf.extend(&max_payout_per_recipient.to_le_bytes());
+                buf.extend(&min_payout.to_le_bytes());
+                buf.extend(&cancellation_fee.to_le_bytes());
+                buf.push(cancellation_fee_percent);
+                buf.extend(&cancel_notice.to_le_bytes());
             }
             Self::StoreResults {
                 total_amount,
@@ -219,34 +935,264 @@ impl EscrowInstruction {
             }
             Self::Cancel => buf.push(5),
             Self::Complete => buf.push(6),
-        }
-        buf
-    }
-
-    fn unpack_u8(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
-        match input.split_first() {
-            Option::Some((value, rest)) => Ok((*value, rest)),
-            _ => Err(ProgramError::InvalidInstructionData),
-        }
-    }
-
-    fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
-        if input.len() >= 8 {
-            let (bytes, rest) = input.split_at(8);
-            Ok((
-                u64::from_le_bytes(
-                    bytes
-                        .try_into()
-                        .or(Err(ProgramError::InvalidInstructionData))?,
-                ),
-                rest,
-            ))
-        } else {
-            Err(ProgramError::InvalidInstructionData)
-        }
-    }
-
-    fn unpack_hash(input: &[u8]) -> Result<(DataHash, &[u8]), ProgramError> {
+            Self::PayoutWithPermit {
+                amount,
+                nonce,
+                expires,
+            } => {
+                buf.push(7);
+                buf.extend(&amount.to_le_bytes());
+                buf.extend(&nonce.to_le_bytes());
+                buf.extend(&expires.to_le_bytes());
+            }
+            Self::Pause => buf.push(8),
+            Self::Unpause => buf.push(9),
+            Self::InitializeConfig {
+                max_oracle_stake,
+                min_duration,
+                max_duration,
+                ref allowed_mints,
+            } => {
+                buf.push(10);
+                buf.push(max_oracle_stake);
+                buf.extend(&min_duration.to_le_bytes());
+                buf.extend(&max_duration.to_le_bytes());
+                Self::pack_mints(allowed_mints, &mut buf);
+            }
+            Self::UpdateConfig {
+                admin,
+                paused,
+                max_oracle_stake,
+                min_duration,
+                max_duration,
+                ref allowed_mints,
+            } => {
+                buf.push(11);
+                buf.extend(admin.as_ref());
+                buf.push(paused as u8);
+                buf.push(max_oracle_stake);
+                buf.extend(&min_duration.to_le_bytes());
+                buf.extend(&max_duration.to_le_bytes());
+                Self::pack_mints(allowed_mints, &mut buf);
+            }
+            Self::UpdateStakingConfig {
+                ref stake_mint,
+                min_oracle_stake,
+                stake_escrow_threshold,
+                ref arbiter,
+            } => {
+                buf.push(12);
+                Self::pack_pubkey_option(stake_mint, &mut buf);
+                buf.extend(&min_oracle_stake.to_le_bytes());
+                buf.extend(&stake_escrow_threshold.to_le_bytes());
+                Self::pack_pubkey_option(arbiter, &mut buf);
+            }
+            Self::InitializeOracleStake => buf.push(13),
+            Self::DepositOracleStake { amount } => {
+                buf.push(14);
+                buf.extend(&amount.to_le_bytes());
+            }
+            Self::WithdrawOracleStake { amount } => {
+                buf.push(15);
+                buf.extend(&amount.to_le_bytes());
+            }
+            Self::SlashOracle { amount } => {
+                buf.push(16);
+                buf.extend(&amount.to_le_bytes());
+            }
+            Self::UpdateReputation { ref updates } => {
+                buf.push(17);
+                buf.push(updates.len() as u8);
+                for update in updates {
+                    buf.extend(update.worker.as_ref());
+                    buf.extend(&update.delta.to_le_bytes());
+                }
+            }
+            Self::StoreIntermediateResults { url, hash } => {
+                buf.push(18);
+                buf.extend(url.as_ref());
+                buf.extend(hash.as_ref());
+            }
+            Self::InitializeAllowList => buf.push(19),
+            Self::UpdateAllowList {
+                ref add,
+                ref remove,
+            } => {
+                buf.push(20);
+                Self::pack_owners(add, &mut buf);
+                Self::pack_owners(remove, &mut buf);
+            }
+            Self::AddVault => buf.push(21),
+            Self::StoreVaultResults { total_amount } => {
+                buf.push(22);
+                buf.extend(&total_amount.to_le_bytes());
+            }
+            Self::VaultPayout { amount } => {
+                buf.push(23);
+                buf.extend(&amount.to_le_bytes());
+            }
+            Self::InitializeTemplate {
+                duration,
+                reputation_oracle_stake,
+                recording_oracle_stake,
+                max_payout_per_recipient,
+                min_payout,
+                cancellation_fee,
+                cancellation_fee_percent,
+                cancel_notice,
+            } => {
+                buf.push(24);
+                buf.extend(&duration.to_le_bytes());
+                buf.push(reputation_oracle_stake);
+                buf.push(recording_oracle_stake);
+                buf.extend(&max_payout_per_recipient.to_le_bytes());
+                buf.extend(&min_payout.to_le_bytes());
+                buf.extend(&cancellation_fee.to_le_bytes());
+                buf.push(cancellation_fee_percent);
+                buf.extend(&cancel_notice.to_le_bytes());
+            }
+            Self::InitializeFromTemplate {
+                manifest_url,
+                manifest_hash,
+            } => {
+                buf.push(25);
+                buf.extend(manifest_url.as_ref());
+                buf.extend(manifest_hash.as_ref());
+            }
+            Self::SetPermitSigner { permit_signer } => {
+                buf.push(26);
+                buf.extend(&permit_signer);
+            }
+        }
+        buf
+    }
+
+    fn unpack_u8(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
+        match input.split_first() {
+            Option::Some((value, rest)) => Ok((*value, rest)),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    fn unpack_bool(input: &[u8]) -> Result<(bool, &[u8]), ProgramError> {
+        match Self::unpack_u8(input)? {
+            (0, rest) => Ok((false, rest)),
+            (1, rest) => Ok((true, rest)),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+        if input.len() >= 8 {
+            let (bytes, rest) = input.split_at(8);
+            Ok((
+                u64::from_le_bytes(
+                    bytes
+                        .try_into()
+                        .or(Err(ProgramError::InvalidInstructionData))?,
+                ),
+                rest,
+            ))
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    fn unpack_i64(input: &[u8]) -> Result<(i64, &[u8]), ProgramError> {
+        let (value, rest) = Self::unpack_u64(input)?;
+        Ok((value as i64, rest))
+    }
+
+    fn unpack_pubkey(input: &[u8]) -> Result<(Pubkey, &[u8]), ProgramError> {
+        if input.len() >= 32 {
+            let (key, rest) = input.split_at(32);
+            Ok((Pubkey::new(key), rest))
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
+    fn unpack_pubkey_option(input: &[u8]) -> Result<(COption<Pubkey>, &[u8]), ProgramError> {
+        match Self::unpack_u8(input)? {
+            (0, rest) => Ok((COption::None, rest)),
+            (1, rest) => {
+                let (key, rest) = Self::unpack_pubkey(rest)?;
+                Ok((COption::Some(key), rest))
+            }
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    fn pack_pubkey_option(value: &COption<Pubkey>, buf: &mut Vec<u8>) {
+        match *value {
+            COption::Some(ref key) => {
+                buf.push(1);
+                buf.extend(key.as_ref());
+            }
+            COption::None => buf.push(0),
+        }
+    }
+
+    fn unpack_mints(input: &[u8]) -> Result<(Vec<Pubkey>, &[u8]), ProgramError> {
+        let (count, mut rest) = Self::unpack_u8(input)?;
+        if count as usize > MAX_ALLOWED_MINTS {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let mut mints = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let (mint, next) = Self::unpack_pubkey(rest)?;
+            mints.push(mint);
+            rest = next;
+        }
+        Ok((mints, rest))
+    }
+
+    fn pack_mints(mints: &[Pubkey], buf: &mut Vec<u8>) {
+        buf.push(mints.len() as u8);
+        for mint in mints {
+            buf.extend(mint.as_ref());
+        }
+    }
+
+    fn unpack_owners(input: &[u8]) -> Result<(Vec<Pubkey>, &[u8]), ProgramError> {
+        let (count, mut rest) = Self::unpack_u8(input)?;
+        if count as usize > MAX_ALLOW_LIST_UPDATES {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let mut owners = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let (owner, next) = Self::unpack_pubkey(rest)?;
+            owners.push(owner);
+            rest = next;
+        }
+        Ok((owners, rest))
+    }
+
+    fn pack_owners(owners: &[Pubkey], buf: &mut Vec<u8>) {
+        buf.push(owners.len() as u8);
+        for owner in owners {
+            buf.extend(owner.as_ref());
+        }
+    }
+
+    fn unpack_reputation_updates(
+        input: &[u8],
+    ) -> Result<(Vec<ReputationUpdate>, &[u8]), ProgramError> {
+        let (count, mut rest) = Self::unpack_u8(input)?;
+        if count == 0 || count as usize > MAX_REPUTATION_UPDATES {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let mut updates = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let (worker, next) = Self::unpack_pubkey(rest)?;
+            let (delta, next) = Self::unpack_i64(next)?;
+            updates.push(ReputationUpdate { worker, delta });
+            rest = next;
+        }
+        Ok((updates, rest))
+    }
+
+    fn unpack_hash(input: &[u8]) -> Result<(DataHash, &[u8]), ProgramError> {
         if input.len() >= 20 {
             let (bytes, rest) = input.split_at(20);
             Ok((
@@ -262,6 +1208,20 @@ impl EscrowInstruction {
         }
     }
 
+    fn unpack_eth_address(input: &[u8]) -> Result<([u8; ETH_ADDRESS_LEN], &[u8]), ProgramError> {
+        if input.len() >= ETH_ADDRESS_LEN {
+            let (bytes, rest) = input.split_at(ETH_ADDRESS_LEN);
+            Ok((
+                bytes
+                    .try_into()
+                    .or(Err(ProgramError::InvalidInstructionData))?,
+                rest,
+            ))
+        } else {
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+
     fn unpack_url(input: &[u8]) -> Result<(DataUrl, &[u8]), ProgramError> {
         if input.len() >= URL_LEN {
             let (bytes, rest) = input.split_at(URL_LEN);
@@ -283,6 +1243,8 @@ pub fn initialize(
     launcher: &Pubkey,
     canceler: &Pubkey,
     canceler_token_account: &Pubkey,
+    guardian: &Pubkey,
+    config: &Pubkey,
     duration: u64,
 ) -> Result<Instruction, ProgramError> {
     let data = EscrowInstruction::Initialize { duration }.pack();
@@ -295,6 +1257,8 @@ pub fn initialize(
         AccountMeta::new_readonly(*launcher, false),
         AccountMeta::new_readonly(*canceler, false),
         AccountMeta::new_readonly(*canceler_token_account, false),
+        AccountMeta::new_readonly(*guardian, false),
+        AccountMeta::new_readonly(*config, false),
     ];
 
     Ok(Instruction {
@@ -317,16 +1281,31 @@ pub fn setup(
     recording_oracle_stake: u8,
     manifest_url: &DataUrl,
     manifest_hash: &DataHash,
+    max_payout_per_recipient: u64,
+    min_payout: u64,
+    cancellation_fee: u64,
+    cancellation_fee_percent: u8,
+    cancel_notice: u64,
+    config: &Pubkey,
+    escrow_token_account: &Pubkey,
+    reputation_oracle_stake_account: &Pubkey,
+    recording_oracle_stake_account: &Pubkey,
+    allow_list: Option<&Pubkey>,
 ) -> Result<Instruction, ProgramError> {
     let data = EscrowInstruction::Setup {
         reputation_oracle_stake,
         recording_oracle_stake,
         manifest_url: *manifest_url,
         manifest_hash: *manifest_hash,
+        max_payout_per_recipient,
+        min_payout,
+        cancellation_fee,
+        cancellation_fee_percent,
+        cancel_notice,
     }
     .pack();
 
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(*escrow, false),
         AccountMeta::new_readonly(*trusted_handler, true),
         AccountMeta::new_readonly(sysvar::clock::id(), false),
@@ -334,7 +1313,14 @@ pub fn setup(
         AccountMeta::new_readonly(*reputation_oracle_token_account, false),
         AccountMeta::new_readonly(*recording_oracle, false),
         AccountMeta::new_readonly(*recording_oracle_token_account, false),
+        AccountMeta::new_readonly(*config, false),
+        AccountMeta::new_readonly(*escrow_token_account, false),
+        AccountMeta::new(*reputation_oracle_stake_account, false),
+        AccountMeta::new(*recording_oracle_stake_account, false),
     ];
+    if let Some(allow_list) = allow_list {
+        accounts.push(AccountMeta::new_readonly(*allow_list, false));
+    }
 
     Ok(Instruction {
         program_id: *escrow_program_id,
@@ -352,6 +1338,10 @@ pub fn store_results(
     total_recipients: u64,
     final_results_url: &DataUrl,
     final_results_hash: &DataHash,
+    results_log: &Pubkey,
+    config: &Pubkey,
+    reputation_oracle_stake: &Pubkey,
+    recording_oracle_stake: &Pubkey,
 ) -> Result<Instruction, ProgramError> {
     let data = EscrowInstruction::StoreResults {
         total_amount,
@@ -365,6 +1355,10 @@ pub fn store_results(
         AccountMeta::new(*escrow, false),
         AccountMeta::new_readonly(*trusted_handler, true),
         AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(*results_log, false),
+        AccountMeta::new_readonly(*config, false),
+        AccountMeta::new(*reputation_oracle_stake, false),
+        AccountMeta::new(*recording_oracle_stake, false),
     ];
 
     Ok(Instruction {
@@ -386,10 +1380,12 @@ pub fn payout(
     recording_oracle_token_account: &Pubkey,
     token_program_id: &Pubkey,
     amount: u64,
+    allow_list: Option<&Pubkey>,
+    recipient_payout: Option<(&Pubkey, &Pubkey)>,
 ) -> Result<Instruction, ProgramError> {
     let data = EscrowInstruction::Payout { amount }.pack();
 
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(*escrow, false),
         AccountMeta::new_readonly(*trusted_handler, true),
         AccountMeta::new_readonly(sysvar::clock::id(), false),
@@ -400,6 +1396,15 @@ pub fn payout(
         AccountMeta::new(*recording_oracle_token_account, false),
         AccountMeta::new_readonly(*token_program_id, false),
     ];
+    if let Some(allow_list) = allow_list {
+        accounts.push(AccountMeta::new_readonly(*allow_list, false));
+    }
+    if let Some((recipient_payout, funder)) = recipient_payout {
+        accounts.push(AccountMeta::new(*recipient_payout, false));
+        accounts.push(AccountMeta::new(*funder, true));
+        accounts.push(AccountMeta::new_readonly(sysvar::rent::id(), false));
+        accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+    }
 
     Ok(Instruction {
         program_id: *escrow_program_id,
@@ -417,17 +1422,31 @@ pub fn cancel(
     escrow_authority: &Pubkey,
     canceler_token_account: &Pubkey,
     token_program_id: &Pubkey,
+    oracle_token_accounts: Option<(&Pubkey, &Pubkey)>,
+    vaults: &[VaultRefund],
 ) -> Result<Instruction, ProgramError> {
     let data = EscrowInstruction::Cancel.pack();
 
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(*escrow, false),
         AccountMeta::new_readonly(*trusted_handler, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
         AccountMeta::new(*escrow_token_account, false),
         AccountMeta::new_readonly(*escrow_authority, false),
         AccountMeta::new(*canceler_token_account, false),
         AccountMeta::new_readonly(*token_program_id, false),
     ];
+    if let Some((reputation_oracle_token_account, recording_oracle_token_account)) =
+        oracle_token_accounts
+    {
+        accounts.push(AccountMeta::new(*reputation_oracle_token_account, false));
+        accounts.push(AccountMeta::new(*recording_oracle_token_account, false));
+    }
+    for vault in vaults {
+        accounts.push(AccountMeta::new(vault.vault, false));
+        accounts.push(AccountMeta::new(vault.token_account, false));
+        accounts.push(AccountMeta::new(vault.canceler_token_account, false));
+    }
 
     Ok(Instruction {
         program_id: *escrow_program_id,
@@ -457,72 +1476,993 @@ pub fn complete(
     })
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+/// Creates `Pause` instruction
+pub fn pause(
+    escrow_program_id: &Pubkey,
+    escrow: &Pubkey,
+    guardian: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::Pause.pack();
 
-    #[test]
-    fn test_instruction_packing() {
-        let check = EscrowInstruction::Initialize {
-            duration: 2592000, // 0x0000000000278D00
-        };
-        let packed = check.pack();
-        let expect: Vec<u8> = vec![1, 0x00, 0x8D, 0x27, 0x00, 0x00, 0x00, 0x00, 0x00];
-        assert_eq!(packed, expect);
-        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
+    let accounts = vec![
+        AccountMeta::new(*escrow, false),
+        AccountMeta::new_readonly(*guardian, true),
+    ];
 
-        let check = EscrowInstruction::Setup {
-            reputation_oracle_stake: 5,
-            recording_oracle_stake: 10,
-            manifest_url: DataUrl::new_from_array([10; URL_LEN]),
-            manifest_hash: DataHash::new_from_array([11; 20]),
-        };
-        let packed = check.pack();
-        let mut expect: Vec<u8> = vec![2, 5, 10];
-        expect.extend(&[10; URL_LEN]);
-        expect.extend(&[11; 20]);
-        assert_eq!(packed, expect);
-        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
 
-        let check = EscrowInstruction::StoreResults {
-            total_amount: 1000000,  // 0x00000000000F4240
-            total_recipients: 1000, // 0x00000000000003E8
-            final_results_url: DataUrl::new_from_array([21; URL_LEN]),
-            final_results_hash: DataHash::new_from_array([22; 20]),
-        };
-        let packed = check.pack();
-        let mut expect: Vec<u8> = vec![3];
-        expect.extend(&[0x40, 0x42, 0x0F, 0x00, 0x00, 0x00, 0x00, 0x00]);
-        expect.extend(&[0xE8, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
-        expect.extend(&[21; URL_LEN]);
-        expect.extend(&[22; 20]);
-        assert_eq!(packed, expect);
-        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
+/// Creates `Unpause` instruction
+pub fn unpause(
+    escrow_program_id: &Pubkey,
+    escrow: &Pubkey,
+    guardian: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::Unpause.pack();
 
-        let check = EscrowInstruction::Payout {
-            amount: 1000000000000, // 0x000000E8D4A51000
-        };
-        let packed = check.pack();
-        let expect: Vec<u8> = vec![4, 0x00, 0x10, 0xA5, 0xD4, 0xE8, 0x00, 0x00, 0x00];
-        assert_eq!(packed, expect);
-        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
+    let accounts = vec![
+        AccountMeta::new(*escrow, false),
+        AccountMeta::new_readonly(*guardian, true),
+    ];
 
-        let check = EscrowInstruction::Cancel;
-        let packed = check.pack();
-        let expect: Vec<u8> = vec![5];
-        assert_eq!(packed, expect);
-        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
 
-        let check = EscrowInstruction::Complete;
-        let packed = check.pack();
-        let expect: Vec<u8> = vec![6];
-        assert_eq!(packed, expect);
-        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
+/// Creates `InitializeConfig` instruction
+pub fn initialize_config(
+    escrow_program_id: &Pubkey,
+    config: &Pubkey,
+    admin: &Pubkey,
+    program_data: &Pubkey,
+    upgrade_authority: &Pubkey,
+    max_oracle_stake: u8,
+    min_duration: u64,
+    max_duration: u64,
+    allowed_mints: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::InitializeConfig {
+        max_oracle_stake,
+        min_duration,
+        max_duration,
+        allowed_mints: allowed_mints.to_vec(),
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*config, false),
+        AccountMeta::new(*admin, true),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*program_data, false),
+        AccountMeta::new_readonly(*upgrade_authority, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `UpdateConfig` instruction
+pub fn update_config(
+    escrow_program_id: &Pubkey,
+    config: &Pubkey,
+    admin: &Pubkey,
+    new_admin: &Pubkey,
+    paused: bool,
+    max_oracle_stake: u8,
+    min_duration: u64,
+    max_duration: u64,
+    allowed_mints: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::UpdateConfig {
+        admin: *new_admin,
+        paused,
+        max_oracle_stake,
+        min_duration,
+        max_duration,
+        allowed_mints: allowed_mints.to_vec(),
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*config, false),
+        AccountMeta::new_readonly(*admin, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `UpdateStakingConfig` instruction
+pub fn update_staking_config(
+    escrow_program_id: &Pubkey,
+    config: &Pubkey,
+    admin: &Pubkey,
+    stake_mint: COption<Pubkey>,
+    min_oracle_stake: u64,
+    stake_escrow_threshold: u64,
+    arbiter: COption<Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::UpdateStakingConfig {
+        stake_mint,
+        min_oracle_stake,
+        stake_escrow_threshold,
+        arbiter,
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*config, false),
+        AccountMeta::new_readonly(*admin, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `InitializeOracleStake` instruction
+pub fn initialize_oracle_stake(
+    escrow_program_id: &Pubkey,
+    oracle_stake: &Pubkey,
+    oracle: &Pubkey,
+    vault: &Pubkey,
+    config: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::InitializeOracleStake.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*oracle_stake, false),
+        AccountMeta::new(*oracle, true),
+        AccountMeta::new_readonly(*vault, false),
+        AccountMeta::new_readonly(*config, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `DepositOracleStake` instruction
+pub fn deposit_oracle_stake(
+    escrow_program_id: &Pubkey,
+    oracle_stake: &Pubkey,
+    vault: &Pubkey,
+    source_token_account: &Pubkey,
+    source_owner: &Pubkey,
+    token_program_id: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::DepositOracleStake { amount }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*oracle_stake, false),
+        AccountMeta::new(*vault, false),
+        AccountMeta::new(*source_token_account, false),
+        AccountMeta::new_readonly(*source_owner, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `WithdrawOracleStake` instruction
+pub fn withdraw_oracle_stake(
+    escrow_program_id: &Pubkey,
+    oracle_stake: &Pubkey,
+    oracle: &Pubkey,
+    vault: &Pubkey,
+    destination_token_account: &Pubkey,
+    token_program_id: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::WithdrawOracleStake { amount }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*oracle_stake, false),
+        AccountMeta::new_readonly(*oracle, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new(*vault, false),
+        AccountMeta::new(*destination_token_account, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `SlashOracle` instruction
+pub fn slash_oracle(
+    escrow_program_id: &Pubkey,
+    config: &Pubkey,
+    slasher: &Pubkey,
+    escrow: &Pubkey,
+    oracle_stake: &Pubkey,
+    vault: &Pubkey,
+    canceler_token_account: &Pubkey,
+    token_program_id: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::SlashOracle { amount }.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*config, false),
+        AccountMeta::new_readonly(*slasher, true),
+        AccountMeta::new_readonly(*escrow, false),
+        AccountMeta::new(*oracle_stake, false),
+        AccountMeta::new(*vault, false),
+        AccountMeta::new(*canceler_token_account, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `UpdateReputation` instruction
+pub fn update_reputation(
+    escrow_program_id: &Pubkey,
+    escrow: &Pubkey,
+    reputation_oracle: &Pubkey,
+    reputation_accounts: &[Pubkey],
+    reputation_job_accounts: &[Pubkey],
+    updates: &[ReputationUpdate],
+) -> Result<Instruction, ProgramError> {
+    if reputation_accounts.len() != updates.len() || reputation_job_accounts.len() != updates.len()
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let data = EscrowInstruction::UpdateReputation {
+        updates: updates.to_vec(),
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*escrow, false),
+        AccountMeta::new(*reputation_oracle, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    for (reputation_account, reputation_job_account) in
+        reputation_accounts.iter().zip(reputation_job_accounts)
+    {
+        accounts.push(AccountMeta::new(*reputation_account, false));
+        accounts.push(AccountMeta::new(*reputation_job_account, false));
+    }
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `StoreIntermediateResults` instruction
+pub fn store_intermediate_results(
+    escrow_program_id: &Pubkey,
+    escrow: &Pubkey,
+    results_log: &Pubkey,
+    results_log_page: &Pubkey,
+    recording_oracle: &Pubkey,
+    url: &DataUrl,
+    hash: &DataHash,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::StoreIntermediateResults {
+        url: *url,
+        hash: *hash,
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*escrow, false),
+        AccountMeta::new(*results_log, false),
+        AccountMeta::new(*results_log_page, false),
+        AccountMeta::new(*recording_oracle, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Size of the payout permit message
+pub const PAYOUT_PERMIT_MESSAGE_LEN: usize = 88;
+
+/// Creates payout permit message to be signed by the reputation oracle
+pub fn payout_permit_message(
+    escrow: &Pubkey,
+    recipient_token_account: &Pubkey,
+    amount: u64,
+    nonce: u64,
+    expires: UnixTimestamp,
+) -> Vec<u8> {
+    let mut message: Vec<u8> = Vec::with_capacity(PAYOUT_PERMIT_MESSAGE_LEN);
+    message.extend(escrow.as_ref());
+    message.extend(recipient_token_account.as_ref());
+    message.extend(&amount.to_le_bytes());
+    message.extend(&nonce.to_le_bytes());
+    message.extend(&expires.to_le_bytes());
+    message
+}
+
+/// Creates `PayoutWithPermit` instruction
+pub fn payout_with_permit(
+    escrow_program_id: &Pubkey,
+    escrow: &Pubkey,
+    escrow_token_account: &Pubkey,
+    escrow_authority: &Pubkey,
+    recipient_token_account: &Pubkey,
+    reputation_oracle_token_account: &Pubkey,
+    recording_oracle_token_account: &Pubkey,
+    token_program_id: &Pubkey,
+    amount: u64,
+    nonce: u64,
+    expires: UnixTimestamp,
+    allow_list: Option<&Pubkey>,
+    recipient_payout: Option<(&Pubkey, &Pubkey)>,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::PayoutWithPermit {
+        amount,
+        nonce,
+        expires,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*escrow, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new(*escrow_token_account, false),
+        AccountMeta::new_readonly(*escrow_authority, false),
+        AccountMeta::new(*recipient_token_account, false),
+        AccountMeta::new(*reputation_oracle_token_account, false),
+        AccountMeta::new(*recording_oracle_token_account, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+    if let Some(allow_list) = allow_list {
+        accounts.push(AccountMeta::new_readonly(*allow_list, false));
+    }
+    if let Some((recipient_payout, funder)) = recipient_payout {
+        accounts.push(AccountMeta::new(*recipient_payout, false));
+        accounts.push(AccountMeta::new(*funder, true));
+        accounts.push(AccountMeta::new_readonly(sysvar::rent::id(), false));
+        accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+    }
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `SetPermitSigner` instruction
+pub fn set_permit_signer(
+    escrow_program_id: &Pubkey,
+    escrow: &Pubkey,
+    trusted_handler: &Pubkey,
+    permit_signer: &[u8; ETH_ADDRESS_LEN],
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::SetPermitSigner {
+        permit_signer: *permit_signer,
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*escrow, false),
+        AccountMeta::new_readonly(*trusted_handler, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `InitializeAllowList` instruction
+pub fn initialize_allow_list(
+    escrow_program_id: &Pubkey,
+    allow_list: &Pubkey,
+    authority: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::InitializeAllowList.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*allow_list, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `UpdateAllowList` instruction
+pub fn update_allow_list(
+    escrow_program_id: &Pubkey,
+    allow_list: &Pubkey,
+    authority: &Pubkey,
+    add: &[Pubkey],
+    remove: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    if add.len() > MAX_ALLOW_LIST_UPDATES || remove.len() > MAX_ALLOW_LIST_UPDATES {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let data = EscrowInstruction::UpdateAllowList {
+        add: add.to_vec(),
+        remove: remove.to_vec(),
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*allow_list, false),
+        AccountMeta::new_readonly(*authority, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `AddVault` instruction
+pub fn add_vault(
+    escrow_program_id: &Pubkey,
+    escrow: &Pubkey,
+    trusted_handler: &Pubkey,
+    vault: &Pubkey,
+    token_mint: &Pubkey,
+    vault_token_account: &Pubkey,
+    canceler_token_account: &Pubkey,
+    config: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::AddVault.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*escrow, false),
+        AccountMeta::new(*trusted_handler, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new(*vault, false),
+        AccountMeta::new_readonly(*token_mint, false),
+        AccountMeta::new_readonly(*vault_token_account, false),
+        AccountMeta::new_readonly(*canceler_token_account, false),
+        AccountMeta::new_readonly(*config, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `StoreVaultResults` instruction
+pub fn store_vault_results(
+    escrow_program_id: &Pubkey,
+    escrow: &Pubkey,
+    trusted_handler: &Pubkey,
+    vault: &Pubkey,
+    total_amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::StoreVaultResults { total_amount }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*escrow, false),
+        AccountMeta::new_readonly(*trusted_handler, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new(*vault, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `VaultPayout` instruction
+pub fn vault_payout(
+    escrow_program_id: &Pubkey,
+    escrow: &Pubkey,
+    trusted_handler: &Pubkey,
+    vault: &Pubkey,
+    vault_token_account: &Pubkey,
+    escrow_authority: &Pubkey,
+    recipient_token_account: &Pubkey,
+    reputation_oracle_token_account: &Pubkey,
+    recording_oracle_token_account: &Pubkey,
+    token_program_id: &Pubkey,
+    amount: u64,
+    allow_list: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::VaultPayout { amount }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*escrow, false),
+        AccountMeta::new_readonly(*trusted_handler, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new(*vault, false),
+        AccountMeta::new(*vault_token_account, false),
+        AccountMeta::new_readonly(*escrow_authority, false),
+        AccountMeta::new(*recipient_token_account, false),
+        AccountMeta::new(*reputation_oracle_token_account, false),
+        AccountMeta::new(*recording_oracle_token_account, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+    if let Some(allow_list) = allow_list {
+        accounts.push(AccountMeta::new_readonly(*allow_list, false));
+    }
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `InitializeTemplate` instruction, accounts and values are taken from `template`
+pub fn initialize_template(
+    escrow_program_id: &Pubkey,
+    template_account: &Pubkey,
+    template: &EscrowTemplate,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::InitializeTemplate {
+        duration: template.duration,
+        reputation_oracle_stake: template.reputation_oracle_stake,
+        recording_oracle_stake: template.recording_oracle_stake,
+        max_payout_per_recipient: template.max_payout_per_recipient,
+        min_payout: template.min_payout,
+        cancellation_fee: template.cancellation_fee,
+        cancellation_fee_percent: template.cancellation_fee_percent,
+        cancel_notice: template.cancel_notice,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*template_account, false),
+        AccountMeta::new_readonly(template.authority, true),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(template.token_mint, false),
+        AccountMeta::new_readonly(template.canceler, false),
+        AccountMeta::new_readonly(template.canceler_token_account, false),
+        AccountMeta::new_readonly(template.guardian, false),
+        AccountMeta::new_readonly(template.reputation_oracle, false),
+        AccountMeta::new_readonly(template.reputation_oracle_token_account, false),
+        AccountMeta::new_readonly(template.recording_oracle, false),
+        AccountMeta::new_readonly(template.recording_oracle_token_account, false),
+    ];
+    if let COption::Some(allow_list) = template.allow_list {
+        accounts.push(AccountMeta::new_readonly(allow_list, false));
+    }
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `InitializeFromTemplate` instruction, `template` is the state of the
+/// `template_account`
+pub fn initialize_from_template(
+    escrow_program_id: &Pubkey,
+    escrow: &Pubkey,
+    template_account: &Pubkey,
+    template: &EscrowTemplate,
+    token_account: &Pubkey,
+    config: &Pubkey,
+    reputation_oracle_stake_account: &Pubkey,
+    recording_oracle_stake_account: &Pubkey,
+    manifest_url: &DataUrl,
+    manifest_hash: &DataHash,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::InitializeFromTemplate {
+        manifest_url: *manifest_url,
+        manifest_hash: *manifest_hash,
+    }
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*escrow, false),
+        AccountMeta::new_readonly(template.authority, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(*template_account, false),
+        AccountMeta::new_readonly(template.token_mint, false),
+        AccountMeta::new_readonly(*token_account, false),
+        AccountMeta::new_readonly(template.canceler, false),
+        AccountMeta::new_readonly(template.canceler_token_account, false),
+        AccountMeta::new_readonly(template.guardian, false),
+        AccountMeta::new_readonly(*config, false),
+        AccountMeta::new_readonly(template.reputation_oracle, false),
+        AccountMeta::new_readonly(template.reputation_oracle_token_account, false),
+        AccountMeta::new_readonly(template.recording_oracle, false),
+        AccountMeta::new_readonly(template.recording_oracle_token_account, false),
+        AccountMeta::new(*reputation_oracle_stake_account, false),
+        AccountMeta::new(*recording_oracle_stake_account, false),
+    ];
+    if let COption::Some(allow_list) = template.allow_list {
+        accounts.push(AccountMeta::new_readonly(allow_list, false));
+    }
+
+    Ok(Instruction {
+        program_id: *escrow_program_id,
+        accounts,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_instruction_packing() {
+        let check = EscrowInstruction::Initialize {
+            duration: 2592000, // 0x0000000000278D00
+        };
+        let packed = check.pack();
+        let expect: Vec<u8> = vec![1, 0x00, 0x8D, 0x27, 0x00, 0x00, 0x00, 0x00, 0x00];
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::Setup {
+            reputation_oracle_stake: 5,
+            recording_oracle_stake: 10,
+            manifest_url: DataUrl::new_from_array([10; URL_LEN]),
+            manifest_hash: DataHash::new_from_array([11; 20]),
+            max_payout_per_recipient: 500, // 0x00000000000001F4
+            min_payout: 10,
+            cancellation_fee: 0,
+            cancellation_fee_percent: 20,
+            cancel_notice: 3600, // 0x0000000000000E10
+        };
+        let packed = check.pack();
+        let mut expect: Vec<u8> = vec![2, 5, 10];
+        expect.extend(&[10; URL_LEN]);
+        expect.extend(&[11; 20]);
+        expect.extend(&[0xF4, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        expect.extend(&[10, 0, 0, 0, 0, 0, 0, 0]);
+        expect.extend(&[0, 0, 0, 0, 0, 0, 0, 0]);
+        expect.push(20);
+        expect.extend(&[0x10, 0x0E, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::StoreResults {
+            total_amount: 1000000,  // 0x00000000000F4240
+            total_recipients: 1000, // 0x00000000000003E8
+            final_results_url: DataUrl::new_from_array([21; URL_LEN]),
+            final_results_hash: DataHash::new_from_array([22; 20]),
+        };
+        let packed = check.pack();
+        let mut expect: Vec<u8> = vec![3];
+        expect.extend(&[0x40, 0x42, 0x0F, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        expect.extend(&[0xE8, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        expect.extend(&[21; URL_LEN]);
+        expect.extend(&[22; 20]);
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::Payout {
+            amount: 1000000000000, // 0x000000E8D4A51000
+        };
+        let packed = check.pack();
+        let expect: Vec<u8> = vec![4, 0x00, 0x10, 0xA5, 0xD4, 0xE8, 0x00, 0x00, 0x00];
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::Cancel;
+        let packed = check.pack();
+        let expect: Vec<u8> = vec![5];
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::Complete;
+        let packed = check.pack();
+        let expect: Vec<u8> = vec![6];
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::PayoutWithPermit {
+            amount: 1000000000000, // 0x000000E8D4A51000
+            nonce: 3,
+            expires: 1606402240, // 0x000000005FBFC0C0
+        };
+        let packed = check.pack();
+        let mut expect: Vec<u8> = vec![7, 0x00, 0x10, 0xA5, 0xD4, 0xE8, 0x00, 0x00, 0x00];
+        expect.extend(&[3, 0, 0, 0, 0, 0, 0, 0]);
+        expect.extend(&[0xC0, 0xC0, 0xBF, 0x5F, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::Pause;
+        let packed = check.pack();
+        let expect: Vec<u8> = vec![8];
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::Unpause;
+        let packed = check.pack();
+        let expect: Vec<u8> = vec![9];
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::InitializeConfig {
+            max_oracle_stake: 30,
+            min_duration: 3600,    // 0x0000000000000E10
+            max_duration: 2592000, // 0x0000000000278D00
+            allowed_mints: vec![Pubkey::new_from_array([1; 32])],
+        };
+        let packed = check.pack();
+        let mut expect: Vec<u8> = vec![10, 30];
+        expect.extend(&[0x10, 0x0E, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        expect.extend(&[0x00, 0x8D, 0x27, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        expect.push(1);
+        expect.extend(&[1; 32]);
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::UpdateConfig {
+            admin: Pubkey::new_from_array([2; 32]),
+            paused: true,
+            max_oracle_stake: 30,
+            min_duration: 3600,    // 0x0000000000000E10
+            max_duration: 2592000, // 0x0000000000278D00
+            allowed_mints: vec![],
+        };
+        let packed = check.pack();
+        let mut expect: Vec<u8> = vec![11];
+        expect.extend(&[2; 32]);
+        expect.extend(&[1, 30]);
+        expect.extend(&[0x10, 0x0E, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        expect.extend(&[0x00, 0x8D, 0x27, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        expect.push(0);
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::UpdateStakingConfig {
+            stake_mint: COption::Some(Pubkey::new_from_array([3; 32])),
+            min_oracle_stake: 1000,          // 0x00000000000003E8
+            stake_escrow_threshold: 1000000, // 0x00000000000F4240
+            arbiter: COption::None,
+        };
+        let packed = check.pack();
+        let mut expect: Vec<u8> = vec![12, 1];
+        expect.extend(&[3; 32]);
+        expect.extend(&[0xE8, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        expect.extend(&[0x40, 0x42, 0x0F, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        expect.push(0);
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::InitializeOracleStake;
+        let packed = check.pack();
+        let expect: Vec<u8> = vec![13];
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::DepositOracleStake { amount: 1000 };
+        let packed = check.pack();
+        let expect: Vec<u8> = vec![14, 0xE8, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::WithdrawOracleStake { amount: 1000 };
+        let packed = check.pack();
+        let expect: Vec<u8> = vec![15, 0xE8, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::SlashOracle { amount: 1000 };
+        let packed = check.pack();
+        let expect: Vec<u8> = vec![16, 0xE8, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::UpdateReputation {
+            updates: vec![
+                ReputationUpdate {
+                    worker: Pubkey::new_from_array([1; 32]),
+                    delta: 5,
+                },
+                ReputationUpdate {
+                    worker: Pubkey::new_from_array([2; 32]),
+                    delta: -1,
+                },
+            ],
+        };
+        let packed = check.pack();
+        let mut expect: Vec<u8> = vec![17, 2];
+        expect.extend(&[1; 32]);
+        expect.extend(&[5, 0, 0, 0, 0, 0, 0, 0]);
+        expect.extend(&[2; 32]);
+        expect.extend(&[0xFF; 8]);
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::StoreIntermediateResults {
+            url: DataUrl::new_from_array([1; URL_LEN]),
+            hash: DataHash::new_from_array([2; 20]),
+        };
+        let packed = check.pack();
+        let mut expect: Vec<u8> = vec![18];
+        expect.extend(&[1; URL_LEN]);
+        expect.extend(&[2; 20]);
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::InitializeAllowList;
+        let packed = check.pack();
+        let expect: Vec<u8> = vec![19];
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::UpdateAllowList {
+            add: vec![
+                Pubkey::new_from_array([1; 32]),
+                Pubkey::new_from_array([2; 32]),
+            ],
+            remove: vec![Pubkey::new_from_array([3; 32])],
+        };
+        let packed = check.pack();
+        let mut expect: Vec<u8> = vec![20, 2];
+        expect.extend(&[1; 32]);
+        expect.extend(&[2; 32]);
+        expect.push(1);
+        expect.extend(&[3; 32]);
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::AddVault;
+        let packed = check.pack();
+        let expect: Vec<u8> = vec![21];
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::StoreVaultResults { total_amount: 500 };
+        let packed = check.pack();
+        let expect: Vec<u8> = vec![22, 0xF4, 0x01, 0, 0, 0, 0, 0, 0];
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::VaultPayout { amount: 42 };
+        let packed = check.pack();
+        let expect: Vec<u8> = vec![23, 42, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::InitializeTemplate {
+            duration: 86400, // 0x0000000000015180
+            reputation_oracle_stake: 5,
+            recording_oracle_stake: 10,
+            max_payout_per_recipient: 500,
+            min_payout: 10,
+            cancellation_fee: 7,
+            cancellation_fee_percent: 0,
+            cancel_notice: 60,
+        };
+        let packed = check.pack();
+        let mut expect: Vec<u8> = vec![24, 0x80, 0x51, 0x01, 0, 0, 0, 0, 0, 5, 10];
+        expect.extend(&[0xF4, 0x01, 0, 0, 0, 0, 0, 0]);
+        expect.extend(&[10, 0, 0, 0, 0, 0, 0, 0]);
+        expect.extend(&[7, 0, 0, 0, 0, 0, 0, 0]);
+        expect.push(0);
+        expect.extend(&[60, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::InitializeFromTemplate {
+            manifest_url: DataUrl::new_from_array([10; URL_LEN]),
+            manifest_hash: DataHash::new_from_array([11; 20]),
+        };
+        let packed = check.pack();
+        let mut expect: Vec<u8> = vec![25];
+        expect.extend(&[10; URL_LEN]);
+        expect.extend(&[11; 20]);
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = EscrowInstruction::SetPermitSigner {
+            permit_signer: [12; ETH_ADDRESS_LEN],
+        };
+        let packed = check.pack();
+        let mut expect: Vec<u8> = vec![26];
+        expect.extend(&[12; ETH_ADDRESS_LEN]);
+        assert_eq!(packed, expect);
+        let unpacked = EscrowInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        // Allow-list updates above the limit are rejected
+        assert_eq!(
+            EscrowInstruction::unpack(&[20, MAX_ALLOW_LIST_UPDATES as u8 + 1]),
+            Err(ProgramError::InvalidInstructionData)
+        );
+
+        // Empty reputation updates are rejected
+        assert_eq!(
+            EscrowInstruction::unpack(&[17, 0]),
+            Err(ProgramError::InvalidInstructionData)
+        );
+
+        // Trailing bytes are rejected
+        let mut trailing = EscrowInstruction::Payout { amount: 1 }.pack();
+        trailing.push(0);
+        assert_eq!(
+            EscrowInstruction::unpack(&trailing),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            EscrowInstruction::unpack(&[5, 0]),
+            Err(ProgramError::InvalidInstructionData)
+        );
     }
 }
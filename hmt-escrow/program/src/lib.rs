@@ -2,11 +2,17 @@
 
 //! Human Protocol Escrow Contract
 
+pub mod borsh_utils;
 pub mod error;
 pub mod instruction;
 pub mod processor;
+pub mod schema;
+pub mod secp256k1;
 pub mod state;
 
+#[cfg(feature = "serde")]
+pub mod serde_utils;
+
 #[cfg(not(feature = "no-entrypoint"))]
 pub mod entrypoint;
 
@@ -1,13 +1,16 @@
 //! Program state processor
 
 use crate::error::EscrowError;
-use crate::instruction::EscrowInstruction;
+use crate::instruction::{payout_permit_message, EscrowInstruction, ReputationUpdate};
+use crate::secp256k1::{self, ETH_ADDRESS_LEN};
 use crate::state::*;
+use arrayref::{array_ref, array_refs};
 use num_traits::FromPrimitive;
-use solana_program::program::invoke_signed;
+use solana_program::program::{invoke, invoke_signed};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    clock::Clock,
+    bpf_loader_upgradeable,
+    clock::{Clock, UnixTimestamp},
     decode_error::DecodeError,
     entrypoint::ProgramResult,
     info,
@@ -15,9 +18,18 @@ use solana_program::{
     program_option::COption,
     program_pack::{IsInitialized, Pack},
     pubkey::Pubkey,
-    sysvar::Sysvar,
+    rent::Rent,
+    system_instruction,
+    sysvar::{self, Sysvar},
 };
 use spl_token::state::Account as TokenAccount;
+use std::convert::TryFrom;
+
+/// Length of the upgradeable loader program data header up to the upgrade authority
+const PROGRAM_DATA_HEADER_LEN: usize = 45;
+
+/// Variant index of `UpgradeableLoaderState::ProgramData`
+const PROGRAM_DATA_VARIANT: u32 = 3;
 
 /// Program state handler.
 pub struct Processor {}
@@ -44,6 +56,315 @@ impl Processor {
         Pubkey::find_program_address(&[&escrow_account_key.to_bytes()[..32]], escrow_program_id)
     }
 
+    /// Generates program config address and its bump seed
+    pub fn find_config_address(escrow_program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[CONFIG_SEED], escrow_program_id)
+    }
+
+    /// Reads program config and checks its address
+    fn get_config(
+        escrow_program_id: &Pubkey,
+        config_info: &AccountInfo,
+    ) -> Result<EscrowConfig, ProgramError> {
+        if config_info.owner != escrow_program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let config = EscrowConfig::unpack(&config_info.data.borrow())?;
+
+        let config_key =
+            Pubkey::create_program_address(&[CONFIG_SEED, &[config.bump_seed]], escrow_program_id)
+                .or(Err(ProgramError::InvalidSeeds))?;
+        if config_key != *config_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        Ok(config)
+    }
+
+    /// Generates program data address of the escrow program deployed with
+    /// the upgradeable BPF loader
+    pub fn find_program_data_address(escrow_program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[escrow_program_id.as_ref()], &bpf_loader_upgradeable::id()).0
+    }
+
+    /// Reads the upgrade authority of the escrow program from its program data account
+    fn get_upgrade_authority(
+        escrow_program_id: &Pubkey,
+        program_data_info: &AccountInfo,
+    ) -> Result<Option<Pubkey>, ProgramError> {
+        if *program_data_info.owner != bpf_loader_upgradeable::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if *program_data_info.key != Self::find_program_data_address(escrow_program_id) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        // Bincode encoded `UpgradeableLoaderState::ProgramData` header:
+        // u32 variant index, u64 deployment slot and optional authority
+        let data = program_data_info.data.borrow();
+        let header = data
+            .get(..PROGRAM_DATA_HEADER_LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let header = array_ref![header, 0, PROGRAM_DATA_HEADER_LEN];
+        let (variant, _slot, authority_option, authority) = array_refs![header, 4, 8, 1, 32];
+        if u32::from_le_bytes(*variant) != PROGRAM_DATA_VARIANT {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        match authority_option {
+            [0] => Ok(None),
+            [1] => Ok(Some(Pubkey::new_from_array(*authority))),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    /// Generates oracle stake address and its bump seed
+    pub fn find_oracle_stake_address(escrow_program_id: &Pubkey, oracle: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[ORACLE_STAKE_SEED, &oracle.to_bytes()], escrow_program_id)
+    }
+
+    /// Generates worker reputation address and its bump seed
+    pub fn find_reputation_address(escrow_program_id: &Pubkey, worker: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[REPUTATION_SEED, &worker.to_bytes()], escrow_program_id)
+    }
+
+    /// Generates address of the reputation job marking the worker rated for the escrow,
+    /// and its bump seed
+    pub fn find_reputation_job_address(
+        escrow_program_id: &Pubkey,
+        worker: &Pubkey,
+        escrow: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[REPUTATION_JOB_SEED, &worker.to_bytes(), &escrow.to_bytes()],
+            escrow_program_id,
+        )
+    }
+
+    /// Generates escrow results log address and its bump seed
+    pub fn find_results_log_address(escrow_program_id: &Pubkey, escrow: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[RESULTS_LOG_SEED, &escrow.to_bytes()], escrow_program_id)
+    }
+
+    /// Generates escrow results log page address and its bump seed
+    pub fn find_results_log_page_address(
+        escrow_program_id: &Pubkey,
+        escrow: &Pubkey,
+        page: u64,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                RESULTS_LOG_PAGE_SEED,
+                &escrow.to_bytes(),
+                &page.to_le_bytes(),
+            ],
+            escrow_program_id,
+        )
+    }
+
+    /// Generates address of the total paid by the escrow to the recipient owner,
+    /// and its bump seed
+    pub fn find_recipient_payout_address(
+        escrow_program_id: &Pubkey,
+        escrow: &Pubkey,
+        owner: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[RECIPIENT_PAYOUT_SEED, &escrow.to_bytes(), &owner.to_bytes()],
+            escrow_program_id,
+        )
+    }
+
+    /// Generates escrow vault address and its bump seed
+    pub fn find_vault_address(
+        escrow_program_id: &Pubkey,
+        escrow: &Pubkey,
+        token_mint: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[VAULT_SEED, &escrow.to_bytes(), &token_mint.to_bytes()],
+            escrow_program_id,
+        )
+    }
+
+    /// Reads oracle stake and checks its address
+    fn get_oracle_stake(
+        escrow_program_id: &Pubkey,
+        oracle_stake_info: &AccountInfo,
+    ) -> Result<OracleStake, ProgramError> {
+        if oracle_stake_info.owner != escrow_program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let oracle_stake = OracleStake::unpack(&oracle_stake_info.data.borrow())?;
+
+        let oracle_stake_key = Pubkey::create_program_address(
+            &[
+                ORACLE_STAKE_SEED,
+                &oracle_stake.oracle.to_bytes(),
+                &[oracle_stake.bump_seed],
+            ],
+            escrow_program_id,
+        )
+        .or(Err(ProgramError::InvalidSeeds))?;
+        if oracle_stake_key != *oracle_stake_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        Ok(oracle_stake)
+    }
+
+    /// Reads recipient allow-list owned by the program
+    fn get_allow_list(
+        escrow_program_id: &Pubkey,
+        allow_list_info: &AccountInfo,
+    ) -> Result<AllowList, ProgramError> {
+        if allow_list_info.owner != escrow_program_id {
+            return Err(EscrowError::InvalidAllowList.into());
+        }
+
+        let allow_list = AllowList::unpack_from_slice(&allow_list_info.data.borrow())?;
+        if !allow_list.is_initialized() {
+            return Err(EscrowError::InvalidAllowList.into());
+        }
+
+        Ok(allow_list)
+    }
+
+    /// Reads escrow template owned by the program
+    fn get_template(
+        escrow_program_id: &Pubkey,
+        template_info: &AccountInfo,
+    ) -> Result<EscrowTemplate, ProgramError> {
+        if template_info.owner != escrow_program_id {
+            return Err(EscrowError::InvalidTemplate.into());
+        }
+
+        let template = EscrowTemplate::unpack_unchecked(&template_info.data.borrow())?;
+        if !template.is_initialized() {
+            return Err(EscrowError::InvalidTemplate.into());
+        }
+
+        Ok(template)
+    }
+
+    /// Reads escrow vault owned by the program and checks it belongs to the escrow
+    fn get_vault(
+        escrow_program_id: &Pubkey,
+        escrow_key: &Pubkey,
+        vault_info: &AccountInfo,
+    ) -> Result<EscrowVault, ProgramError> {
+        if vault_info.owner != escrow_program_id {
+            return Err(EscrowError::InvalidVault.into());
+        }
+
+        let vault = EscrowVault::unpack(&vault_info.data.borrow())?;
+        if vault.escrow != *escrow_key {
+            return Err(EscrowError::InvalidVault.into());
+        }
+
+        Ok(vault)
+    }
+
+    /// Checks that the oracle has enough stake and locks it until the escrow expires
+    fn lock_oracle_stake(
+        escrow_program_id: &Pubkey,
+        config: &EscrowConfig,
+        escrow: &Escrow,
+        oracle: &Pubkey,
+        oracle_stake_info: &AccountInfo,
+    ) -> ProgramResult {
+        let mut oracle_stake = Self::get_oracle_stake(escrow_program_id, oracle_stake_info)
+            .or(Err(EscrowError::InsufficientOracleStake))?;
+        if oracle_stake.oracle != *oracle {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if oracle_stake.amount < config.min_oracle_stake {
+            return Err(EscrowError::InsufficientOracleStake.into());
+        }
+
+        oracle_stake.locked_until = oracle_stake.locked_until.max(escrow.expires);
+
+        OracleStake::pack(oracle_stake, &mut oracle_stake_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Issue a spl_token `Transfer` instruction from the oracle stake vault.
+    pub fn oracle_stake_transfer<'a>(
+        oracle_stake: &OracleStake,
+        token_program: AccountInfo<'a>,
+        vault: AccountInfo<'a>,
+        destination: AccountInfo<'a>,
+        oracle_stake_info: AccountInfo<'a>,
+        amount: u64,
+    ) -> ProgramResult {
+        // Oracle stake signature must only reach the token program
+        if *token_program.key != spl_token::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let oracle_stake_signature_seeds = [
+            ORACLE_STAKE_SEED,
+            &oracle_stake.oracle.to_bytes()[..32],
+            &[oracle_stake.bump_seed],
+        ];
+        let signers = &[&oracle_stake_signature_seeds[..]];
+
+        let ix = spl_token::instruction::transfer(
+            token_program.key,
+            vault.key,
+            destination.key,
+            oracle_stake_info.key,
+            &[],
+            amount,
+        )?;
+
+        invoke_signed(
+            &ix,
+            &[vault, destination, oracle_stake_info, token_program],
+            signers,
+        )
+    }
+
+    /// Creates program owned account at the derived address
+    pub fn create_program_account<'a>(
+        escrow_program_id: &Pubkey,
+        account_info: AccountInfo<'a>,
+        funder_info: AccountInfo<'a>,
+        system_program_info: AccountInfo<'a>,
+        rent: &Rent,
+        space: usize,
+        signer_seeds: &[&[u8]],
+    ) -> ProgramResult {
+        // Fund the new account with the minimum balance to be rent exempt
+        let required_lamports = rent
+            .minimum_balance(space)
+            .max(1)
+            .saturating_sub(account_info.lamports());
+        if required_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(funder_info.key, account_info.key, required_lamports),
+                &[
+                    funder_info.clone(),
+                    account_info.clone(),
+                    system_program_info.clone(),
+                ],
+            )?;
+        }
+
+        invoke_signed(
+            &system_instruction::allocate(account_info.key, space as u64),
+            &[account_info.clone(), system_program_info.clone()],
+            &[signer_seeds],
+        )?;
+
+        invoke_signed(
+            &system_instruction::assign(account_info.key, escrow_program_id),
+            &[account_info, system_program_info],
+            &[signer_seeds],
+        )
+    }
+
     /// Verifies if transaction is signed by the trusted handler
     fn check_trusted_handler(escrow: &Escrow, trusted_handler_info: &AccountInfo) -> ProgramResult {
         // Check if instruction is signed by the trusted handler
@@ -80,6 +401,19 @@ impl Processor {
         clock: &Clock,
         trusted_handler_info: &AccountInfo,
         allowed_states: Vec<EscrowState>,
+    ) -> Result<Escrow, ProgramError> {
+        let escrow =
+            Self::get_escrow_with_state_check_unsigned(escrow_info, clock, allowed_states)?;
+
+        Self::check_trusted_handler(&escrow, trusted_handler_info)?;
+
+        Ok(escrow)
+    }
+
+    fn get_escrow_with_state_check_unsigned(
+        escrow_info: &AccountInfo,
+        clock: &Clock,
+        allowed_states: Vec<EscrowState>,
     ) -> Result<Escrow, ProgramError> {
         let escrow = Escrow::unpack_unchecked(&escrow_info.data.borrow())?;
 
@@ -88,6 +422,11 @@ impl Processor {
             return Err(ProgramError::UninitializedAccount);
         }
 
+        // Check if escrow is paused by the guardian
+        if escrow.paused {
+            return Err(EscrowError::Paused.into());
+        }
+
         // Check escrow account expiration
         if escrow.expires < clock.unix_timestamp {
             return Err(EscrowError::EscrowExpired.into());
@@ -98,8 +437,6 @@ impl Processor {
             return Err(EscrowError::WrongState.into());
         }
 
-        Self::check_trusted_handler(&escrow, trusted_handler_info)?;
-
         Ok(escrow)
     }
 
@@ -114,6 +451,11 @@ impl Processor {
         bump_seed: u8,
         amount: u64,
     ) -> ProgramResult {
+        // Escrow authority signature must only reach the token program
+        if *token_program.key != spl_token::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
         let authority_signature_seeds = [&escrow_account_key.to_bytes()[..32], &[bump_seed]];
         let signers = &[&authority_signature_seeds[..]];
 
@@ -148,6 +490,8 @@ impl Processor {
         let launcher_info = next_account_info(account_info_iter)?;
         let canceler_info = next_account_info(account_info_iter)?;
         let canceler_token_account_info = next_account_info(account_info_iter)?;
+        let guardian_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
 
         let escrow = Box::new(Escrow::unpack_unchecked(&escrow_info.data.borrow())?);
 
@@ -161,6 +505,18 @@ impl Processor {
             return Err(EscrowError::EscrowExpired.into());
         }
 
+        // Check program config limits
+        let config = Self::get_config(program_id, config_info)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        if duration < config.min_duration || duration > config.max_duration {
+            return Err(EscrowError::DurationOutOfBounds.into());
+        }
+        if !config.is_mint_allowed(token_mint_info.key) {
+            return Err(EscrowError::MintNotAllowed.into());
+        }
+
         // Calculate authority key and bump seed
         let (authority_key, bump_seed) =
             Self::find_authority_bump_seed(program_id, escrow_info.key);
@@ -184,15 +540,21 @@ impl Processor {
             return Err(EscrowError::WrongTokenMint.into());
         }
 
+        let expires = i64::try_from(duration)
+            .ok()
+            .and_then(|duration| clock.unix_timestamp.checked_add(duration))
+            .ok_or(EscrowError::DurationOutOfBounds)?;
+
         let escrow = Box::new(Escrow {
             state: EscrowState::Launched,
-            expires: clock.unix_timestamp + duration as i64,
+            expires,
             bump_seed,
             token_mint: *token_mint_info.key,
             token_account: *token_account_info.key,
             launcher: *launcher_info.key,
             canceler: *canceler_info.key,
             canceler_token_account: *canceler_token_account_info.key,
+            guardian: *guardian_info.key,
             ..Default::default()
         });
 
@@ -201,12 +563,19 @@ impl Processor {
     }
 
     /// Processes `Setup` instruction.
+    #[allow(clippy::too_many_arguments)]
     pub fn process_setup(
+        program_id: &Pubkey,
         accounts: &[AccountInfo],
         reputation_oracle_stake: u8,
         recording_oracle_stake: u8,
         manifest_url: &DataUrl,
         manifest_hash: &DataHash,
+        max_payout_per_recipient: u64,
+        min_payout: u64,
+        cancellation_fee: u64,
+        cancellation_fee_percent: u8,
+        cancel_notice: u64,
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         let escrow_info = next_account_info(account_info_iter)?;
@@ -217,6 +586,10 @@ impl Processor {
         let reputation_oracle_token_account_info = next_account_info(account_info_iter)?;
         let recording_oracle_info = next_account_info(account_info_iter)?;
         let recording_oracle_token_account_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let token_account_info = next_account_info(account_info_iter)?;
+        let reputation_oracle_stake_info = next_account_info(account_info_iter)?;
+        let recording_oracle_stake_info = next_account_info(account_info_iter)?;
 
         let mut escrow = Self::get_escrow_with_state_check(
             escrow_info,
@@ -233,6 +606,38 @@ impl Processor {
             return Err(EscrowError::StakeOutOfBounds.into());
         }
 
+        // Check payout limits validity
+        if max_payout_per_recipient != 0 && min_payout > max_payout_per_recipient {
+            return Err(EscrowError::InvalidPayoutLimits.into());
+        }
+
+        // Check cancellation fee validity, either fixed or percent
+        if cancellation_fee_percent > 100
+            || (cancellation_fee != 0 && cancellation_fee_percent != 0)
+        {
+            return Err(EscrowError::InvalidCancellationFee.into());
+        }
+        let cancel_notice_end = if cancel_notice == 0 {
+            0
+        } else {
+            UnixTimestamp::try_from(cancel_notice)
+                .ok()
+                .and_then(|notice| clock.unix_timestamp.checked_add(notice))
+                .ok_or(ProgramError::InvalidInstructionData)?
+        };
+        if cancel_notice_end > escrow.expires {
+            return Err(EscrowError::CancelNoticeTooLong.into());
+        }
+
+        // Check program config limits
+        let config = Self::get_config(program_id, config_info)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        if total_stake > config.max_oracle_stake {
+            return Err(EscrowError::StakeOutOfBounds.into());
+        }
+
         // Check token account mints
         let reputation_oracle_token_account =
             TokenAccount::unpack_unchecked(&reputation_oracle_token_account_info.data.borrow())?;
@@ -245,6 +650,28 @@ impl Processor {
             return Err(EscrowError::WrongTokenMint.into());
         }
 
+        // Large escrows can only be handled by oracles with locked stake
+        if *token_account_info.key != escrow.token_account {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let token_account = TokenAccount::unpack_unchecked(&token_account_info.data.borrow())?;
+        if config.is_oracle_stake_required(token_account.amount) {
+            Self::lock_oracle_stake(
+                program_id,
+                &config,
+                &escrow,
+                reputation_oracle_info.key,
+                reputation_oracle_stake_info,
+            )?;
+            Self::lock_oracle_stake(
+                program_id,
+                &config,
+                &escrow,
+                recording_oracle_info.key,
+                recording_oracle_stake_info,
+            )?;
+        }
+
         // Update escrow fields with the new values
         escrow.reputation_oracle = COption::Some(*reputation_oracle_info.key);
         escrow.reputation_oracle_token_account =
@@ -259,6 +686,19 @@ impl Processor {
         escrow.manifest_url = *manifest_url;
         escrow.manifest_hash = *manifest_hash;
 
+        escrow.max_payout_per_recipient = max_payout_per_recipient;
+        escrow.min_payout = min_payout;
+
+        escrow.cancellation_fee = cancellation_fee;
+        escrow.cancellation_fee_percent = cancellation_fee_percent;
+        escrow.cancel_notice_end = cancel_notice_end;
+
+        // Payout recipients can be limited to an allow-list shared by many escrows
+        if let Some(allow_list_info) = account_info_iter.next() {
+            Self::get_allow_list(program_id, allow_list_info)?;
+            escrow.allow_list = COption::Some(*allow_list_info.key);
+        }
+
         escrow.state = EscrowState::Pending;
 
         Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
@@ -267,6 +707,7 @@ impl Processor {
 
     /// Processes `StoreResults` instruction.
     pub fn process_store_results(
+        program_id: &Pubkey,
         accounts: &[AccountInfo],
         total_amount: u64,
         total_recipients: u64,
@@ -277,6 +718,10 @@ impl Processor {
         let escrow_info = next_account_info(account_info_iter)?;
         let trusted_handler_info = next_account_info(account_info_iter)?;
         let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+        let results_log_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let reputation_oracle_stake_info = next_account_info(account_info_iter)?;
+        let recording_oracle_stake_info = next_account_info(account_info_iter)?;
 
         let mut escrow = Self::get_escrow_with_state_check(
             escrow_info,
@@ -285,41 +730,77 @@ impl Processor {
             vec![EscrowState::Pending, EscrowState::Partial],
         )?;
 
+        // Final totals cannot go below what was already paid out
+        if total_amount < escrow.sent_amount || total_recipients < escrow.sent_recipients {
+            return Err(EscrowError::TooManyPayouts.into());
+        }
+
+        // Escrow can be funded after setup, so large payouts need locked
+        // oracle stakes even if the escrow was below the threshold at setup
+        let config = Self::get_config(program_id, config_info)?;
+        if config.is_oracle_stake_required(total_amount) {
+            let reputation_oracle = escrow
+                .reputation_oracle
+                .ok_or(EscrowError::OracleNotInitialized)?;
+            let recording_oracle = escrow
+                .recording_oracle
+                .ok_or(EscrowError::OracleNotInitialized)?;
+            Self::lock_oracle_stake(
+                program_id,
+                &config,
+                &escrow,
+                &reputation_oracle,
+                reputation_oracle_stake_info,
+            )?;
+            Self::lock_oracle_stake(
+                program_id,
+                &config,
+                &escrow,
+                &recording_oracle,
+                recording_oracle_stake_info,
+            )?;
+        }
+
         // Save final amounts and results
         escrow.total_amount = total_amount;
         escrow.total_recipients = total_recipients;
         escrow.final_results_url = *final_results_url;
         escrow.final_results_hash = *final_results_hash;
 
+        // Commit to the intermediate results stored so far
+        let (results_log_key, _) = Self::find_results_log_address(program_id, escrow_info.key);
+        if results_log_key != *results_log_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if results_log_info.owner == program_id {
+            let results_log = ResultsLog::unpack_from_slice(&results_log_info.data.borrow())?;
+            escrow.results_log_head = results_log.head;
+            escrow.results_log_count = results_log.count;
+        }
+
         Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
 
         Ok(())
     }
 
-    /// Processes `Payout` instruction.
-    pub fn process_payout(
+    /// Sends tokens to the payout recipient and fees to the oracles, updates escrow totals.
+    ///
+    /// The allow-list and the recipient payout accounts the escrow requires are
+    /// read from the remaining accounts.
+    #[allow(clippy::too_many_arguments)]
+    fn send_payout<'a>(
         program_id: &Pubkey,
-        accounts: &[AccountInfo],
+        escrow_info: &AccountInfo<'a>,
+        escrow: &mut Escrow,
+        token_account_info: &AccountInfo<'a>,
+        authority_info: &AccountInfo<'a>,
+        recipient_token_account_info: &AccountInfo<'a>,
+        reputation_oracle_token_account_info: &AccountInfo<'a>,
+        recording_oracle_token_account_info: &AccountInfo<'a>,
+        token_program_info: &AccountInfo<'a>,
+        account_info_iter: &mut std::slice::Iter<AccountInfo<'a>>,
         amount: u64,
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let escrow_info = next_account_info(account_info_iter)?;
-        let trusted_handler_info = next_account_info(account_info_iter)?;
-        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
-        let token_account_info = next_account_info(account_info_iter)?;
-        let authority_info = next_account_info(account_info_iter)?;
-        let recipient_token_account_info = next_account_info(account_info_iter)?;
-        let reputation_oracle_token_account_info = next_account_info(account_info_iter)?;
-        let recording_oracle_token_account_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
-
-        let mut escrow = Self::get_escrow_with_state_check(
-            escrow_info,
-            clock,
-            trusted_handler_info,
-            vec![EscrowState::Pending, EscrowState::Partial],
-        )?;
-
         // Check all accounts validity
         if *token_account_info.key != escrow.token_account
             || *reputation_oracle_token_account_info.key
@@ -336,6 +817,34 @@ impl Processor {
             return Err(ProgramError::InvalidInstructionData);
         }
 
+        // Check payout limits
+        if amount == 0 || amount < escrow.min_payout {
+            return Err(EscrowError::PayoutBelowMinimum.into());
+        }
+
+        let allow_list_info = if escrow.allow_list.is_some() {
+            account_info_iter.next()
+        } else {
+            None
+        };
+        Self::check_recipient_allowed(
+            program_id,
+            escrow,
+            recipient_token_account_info,
+            allow_list_info,
+        )?;
+
+        if escrow.max_payout_per_recipient != 0 {
+            Self::add_recipient_payout(
+                program_id,
+                escrow_info,
+                escrow,
+                recipient_token_account_info,
+                account_info_iter,
+                amount,
+            )?;
+        }
+
         // Check account balance
         let token_account = TokenAccount::unpack_unchecked(&token_account_info.data.borrow())?;
         if token_account.amount < amount {
@@ -343,12 +852,139 @@ impl Processor {
         }
 
         // Check if not too many payouts
-        if (escrow.sent_amount + amount > escrow.total_amount)
-            || (escrow.sent_recipients + 1 > escrow.total_recipients)
-        {
+        let sent_amount = escrow
+            .sent_amount
+            .checked_add(amount)
+            .ok_or(EscrowError::TooManyPayouts)?;
+        if sent_amount > escrow.total_amount || escrow.sent_recipients >= escrow.total_recipients {
             return Err(EscrowError::TooManyPayouts.into());
         }
 
+        Self::transfer_payout(
+            escrow_info,
+            escrow,
+            token_account_info,
+            authority_info,
+            recipient_token_account_info,
+            reputation_oracle_token_account_info,
+            recording_oracle_token_account_info,
+            token_program_info,
+            amount,
+        )?;
+
+        escrow.sent_amount = sent_amount;
+        escrow.sent_recipients += 1;
+        Self::update_payout_state(escrow);
+
+        Ok(())
+    }
+
+    /// Checks that the recipient is a token account and its owner is in the
+    /// escrow allow-list
+    fn check_recipient_allowed(
+        program_id: &Pubkey,
+        escrow: &Escrow,
+        recipient_token_account_info: &AccountInfo,
+        allow_list_info: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        // Owner in the data of an account of another program means nothing
+        if *recipient_token_account_info.owner != spl_token::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if let COption::Some(allow_list_key) = escrow.allow_list {
+            let allow_list_info = allow_list_info.ok_or(EscrowError::InvalidAllowList)?;
+            if *allow_list_info.key != allow_list_key {
+                return Err(EscrowError::InvalidAllowList.into());
+            }
+            let allow_list = Self::get_allow_list(program_id, allow_list_info)?;
+            let recipient_token_account =
+                TokenAccount::unpack(&recipient_token_account_info.data.borrow())?;
+            if !allow_list.contains(
+                &allow_list_info.data.borrow(),
+                &recipient_token_account.owner,
+            )? {
+                return Err(EscrowError::RecipientNotAllowed.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds payout amount to the total paid to the recipient owner, creating its
+    /// account on the first payout, and checks the total against the escrow limit
+    fn add_recipient_payout<'a>(
+        program_id: &Pubkey,
+        escrow_info: &AccountInfo<'a>,
+        escrow: &Escrow,
+        recipient_token_account_info: &AccountInfo<'a>,
+        account_info_iter: &mut std::slice::Iter<AccountInfo<'a>>,
+        amount: u64,
+    ) -> ProgramResult {
+        let recipient_payout_info = next_account_info(account_info_iter)?;
+        let funder_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        // Limit applies to the owner, not to each of its token accounts
+        let owner = TokenAccount::unpack(&recipient_token_account_info.data.borrow())?.owner;
+        let (recipient_payout_key, bump_seed) =
+            Self::find_recipient_payout_address(program_id, escrow_info.key, &owner);
+        if recipient_payout_key != *recipient_payout_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let mut recipient_payout = if recipient_payout_info.owner == program_id {
+            RecipientPayout::unpack_from_slice(&recipient_payout_info.data.borrow())?
+        } else {
+            Self::create_program_account(
+                program_id,
+                recipient_payout_info.clone(),
+                funder_info.clone(),
+                system_program_info.clone(),
+                rent,
+                RecipientPayout::LEN,
+                &[
+                    RECIPIENT_PAYOUT_SEED,
+                    &escrow_info.key.to_bytes(),
+                    &owner.to_bytes(),
+                    &[bump_seed],
+                ],
+            )?;
+            RecipientPayout {
+                is_initialized: true,
+                bump_seed,
+                escrow: *escrow_info.key,
+                owner,
+                amount: 0,
+            }
+        };
+
+        recipient_payout.amount = recipient_payout
+            .amount
+            .checked_add(amount)
+            .ok_or(EscrowError::PayoutAboveMaximum)?;
+        if recipient_payout.amount > escrow.max_payout_per_recipient {
+            return Err(EscrowError::PayoutAboveMaximum.into());
+        }
+        RecipientPayout::pack(
+            recipient_payout,
+            &mut recipient_payout_info.data.borrow_mut(),
+        )
+    }
+
+    /// Splits payout amount into the recipient amount and oracle fees and sends them
+    #[allow(clippy::too_many_arguments)]
+    fn transfer_payout<'a>(
+        escrow_info: &AccountInfo<'a>,
+        escrow: &Escrow,
+        token_account_info: &AccountInfo<'a>,
+        authority_info: &AccountInfo<'a>,
+        recipient_token_account_info: &AccountInfo<'a>,
+        reputation_oracle_token_account_info: &AccountInfo<'a>,
+        recording_oracle_token_account_info: &AccountInfo<'a>,
+        token_program_info: &AccountInfo<'a>,
+        amount: u64,
+    ) -> ProgramResult {
         // Calculate fees
         let reputation_oracle_fee_amount = amount
             .checked_mul(escrow.reputation_oracle_stake as u64)
@@ -399,100 +1035,1519 @@ impl Processor {
             )?;
         }
 
-        escrow.sent_amount += amount;
-        escrow.sent_recipients += 1;
+        Ok(())
+    }
 
-        if escrow.sent_recipients == escrow.total_recipients
+    /// Moves escrow into `Paid` once the escrow token and all the vaults are paid out
+    fn update_payout_state(escrow: &mut Escrow) {
+        if escrow.sent_recipients != 0
+            && escrow.sent_recipients == escrow.total_recipients
             && escrow.sent_amount == escrow.total_amount
+            && escrow.unpaid_vaults == 0
         {
             escrow.state = EscrowState::Paid;
         } else {
             escrow.state = EscrowState::Partial;
         }
-
-        Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
-
-        Ok(())
     }
 
-    /// Processes `Cancel` instruction.
-    pub fn process_cancel(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
+    /// Processes `Payout` instruction.
+    pub fn process_payout(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let escrow_info = next_account_info(account_info_iter)?;
+        let trusted_handler_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+        let token_account_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let recipient_token_account_info = next_account_info(account_info_iter)?;
+        let reputation_oracle_token_account_info = next_account_info(account_info_iter)?;
+        let recording_oracle_token_account_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let mut escrow = Self::get_escrow_with_state_check(
+            escrow_info,
+            clock,
+            trusted_handler_info,
+            vec![EscrowState::Pending, EscrowState::Partial],
+        )?;
+
+        Self::send_payout(
+            program_id,
+            escrow_info,
+            &mut escrow,
+            token_account_info,
+            authority_info,
+            recipient_token_account_info,
+            reputation_oracle_token_account_info,
+            recording_oracle_token_account_info,
+            token_program_info,
+            account_info_iter,
+            amount,
+        )?;
+
+        Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Processes `PayoutWithPermit` instruction.
+    pub fn process_payout_with_permit(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        nonce: u64,
+        expires: UnixTimestamp,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let escrow_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+        let instructions_info = next_account_info(account_info_iter)?;
+        let token_account_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let recipient_token_account_info = next_account_info(account_info_iter)?;
+        let reputation_oracle_token_account_info = next_account_info(account_info_iter)?;
+        let recording_oracle_token_account_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let mut escrow = Self::get_escrow_with_state_check_unsigned(
+            escrow_info,
+            clock,
+            vec![EscrowState::Pending, EscrowState::Partial],
+        )?;
+
+        // Check permit expiration and nonce to prevent replays
+        if expires < clock.unix_timestamp {
+            return Err(EscrowError::PermitExpired.into());
+        }
+        if nonce != escrow.payout_nonce {
+            return Err(EscrowError::InvalidPermitNonce.into());
+        }
+
+        // Check that previous instruction verified reputation oracle signature
+        if !sysvar::instructions::check_id(instructions_info.key) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if escrow.permit_signer == [0; ETH_ADDRESS_LEN] {
+            return Err(EscrowError::InvalidPermitSignature.into());
+        }
+        let message = payout_permit_message(
+            escrow_info.key,
+            recipient_token_account_info.key,
+            amount,
+            nonce,
+            expires,
+        );
+        {
+            let instructions_data = instructions_info.data.borrow();
+            let current_index = sysvar::instructions::load_current_index(&instructions_data);
+            if current_index == 0 {
+                return Err(EscrowError::InvalidPermitSignature.into());
+            }
+            let secp256k1_index =
+                u8::try_from(current_index - 1).or(Err(EscrowError::InvalidPermitSignature))?;
+            let secp256k1_instruction = sysvar::instructions::load_instruction_at(
+                secp256k1_index as usize,
+                &instructions_data,
+            )
+            .or(Err(EscrowError::InvalidPermitSignature))?;
+            secp256k1::check_secp256k1_instruction(
+                &secp256k1_instruction,
+                secp256k1_index,
+                &escrow.permit_signer,
+                &message,
+            )?;
+        }
+
+        escrow.payout_nonce = escrow
+            .payout_nonce
+            .checked_add(1)
+            .ok_or(EscrowError::InvalidPermitNonce)?;
+
+        Self::send_payout(
+            program_id,
+            escrow_info,
+            &mut escrow,
+            token_account_info,
+            authority_info,
+            recipient_token_account_info,
+            reputation_oracle_token_account_info,
+            recording_oracle_token_account_info,
+            token_program_info,
+            account_info_iter,
+            amount,
+        )?;
+
+        Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Processes `Cancel` instruction.
+    pub fn process_cancel(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let escrow_info = next_account_info(account_info_iter)?;
+        let trusted_handler_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+        let token_account_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let canceler_token_account_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let mut escrow = Escrow::unpack_unchecked(&escrow_info.data.borrow())?;
+
+        // Check if escrow account exists and is initialized
+        if !escrow.is_initialized() {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        // Check escrow state
+        if escrow.state == EscrowState::Complete || escrow.state == EscrowState::Paid {
+            return Err(EscrowError::WrongState.into());
+        }
+
+        Self::check_trusted_handler(&escrow, trusted_handler_info)?;
+
+        // Oracles get a notice period after setup, unless the guardian paused the
+        // escrow and cancel is the emergency exit
+        if !escrow.paused && clock.unix_timestamp < escrow.cancel_notice_end {
+            return Err(EscrowError::CancelNoticePeriod.into());
+        }
+
+        // Check all accounts validity
+        if *token_account_info.key != escrow.token_account
+            || *canceler_token_account_info.key != escrow.canceler_token_account
+            || *authority_info.key
+                != Self::authority_id(program_id, escrow_info.key, escrow.bump_seed)?
+        {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        // Escrow token account or one of the vaults must hold tokens
+        let token_account = TokenAccount::unpack_unchecked(&token_account_info.data.borrow())?;
+        let mut has_balance = token_account.amount != 0;
+
+        // Pay the cancellation fee to the oracles in proportion to their fees
+        let mut refund_amount = token_account.amount;
+        if escrow.has_cancellation_fee() {
+            let reputation_oracle_token_account_info = next_account_info(account_info_iter)?;
+            let recording_oracle_token_account_info = next_account_info(account_info_iter)?;
+            if escrow.reputation_oracle_token_account
+                != COption::Some(*reputation_oracle_token_account_info.key)
+                || escrow.recording_oracle_token_account
+                    != COption::Some(*recording_oracle_token_account_info.key)
+            {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+
+            let fee_amount = escrow.cancellation_fee_amount(token_account.amount);
+            let total_stake =
+                escrow.reputation_oracle_stake as u128 + escrow.recording_oracle_stake as u128;
+            let reputation_oracle_fee_amount =
+                (fee_amount as u128 * escrow.reputation_oracle_stake as u128)
+                    .checked_div(total_stake)
+                    .ok_or(ProgramError::InvalidAccountData)? as u64;
+            let recording_oracle_fee_amount = fee_amount - reputation_oracle_fee_amount;
+
+            for (oracle_token_account_info, amount) in [
+                (
+                    reputation_oracle_token_account_info,
+                    reputation_oracle_fee_amount,
+                ),
+                (
+                    recording_oracle_token_account_info,
+                    recording_oracle_fee_amount,
+                ),
+            ]
+            .iter()
+            {
+                if *amount != 0 {
+                    Self::token_transfer(
+                        escrow_info.key,
+                        token_program_info.clone(),
+                        token_account_info.clone(),
+                        (*oracle_token_account_info).clone(),
+                        authority_info.clone(),
+                        escrow.bump_seed,
+                        *amount,
+                    )?;
+                }
+            }
+            refund_amount -= fee_amount;
+        }
+
+        // Call token contract to do transfer
+        if refund_amount != 0 {
+            Self::token_transfer(
+                escrow_info.key,
+                token_program_info.clone(),
+                token_account_info.clone(),
+                canceler_token_account_info.clone(),
+                authority_info.clone(),
+                escrow.bump_seed,
+                refund_amount,
+            )?;
+        }
+
+        // Refund every vault in the order they were added
+        for index in 0..escrow.vault_count {
+            let vault_info = next_account_info(account_info_iter)?;
+            let vault_token_account_info = next_account_info(account_info_iter)?;
+            let vault_canceler_token_account_info = next_account_info(account_info_iter)?;
+
+            let vault = Self::get_vault(program_id, escrow_info.key, vault_info)?;
+            if vault.index != index {
+                return Err(EscrowError::InvalidVault.into());
+            }
+            if *vault_token_account_info.key != vault.token_account
+                || *vault_canceler_token_account_info.key != vault.canceler_token_account
+            {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+
+            let vault_token_account =
+                TokenAccount::unpack_unchecked(&vault_token_account_info.data.borrow())?;
+            if vault_token_account.amount != 0 {
+                has_balance = true;
+                Self::token_transfer(
+                    escrow_info.key,
+                    token_program_info.clone(),
+                    vault_token_account_info.clone(),
+                    vault_canceler_token_account_info.clone(),
+                    authority_info.clone(),
+                    escrow.bump_seed,
+                    vault_token_account.amount,
+                )?;
+            }
+        }
+        if !has_balance {
+            return Err(EscrowError::NotEnoughBalance.into());
+        }
+
+        escrow.state = EscrowState::Cancelled;
+
+        Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Processes `Complete` instruction.
+    pub fn process_complete(accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let escrow_info = next_account_info(account_info_iter)?;
+        let trusted_handler_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+        let mut escrow = Self::get_escrow_with_state_check(
+            escrow_info,
+            clock,
+            trusted_handler_info,
+            vec![EscrowState::Paid],
+        )?;
+
+        escrow.state = EscrowState::Complete;
+
+        Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Processes `SetPermitSigner` instruction.
+    pub fn process_set_permit_signer(
+        accounts: &[AccountInfo],
+        permit_signer: &[u8; ETH_ADDRESS_LEN],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let escrow_info = next_account_info(account_info_iter)?;
+        let trusted_handler_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+        let mut escrow = Self::get_escrow_with_state_check(
+            escrow_info,
+            clock,
+            trusted_handler_info,
+            vec![
+                EscrowState::Launched,
+                EscrowState::Pending,
+                EscrowState::Partial,
+            ],
+        )?;
+
+        escrow.permit_signer = *permit_signer;
+
+        Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Processes `Pause` and `Unpause` instructions.
+    pub fn process_set_paused(accounts: &[AccountInfo], paused: bool) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let escrow_info = next_account_info(account_info_iter)?;
+        let guardian_info = next_account_info(account_info_iter)?;
+
+        let mut escrow = Escrow::unpack_unchecked(&escrow_info.data.borrow())?;
+
+        // Check if escrow account exists and is initialized
+        if !escrow.is_initialized() {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        // Only the guardian can pause or unpause
+        if !guardian_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if *guardian_info.key != escrow.guardian {
+            return Err(EscrowError::UnauthorizedSigner.into());
+        }
+
+        // Finished escrows cannot be paused
+        if escrow.state == EscrowState::Complete || escrow.state == EscrowState::Cancelled {
+            return Err(EscrowError::WrongState.into());
+        }
+        if escrow.paused == paused {
+            return Err(EscrowError::WrongState.into());
+        }
+
+        escrow.paused = paused;
+
+        Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Checks program config values validity
+    fn check_config_values(
+        max_oracle_stake: u8,
+        min_duration: u64,
+        max_duration: u64,
+    ) -> ProgramResult {
+        if max_oracle_stake > 100 || min_duration == 0 || min_duration > max_duration {
+            return Err(EscrowError::InvalidConfig.into());
+        }
+        Ok(())
+    }
+
+    /// Processes `InitializeConfig` instruction.
+    pub fn process_initialize_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        max_oracle_stake: u8,
+        min_duration: u64,
+        max_duration: u64,
+        allowed_mints: &[Pubkey],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let config_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let program_data_info = next_account_info(account_info_iter)?;
+        let upgrade_authority_info = next_account_info(account_info_iter)?;
+
+        if !admin_info.is_signer || !upgrade_authority_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if Self::get_upgrade_authority(program_id, program_data_info)?
+            != Some(*upgrade_authority_info.key)
+        {
+            return Err(EscrowError::UnauthorizedSigner.into());
+        }
+
+        let (config_key, bump_seed) = Self::find_config_address(program_id);
+        if config_key != *config_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if config_info.owner == program_id {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        Self::check_config_values(max_oracle_stake, min_duration, max_duration)?;
+
+        Self::create_program_account(
+            program_id,
+            config_info.clone(),
+            admin_info.clone(),
+            system_program_info.clone(),
+            rent,
+            EscrowConfig::LEN,
+            &[CONFIG_SEED, &[bump_seed]],
+        )?;
+
+        let mut config = EscrowConfig {
+            is_initialized: true,
+            bump_seed,
+            admin: *admin_info.key,
+            paused: false,
+            max_oracle_stake,
+            min_duration,
+            max_duration,
+            ..Default::default()
+        };
+        config.set_allowed_mints(allowed_mints)?;
+
+        EscrowConfig::pack(config, &mut config_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes `UpdateConfig` instruction.
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_update_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new_admin: &Pubkey,
+        paused: bool,
+        max_oracle_stake: u8,
+        min_duration: u64,
+        max_duration: u64,
+        allowed_mints: &[Pubkey],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let config_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+
+        let mut config = Self::get_config(program_id, config_info)?;
+
+        if !admin_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if *admin_info.key != config.admin {
+            return Err(EscrowError::UnauthorizedSigner.into());
+        }
+
+        Self::check_config_values(max_oracle_stake, min_duration, max_duration)?;
+
+        config.admin = *new_admin;
+        config.paused = paused;
+        config.max_oracle_stake = max_oracle_stake;
+        config.min_duration = min_duration;
+        config.max_duration = max_duration;
+        config.set_allowed_mints(allowed_mints)?;
+
+        EscrowConfig::pack(config, &mut config_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes `UpdateStakingConfig` instruction.
+    pub fn process_update_staking_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        stake_mint: COption<Pubkey>,
+        min_oracle_stake: u64,
+        stake_escrow_threshold: u64,
+        arbiter: COption<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let config_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+
+        let mut config = Self::get_config(program_id, config_info)?;
+
+        if !admin_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if *admin_info.key != config.admin {
+            return Err(EscrowError::UnauthorizedSigner.into());
+        }
+
+        config.stake_mint = stake_mint;
+        config.min_oracle_stake = min_oracle_stake;
+        config.stake_escrow_threshold = stake_escrow_threshold;
+        config.arbiter = arbiter;
+
+        EscrowConfig::pack(config, &mut config_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes `InitializeOracleStake` instruction.
+    pub fn process_initialize_oracle_stake(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let oracle_stake_info = next_account_info(account_info_iter)?;
+        let oracle_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        if !oracle_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let (oracle_stake_key, bump_seed) =
+            Self::find_oracle_stake_address(program_id, oracle_info.key);
+        if oracle_stake_key != *oracle_stake_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if oracle_stake_info.owner == program_id {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        // Vault must hold stake mint tokens and be owned by the oracle stake account
+        let config = Self::get_config(program_id, config_info)?;
+        if *vault_info.owner != spl_token::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let vault = TokenAccount::unpack(&vault_info.data.borrow())?;
+        if COption::Some(vault.mint) != config.stake_mint {
+            return Err(EscrowError::WrongTokenMint.into());
+        }
+        if vault.owner != oracle_stake_key
+            || vault.delegate.is_some()
+            || vault.close_authority.is_some()
+        {
+            return Err(EscrowError::TokenAccountAuthority.into());
+        }
+
+        Self::create_program_account(
+            program_id,
+            oracle_stake_info.clone(),
+            oracle_info.clone(),
+            system_program_info.clone(),
+            rent,
+            OracleStake::LEN,
+            &[ORACLE_STAKE_SEED, &oracle_info.key.to_bytes(), &[bump_seed]],
+        )?;
+
+        let oracle_stake = OracleStake {
+            is_initialized: true,
+            bump_seed,
+            oracle: *oracle_info.key,
+            vault: *vault_info.key,
+            amount: 0,
+            locked_until: 0,
+        };
+
+        OracleStake::pack(oracle_stake, &mut oracle_stake_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes `DepositOracleStake` instruction.
+    pub fn process_deposit_oracle_stake(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let oracle_stake_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let source_owner_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let mut oracle_stake = Self::get_oracle_stake(program_id, oracle_stake_info)?;
+        if *vault_info.key != oracle_stake.vault {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        if *token_program_info.key != spl_token::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        // Stake is credited with what the vault actually received
+        let vault_amount = TokenAccount::unpack(&vault_info.data.borrow())?.amount;
+        invoke(
+            &spl_token::instruction::transfer(
+                token_program_info.key,
+                source_info.key,
+                vault_info.key,
+                source_owner_info.key,
+                &[],
+                amount,
+            )?,
+            &[
+                source_info.clone(),
+                vault_info.clone(),
+                source_owner_info.clone(),
+                token_program_info.clone(),
+            ],
+        )?;
+
+        let deposited = TokenAccount::unpack(&vault_info.data.borrow())?
+            .amount
+            .checked_sub(vault_amount)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        oracle_stake.amount = oracle_stake
+            .amount
+            .checked_add(deposited)
+            .ok_or(ProgramError::InvalidArgument)?;
+
+        OracleStake::pack(oracle_stake, &mut oracle_stake_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes `WithdrawOracleStake` instruction.
+    pub fn process_withdraw_oracle_stake(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let oracle_stake_info = next_account_info(account_info_iter)?;
+        let oracle_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let mut oracle_stake = Self::get_oracle_stake(program_id, oracle_stake_info)?;
+
+        if !oracle_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if *oracle_info.key != oracle_stake.oracle {
+            return Err(EscrowError::UnauthorizedSigner.into());
+        }
+        if *vault_info.key != oracle_stake.vault {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        if clock.unix_timestamp < oracle_stake.locked_until {
+            return Err(EscrowError::OracleStakeLocked.into());
+        }
+        if amount > oracle_stake.amount {
+            return Err(EscrowError::NotEnoughBalance.into());
+        }
+
+        Self::oracle_stake_transfer(
+            &oracle_stake,
+            token_program_info.clone(),
+            vault_info.clone(),
+            destination_info.clone(),
+            oracle_stake_info.clone(),
+            amount,
+        )?;
+
+        oracle_stake.amount -= amount;
+
+        OracleStake::pack(oracle_stake, &mut oracle_stake_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes `SlashOracle` instruction.
+    pub fn process_slash_oracle(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let config_info = next_account_info(account_info_iter)?;
+        let slasher_info = next_account_info(account_info_iter)?;
+        let escrow_info = next_account_info(account_info_iter)?;
+        let oracle_stake_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let canceler_token_account_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        // Only the dispute arbiter or the admin can slash
+        let config = Self::get_config(program_id, config_info)?;
+        if !slasher_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if !config.is_slasher(slasher_info.key) {
+            return Err(EscrowError::UnauthorizedSigner.into());
+        }
+
+        if escrow_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let escrow = Escrow::unpack(&escrow_info.data.borrow())?;
+
+        // Oracle must be working on this escrow
+        let mut oracle_stake = Self::get_oracle_stake(program_id, oracle_stake_info)?;
+        if escrow.reputation_oracle != COption::Some(oracle_stake.oracle)
+            && escrow.recording_oracle != COption::Some(oracle_stake.oracle)
+        {
+            return Err(EscrowError::OracleNotInitialized.into());
+        }
+        if *vault_info.key != oracle_stake.vault {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        // Slashed tokens go to the escrow canceler
+        let canceler_token_account =
+            TokenAccount::unpack(&canceler_token_account_info.data.borrow())?;
+        if canceler_token_account.owner != escrow.canceler {
+            return Err(EscrowError::TokenAccountAuthority.into());
+        }
+
+        if amount > oracle_stake.amount {
+            return Err(EscrowError::NotEnoughBalance.into());
+        }
+
+        Self::oracle_stake_transfer(
+            &oracle_stake,
+            token_program_info.clone(),
+            vault_info.clone(),
+            canceler_token_account_info.clone(),
+            oracle_stake_info.clone(),
+            amount,
+        )?;
+
+        oracle_stake.amount -= amount;
+
+        OracleStake::pack(oracle_stake, &mut oracle_stake_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes `UpdateReputation` instruction.
+    pub fn process_update_reputation(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        updates: &[ReputationUpdate],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let escrow_info = next_account_info(account_info_iter)?;
+        let reputation_oracle_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        // Reputation is only attributed to jobs of the real escrows
+        if escrow_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let escrow = Self::get_escrow_with_state_check_unsigned(
+            escrow_info,
+            clock,
+            vec![EscrowState::Partial, EscrowState::Paid],
+        )?;
+
+        // Only the escrow reputation oracle can rate workers
+        if !reputation_oracle_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if escrow.reputation_oracle != COption::Some(*reputation_oracle_info.key) {
+            return Err(EscrowError::UnauthorizedSigner.into());
+        }
+
+        for update in updates {
+            let reputation_info = next_account_info(account_info_iter)?;
+            let reputation_job_info = next_account_info(account_info_iter)?;
+
+            let (reputation_key, bump_seed) =
+                Self::find_reputation_address(program_id, &update.worker);
+            if reputation_key != *reputation_info.key {
+                return Err(ProgramError::InvalidSeeds);
+            }
+
+            // Create reputation account on the first update
+            let mut reputation = if reputation_info.owner == program_id {
+                Reputation::unpack(&reputation_info.data.borrow())?
+            } else {
+                Self::create_program_account(
+                    program_id,
+                    reputation_info.clone(),
+                    reputation_oracle_info.clone(),
+                    system_program_info.clone(),
+                    rent,
+                    Reputation::LEN,
+                    &[REPUTATION_SEED, &update.worker.to_bytes(), &[bump_seed]],
+                )?;
+                Reputation {
+                    is_initialized: true,
+                    bump_seed,
+                    worker: update.worker,
+                    ..Default::default()
+                }
+            };
+
+            reputation.score = reputation
+                .score
+                .checked_add(update.delta)
+                .ok_or(ProgramError::InvalidArgument)?;
+            reputation.last_escrow = *escrow_info.key;
+
+            // Empty job account marks the worker rated for the escrow, so the job
+            // is only counted once however the updates of escrows interleave
+            let (reputation_job_key, job_bump_seed) =
+                Self::find_reputation_job_address(program_id, &update.worker, escrow_info.key);
+            if reputation_job_key != *reputation_job_info.key {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            if reputation_job_info.owner != program_id {
+                Self::create_program_account(
+                    program_id,
+                    reputation_job_info.clone(),
+                    reputation_oracle_info.clone(),
+                    system_program_info.clone(),
+                    rent,
+                    0,
+                    &[
+                        REPUTATION_JOB_SEED,
+                        &update.worker.to_bytes(),
+                        &escrow_info.key.to_bytes(),
+                        &[job_bump_seed],
+                    ],
+                )?;
+                reputation.jobs_completed = reputation
+                    .jobs_completed
+                    .checked_add(1)
+                    .ok_or(ProgramError::InvalidArgument)?;
+            }
+
+            Reputation::pack(reputation, &mut reputation_info.data.borrow_mut())?;
+        }
+
+        Ok(())
+    }
+
+    /// Processes `StoreIntermediateResults` instruction.
+    pub fn process_store_intermediate_results(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        url: &DataUrl,
+        hash: &DataHash,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let escrow_info = next_account_info(account_info_iter)?;
+        let results_log_info = next_account_info(account_info_iter)?;
+        let results_log_page_info = next_account_info(account_info_iter)?;
+        let recording_oracle_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        if escrow_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let escrow = Self::get_escrow_with_state_check_unsigned(
+            escrow_info,
+            clock,
+            vec![EscrowState::Pending, EscrowState::Partial],
+        )?;
+
+        // Only the escrow recording oracle can store intermediate results
+        if !recording_oracle_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if escrow.recording_oracle != COption::Some(*recording_oracle_info.key) {
+            return Err(EscrowError::UnauthorizedSigner.into());
+        }
+
+        let (results_log_key, bump_seed) =
+            Self::find_results_log_address(program_id, escrow_info.key);
+        if results_log_key != *results_log_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        // Create results log on the first call
+        let mut results_log = if results_log_info.owner == program_id {
+            ResultsLog::unpack_from_slice(&results_log_info.data.borrow())?
+        } else {
+            Self::create_program_account(
+                program_id,
+                results_log_info.clone(),
+                recording_oracle_info.clone(),
+                system_program_info.clone(),
+                rent,
+                ResultsLog::LEN,
+                &[RESULTS_LOG_SEED, &escrow_info.key.to_bytes(), &[bump_seed]],
+            )?;
+            ResultsLog {
+                is_initialized: true,
+                bump_seed,
+                escrow: *escrow_info.key,
+                ..Default::default()
+            }
+        };
+
+        // Entries are appended to the page holding the next entry, the first
+        // entry of a page creates it, so filled pages are never overwritten
+        let page = ResultsLog::page(results_log.count);
+        let (results_log_page_key, page_bump_seed) =
+            Self::find_results_log_page_address(program_id, escrow_info.key, page);
+        if results_log_page_key != *results_log_page_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if results_log_page_info.owner != program_id {
+            Self::create_program_account(
+                program_id,
+                results_log_page_info.clone(),
+                recording_oracle_info.clone(),
+                system_program_info.clone(),
+                rent,
+                ResultsLogPage::ACCOUNT_LEN,
+                &[
+                    RESULTS_LOG_PAGE_SEED,
+                    &escrow_info.key.to_bytes(),
+                    &page.to_le_bytes(),
+                    &[page_bump_seed],
+                ],
+            )?;
+            let results_log_page = ResultsLogPage {
+                is_initialized: true,
+                bump_seed: page_bump_seed,
+                escrow: *escrow_info.key,
+                page,
+            };
+            ResultsLogPage::pack_into_slice(
+                &results_log_page,
+                &mut results_log_page_info.data.borrow_mut(),
+            );
+        }
+
+        let entry = ResultsLogEntry {
+            url: *url,
+            hash: *hash,
+            timestamp: clock.unix_timestamp,
+            signer: *recording_oracle_info.key,
+        };
+        let offset = ResultsLogPage::entry_offset(results_log.count as usize);
+        let mut page_data = results_log_page_info.data.borrow_mut();
+        let entry_data = page_data
+            .get_mut(offset..offset + ResultsLogEntry::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        ResultsLogEntry::pack(entry, entry_data)?;
+
+        results_log.head = entry.next_head(&results_log.head);
+        results_log.count += 1;
+        ResultsLog::pack(results_log, &mut results_log_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Processes `InitializeAllowList` instruction.
+    pub fn process_initialize_allow_list(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let allow_list_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+        if !authority_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if allow_list_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let mut data = allow_list_info.data.borrow_mut();
+        let allow_list = AllowList::unpack_from_slice(&data)?;
+        if allow_list.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        if !rent.is_exempt(allow_list_info.lamports(), data.len()) {
+            return Err(ProgramError::AccountNotRentExempt);
+        }
+        if AllowList::capacity(&data) == 0 {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+
+        let allow_list = AllowList {
+            is_initialized: true,
+            authority: *authority_info.key,
+            count: 0,
+        };
+
+        AllowList::pack_into_slice(&allow_list, &mut data);
+        Ok(())
+    }
+
+    /// Processes `UpdateAllowList` instruction.
+    pub fn process_update_allow_list(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        add: &[Pubkey],
+        remove: &[Pubkey],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let allow_list_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+
+        let mut allow_list = Self::get_allow_list(program_id, allow_list_info)?;
+
+        // Only the compliance authority can change the list
+        if !authority_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if *authority_info.key != allow_list.authority {
+            return Err(EscrowError::UnauthorizedSigner.into());
+        }
+
+        let mut data = allow_list_info.data.borrow_mut();
+        for owner in remove {
+            allow_list.remove(&mut data, owner)?;
+        }
+        for owner in add {
+            allow_list.insert(&mut data, owner)?;
+        }
+
+        AllowList::pack_into_slice(&allow_list, &mut data);
+        Ok(())
+    }
+
+    /// Processes `AddVault` instruction.
+    pub fn process_add_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let escrow_info = next_account_info(account_info_iter)?;
+        let trusted_handler_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let token_account_info = next_account_info(account_info_iter)?;
+        let canceler_token_account_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        if escrow_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut escrow = Self::get_escrow_with_state_check(
+            escrow_info,
+            clock,
+            trusted_handler_info,
+            vec![EscrowState::Launched],
+        )?;
+
+        if escrow.vault_count >= MAX_ESCROW_VAULTS {
+            return Err(EscrowError::TooManyVaults.into());
+        }
+
+        // Vault mint must be allowed by the program config as well
+        let config = Self::get_config(program_id, config_info)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        if *token_mint_info.key == escrow.token_mint {
+            return Err(EscrowError::WrongTokenMint.into());
+        }
+        if !config.is_mint_allowed(token_mint_info.key) {
+            return Err(EscrowError::MintNotAllowed.into());
+        }
+
+        // Token account should be owned by the contract authority
+        let authority_key = Self::authority_id(program_id, escrow_info.key, escrow.bump_seed)?;
+        let token_account = TokenAccount::unpack_unchecked(&token_account_info.data.borrow())?;
+        if token_account.owner != authority_key {
+            return Err(EscrowError::TokenAccountAuthority.into());
+        }
+
+        // Check token account mints
+        if token_account.mint != *token_mint_info.key {
+            return Err(EscrowError::WrongTokenMint.into());
+        }
+        let canceler_token_account =
+            TokenAccount::unpack_unchecked(&canceler_token_account_info.data.borrow())?;
+        if canceler_token_account.mint != *token_mint_info.key {
+            return Err(EscrowError::WrongTokenMint.into());
+        }
+
+        let (vault_key, bump_seed) =
+            Self::find_vault_address(program_id, escrow_info.key, token_mint_info.key);
+        if vault_key != *vault_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if vault_info.owner == program_id {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        Self::create_program_account(
+            program_id,
+            vault_info.clone(),
+            trusted_handler_info.clone(),
+            system_program_info.clone(),
+            rent,
+            EscrowVault::LEN,
+            &[
+                VAULT_SEED,
+                &escrow_info.key.to_bytes(),
+                &token_mint_info.key.to_bytes(),
+                &[bump_seed],
+            ],
+        )?;
+
+        let vault = EscrowVault {
+            is_initialized: true,
+            bump_seed,
+            escrow: *escrow_info.key,
+            index: escrow.vault_count,
+            token_mint: *token_mint_info.key,
+            token_account: *token_account_info.key,
+            canceler_token_account: *canceler_token_account_info.key,
+            ..Default::default()
+        };
+        EscrowVault::pack(vault, &mut vault_info.data.borrow_mut())?;
+
+        // Escrow cannot be paid until the vault total is stored and paid out
+        escrow.vault_count += 1;
+        escrow.unpaid_vaults += 1;
+
+        Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes `StoreVaultResults` instruction.
+    pub fn process_store_vault_results(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        total_amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
         let escrow_info = next_account_info(account_info_iter)?;
         let trusted_handler_info = next_account_info(account_info_iter)?;
-        let token_account_info = next_account_info(account_info_iter)?;
-        let authority_info = next_account_info(account_info_iter)?;
-        let canceler_token_account_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+        let vault_info = next_account_info(account_info_iter)?;
 
-        let mut escrow = Escrow::unpack_unchecked(&escrow_info.data.borrow())?;
+        let mut escrow = Self::get_escrow_with_state_check(
+            escrow_info,
+            clock,
+            trusted_handler_info,
+            vec![EscrowState::Pending, EscrowState::Partial],
+        )?;
+        let mut vault = Self::get_vault(program_id, escrow_info.key, vault_info)?;
 
-        // Check if escrow account exists and is initialized
-        if !escrow.is_initialized() {
-            return Err(ProgramError::UninitializedAccount);
+        // Final total cannot go below what was already paid out
+        if total_amount < vault.sent_amount {
+            return Err(EscrowError::TooManyPayouts.into());
         }
 
-        // Check escrow state
-        if escrow.state == EscrowState::Complete || escrow.state == EscrowState::Paid {
-            return Err(EscrowError::WrongState.into());
+        let was_paid = vault.is_paid();
+        vault.total_amount = total_amount;
+        vault.results_stored = true;
+        if was_paid && !vault.is_paid() {
+            escrow.unpaid_vaults += 1;
+        } else if !was_paid && vault.is_paid() {
+            escrow.unpaid_vaults = escrow
+                .unpaid_vaults
+                .checked_sub(1)
+                .ok_or(ProgramError::InvalidAccountData)?;
+        }
+        if escrow.state == EscrowState::Partial {
+            Self::update_payout_state(&mut escrow);
         }
 
-        Self::check_trusted_handler(&escrow, trusted_handler_info)?;
+        EscrowVault::pack(vault, &mut vault_info.data.borrow_mut())?;
+        Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes `VaultPayout` instruction.
+    pub fn process_vault_payout(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let escrow_info = next_account_info(account_info_iter)?;
+        let trusted_handler_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let token_account_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let recipient_token_account_info = next_account_info(account_info_iter)?;
+        let reputation_oracle_token_account_info = next_account_info(account_info_iter)?;
+        let recording_oracle_token_account_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let allow_list_info = account_info_iter.next();
+
+        let mut escrow = Self::get_escrow_with_state_check(
+            escrow_info,
+            clock,
+            trusted_handler_info,
+            vec![EscrowState::Pending, EscrowState::Partial],
+        )?;
+        let mut vault = Self::get_vault(program_id, escrow_info.key, vault_info)?;
 
         // Check all accounts validity
-        if *token_account_info.key != escrow.token_account
-            || *canceler_token_account_info.key != escrow.canceler_token_account
+        if *token_account_info.key != vault.token_account
             || *authority_info.key
                 != Self::authority_id(program_id, escrow_info.key, escrow.bump_seed)?
         {
             return Err(ProgramError::InvalidInstructionData);
         }
 
+        // Oracle fees go to the oracle token accounts of the vault mint
+        let reputation_oracle = escrow
+            .reputation_oracle
+            .ok_or(EscrowError::OracleNotInitialized)?;
+        let recording_oracle = escrow
+            .recording_oracle
+            .ok_or(EscrowError::OracleNotInitialized)?;
+        let reputation_oracle_token_account =
+            TokenAccount::unpack(&reputation_oracle_token_account_info.data.borrow())?;
+        let recording_oracle_token_account =
+            TokenAccount::unpack(&recording_oracle_token_account_info.data.borrow())?;
+        if reputation_oracle_token_account.owner != reputation_oracle
+            || recording_oracle_token_account.owner != recording_oracle
+        {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        if reputation_oracle_token_account.mint != vault.token_mint
+            || recording_oracle_token_account.mint != vault.token_mint
+        {
+            return Err(EscrowError::WrongTokenMint.into());
+        }
+
+        // Escrow payout limits are in escrow token units, they do not apply to the vault
+        // tokens. Vault payouts are not counted in the escrow recipients either.
+        if amount == 0 {
+            return Err(EscrowError::PayoutBelowMinimum.into());
+        }
+
+        Self::check_recipient_allowed(
+            program_id,
+            &escrow,
+            recipient_token_account_info,
+            allow_list_info,
+        )?;
+
         // Check account balance
         let token_account = TokenAccount::unpack_unchecked(&token_account_info.data.borrow())?;
-        if token_account.amount == 0 {
+        if token_account.amount < amount {
             return Err(EscrowError::NotEnoughBalance.into());
         }
 
-        // Call token contract to do transfer
-        Self::token_transfer(
-            escrow_info.key,
-            token_program_info.clone(),
-            token_account_info.clone(),
-            canceler_token_account_info.clone(),
-            authority_info.clone(),
-            escrow.bump_seed,
-            token_account.amount,
+        // Check if not too many payouts
+        let sent_amount = vault
+            .sent_amount
+            .checked_add(amount)
+            .ok_or(EscrowError::TooManyPayouts)?;
+        if !vault.results_stored || sent_amount > vault.total_amount {
+            return Err(EscrowError::TooManyPayouts.into());
+        }
+
+        Self::transfer_payout(
+            escrow_info,
+            &escrow,
+            token_account_info,
+            authority_info,
+            recipient_token_account_info,
+            reputation_oracle_token_account_info,
+            recording_oracle_token_account_info,
+            token_program_info,
+            amount,
         )?;
 
-        escrow.state = EscrowState::Cancelled;
+        vault.sent_amount = sent_amount;
+        if vault.is_paid() {
+            escrow.unpaid_vaults = escrow
+                .unpaid_vaults
+                .checked_sub(1)
+                .ok_or(ProgramError::InvalidAccountData)?;
+        }
+        Self::update_payout_state(&mut escrow);
 
+        EscrowVault::pack(vault, &mut vault_info.data.borrow_mut())?;
         Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
 
         Ok(())
     }
 
-    /// Processes `Complete` instruction.
-    pub fn process_complete(accounts: &[AccountInfo]) -> ProgramResult {
+    /// Processes `InitializeTemplate` instruction.
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_initialize_template(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        duration: u64,
+        reputation_oracle_stake: u8,
+        recording_oracle_stake: u8,
+        max_payout_per_recipient: u64,
+        min_payout: u64,
+        cancellation_fee: u64,
+        cancellation_fee_percent: u8,
+        cancel_notice: u64,
+    ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
-        let escrow_info = next_account_info(account_info_iter)?;
-        let trusted_handler_info = next_account_info(account_info_iter)?;
-        let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+        let template_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let canceler_info = next_account_info(account_info_iter)?;
+        let canceler_token_account_info = next_account_info(account_info_iter)?;
+        let guardian_info = next_account_info(account_info_iter)?;
+        let reputation_oracle_info = next_account_info(account_info_iter)?;
+        let reputation_oracle_token_account_info = next_account_info(account_info_iter)?;
+        let recording_oracle_info = next_account_info(account_info_iter)?;
+        let recording_oracle_token_account_info = next_account_info(account_info_iter)?;
 
-        let mut escrow = Self::get_escrow_with_state_check(
-            escrow_info,
-            clock,
-            trusted_handler_info,
-            vec![EscrowState::Paid],
-        )?;
+        if !authority_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if template_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
 
-        escrow.state = EscrowState::Complete;
+        let template = EscrowTemplate::unpack_unchecked(&template_info.data.borrow())?;
+        if template.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        if !rent.is_exempt(template_info.lamports(), template_info.data_len()) {
+            return Err(ProgramError::AccountNotRentExempt);
+        }
 
-        Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
+        // Check values the same way as Initialize and Setup do
+        if duration == 0 {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+        let total_stake: u8 = reputation_oracle_stake
+            .checked_add(recording_oracle_stake)
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        if total_stake == 0 || total_stake > 100 {
+            return Err(EscrowError::StakeOutOfBounds.into());
+        }
+        if max_payout_per_recipient != 0 && min_payout > max_payout_per_recipient {
+            return Err(EscrowError::InvalidPayoutLimits.into());
+        }
+        if cancellation_fee_percent > 100
+            || (cancellation_fee != 0 && cancellation_fee_percent != 0)
+        {
+            return Err(EscrowError::InvalidCancellationFee.into());
+        }
+        if cancel_notice > duration {
+            return Err(EscrowError::CancelNoticeTooLong.into());
+        }
 
+        // Check token account mints
+        for token_account_info in &[
+            canceler_token_account_info,
+            reputation_oracle_token_account_info,
+            recording_oracle_token_account_info,
+        ] {
+            let token_account = TokenAccount::unpack_unchecked(&token_account_info.data.borrow())?;
+            if token_account.mint != *token_mint_info.key {
+                return Err(EscrowError::WrongTokenMint.into());
+            }
+        }
+
+        let allow_list = match account_info_iter.next() {
+            Some(allow_list_info) => {
+                Self::get_allow_list(program_id, allow_list_info)?;
+                COption::Some(*allow_list_info.key)
+            }
+            None => COption::None,
+        };
+
+        let template = EscrowTemplate {
+            is_initialized: true,
+            authority: *authority_info.key,
+            token_mint: *token_mint_info.key,
+            canceler: *canceler_info.key,
+            canceler_token_account: *canceler_token_account_info.key,
+            guardian: *guardian_info.key,
+            duration,
+            reputation_oracle: *reputation_oracle_info.key,
+            reputation_oracle_token_account: *reputation_oracle_token_account_info.key,
+            reputation_oracle_stake,
+            recording_oracle: *recording_oracle_info.key,
+            recording_oracle_token_account: *recording_oracle_token_account_info.key,
+            recording_oracle_stake,
+            max_payout_per_recipient,
+            min_payout,
+            cancellation_fee,
+            cancellation_fee_percent,
+            cancel_notice,
+            allow_list,
+        };
+
+        EscrowTemplate::pack(template, &mut template_info.data.borrow_mut())?;
         Ok(())
     }
 
+    /// Processes `InitializeFromTemplate` instruction.
+    pub fn process_initialize_from_template(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        manifest_url: &DataUrl,
+        manifest_hash: &DataHash,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let escrow_info = next_account_info(account_info_iter)?;
+        let launcher_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let template_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let token_account_info = next_account_info(account_info_iter)?;
+        let canceler_info = next_account_info(account_info_iter)?;
+        let canceler_token_account_info = next_account_info(account_info_iter)?;
+        let guardian_info = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let reputation_oracle_info = next_account_info(account_info_iter)?;
+        let reputation_oracle_token_account_info = next_account_info(account_info_iter)?;
+        let recording_oracle_info = next_account_info(account_info_iter)?;
+        let recording_oracle_token_account_info = next_account_info(account_info_iter)?;
+        let reputation_oracle_stake_info = next_account_info(account_info_iter)?;
+        let recording_oracle_stake_info = next_account_info(account_info_iter)?;
+
+        let template = Self::get_template(program_id, template_info)?;
+        if !launcher_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if *launcher_info.key != template.authority {
+            return Err(EscrowError::UnauthorizedSigner.into());
+        }
+
+        // Accounts must be the ones stored in the template
+        if *token_mint_info.key != template.token_mint
+            || *canceler_info.key != template.canceler
+            || *canceler_token_account_info.key != template.canceler_token_account
+            || *guardian_info.key != template.guardian
+            || *reputation_oracle_info.key != template.reputation_oracle
+            || *reputation_oracle_token_account_info.key != template.reputation_oracle_token_account
+            || *recording_oracle_info.key != template.recording_oracle
+            || *recording_oracle_token_account_info.key != template.recording_oracle_token_account
+        {
+            return Err(EscrowError::InvalidTemplate.into());
+        }
+
+        Self::process_initialize(
+            program_id,
+            &[
+                escrow_info.clone(),
+                clock_info.clone(),
+                token_mint_info.clone(),
+                token_account_info.clone(),
+                launcher_info.clone(),
+                canceler_info.clone(),
+                canceler_token_account_info.clone(),
+                guardian_info.clone(),
+                config_info.clone(),
+            ],
+            template.duration,
+        )?;
+
+        let mut setup_accounts = vec![
+            escrow_info.clone(),
+            launcher_info.clone(),
+            clock_info.clone(),
+            reputation_oracle_info.clone(),
+            reputation_oracle_token_account_info.clone(),
+            recording_oracle_info.clone(),
+            recording_oracle_token_account_info.clone(),
+            config_info.clone(),
+            token_account_info.clone(),
+            reputation_oracle_stake_info.clone(),
+            recording_oracle_stake_info.clone(),
+        ];
+        if let COption::Some(allow_list) = template.allow_list {
+            let allow_list_info = next_account_info(account_info_iter)?;
+            if *allow_list_info.key != allow_list {
+                return Err(EscrowError::InvalidAllowList.into());
+            }
+            setup_accounts.push(allow_list_info.clone());
+        }
+
+        Self::process_setup(
+            program_id,
+            &setup_accounts,
+            template.reputation_oracle_stake,
+            template.recording_oracle_stake,
+            manifest_url,
+            manifest_hash,
+            template.max_payout_per_recipient,
+            template.min_payout,
+            template.cancellation_fee,
+            template.cancellation_fee_percent,
+            template.cancel_notice,
+        )
+    }
+
     /// Processes all Escrow instructions
     pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
         let instruction = EscrowInstruction::unpack(input)?;
@@ -507,14 +2562,25 @@ impl Processor {
                 recording_oracle_stake,
                 manifest_url,
                 manifest_hash,
+                max_payout_per_recipient,
+                min_payout,
+                cancellation_fee,
+                cancellation_fee_percent,
+                cancel_notice,
             } => {
                 info!("Instruction: Setup");
                 Self::process_setup(
+                    program_id,
                     accounts,
                     reputation_oracle_stake,
                     recording_oracle_stake,
                     &manifest_url,
                     &manifest_hash,
+                    max_payout_per_recipient,
+                    min_payout,
+                    cancellation_fee,
+                    cancellation_fee_percent,
+                    cancel_notice,
                 )
             }
             EscrowInstruction::StoreResults {
@@ -525,6 +2591,7 @@ impl Processor {
             } => {
                 info!("Instruction: Store Results");
                 Self::process_store_results(
+                    program_id,
                     accounts,
                     total_amount,
                     total_recipients,
@@ -544,6 +2611,158 @@ impl Processor {
                 info!("Instruction: Payout");
                 Self::process_complete(accounts)
             }
+            EscrowInstruction::PayoutWithPermit {
+                amount,
+                nonce,
+                expires,
+            } => {
+                info!("Instruction: Payout With Permit");
+                Self::process_payout_with_permit(program_id, accounts, amount, nonce, expires)
+            }
+            EscrowInstruction::Pause => {
+                info!("Instruction: Pause");
+                Self::process_set_paused(accounts, true)
+            }
+            EscrowInstruction::Unpause => {
+                info!("Instruction: Unpause");
+                Self::process_set_paused(accounts, false)
+            }
+            EscrowInstruction::InitializeConfig {
+                max_oracle_stake,
+                min_duration,
+                max_duration,
+                allowed_mints,
+            } => {
+                info!("Instruction: Initialize Config");
+                Self::process_initialize_config(
+                    program_id,
+                    accounts,
+                    max_oracle_stake,
+                    min_duration,
+                    max_duration,
+                    &allowed_mints,
+                )
+            }
+            EscrowInstruction::UpdateConfig {
+                admin,
+                paused,
+                max_oracle_stake,
+                min_duration,
+                max_duration,
+                allowed_mints,
+            } => {
+                info!("Instruction: Update Config");
+                Self::process_update_config(
+                    program_id,
+                    accounts,
+                    &admin,
+                    paused,
+                    max_oracle_stake,
+                    min_duration,
+                    max_duration,
+                    &allowed_mints,
+                )
+            }
+            EscrowInstruction::UpdateStakingConfig {
+                stake_mint,
+                min_oracle_stake,
+                stake_escrow_threshold,
+                arbiter,
+            } => {
+                info!("Instruction: Update Staking Config");
+                Self::process_update_staking_config(
+                    program_id,
+                    accounts,
+                    stake_mint,
+                    min_oracle_stake,
+                    stake_escrow_threshold,
+                    arbiter,
+                )
+            }
+            EscrowInstruction::InitializeOracleStake => {
+                info!("Instruction: Initialize Oracle Stake");
+                Self::process_initialize_oracle_stake(program_id, accounts)
+            }
+            EscrowInstruction::DepositOracleStake { amount } => {
+                info!("Instruction: Deposit Oracle Stake");
+                Self::process_deposit_oracle_stake(program_id, accounts, amount)
+            }
+            EscrowInstruction::WithdrawOracleStake { amount } => {
+                info!("Instruction: Withdraw Oracle Stake");
+                Self::process_withdraw_oracle_stake(program_id, accounts, amount)
+            }
+            EscrowInstruction::SlashOracle { amount } => {
+                info!("Instruction: Slash Oracle");
+                Self::process_slash_oracle(program_id, accounts, amount)
+            }
+            EscrowInstruction::UpdateReputation { updates } => {
+                info!("Instruction: Update Reputation");
+                Self::process_update_reputation(program_id, accounts, &updates)
+            }
+            EscrowInstruction::StoreIntermediateResults { url, hash } => {
+                info!("Instruction: Store Intermediate Results");
+                Self::process_store_intermediate_results(program_id, accounts, &url, &hash)
+            }
+            EscrowInstruction::InitializeAllowList => {
+                info!("Instruction: Initialize Allow List");
+                Self::process_initialize_allow_list(program_id, accounts)
+            }
+            EscrowInstruction::UpdateAllowList { add, remove } => {
+                info!("Instruction: Update Allow List");
+                Self::process_update_allow_list(program_id, accounts, &add, &remove)
+            }
+            EscrowInstruction::AddVault => {
+                info!("Instruction: Add Vault");
+                Self::process_add_vault(program_id, accounts)
+            }
+            EscrowInstruction::StoreVaultResults { total_amount } => {
+                info!("Instruction: Store Vault Results");
+                Self::process_store_vault_results(program_id, accounts, total_amount)
+            }
+            EscrowInstruction::VaultPayout { amount } => {
+                info!("Instruction: Vault Payout");
+                Self::process_vault_payout(program_id, accounts, amount)
+            }
+            EscrowInstruction::InitializeTemplate {
+                duration,
+                reputation_oracle_stake,
+                recording_oracle_stake,
+                max_payout_per_recipient,
+                min_payout,
+                cancellation_fee,
+                cancellation_fee_percent,
+                cancel_notice,
+            } => {
+                info!("Instruction: Initialize Template");
+                Self::process_initialize_template(
+                    program_id,
+                    accounts,
+                    duration,
+                    reputation_oracle_stake,
+                    recording_oracle_stake,
+                    max_payout_per_recipient,
+                    min_payout,
+                    cancellation_fee,
+                    cancellation_fee_percent,
+                    cancel_notice,
+                )
+            }
+            EscrowInstruction::InitializeFromTemplate {
+                manifest_url,
+                manifest_hash,
+            } => {
+                info!("Instruction: Initialize From Template");
+                Self::process_initialize_from_template(
+                    program_id,
+                    accounts,
+                    &manifest_url,
+                    &manifest_hash,
+                )
+            }
+            EscrowInstruction::SetPermitSigner { permit_signer } => {
+                info!("Instruction: Set Permit Signer");
+                Self::process_set_permit_signer(accounts, &permit_signer)
+            }
         }
     }
 }
@@ -563,6 +2782,30 @@ impl PrintProgramError for EscrowError {
             EscrowError::NotEnoughBalance => info!("Error: not enough balance"),
             EscrowError::OracleNotInitialized => info!("Error: oracle not initialized"),
             EscrowError::TooManyPayouts => info!("Error: too many payouts"),
+            EscrowError::InvalidPermitSignature => info!("Error: invalid permit signature"),
+            EscrowError::PermitExpired => info!("Error: permit expired"),
+            EscrowError::InvalidPermitNonce => info!("Error: invalid permit nonce"),
+            EscrowError::Paused => info!("Error: escrow paused"),
+            EscrowError::ProgramPaused => info!("Error: program paused"),
+            EscrowError::DurationOutOfBounds => info!("Error: duration out of bounds"),
+            EscrowError::MintNotAllowed => info!("Error: mint not allowed"),
+            EscrowError::InvalidConfig => info!("Error: invalid config"),
+            EscrowError::InsufficientOracleStake => info!("Error: insufficient oracle stake"),
+            EscrowError::OracleStakeLocked => info!("Error: oracle stake locked"),
+            EscrowError::PayoutAboveMaximum => info!("Error: payout above maximum per recipient"),
+            EscrowError::PayoutBelowMinimum => info!("Error: payout below minimum"),
+            EscrowError::InvalidPayoutLimits => info!("Error: invalid payout limits"),
+            EscrowError::AllowListFull => info!("Error: allow-list full"),
+            EscrowError::InvalidAllowList => info!("Error: invalid allow-list"),
+            EscrowError::RecipientNotAllowed => info!("Error: recipient not allowed"),
+            EscrowError::TooManyVaults => info!("Error: too many vaults"),
+            EscrowError::InvalidVault => info!("Error: invalid vault"),
+            EscrowError::InvalidCancellationFee => info!("Error: invalid cancellation fee"),
+            EscrowError::CancelNoticePeriod => info!("Error: cancel notice period not over"),
+            EscrowError::InvalidTemplate => info!("Error: invalid template"),
+            EscrowError::CancelNoticeTooLong => {
+                info!("Error: cancel notice ends after the escrow expires")
+            }
         }
     }
 }
@@ -0,0 +1,224 @@
+//! Borsh schema of the packed program accounts
+//!
+//! `Pubkey` and `COption` do not implement Borsh in this sdk version, so the
+//! account is described by a mirror type with the same byte layout as `Pack`.
+
+use crate::state::{DataHash, DataUrl, Escrow, EscrowState};
+use arrayref::array_ref;
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use solana_program::{program_option::COption, pubkey::Pubkey};
+
+/// Packed `Pubkey`
+#[derive(Clone, Copy, Debug, Default, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct PackedPubkey(pub [u8; 32]);
+
+impl From<&Pubkey> for PackedPubkey {
+    fn from(key: &Pubkey) -> Self {
+        Self(key.to_bytes())
+    }
+}
+
+/// Packed `COption<Pubkey>`, a `u32` tag followed by the key
+#[derive(Clone, Copy, Debug, Default, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct PackedCOptionPubkey {
+    /// 1 if the key is present, 0 otherwise
+    pub tag: u32,
+    /// Key, zeroed if not present
+    pub key: PackedPubkey,
+}
+
+impl From<&COption<Pubkey>> for PackedCOptionPubkey {
+    fn from(key: &COption<Pubkey>) -> Self {
+        match key {
+            COption::Some(key) => Self {
+                tag: 1,
+                key: key.into(),
+            },
+            COption::None => Self::default(),
+        }
+    }
+}
+
+/// Byte layout of the [Escrow](../state/struct.Escrow.html) account
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct EscrowLayout {
+    /// Escrow expiration timestamp
+    pub expires: i64,
+    /// Program authority bump seed
+    pub bump_seed: u8,
+    /// Mint for the token handled by the escrow
+    pub token_mint: PackedPubkey,
+    /// Account to hold tokens for sendout
+    pub token_account: PackedPubkey,
+    /// Pubkey of the reputation oracle
+    pub reputation_oracle: PackedCOptionPubkey,
+    /// Account for the reputation oracle to receive fee
+    pub reputation_oracle_token_account: PackedCOptionPubkey,
+    /// Reputation oracle fee (in percents)
+    pub reputation_oracle_stake: u8,
+    /// Pubkey of the recording oracle
+    pub recording_oracle: PackedCOptionPubkey,
+    /// Account for the recording oracle to receive fee
+    pub recording_oracle_token_account: PackedCOptionPubkey,
+    /// Recording oracle fee (in percents)
+    pub recording_oracle_stake: u8,
+    /// Launcher pubkey
+    pub launcher: PackedPubkey,
+    /// Canceler pubkey
+    pub canceler: PackedPubkey,
+    /// Account for the canceler to receive back tokens
+    pub canceler_token_account: PackedPubkey,
+    /// Total amount of tokens to pay out
+    pub total_amount: u64,
+    /// Total number of recepients
+    pub total_recipients: u64,
+    /// Amount in tokens already sent
+    pub sent_amount: u64,
+    /// Number of recepients already sent to
+    pub sent_recipients: u64,
+    /// Current state of escrow entity
+    pub state: EscrowState,
+    /// Job manifest url, zero padded
+    pub manifest_url: DataUrl,
+    /// Job manifest hash
+    pub manifest_hash: DataHash,
+    /// Job results url, zero padded
+    pub final_results_url: DataUrl,
+    /// Job results hash
+    pub final_results_hash: DataHash,
+    /// Nonce expected in the next payout permit
+    pub payout_nonce: u64,
+    /// Ethereum address of the payout permit signer, zero if not set
+    pub permit_signer: [u8; 20],
+    /// Guardian pubkey
+    pub guardian: PackedPubkey,
+    /// Paused flag
+    pub paused: bool,
+    /// Results log head committed by the last `StoreResults`
+    pub results_log_head: [u8; 32],
+    /// Number of results log entries committed by the last `StoreResults`
+    pub results_log_count: u64,
+    /// Maximum total payout to a single recipient, zero for no limit
+    pub max_payout_per_recipient: u64,
+    /// Minimum payout amount
+    pub min_payout: u64,
+    /// Allow-list of the payout recipient owners
+    pub allow_list: PackedCOptionPubkey,
+    /// Number of additional token vaults
+    pub vault_count: u8,
+    /// Number of vaults not paid out yet
+    pub unpaid_vaults: u8,
+    /// Fixed cancellation fee
+    pub cancellation_fee: u64,
+    /// Cancellation fee percent of the remaining balance
+    pub cancellation_fee_percent: u8,
+    /// Cancel is blocked until this timestamp
+    pub cancel_notice_end: i64,
+}
+
+impl From<&Escrow> for EscrowLayout {
+    fn from(escrow: &Escrow) -> Self {
+        Self {
+            expires: escrow.expires,
+            bump_seed: escrow.bump_seed,
+            token_mint: (&escrow.token_mint).into(),
+            token_account: (&escrow.token_account).into(),
+            reputation_oracle: (&escrow.reputation_oracle).into(),
+            reputation_oracle_token_account: (&escrow.reputation_oracle_token_account).into(),
+            reputation_oracle_stake: escrow.reputation_oracle_stake,
+            recording_oracle: (&escrow.recording_oracle).into(),
+            recording_oracle_token_account: (&escrow.recording_oracle_token_account).into(),
+            recording_oracle_stake: escrow.recording_oracle_stake,
+            launcher: (&escrow.launcher).into(),
+            canceler: (&escrow.canceler).into(),
+            canceler_token_account: (&escrow.canceler_token_account).into(),
+            total_amount: escrow.total_amount,
+            total_recipients: escrow.total_recipients,
+            sent_amount: escrow.sent_amount,
+            sent_recipients: escrow.sent_recipients,
+            state: escrow.state,
+            manifest_url: escrow.manifest_url,
+            manifest_hash: escrow.manifest_hash,
+            final_results_url: escrow.final_results_url,
+            final_results_hash: escrow.final_results_hash,
+            payout_nonce: escrow.payout_nonce,
+            permit_signer: escrow.permit_signer,
+            guardian: (&escrow.guardian).into(),
+            paused: escrow.paused,
+            results_log_head: *array_ref![escrow.results_log_head.as_ref(), 0, 32],
+            results_log_count: escrow.results_log_count,
+            max_payout_per_recipient: escrow.max_payout_per_recipient,
+            min_payout: escrow.min_payout,
+            allow_list: (&escrow.allow_list).into(),
+            vault_count: escrow.vault_count,
+            unpaid_vaults: escrow.unpaid_vaults,
+            cancellation_fee: escrow.cancellation_fee,
+            cancellation_fee_percent: escrow.cancellation_fee_percent,
+            cancel_notice_end: escrow.cancel_notice_end,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::borsh_utils;
+    use solana_program::{hash::Hash, program_pack::Pack};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_get_packed_len() {
+        assert_eq!(
+            Escrow::get_packed_len(),
+            borsh_utils::get_packed_len::<EscrowLayout>()
+        );
+    }
+
+    #[test]
+    fn test_layout_matches_pack() {
+        let escrow = Escrow {
+            state: EscrowState::Pending,
+            expires: -2,
+            bump_seed: 254,
+            token_mint: Pubkey::new_from_array([1; 32]),
+            token_account: Pubkey::new_from_array([2; 32]),
+            reputation_oracle: COption::Some(Pubkey::new_from_array([3; 32])),
+            reputation_oracle_token_account: COption::None,
+            reputation_oracle_stake: 5,
+            recording_oracle: COption::Some(Pubkey::new_from_array([4; 32])),
+            recording_oracle_token_account: COption::Some(Pubkey::new_from_array([5; 32])),
+            recording_oracle_stake: 6,
+            launcher: Pubkey::new_from_array([6; 32]),
+            canceler: Pubkey::new_from_array([7; 32]),
+            canceler_token_account: Pubkey::new_from_array([8; 32]),
+            total_amount: 1000,
+            total_recipients: 10,
+            sent_amount: 300,
+            sent_recipients: 3,
+            manifest_url: DataUrl::from_str("https://example.com/manifest").unwrap(),
+            manifest_hash: DataHash::new_from_array([9; 20]),
+            final_results_url: DataUrl::from_str("https://example.com/results").unwrap(),
+            final_results_hash: DataHash::new_from_array([10; 20]),
+            payout_nonce: 7,
+            permit_signer: [14; 20],
+            guardian: Pubkey::new_from_array([11; 32]),
+            paused: true,
+            results_log_head: Hash::new_from_array([12; 32]),
+            results_log_count: 2,
+            max_payout_per_recipient: 400,
+            min_payout: 50,
+            allow_list: COption::Some(Pubkey::new_from_array([13; 32])),
+            vault_count: 3,
+            unpaid_vaults: 2,
+            cancellation_fee: 0,
+            cancellation_fee_percent: 20,
+            cancel_notice_end: -3,
+        };
+        let mut packed = vec![0; Escrow::get_packed_len()];
+        Escrow::pack(escrow, &mut packed).unwrap();
+
+        let layout = EscrowLayout::from(&escrow);
+        assert_eq!(layout.try_to_vec().unwrap(), packed);
+        assert_eq!(EscrowLayout::try_from_slice(&packed).unwrap(), layout);
+    }
+}
@@ -0,0 +1,177 @@
+//! Secp256k1 signature verification program helpers
+
+use crate::error::EscrowError;
+use arrayref::{array_ref, array_refs};
+use solana_program::{
+    entrypoint::ProgramResult, instruction::Instruction, program_error::ProgramError,
+};
+
+solana_program::declare_id!("KeccakSecp256k11111111111111111111111111111");
+
+/// Size of the Ethereum address of the signing key
+pub const ETH_ADDRESS_LEN: usize = 20;
+/// Size of the signature
+pub const SIGNATURE_LEN: usize = 64;
+/// Size of the signature offsets structure
+pub const SIGNATURE_OFFSETS_LEN: usize = 11;
+/// Offset of the first signature offsets structure, after number of signatures
+pub const SIGNATURE_OFFSETS_START: usize = 1;
+/// Offset of the Ethereum address in instructions created by `new_secp256k1_instruction`
+pub const ETH_ADDRESS_OFFSET: usize = SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN;
+/// Offset of the signature in instructions created by `new_secp256k1_instruction`,
+/// followed by the recovery id
+pub const SIGNATURE_OFFSET: usize = ETH_ADDRESS_OFFSET + ETH_ADDRESS_LEN;
+/// Offset of the message in instructions created by `new_secp256k1_instruction`
+pub const MESSAGE_OFFSET: usize = SIGNATURE_OFFSET + SIGNATURE_LEN + 1;
+
+/// Creates Secp256k1 program instruction verifying a single signature.
+///
+/// Signature of the Keccak256 hash of the `message` is produced off-chain,
+/// so this instruction can be built and sent by anyone, not only by the owner
+/// of the signing key. `instruction_index` is the position of this instruction
+/// within the transaction, the program reads all the data from it.
+pub fn new_secp256k1_instruction(
+    eth_address: &[u8; ETH_ADDRESS_LEN],
+    signature: &[u8; SIGNATURE_LEN],
+    recovery_id: u8,
+    message: &[u8],
+    instruction_index: u8,
+) -> Instruction {
+    let mut data: Vec<u8> = Vec::with_capacity(MESSAGE_OFFSET + message.len());
+    data.push(1); // number of signatures
+    data.extend(&(SIGNATURE_OFFSET as u16).to_le_bytes());
+    data.push(instruction_index);
+    data.extend(&(ETH_ADDRESS_OFFSET as u16).to_le_bytes());
+    data.push(instruction_index);
+    data.extend(&(MESSAGE_OFFSET as u16).to_le_bytes());
+    data.extend(&(message.len() as u16).to_le_bytes());
+    data.push(instruction_index);
+    data.extend(eth_address);
+    data.extend(signature.as_ref());
+    data.push(recovery_id);
+    data.extend(message);
+
+    Instruction {
+        program_id: id(),
+        accounts: vec![],
+        data,
+    }
+}
+
+/// Checks that the Secp256k1 program instruction verifies a signature of the
+/// `message` by the key with the `eth_address`.
+///
+/// Only single signature instructions with all the data stored in the
+/// instruction itself are accepted. `instruction_index` is the position of the
+/// Secp256k1 instruction within the transaction.
+pub fn check_secp256k1_instruction(
+    instruction: &Instruction,
+    instruction_index: u8,
+    eth_address: &[u8; ETH_ADDRESS_LEN],
+    message: &[u8],
+) -> ProgramResult {
+    if instruction.program_id != id() {
+        return Err(EscrowError::InvalidPermitSignature.into());
+    }
+
+    let data = &instruction.data;
+    if data.len() < SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN || data[0] != 1 {
+        return Err(EscrowError::InvalidPermitSignature.into());
+    }
+
+    let offsets = array_ref![data, SIGNATURE_OFFSETS_START, SIGNATURE_OFFSETS_LEN];
+    #[allow(clippy::ptr_offset_with_cast)]
+    let (
+        _signature_offset,
+        signature_instruction_index,
+        eth_address_offset,
+        eth_address_instruction_index,
+        message_data_offset,
+        message_data_size,
+        message_instruction_index,
+    ) = array_refs![offsets, 2, 1, 2, 1, 2, 2, 1];
+
+    // All data must be taken from the Secp256k1 instruction itself
+    for index in &[
+        signature_instruction_index,
+        eth_address_instruction_index,
+        message_instruction_index,
+    ] {
+        if index[0] != instruction_index {
+            return Err(EscrowError::InvalidPermitSignature.into());
+        }
+    }
+
+    let eth_address_offset = u16::from_le_bytes(*eth_address_offset) as usize;
+    let signed_eth_address = data
+        .get(eth_address_offset..eth_address_offset + ETH_ADDRESS_LEN)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    if signed_eth_address != eth_address {
+        return Err(EscrowError::InvalidPermitSignature.into());
+    }
+
+    let message_data_offset = u16::from_le_bytes(*message_data_offset) as usize;
+    let message_data_size = u16::from_le_bytes(*message_data_size) as usize;
+    let signed_message = data
+        .get(message_data_offset..message_data_offset + message_data_size)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    if signed_message != message {
+        return Err(EscrowError::InvalidPermitSignature.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_check_secp256k1_instruction() {
+        let eth_address = [1; ETH_ADDRESS_LEN];
+        let message = [2; 88];
+        let instruction =
+            new_secp256k1_instruction(&eth_address, &[3; SIGNATURE_LEN], 0, &message, 0);
+        assert_eq!(instruction.data.len(), MESSAGE_OFFSET + message.len());
+
+        assert_eq!(
+            check_secp256k1_instruction(&instruction, 0, &eth_address, &message),
+            Ok(())
+        );
+
+        // Wrong signer
+        assert_eq!(
+            check_secp256k1_instruction(&instruction, 0, &[4; ETH_ADDRESS_LEN], &message),
+            Err(EscrowError::InvalidPermitSignature.into())
+        );
+
+        // Wrong message
+        assert_eq!(
+            check_secp256k1_instruction(&instruction, 0, &eth_address, &[5; 88]),
+            Err(EscrowError::InvalidPermitSignature.into())
+        );
+
+        // Wrong program
+        let mut wrong_program = instruction.clone();
+        wrong_program.program_id = Pubkey::new_from_array([6; 32]);
+        assert_eq!(
+            check_secp256k1_instruction(&wrong_program, 0, &eth_address, &message),
+            Err(EscrowError::InvalidPermitSignature.into())
+        );
+
+        // Ethereum address taken from another instruction
+        let mut wrong_index = instruction.clone();
+        wrong_index.data[6] = 1;
+        assert_eq!(
+            check_secp256k1_instruction(&wrong_index, 0, &eth_address, &message),
+            Err(EscrowError::InvalidPermitSignature.into())
+        );
+
+        // Instruction is at another position in the transaction
+        assert_eq!(
+            check_secp256k1_instruction(&instruction, 1, &eth_address, &message),
+            Err(EscrowError::InvalidPermitSignature.into())
+        );
+    }
+}
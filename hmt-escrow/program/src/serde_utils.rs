@@ -0,0 +1,185 @@
+//! Serde helpers for the JSON representation of the program state
+
+use crate::state::{DataHash, DataUrl};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
+
+/// Encodes bytes as a lowercase hex string
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Decodes a hex string of exactly `len` bytes
+fn from_hex<E: de::Error>(s: &str, len: usize) -> Result<Vec<u8>, E> {
+    if !s.is_ascii() || s.len() != len * 2 {
+        return Err(E::custom(format!("expected {} hex encoded bytes", len)));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(E::custom))
+        .collect()
+}
+
+impl Serialize for DataUrl {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str().map_err(ser::Error::custom)?)
+    }
+}
+
+impl<'de> Deserialize<'de> for DataUrl {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let url = String::deserialize(deserializer)?;
+        DataUrl::from_str(&url).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for DataHash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_hex(self.as_ref()))
+    }
+}
+
+impl<'de> Deserialize<'de> for DataHash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hash = String::deserialize(deserializer)?;
+        let bytes = from_hex(&hash, std::mem::size_of::<DataHash>())?;
+        DataHash::new_from_slice(&bytes).map_err(de::Error::custom)
+    }
+}
+
+/// `Pubkey` as a base58 string
+pub mod pubkey {
+    use super::*;
+    use solana_program::pubkey::Pubkey;
+
+    /// Serializes the key as a base58 string
+    pub fn serialize<S: Serializer>(key: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(key)
+    }
+
+    /// Deserializes the key from a base58 string
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        let key = String::deserialize(deserializer)?;
+        Pubkey::from_str(&key).map_err(de::Error::custom)
+    }
+}
+
+/// `COption<Pubkey>` as a nullable base58 string
+pub mod coption_pubkey {
+    use super::*;
+    use solana_program::{program_option::COption, pubkey::Pubkey};
+
+    /// Serializes the key as a base58 string or null
+    pub fn serialize<S: Serializer>(
+        key: &COption<Pubkey>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match key {
+            COption::Some(key) => serializer.collect_str(key),
+            COption::None => serializer.serialize_none(),
+        }
+    }
+
+    /// Deserializes the key from a base58 string or null
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<COption<Pubkey>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(key) => Pubkey::from_str(&key)
+                .map(COption::Some)
+                .map_err(de::Error::custom),
+            None => Ok(COption::None),
+        }
+    }
+}
+
+/// `Hash` as a hex string
+pub mod hash {
+    use super::*;
+    use solana_program::hash::{Hash, HASH_BYTES};
+
+    /// Serializes the hash as a hex string
+    pub fn serialize<S: Serializer>(hash: &Hash, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_hex(hash.as_ref()))
+    }
+
+    /// Deserializes the hash from a hex string
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Hash, D::Error> {
+        let hash = String::deserialize(deserializer)?;
+        Ok(Hash::new(&from_hex::<D::Error>(&hash, HASH_BYTES)?))
+    }
+}
+
+/// Ethereum address as a hex string
+pub mod eth_address {
+    use super::*;
+    use crate::secp256k1::ETH_ADDRESS_LEN;
+
+    /// Serializes the address as a hex string
+    pub fn serialize<S: Serializer>(
+        address: &[u8; ETH_ADDRESS_LEN],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_hex(address))
+    }
+
+    /// Deserializes the address from a hex string
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[u8; ETH_ADDRESS_LEN], D::Error> {
+        let address = String::deserialize(deserializer)?;
+        let mut bytes = [0; ETH_ADDRESS_LEN];
+        bytes.copy_from_slice(&from_hex::<D::Error>(&address, ETH_ADDRESS_LEN)?);
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::{DataHash, DataUrl, Escrow, EscrowState};
+    use solana_program::{hash::Hash, program_option::COption, pubkey::Pubkey};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_escrow_json() {
+        let escrow = Escrow {
+            state: EscrowState::Partial,
+            expires: 1_606_000_000,
+            bump_seed: 255,
+            token_mint: Pubkey::new_from_array([1; 32]),
+            reputation_oracle: COption::Some(Pubkey::new_from_array([2; 32])),
+            manifest_url: DataUrl::from_str("https://example.com/manifest.json").unwrap(),
+            manifest_hash: DataHash::new_from_array([0xab; 20]),
+            permit_signer: [0xee; 20],
+            results_log_head: Hash::new_from_array([0x0f; 32]),
+            ..Escrow::default()
+        };
+
+        let json = serde_json::to_value(&escrow).unwrap();
+        assert_eq!(json["state"], "Partial");
+        assert_eq!(
+            json["token_mint"],
+            Pubkey::new_from_array([1; 32]).to_string()
+        );
+        assert_eq!(
+            json["reputation_oracle"],
+            Pubkey::new_from_array([2; 32]).to_string()
+        );
+        assert!(json["recording_oracle"].is_null());
+        assert_eq!(json["manifest_url"], "https://example.com/manifest.json");
+        assert_eq!(json["manifest_hash"], "ab".repeat(20));
+        assert_eq!(json["final_results_url"], "");
+        assert_eq!(json["permit_signer"], "ee".repeat(20));
+        assert_eq!(json["results_log_head"], "0f".repeat(32));
+
+        let decoded: Escrow = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, escrow);
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        assert!(serde_json::from_str::<DataHash>("\"abcd\"").is_err());
+        assert!(serde_json::from_str::<DataHash>(&format!("\"{}\"", "zz".repeat(20))).is_err());
+        assert!(serde_json::from_str::<DataUrl>(&format!("\"{}\"", "a".repeat(257))).is_err());
+    }
+}
@@ -1,22 +1,87 @@
 //! State types
 
+use crate::error::EscrowError;
+use crate::secp256k1::ETH_ADDRESS_LEN;
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use num_enum::TryFromPrimitive;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use solana_program::{
     clock::UnixTimestamp,
+    hash::{hashv, Hash},
     program_error::ProgramError,
     program_option::COption,
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::Pubkey,
 };
-use std::{fmt, mem, str::FromStr};
+use std::{fmt, mem, str, str::FromStr};
 
 /// Size for the URL field
 pub const URL_LEN: usize = 256;
 
+/// Offset of the token mint in the packed escrow account
+pub const ESCROW_TOKEN_MINT_OFFSET: usize = 9;
+
+/// Offset of the launcher in the packed escrow account
+pub const ESCROW_LAUNCHER_OFFSET: usize = 219;
+
+/// Offset of the canceler in the packed escrow account
+pub const ESCROW_CANCELER_OFFSET: usize = 251;
+
+/// Offset of the state in the packed escrow account
+pub const ESCROW_STATE_OFFSET: usize = 347;
+
+/// Offset of the escrow in the packed escrow vault account
+pub const ESCROW_VAULT_ESCROW_OFFSET: usize = 2;
+
+/// Maximum number of token mints in the program config allow-list
+pub const MAX_ALLOWED_MINTS: usize = 10;
+
+/// Seed for the program config address
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Seed for the oracle stake addresses, followed by the oracle key
+pub const ORACLE_STAKE_SEED: &[u8] = b"oracle-stake";
+
+/// Seed for the worker reputation addresses, followed by the worker key
+pub const REPUTATION_SEED: &[u8] = b"reputation";
+
+/// Seed for the reputation job addresses, followed by the worker and escrow keys
+pub const REPUTATION_JOB_SEED: &[u8] = b"reputation-job";
+
+/// Maximum number of workers in a single reputation update
+pub const MAX_REPUTATION_UPDATES: usize = 8;
+
+/// Seed for the escrow results log addresses, followed by the escrow key
+pub const RESULTS_LOG_SEED: &[u8] = b"results-log";
+
+/// Seed for the escrow results log page addresses, followed by the escrow key
+/// and the little-endian page number
+pub const RESULTS_LOG_PAGE_SEED: &[u8] = b"results-log-page";
+
+/// Number of entries stored in a single results log page
+pub const RESULTS_LOG_PAGE_ENTRIES: usize = 24;
+
+/// Seed for the escrow recipient payout addresses, followed by the escrow key
+/// and the recipient token account owner key
+pub const RECIPIENT_PAYOUT_SEED: &[u8] = b"recipient-payout";
+
+/// Maximum number of owners added or removed by a single allow-list update
+pub const MAX_ALLOW_LIST_UPDATES: usize = 16;
+
+/// Seed for the escrow vault addresses, followed by the escrow and mint keys
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/// Maximum number of additional token vaults of a single escrow
+pub const MAX_ESCROW_VAULTS: u8 = 4;
+
 /// Escrow state.
 #[repr(u8)]
-#[derive(Clone, Copy, Debug, PartialEq, TryFromPrimitive)]
+#[derive(
+    Clone, Copy, Debug, PartialEq, TryFromPrimitive, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum EscrowState {
     /// Escrow is not yet initialized
     Uninitialized,
@@ -42,7 +107,7 @@ impl Default for EscrowState {
 
 /// Stores job manifest hash
 #[repr(transparent)]
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
 pub struct DataHash([u8; 20]);
 
 impl AsRef<[u8]> for DataHash {
@@ -69,7 +134,7 @@ impl DataHash {
 
 /// Stores data URL
 #[repr(transparent)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, BorshSerialize, BorshDeserialize, BorshSchema)]
 pub struct DataUrl([u8; URL_LEN]);
 
 impl Default for DataUrl {
@@ -80,7 +145,10 @@ impl Default for DataUrl {
 
 impl fmt::Debug for DataUrl {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0[..].fmt(f)
+        match self.as_str() {
+            Ok(url) => fmt::Debug::fmt(url, f),
+            Err(_) => fmt::Debug::fmt(&self.0[..], f),
+        }
     }
 }
 
@@ -117,11 +185,17 @@ impl DataUrl {
     pub const fn new_from_array(data: [u8; URL_LEN]) -> Self {
         Self(data)
     }
+    /// URL string without the trailing zero padding
+    pub fn as_str(&self) -> Result<&str, ProgramError> {
+        let length = self.0.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+        str::from_utf8(&self.0[..length]).or(Err(ProgramError::InvalidAccountData))
+    }
 }
 
 /// Escrow data
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Escrow {
     /// Current state of escrow entity: Uninitialized, Launched, Pending, Partial, Paid, Complete, Cancelled
     pub state: EscrowState,
@@ -130,26 +204,35 @@ pub struct Escrow {
     /// Program authority bump seed
     pub bump_seed: u8,
     /// Mint for the token handled by the escrow
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::pubkey"))]
     pub token_mint: Pubkey,
     /// Account to hold tokens for sendout, its owner should be escrow contract authority
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::pubkey"))]
     pub token_account: Pubkey,
     /// Pubkey of the reputation oracle
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::coption_pubkey"))]
     pub reputation_oracle: COption<Pubkey>,
     /// Account for the reputation oracle to receive fee
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::coption_pubkey"))]
     pub reputation_oracle_token_account: COption<Pubkey>,
     /// Reputation oracle fee (in percents)
     pub reputation_oracle_stake: u8,
     /// Pubkey of the recording oracle
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::coption_pubkey"))]
     pub recording_oracle: COption<Pubkey>,
     /// Account for the recording oracle to receive fee
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::coption_pubkey"))]
     pub recording_oracle_token_account: COption<Pubkey>,
     /// Recording oracle fee (in percents)
     pub recording_oracle_stake: u8,
     /// Launcher pubkey
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::pubkey"))]
     pub launcher: Pubkey,
     /// Canceler pubkey
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::pubkey"))]
     pub canceler: Pubkey,
     /// Account for the canceler to receive back tokens
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::pubkey"))]
     pub canceler_token_account: Pubkey,
     /// Total amount of tokens to pay out
     pub total_amount: u64,
@@ -167,6 +250,55 @@ pub struct Escrow {
     pub final_results_url: DataUrl,
     /// Job results hash
     pub final_results_hash: DataHash,
+    /// Nonce expected in the next payout permit signed by the reputation oracle
+    pub payout_nonce: u64,
+    /// Ethereum address of the reputation oracle key signing payout permits,
+    /// zero when permits are not accepted
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::eth_address"))]
+    pub permit_signer: [u8; ETH_ADDRESS_LEN],
+    /// Guardian pubkey, able to pause and unpause the escrow
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::pubkey"))]
+    pub guardian: Pubkey,
+    /// Paused escrow rejects all instructions except unpause and cancel
+    pub paused: bool,
+    /// Results log head committed by the last `StoreResults`
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::hash"))]
+    pub results_log_head: Hash,
+    /// Number of results log entries committed by the last `StoreResults`
+    pub results_log_count: u64,
+    /// Maximum amount of tokens a single recipient can be paid in total, zero for no limit
+    pub max_payout_per_recipient: u64,
+    /// Minimum amount of tokens in a single payout
+    pub min_payout: u64,
+    /// Allow-list of the payout recipient owners, any recipient if not set
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::coption_pubkey"))]
+    pub allow_list: COption<Pubkey>,
+    /// Number of additional token vaults, each paid and refunded separately
+    pub vault_count: u8,
+    /// Number of vaults without stored results or not fully paid out yet
+    pub unpaid_vaults: u8,
+    /// Fixed amount of tokens paid to the oracles on cancel, capped by the balance
+    pub cancellation_fee: u64,
+    /// Percent of the remaining balance paid to the oracles on cancel
+    pub cancellation_fee_percent: u8,
+    /// Cancel is blocked until this timestamp, set by `Setup` from the notice period
+    pub cancel_notice_end: UnixTimestamp,
+}
+
+impl Escrow {
+    /// Cancellation fee taken from the remaining `balance`, split between the oracles
+    pub fn cancellation_fee_amount(&self, balance: u64) -> u64 {
+        if self.cancellation_fee_percent != 0 {
+            (balance as u128 * self.cancellation_fee_percent as u128 / 100) as u64
+        } else {
+            self.cancellation_fee.min(balance)
+        }
+    }
+
+    /// Checks if the oracles are paid a fee on cancel
+    pub fn has_cancellation_fee(&self) -> bool {
+        self.cancellation_fee != 0 || self.cancellation_fee_percent != 0
+    }
 }
 
 impl Sealed for Escrow {}
@@ -177,7 +309,7 @@ impl IsInitialized for Escrow {
 }
 
 impl Pack for Escrow {
-    const LEN: usize = 388 + URL_LEN + URL_LEN;
+    const LEN: usize = 560 + URL_LEN + URL_LEN;
 
     /// Packs a [EscrowInfo](struct.EscrowInfo.html) into a byte buffer.
     fn pack_into_slice(&self, output: &mut [u8]) {
@@ -206,9 +338,23 @@ impl Pack for Escrow {
             manifest_hash_dst,
             final_results_url_dst,
             final_results_hash_dst,
+            payout_nonce_dst,
+            permit_signer_dst,
+            guardian_dst,
+            paused_dst,
+            results_log_head_dst,
+            results_log_count_dst,
+            max_payout_per_recipient_dst,
+            min_payout_dst,
+            allow_list_dst,
+            vault_count_dst,
+            unpaid_vaults_dst,
+            cancellation_fee_dst,
+            cancellation_fee_percent_dst,
+            cancel_notice_end_dst,
         ) = mut_array_refs![
             output, 8, 1, 32, 32, 36, 36, 1, 36, 36, 1, 32, 32, 32, 8, 8, 8, 8, 1, URL_LEN, 20,
-            URL_LEN, 20
+            URL_LEN, 20, 8, 20, 32, 1, 32, 8, 8, 8, 36, 1, 1, 8, 1, 8
         ];
         expires_dst.copy_from_slice(&self.expires.to_le_bytes());
         bump_seed_dst[0] = self.bump_seed;
@@ -238,10 +384,27 @@ impl Pack for Escrow {
         manifest_hash_dst.copy_from_slice(self.manifest_hash.as_ref());
         final_results_url_dst.copy_from_slice(self.final_results_url.as_ref());
         final_results_hash_dst.copy_from_slice(self.final_results_hash.as_ref());
+        payout_nonce_dst.copy_from_slice(&self.payout_nonce.to_le_bytes());
+        permit_signer_dst.copy_from_slice(&self.permit_signer);
+        guardian_dst.copy_from_slice(self.guardian.as_ref());
+        paused_dst[0] = self.paused as u8;
+        results_log_head_dst.copy_from_slice(self.results_log_head.as_ref());
+        results_log_count_dst.copy_from_slice(&self.results_log_count.to_le_bytes());
+        max_payout_per_recipient_dst.copy_from_slice(&self.max_payout_per_recipient.to_le_bytes());
+        min_payout_dst.copy_from_slice(&self.min_payout.to_le_bytes());
+        pack_coption_key(&self.allow_list, allow_list_dst);
+        vault_count_dst[0] = self.vault_count;
+        unpaid_vaults_dst[0] = self.unpaid_vaults;
+        cancellation_fee_dst.copy_from_slice(&self.cancellation_fee.to_le_bytes());
+        cancellation_fee_percent_dst[0] = self.cancellation_fee_percent;
+        cancel_notice_end_dst.copy_from_slice(&self.cancel_notice_end.to_le_bytes());
     }
 
     /// Unpacks a byte buffer into a [EscrowInfo](struct.EscrowInfo.html).
     fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = input
+            .get(..Escrow::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
         let input = array_ref![input, 0, Escrow::LEN];
         #[allow(clippy::ptr_offset_with_cast)]
         let (
@@ -267,9 +430,23 @@ impl Pack for Escrow {
             manifest_hash_src,
             final_results_url_src,
             final_results_hash_src,
+            payout_nonce_src,
+            permit_signer_src,
+            guardian_src,
+            paused_src,
+            results_log_head_src,
+            results_log_count_src,
+            max_payout_per_recipient_src,
+            min_payout_src,
+            allow_list_src,
+            vault_count_src,
+            unpaid_vaults_src,
+            cancellation_fee_src,
+            cancellation_fee_percent_src,
+            cancel_notice_end_src,
         ) = array_refs![
             input, 8, 1, 32, 32, 36, 36, 1, 36, 36, 1, 32, 32, 32, 8, 8, 8, 8, 1, URL_LEN, 20,
-            URL_LEN, 20
+            URL_LEN, 20, 8, 20, 32, 1, 32, 8, 8, 8, 36, 1, 1, 8, 1, 8
         ];
         Ok(Escrow {
             expires: UnixTimestamp::from_le_bytes(*expires_src),
@@ -304,6 +481,1020 @@ impl Pack for Escrow {
 
             final_results_url: DataUrl::new_from_array(*final_results_url_src),
             final_results_hash: DataHash::new_from_array(*final_results_hash_src),
+
+            payout_nonce: u64::from_le_bytes(*payout_nonce_src),
+            permit_signer: *permit_signer_src,
+
+            guardian: Pubkey::new_from_array(*guardian_src),
+            paused: unpack_bool(paused_src)?,
+
+            results_log_head: Hash::new_from_array(*results_log_head_src),
+            results_log_count: u64::from_le_bytes(*results_log_count_src),
+
+            max_payout_per_recipient: u64::from_le_bytes(*max_payout_per_recipient_src),
+            min_payout: u64::from_le_bytes(*min_payout_src),
+
+            allow_list: unpack_coption_key(allow_list_src)?,
+
+            vault_count: vault_count_src[0],
+            unpaid_vaults: unpaid_vaults_src[0],
+
+            cancellation_fee: u64::from_le_bytes(*cancellation_fee_src),
+            cancellation_fee_percent: cancellation_fee_percent_src[0],
+            cancel_notice_end: UnixTimestamp::from_le_bytes(*cancel_notice_end_src),
+        })
+    }
+}
+
+/// Program-wide configuration, singleton account at the program address
+/// derived from `CONFIG_SEED`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EscrowConfig {
+    /// Config is initialized
+    pub is_initialized: bool,
+    /// Config address bump seed
+    pub bump_seed: u8,
+    /// Admin allowed to update the config
+    pub admin: Pubkey,
+    /// Global pause flag, no new escrows can be initialized or set up while set
+    pub paused: bool,
+    /// Maximum combined reputation and recording oracle stake (in percents)
+    pub max_oracle_stake: u8,
+    /// Minimum escrow duration in seconds
+    pub min_duration: u64,
+    /// Maximum escrow duration in seconds
+    pub max_duration: u64,
+    /// Number of used entries in `allowed_mints`
+    pub allowed_mints_count: u8,
+    /// Token mints accepted by escrows, any mint is accepted if the list is empty
+    pub allowed_mints: [Pubkey; MAX_ALLOWED_MINTS],
+    /// Mint of the tokens locked by oracles, oracle staking is disabled if not set
+    pub stake_mint: COption<Pubkey>,
+    /// Minimum stake each oracle has to lock to set up large escrows
+    pub min_oracle_stake: u64,
+    /// Escrows holding at least this amount of tokens require staked oracles
+    pub stake_escrow_threshold: u64,
+    /// Dispute arbiter allowed to slash oracle stakes along with the admin
+    pub arbiter: COption<Pubkey>,
+}
+
+impl EscrowConfig {
+    /// Token mints in the allow-list
+    pub fn allowed_mints(&self) -> &[Pubkey] {
+        let count = (self.allowed_mints_count as usize).min(MAX_ALLOWED_MINTS);
+        &self.allowed_mints[..count]
+    }
+
+    /// Replaces token mints allow-list
+    pub fn set_allowed_mints(&mut self, mints: &[Pubkey]) -> Result<(), ProgramError> {
+        if mints.len() > MAX_ALLOWED_MINTS {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.allowed_mints = Default::default();
+        self.allowed_mints[..mints.len()].copy_from_slice(mints);
+        self.allowed_mints_count = mints.len() as u8;
+        Ok(())
+    }
+
+    /// Checks if escrows can hold tokens of this mint
+    pub fn is_mint_allowed(&self, mint: &Pubkey) -> bool {
+        let allowed_mints = self.allowed_mints();
+        allowed_mints.is_empty() || allowed_mints.contains(mint)
+    }
+
+    /// Checks if oracles must lock stake to set up escrow holding `escrow_amount` tokens
+    pub fn is_oracle_stake_required(&self, escrow_amount: u64) -> bool {
+        self.stake_mint.is_some() && escrow_amount >= self.stake_escrow_threshold
+    }
+
+    /// Checks if the key is allowed to slash oracle stakes
+    pub fn is_slasher(&self, key: &Pubkey) -> bool {
+        *key == self.admin || self.arbiter == COption::Some(*key)
+    }
+}
+
+impl Sealed for EscrowConfig {}
+impl IsInitialized for EscrowConfig {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for EscrowConfig {
+    const LEN: usize = 141 + 32 * MAX_ALLOWED_MINTS;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, EscrowConfig::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized_dst,
+            bump_seed_dst,
+            admin_dst,
+            paused_dst,
+            max_oracle_stake_dst,
+            min_duration_dst,
+            max_duration_dst,
+            allowed_mints_count_dst,
+            allowed_mints_dst,
+            stake_mint_dst,
+            min_oracle_stake_dst,
+            stake_escrow_threshold_dst,
+            arbiter_dst,
+        ) = mut_array_refs![
+            output,
+            1,
+            1,
+            32,
+            1,
+            1,
+            8,
+            8,
+            1,
+            32 * MAX_ALLOWED_MINTS,
+            36,
+            8,
+            8,
+            36
+        ];
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        admin_dst.copy_from_slice(self.admin.as_ref());
+        paused_dst[0] = self.paused as u8;
+        max_oracle_stake_dst[0] = self.max_oracle_stake;
+        min_duration_dst.copy_from_slice(&self.min_duration.to_le_bytes());
+        max_duration_dst.copy_from_slice(&self.max_duration.to_le_bytes());
+        allowed_mints_count_dst[0] = self.allowed_mints_count;
+        for (mint, mint_dst) in self
+            .allowed_mints
+            .iter()
+            .zip(allowed_mints_dst.chunks_exact_mut(32))
+        {
+            mint_dst.copy_from_slice(mint.as_ref());
+        }
+        pack_coption_key(&self.stake_mint, stake_mint_dst);
+        min_oracle_stake_dst.copy_from_slice(&self.min_oracle_stake.to_le_bytes());
+        stake_escrow_threshold_dst.copy_from_slice(&self.stake_escrow_threshold.to_le_bytes());
+        pack_coption_key(&self.arbiter, arbiter_dst);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = input
+            .get(..EscrowConfig::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let input = array_ref![input, 0, EscrowConfig::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized_src,
+            bump_seed_src,
+            admin_src,
+            paused_src,
+            max_oracle_stake_src,
+            min_duration_src,
+            max_duration_src,
+            allowed_mints_count_src,
+            allowed_mints_src,
+            stake_mint_src,
+            min_oracle_stake_src,
+            stake_escrow_threshold_src,
+            arbiter_src,
+        ) = array_refs![
+            input,
+            1,
+            1,
+            32,
+            1,
+            1,
+            8,
+            8,
+            1,
+            32 * MAX_ALLOWED_MINTS,
+            36,
+            8,
+            8,
+            36
+        ];
+        if allowed_mints_count_src[0] as usize > MAX_ALLOWED_MINTS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut allowed_mints: [Pubkey; MAX_ALLOWED_MINTS] = Default::default();
+        for (mint, mint_src) in allowed_mints
+            .iter_mut()
+            .zip(allowed_mints_src.chunks_exact(32))
+        {
+            *mint = Pubkey::new(mint_src);
+        }
+        Ok(EscrowConfig {
+            is_initialized: unpack_bool(is_initialized_src)?,
+            bump_seed: bump_seed_src[0],
+            admin: Pubkey::new_from_array(*admin_src),
+            paused: unpack_bool(paused_src)?,
+            max_oracle_stake: max_oracle_stake_src[0],
+            min_duration: u64::from_le_bytes(*min_duration_src),
+            max_duration: u64::from_le_bytes(*max_duration_src),
+            allowed_mints_count: allowed_mints_count_src[0],
+            allowed_mints,
+            stake_mint: unpack_coption_key(stake_mint_src)?,
+            min_oracle_stake: u64::from_le_bytes(*min_oracle_stake_src),
+            stake_escrow_threshold: u64::from_le_bytes(*stake_escrow_threshold_src),
+            arbiter: unpack_coption_key(arbiter_src)?,
+        })
+    }
+}
+
+/// Oracle stake registry entry, one per oracle at the program address derived
+/// from `ORACLE_STAKE_SEED` and the oracle key. The entry account is also the
+/// owner of the vault token account holding the locked tokens.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OracleStake {
+    /// Oracle stake is initialized
+    pub is_initialized: bool,
+    /// Oracle stake address bump seed
+    pub bump_seed: u8,
+    /// Oracle account
+    pub oracle: Pubkey,
+    /// Token account holding the locked tokens
+    pub vault: Pubkey,
+    /// Amount of locked tokens
+    pub amount: u64,
+    /// Stake cannot be withdrawn until this time, extended by every escrow setup
+    pub locked_until: UnixTimestamp,
+}
+
+impl Sealed for OracleStake {}
+impl IsInitialized for OracleStake {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for OracleStake {
+    const LEN: usize = 82;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, OracleStake::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized_dst,
+            bump_seed_dst,
+            oracle_dst,
+            vault_dst,
+            amount_dst,
+            locked_until_dst,
+        ) = mut_array_refs![output, 1, 1, 32, 32, 8, 8];
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        oracle_dst.copy_from_slice(self.oracle.as_ref());
+        vault_dst.copy_from_slice(self.vault.as_ref());
+        *amount_dst = self.amount.to_le_bytes();
+        *locked_until_dst = self.locked_until.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = input
+            .get(..OracleStake::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let input = array_ref![input, 0, OracleStake::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized_src,
+            bump_seed_src,
+            oracle_src,
+            vault_src,
+            amount_src,
+            locked_until_src,
+        ) = array_refs![input, 1, 1, 32, 32, 8, 8];
+        Ok(OracleStake {
+            is_initialized: unpack_bool(is_initialized_src)?,
+            bump_seed: bump_seed_src[0],
+            oracle: Pubkey::new_from_array(*oracle_src),
+            vault: Pubkey::new_from_array(*vault_src),
+            amount: u64::from_le_bytes(*amount_src),
+            locked_until: UnixTimestamp::from_le_bytes(*locked_until_src),
+        })
+    }
+}
+
+/// Worker reputation, one per worker at the program address derived from
+/// `REPUTATION_SEED` and the worker key. Updated by the reputation oracles of
+/// the escrows the worker was paid from.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Reputation {
+    /// Reputation is initialized
+    pub is_initialized: bool,
+    /// Reputation address bump seed
+    pub bump_seed: u8,
+    /// Worker account
+    pub worker: Pubkey,
+    /// Cumulative reputation score
+    pub score: i64,
+    /// Number of distinct escrows the worker was rated for
+    pub jobs_completed: u64,
+    /// Escrow of the last reputation update
+    pub last_escrow: Pubkey,
+}
+
+impl Sealed for Reputation {}
+impl IsInitialized for Reputation {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Reputation {
+    const LEN: usize = 82;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, Reputation::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized_dst,
+            bump_seed_dst,
+            worker_dst,
+            score_dst,
+            jobs_completed_dst,
+            last_escrow_dst,
+        ) = mut_array_refs![output, 1, 1, 32, 8, 8, 32];
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        worker_dst.copy_from_slice(self.worker.as_ref());
+        *score_dst = self.score.to_le_bytes();
+        *jobs_completed_dst = self.jobs_completed.to_le_bytes();
+        last_escrow_dst.copy_from_slice(self.last_escrow.as_ref());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = input
+            .get(..Reputation::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let input = array_ref![input, 0, Reputation::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized_src,
+            bump_seed_src,
+            worker_src,
+            score_src,
+            jobs_completed_src,
+            last_escrow_src,
+        ) = array_refs![input, 1, 1, 32, 8, 8, 32];
+        Ok(Reputation {
+            is_initialized: unpack_bool(is_initialized_src)?,
+            bump_seed: bump_seed_src[0],
+            worker: Pubkey::new_from_array(*worker_src),
+            score: i64::from_le_bytes(*score_src),
+            jobs_completed: u64::from_le_bytes(*jobs_completed_src),
+            last_escrow: Pubkey::new_from_array(*last_escrow_src),
+        })
+    }
+}
+
+/// Escrow results log, one per escrow at the program address derived from
+/// `RESULTS_LOG_SEED` and the escrow key. Entries are appended to the log pages,
+/// each entry extends the hash chain ending at `head`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResultsLog {
+    /// Results log is initialized
+    pub is_initialized: bool,
+    /// Results log address bump seed
+    pub bump_seed: u8,
+    /// Escrow account
+    pub escrow: Pubkey,
+    /// Hash chain head covering all entries
+    pub head: Hash,
+    /// Number of entries
+    pub count: u64,
+}
+
+impl ResultsLog {
+    /// Number of the page holding the entry
+    pub fn page(index: u64) -> u64 {
+        index / RESULTS_LOG_PAGE_ENTRIES as u64
+    }
+
+    /// Number of pages holding all the entries
+    pub fn page_count(&self) -> u64 {
+        Self::page(self.count + RESULTS_LOG_PAGE_ENTRIES as u64 - 1)
+    }
+}
+
+impl Sealed for ResultsLog {}
+impl IsInitialized for ResultsLog {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for ResultsLog {
+    const LEN: usize = 74;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, ResultsLog::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized_dst, bump_seed_dst, escrow_dst, head_dst, count_dst) =
+            mut_array_refs![output, 1, 1, 32, 32, 8];
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        escrow_dst.copy_from_slice(self.escrow.as_ref());
+        head_dst.copy_from_slice(self.head.as_ref());
+        *count_dst = self.count.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = input
+            .get(..ResultsLog::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let input = array_ref![input, 0, ResultsLog::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized_src, bump_seed_src, escrow_src, head_src, count_src) =
+            array_refs![input, 1, 1, 32, 32, 8];
+        Ok(ResultsLog {
+            is_initialized: unpack_bool(is_initialized_src)?,
+            bump_seed: bump_seed_src[0],
+            escrow: Pubkey::new_from_array(*escrow_src),
+            head: Hash::new_from_array(*head_src),
+            count: u64::from_le_bytes(*count_src),
+        })
+    }
+}
+
+/// Escrow results log page header, at the program address derived from
+/// `RESULTS_LOG_PAGE_SEED`, the escrow key and the page number. Followed by
+/// `RESULTS_LOG_PAGE_ENTRIES` packed entries, page `n` holds the entries from
+/// `n * RESULTS_LOG_PAGE_ENTRIES` on. Entries are never overwritten.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResultsLogPage {
+    /// Results log page is initialized
+    pub is_initialized: bool,
+    /// Results log page address bump seed
+    pub bump_seed: u8,
+    /// Escrow account
+    pub escrow: Pubkey,
+    /// Page number
+    pub page: u64,
+}
+
+impl ResultsLogPage {
+    /// Size of the results log page account with all the entries
+    pub const ACCOUNT_LEN: usize =
+        ResultsLogPage::LEN + RESULTS_LOG_PAGE_ENTRIES * ResultsLogEntry::LEN;
+
+    /// Offset of the entry in the data of the page holding it
+    pub fn entry_offset(index: usize) -> usize {
+        ResultsLogPage::LEN + (index % RESULTS_LOG_PAGE_ENTRIES) * ResultsLogEntry::LEN
+    }
+
+    /// Reads entry from the data of the page holding it
+    pub fn unpack_entry(data: &[u8], index: usize) -> Result<ResultsLogEntry, ProgramError> {
+        let offset = Self::entry_offset(index);
+        let entry_data = data
+            .get(offset..offset + ResultsLogEntry::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        ResultsLogEntry::unpack_from_slice(entry_data)
+    }
+}
+
+impl Sealed for ResultsLogPage {}
+impl IsInitialized for ResultsLogPage {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for ResultsLogPage {
+    const LEN: usize = 42;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, ResultsLogPage::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized_dst, bump_seed_dst, escrow_dst, page_dst) =
+            mut_array_refs![output, 1, 1, 32, 8];
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        escrow_dst.copy_from_slice(self.escrow.as_ref());
+        *page_dst = self.page.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = input
+            .get(..ResultsLogPage::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let input = array_ref![input, 0, ResultsLogPage::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized_src, bump_seed_src, escrow_src, page_src) =
+            array_refs![input, 1, 1, 32, 8];
+        Ok(ResultsLogPage {
+            is_initialized: unpack_bool(is_initialized_src)?,
+            bump_seed: bump_seed_src[0],
+            escrow: Pubkey::new_from_array(*escrow_src),
+            page: u64::from_le_bytes(*page_src),
+        })
+    }
+}
+
+/// Intermediate results stored in the escrow results log
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResultsLogEntry {
+    /// Intermediate results url
+    pub url: DataUrl,
+    /// Intermediate results hash
+    pub hash: DataHash,
+    /// Time the entry was stored
+    pub timestamp: UnixTimestamp,
+    /// Signer of the entry
+    pub signer: Pubkey,
+}
+
+impl ResultsLogEntry {
+    /// Calculates the hash chain head after appending this entry
+    pub fn next_head(&self, head: &Hash) -> Hash {
+        hashv(&[
+            head.as_ref(),
+            self.url.as_ref(),
+            self.hash.as_ref(),
+            &self.timestamp.to_le_bytes(),
+            self.signer.as_ref(),
+        ])
+    }
+}
+
+impl Sealed for ResultsLogEntry {}
+
+impl Pack for ResultsLogEntry {
+    const LEN: usize = 60 + URL_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, ResultsLogEntry::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (url_dst, hash_dst, timestamp_dst, signer_dst) =
+            mut_array_refs![output, URL_LEN, 20, 8, 32];
+        url_dst.copy_from_slice(self.url.as_ref());
+        hash_dst.copy_from_slice(self.hash.as_ref());
+        *timestamp_dst = self.timestamp.to_le_bytes();
+        signer_dst.copy_from_slice(self.signer.as_ref());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = input
+            .get(..ResultsLogEntry::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let input = array_ref![input, 0, ResultsLogEntry::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (url_src, hash_src, timestamp_src, signer_src) = array_refs![input, URL_LEN, 20, 8, 32];
+        Ok(ResultsLogEntry {
+            url: DataUrl::new_from_array(*url_src),
+            hash: DataHash::new_from_array(*hash_src),
+            timestamp: UnixTimestamp::from_le_bytes(*timestamp_src),
+            signer: Pubkey::new_from_array(*signer_src),
+        })
+    }
+}
+
+/// Total paid by the escrow to the owner of the recipient token accounts, at the
+/// program address derived from `RECIPIENT_PAYOUT_SEED`, the escrow key and the
+/// owner key. Created by the first payout to the owner when the escrow limits
+/// the payout per recipient.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RecipientPayout {
+    /// Recipient payout is initialized
+    pub is_initialized: bool,
+    /// Recipient payout address bump seed
+    pub bump_seed: u8,
+    /// Escrow account
+    pub escrow: Pubkey,
+    /// Owner of the recipient token accounts
+    pub owner: Pubkey,
+    /// Amount paid to the owner, oracle fees included
+    pub amount: u64,
+}
+
+impl Sealed for RecipientPayout {}
+impl IsInitialized for RecipientPayout {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for RecipientPayout {
+    const LEN: usize = 74;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, RecipientPayout::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized_dst, bump_seed_dst, escrow_dst, owner_dst, amount_dst) =
+            mut_array_refs![output, 1, 1, 32, 32, 8];
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        escrow_dst.copy_from_slice(self.escrow.as_ref());
+        owner_dst.copy_from_slice(self.owner.as_ref());
+        *amount_dst = self.amount.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = input
+            .get(..RecipientPayout::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let input = array_ref![input, 0, RecipientPayout::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized_src, bump_seed_src, escrow_src, owner_src, amount_src) =
+            array_refs![input, 1, 1, 32, 32, 8];
+        Ok(RecipientPayout {
+            is_initialized: unpack_bool(is_initialized_src)?,
+            bump_seed: bump_seed_src[0],
+            escrow: Pubkey::new_from_array(*escrow_src),
+            owner: Pubkey::new_from_array(*owner_src),
+            amount: u64::from_le_bytes(*amount_src),
+        })
+    }
+}
+
+/// Recipient allow-list header, followed by the sorted keys of the allowed
+/// recipient token account owners. Maintained by its authority and shared by
+/// any number of escrows, capacity is set by the account size.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AllowList {
+    /// Allow-list is initialized
+    pub is_initialized: bool,
+    /// Compliance authority, able to add and remove owners
+    pub authority: Pubkey,
+    /// Number of owners in the list
+    pub count: u64,
+}
+
+impl AllowList {
+    /// Size of the allow-list account holding `capacity` owners
+    pub fn account_len(capacity: usize) -> usize {
+        AllowList::LEN + capacity * 32
+    }
+
+    /// Number of owners the allow-list account data can hold
+    pub fn capacity(data: &[u8]) -> usize {
+        data.len().saturating_sub(AllowList::LEN) / 32
+    }
+
+    /// Reads owner from the allow-list account data
+    pub fn unpack_owner(data: &[u8], index: usize) -> Result<Pubkey, ProgramError> {
+        let offset = AllowList::LEN + index * 32;
+        data.get(offset..offset + 32)
+            .map(Pubkey::new)
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    /// Looks up the owner, returns its index or the index to insert it at
+    fn search(&self, data: &[u8], owner: &Pubkey) -> Result<Result<usize, usize>, ProgramError> {
+        if self.count > Self::capacity(data) as u64 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (mut low, mut high) = (0, self.count as usize);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            match Self::unpack_owner(data, middle)?.cmp(owner) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return Ok(Ok(middle)),
+            }
+        }
+        Ok(Err(low))
+    }
+
+    /// Checks if the owner is in the allow-list
+    pub fn contains(&self, data: &[u8], owner: &Pubkey) -> Result<bool, ProgramError> {
+        Ok(self.search(data, owner)?.is_ok())
+    }
+
+    /// Adds owner keeping the list sorted, returns false if it is already there
+    pub fn insert(&mut self, data: &mut [u8], owner: &Pubkey) -> Result<bool, ProgramError> {
+        let index = match self.search(data, owner)? {
+            Ok(_) => return Ok(false),
+            Err(index) => index,
+        };
+        if self.count as usize >= Self::capacity(data) {
+            return Err(EscrowError::AllowListFull.into());
+        }
+        let offset = AllowList::LEN + index * 32;
+        let end = AllowList::LEN + self.count as usize * 32;
+        data.copy_within(offset..end, offset + 32);
+        data[offset..offset + 32].copy_from_slice(owner.as_ref());
+        self.count += 1;
+        Ok(true)
+    }
+
+    /// Removes owner, returns false if it is not in the list
+    pub fn remove(&mut self, data: &mut [u8], owner: &Pubkey) -> Result<bool, ProgramError> {
+        let index = match self.search(data, owner)? {
+            Ok(index) => index,
+            Err(_) => return Ok(false),
+        };
+        let offset = AllowList::LEN + index * 32;
+        let end = AllowList::LEN + self.count as usize * 32;
+        data.copy_within(offset + 32..end, offset);
+        data[end - 32..end].copy_from_slice(&[0; 32]);
+        self.count -= 1;
+        Ok(true)
+    }
+}
+
+impl Sealed for AllowList {}
+impl IsInitialized for AllowList {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for AllowList {
+    const LEN: usize = 41;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, AllowList::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized_dst, authority_dst, count_dst) = mut_array_refs![output, 1, 32, 8];
+        is_initialized_dst[0] = self.is_initialized as u8;
+        authority_dst.copy_from_slice(self.authority.as_ref());
+        *count_dst = self.count.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = input
+            .get(..AllowList::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let input = array_ref![input, 0, AllowList::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized_src, authority_src, count_src) = array_refs![input, 1, 32, 8];
+        Ok(AllowList {
+            is_initialized: unpack_bool(is_initialized_src)?,
+            authority: Pubkey::new_from_array(*authority_src),
+            count: u64::from_le_bytes(*count_src),
+        })
+    }
+}
+
+/// Additional token vault of the escrow, one per escrow mint at the program
+/// address derived from `VAULT_SEED`, the escrow key and the mint key. Keeps
+/// its own payout totals, the escrow is paid once all its vaults are paid.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EscrowVault {
+    /// Vault is initialized
+    pub is_initialized: bool,
+    /// Vault address bump seed
+    pub bump_seed: u8,
+    /// Escrow account
+    pub escrow: Pubkey,
+    /// Vault position in the escrow, vaults are refunded in this order
+    pub index: u8,
+    /// Mint for the token held by the vault
+    pub token_mint: Pubkey,
+    /// Account to hold tokens for sendout, its owner should be escrow contract authority
+    pub token_account: Pubkey,
+    /// Account for the canceler to receive back tokens
+    pub canceler_token_account: Pubkey,
+    /// Total amount of tokens to pay out
+    pub total_amount: u64,
+    /// Amount in tokens already sent
+    pub sent_amount: u64,
+    /// Total amount is set by `StoreVaultResults`
+    pub results_stored: bool,
+}
+
+impl EscrowVault {
+    /// Vault results are stored and the total amount is sent
+    pub fn is_paid(&self) -> bool {
+        self.results_stored && self.sent_amount >= self.total_amount
+    }
+}
+
+impl Sealed for EscrowVault {}
+impl IsInitialized for EscrowVault {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for EscrowVault {
+    const LEN: usize = 148;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, EscrowVault::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized_dst,
+            bump_seed_dst,
+            escrow_dst,
+            index_dst,
+            token_mint_dst,
+            token_account_dst,
+            canceler_token_account_dst,
+            total_amount_dst,
+            sent_amount_dst,
+            results_stored_dst,
+        ) = mut_array_refs![output, 1, 1, 32, 1, 32, 32, 32, 8, 8, 1];
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        escrow_dst.copy_from_slice(self.escrow.as_ref());
+        index_dst[0] = self.index;
+        token_mint_dst.copy_from_slice(self.token_mint.as_ref());
+        token_account_dst.copy_from_slice(self.token_account.as_ref());
+        canceler_token_account_dst.copy_from_slice(self.canceler_token_account.as_ref());
+        *total_amount_dst = self.total_amount.to_le_bytes();
+        *sent_amount_dst = self.sent_amount.to_le_bytes();
+        results_stored_dst[0] = self.results_stored as u8;
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = input
+            .get(..EscrowVault::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let input = array_ref![input, 0, EscrowVault::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized_src,
+            bump_seed_src,
+            escrow_src,
+            index_src,
+            token_mint_src,
+            token_account_src,
+            canceler_token_account_src,
+            total_amount_src,
+            sent_amount_src,
+            results_stored_src,
+        ) = array_refs![input, 1, 1, 32, 1, 32, 32, 32, 8, 8, 1];
+        Ok(EscrowVault {
+            is_initialized: unpack_bool(is_initialized_src)?,
+            bump_seed: bump_seed_src[0],
+            escrow: Pubkey::new_from_array(*escrow_src),
+            index: index_src[0],
+            token_mint: Pubkey::new_from_array(*token_mint_src),
+            token_account: Pubkey::new_from_array(*token_account_src),
+            canceler_token_account: Pubkey::new_from_array(*canceler_token_account_src),
+            total_amount: u64::from_le_bytes(*total_amount_src),
+            sent_amount: u64::from_le_bytes(*sent_amount_src),
+            results_stored: unpack_bool(results_stored_src)?,
+        })
+    }
+}
+
+/// Escrow template, keeps the configuration shared by repeat jobs so that
+/// `InitializeFromTemplate` only needs the job manifest. Escrows can only be
+/// created from the template by its authority.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EscrowTemplate {
+    /// Template is initialized
+    pub is_initialized: bool,
+    /// Template authority, launcher of the escrows created from the template
+    pub authority: Pubkey,
+    /// Mint for the token handled by the escrows
+    pub token_mint: Pubkey,
+    /// Escrow canceler
+    pub canceler: Pubkey,
+    /// Account for the canceler to receive back tokens
+    pub canceler_token_account: Pubkey,
+    /// Escrow guardian, able to pause the escrows
+    pub guardian: Pubkey,
+    /// Escrow duration in seconds
+    pub duration: u64,
+    /// Reputation oracle
+    pub reputation_oracle: Pubkey,
+    /// Reputation oracle's token account to receive fees
+    pub reputation_oracle_token_account: Pubkey,
+    /// Reputation oracle fee in percents
+    pub reputation_oracle_stake: u8,
+    /// Recording oracle
+    pub recording_oracle: Pubkey,
+    /// Recording oracle's token account to receive fees
+    pub recording_oracle_token_account: Pubkey,
+    /// Recording oracle fee in percents
+    pub recording_oracle_stake: u8,
+    /// Maximum amount of tokens a single recipient can be paid in total, zero for no limit
+    pub max_payout_per_recipient: u64,
+    /// Minimum amount of tokens in a single payout
+    pub min_payout: u64,
+    /// Fixed amount of tokens paid to the oracles on cancel
+    pub cancellation_fee: u64,
+    /// Percent of the remaining balance paid to the oracles on cancel
+    pub cancellation_fee_percent: u8,
+    /// Period in seconds after setup during which cancel is blocked
+    pub cancel_notice: u64,
+    /// Recipient allow-list of the escrows
+    pub allow_list: COption<Pubkey>,
+}
+
+impl Sealed for EscrowTemplate {}
+impl IsInitialized for EscrowTemplate {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for EscrowTemplate {
+    const LEN: usize = 368;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, EscrowTemplate::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized_dst,
+            authority_dst,
+            token_mint_dst,
+            canceler_dst,
+            canceler_token_account_dst,
+            guardian_dst,
+            duration_dst,
+            reputation_oracle_dst,
+            reputation_oracle_token_account_dst,
+            reputation_oracle_stake_dst,
+            recording_oracle_dst,
+            recording_oracle_token_account_dst,
+            recording_oracle_stake_dst,
+            max_payout_per_recipient_dst,
+            min_payout_dst,
+            cancellation_fee_dst,
+            cancellation_fee_percent_dst,
+            cancel_notice_dst,
+            allow_list_dst,
+        ) = mut_array_refs![
+            output, 1, 32, 32, 32, 32, 32, 8, 32, 32, 1, 32, 32, 1, 8, 8, 8, 1, 8, 36
+        ];
+        is_initialized_dst[0] = self.is_initialized as u8;
+        authority_dst.copy_from_slice(self.authority.as_ref());
+        token_mint_dst.copy_from_slice(self.token_mint.as_ref());
+        canceler_dst.copy_from_slice(self.canceler.as_ref());
+        canceler_token_account_dst.copy_from_slice(self.canceler_token_account.as_ref());
+        guardian_dst.copy_from_slice(self.guardian.as_ref());
+        *duration_dst = self.duration.to_le_bytes();
+        reputation_oracle_dst.copy_from_slice(self.reputation_oracle.as_ref());
+        reputation_oracle_token_account_dst
+            .copy_from_slice(self.reputation_oracle_token_account.as_ref());
+        reputation_oracle_stake_dst[0] = self.reputation_oracle_stake;
+        recording_oracle_dst.copy_from_slice(self.recording_oracle.as_ref());
+        recording_oracle_token_account_dst
+            .copy_from_slice(self.recording_oracle_token_account.as_ref());
+        recording_oracle_stake_dst[0] = self.recording_oracle_stake;
+        *max_payout_per_recipient_dst = self.max_payout_per_recipient.to_le_bytes();
+        *min_payout_dst = self.min_payout.to_le_bytes();
+        *cancellation_fee_dst = self.cancellation_fee.to_le_bytes();
+        cancellation_fee_percent_dst[0] = self.cancellation_fee_percent;
+        *cancel_notice_dst = self.cancel_notice.to_le_bytes();
+        pack_coption_key(&self.allow_list, allow_list_dst);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = input
+            .get(..EscrowTemplate::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let input = array_ref![input, 0, EscrowTemplate::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized_src,
+            authority_src,
+            token_mint_src,
+            canceler_src,
+            canceler_token_account_src,
+            guardian_src,
+            duration_src,
+            reputation_oracle_src,
+            reputation_oracle_token_account_src,
+            reputation_oracle_stake_src,
+            recording_oracle_src,
+            recording_oracle_token_account_src,
+            recording_oracle_stake_src,
+            max_payout_per_recipient_src,
+            min_payout_src,
+            cancellation_fee_src,
+            cancellation_fee_percent_src,
+            cancel_notice_src,
+            allow_list_src,
+        ) = array_refs![input, 1, 32, 32, 32, 32, 32, 8, 32, 32, 1, 32, 32, 1, 8, 8, 8, 1, 8, 36];
+        Ok(EscrowTemplate {
+            is_initialized: unpack_bool(is_initialized_src)?,
+            authority: Pubkey::new_from_array(*authority_src),
+            token_mint: Pubkey::new_from_array(*token_mint_src),
+            canceler: Pubkey::new_from_array(*canceler_src),
+            canceler_token_account: Pubkey::new_from_array(*canceler_token_account_src),
+            guardian: Pubkey::new_from_array(*guardian_src),
+            duration: u64::from_le_bytes(*duration_src),
+            reputation_oracle: Pubkey::new_from_array(*reputation_oracle_src),
+            reputation_oracle_token_account: Pubkey::new_from_array(
+                *reputation_oracle_token_account_src,
+            ),
+            reputation_oracle_stake: reputation_oracle_stake_src[0],
+            recording_oracle: Pubkey::new_from_array(*recording_oracle_src),
+            recording_oracle_token_account: Pubkey::new_from_array(
+                *recording_oracle_token_account_src,
+            ),
+            recording_oracle_stake: recording_oracle_stake_src[0],
+            max_payout_per_recipient: u64::from_le_bytes(*max_payout_per_recipient_src),
+            min_payout: u64::from_le_bytes(*min_payout_src),
+            cancellation_fee: u64::from_le_bytes(*cancellation_fee_src),
+            cancellation_fee_percent: cancellation_fee_percent_src[0],
+            cancel_notice: u64::from_le_bytes(*cancel_notice_src),
+            allow_list: unpack_coption_key(allow_list_src)?,
         })
     }
 }
@@ -321,6 +1512,13 @@ fn pack_coption_key(src: &COption<Pubkey>, dst: &mut [u8; 36]) {
         }
     }
 }
+fn unpack_bool(src: &[u8; 1]) -> Result<bool, ProgramError> {
+    match src[0] {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
 fn unpack_coption_key(src: &[u8; 36]) -> Result<COption<Pubkey>, ProgramError> {
     let (tag, body) = array_refs![src, 4, 32];
     match *tag {
@@ -360,10 +1558,334 @@ mod test {
             manifest_hash: DataHash::new_from_array([11; 20]),
             final_results_url: DataUrl::new_from_array([12; URL_LEN]),
             final_results_hash: DataHash::new_from_array([13; 20]),
+            payout_nonce: 42,
+            permit_signer: [17; ETH_ADDRESS_LEN],
+            guardian: Pubkey::new_from_array([14; 32]),
+            paused: true,
+            results_log_head: Hash::new_from_array([15; 32]),
+            results_log_count: 3,
+            max_payout_per_recipient: 5000000,
+            min_payout: 1000,
+            allow_list: COption::Some(Pubkey::new_from_array([16; 32])),
+            vault_count: 2,
+            unpaid_vaults: 1,
+            cancellation_fee: 12,
+            cancellation_fee_percent: 0,
+            cancel_notice_end: 1_700_000_000,
         };
-        let mut packed_obj: [u8; 4484] = [0; 4484];
+        let mut packed_obj = vec![0; Escrow::LEN];
         Escrow::pack(obj, &mut packed_obj).unwrap();
         let unpacked_obj = Escrow::unpack(&packed_obj).unwrap();
         assert_eq!(unpacked_obj, obj);
+
+        // Short input is rejected instead of panicking
+        assert_eq!(
+            Escrow::unpack_from_slice(&packed_obj[..Escrow::LEN - 1]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_escrow_offsets() {
+        let obj = Escrow {
+            state: EscrowState::Partial,
+            token_mint: Pubkey::new_from_array([1; 32]),
+            launcher: Pubkey::new_from_array([2; 32]),
+            canceler: Pubkey::new_from_array([3; 32]),
+            ..Escrow::default()
+        };
+        let mut packed_obj = vec![0; Escrow::LEN];
+        Escrow::pack(obj, &mut packed_obj).unwrap();
+        assert_eq!(
+            packed_obj[ESCROW_TOKEN_MINT_OFFSET..ESCROW_TOKEN_MINT_OFFSET + 32],
+            obj.token_mint.to_bytes()
+        );
+        assert_eq!(
+            packed_obj[ESCROW_LAUNCHER_OFFSET..ESCROW_LAUNCHER_OFFSET + 32],
+            obj.launcher.to_bytes()
+        );
+        assert_eq!(
+            packed_obj[ESCROW_CANCELER_OFFSET..ESCROW_CANCELER_OFFSET + 32],
+            obj.canceler.to_bytes()
+        );
+        assert_eq!(packed_obj[ESCROW_STATE_OFFSET], EscrowState::Partial as u8);
+
+        let vault = EscrowVault {
+            is_initialized: true,
+            bump_seed: 255,
+            escrow: Pubkey::new_from_array([4; 32]),
+            index: 3,
+            ..EscrowVault::default()
+        };
+        let mut packed_vault = vec![0; EscrowVault::LEN];
+        EscrowVault::pack(vault, &mut packed_vault).unwrap();
+        assert_eq!(
+            packed_vault[ESCROW_VAULT_ESCROW_OFFSET..ESCROW_VAULT_ESCROW_OFFSET + 32],
+            vault.escrow.to_bytes()
+        );
+    }
+
+    #[test]
+    fn test_cancellation_fee_amount() {
+        let mut obj = Escrow::default();
+        assert!(!obj.has_cancellation_fee());
+        assert_eq!(obj.cancellation_fee_amount(1000), 0);
+
+        // Fixed fee is capped by the balance
+        obj.cancellation_fee = 300;
+        assert!(obj.has_cancellation_fee());
+        assert_eq!(obj.cancellation_fee_amount(1000), 300);
+        assert_eq!(obj.cancellation_fee_amount(200), 200);
+
+        obj.cancellation_fee = 0;
+        obj.cancellation_fee_percent = 15;
+        assert!(obj.has_cancellation_fee());
+        assert_eq!(obj.cancellation_fee_amount(1000), 150);
+        assert_eq!(obj.cancellation_fee_amount(999), 149);
+    }
+
+    #[test]
+    fn test_config_packing() {
+        let mut obj = EscrowConfig {
+            is_initialized: true,
+            bump_seed: 251,
+            admin: Pubkey::new_from_array([1; 32]),
+            paused: true,
+            max_oracle_stake: 30,
+            min_duration: 3600,
+            max_duration: 2592000,
+            stake_mint: COption::Some(Pubkey::new_from_array([5; 32])),
+            min_oracle_stake: 1000,
+            stake_escrow_threshold: 1000000,
+            arbiter: COption::Some(Pubkey::new_from_array([6; 32])),
+            ..Default::default()
+        };
+        obj.set_allowed_mints(&[
+            Pubkey::new_from_array([2; 32]),
+            Pubkey::new_from_array([3; 32]),
+        ])
+        .unwrap();
+        let mut packed_obj: [u8; EscrowConfig::LEN] = [0; EscrowConfig::LEN];
+        EscrowConfig::pack(obj, &mut packed_obj).unwrap();
+        let unpacked_obj = EscrowConfig::unpack(&packed_obj).unwrap();
+        assert_eq!(unpacked_obj, obj);
+        assert_eq!(unpacked_obj.allowed_mints().len(), 2);
+        assert!(unpacked_obj.is_mint_allowed(&Pubkey::new_from_array([3; 32])));
+        assert!(!unpacked_obj.is_mint_allowed(&Pubkey::new_from_array([4; 32])));
+
+        obj.set_allowed_mints(&[]).unwrap();
+        assert!(obj.is_mint_allowed(&Pubkey::new_from_array([4; 32])));
+        assert!(obj
+            .set_allowed_mints(&[Pubkey::default(); MAX_ALLOWED_MINTS + 1])
+            .is_err());
+
+        assert!(!obj.is_oracle_stake_required(999999));
+        assert!(obj.is_oracle_stake_required(1000000));
+        assert!(obj.is_slasher(&Pubkey::new_from_array([1; 32])));
+        assert!(obj.is_slasher(&Pubkey::new_from_array([6; 32])));
+        assert!(!obj.is_slasher(&Pubkey::new_from_array([7; 32])));
+        obj.stake_mint = COption::None;
+        assert!(!obj.is_oracle_stake_required(1000000));
+    }
+
+    #[test]
+    fn test_oracle_stake_packing() {
+        let obj = OracleStake {
+            is_initialized: true,
+            bump_seed: 252,
+            oracle: Pubkey::new_from_array([1; 32]),
+            vault: Pubkey::new_from_array([2; 32]),
+            amount: 5000,
+            locked_until: 1606402240,
+        };
+        let mut packed_obj: [u8; OracleStake::LEN] = [0; OracleStake::LEN];
+        OracleStake::pack(obj, &mut packed_obj).unwrap();
+        let unpacked_obj = OracleStake::unpack(&packed_obj).unwrap();
+        assert_eq!(unpacked_obj, obj);
+    }
+
+    #[test]
+    fn test_reputation_packing() {
+        let obj = Reputation {
+            is_initialized: true,
+            bump_seed: 253,
+            worker: Pubkey::new_from_array([1; 32]),
+            score: -42,
+            jobs_completed: 7,
+            last_escrow: Pubkey::new_from_array([2; 32]),
+        };
+        let mut packed_obj: [u8; Reputation::LEN] = [0; Reputation::LEN];
+        Reputation::pack(obj, &mut packed_obj).unwrap();
+        let unpacked_obj = Reputation::unpack(&packed_obj).unwrap();
+        assert_eq!(unpacked_obj, obj);
+    }
+
+    #[test]
+    fn test_results_log_packing() {
+        let obj = ResultsLog {
+            is_initialized: true,
+            bump_seed: 254,
+            escrow: Pubkey::new_from_array([1; 32]),
+            head: Hash::new_from_array([2; 32]),
+            count: 2,
+        };
+        let mut packed_obj: [u8; ResultsLog::LEN] = [0; ResultsLog::LEN];
+        ResultsLog::pack(obj, &mut packed_obj).unwrap();
+        let unpacked_obj = ResultsLog::unpack(&packed_obj).unwrap();
+        assert_eq!(unpacked_obj, obj);
+
+        let page = ResultsLogPage {
+            is_initialized: true,
+            bump_seed: 253,
+            escrow: Pubkey::new_from_array([1; 32]),
+            page: 1,
+        };
+        let mut packed_page: [u8; ResultsLogPage::LEN] = [0; ResultsLogPage::LEN];
+        ResultsLogPage::pack(page, &mut packed_page).unwrap();
+        let unpacked_page = ResultsLogPage::unpack(&packed_page).unwrap();
+        assert_eq!(unpacked_page, page);
+
+        let entry = ResultsLogEntry {
+            url: DataUrl::new_from_array([3; URL_LEN]),
+            hash: DataHash::new_from_array([4; 20]),
+            timestamp: 1606402240,
+            signer: Pubkey::new_from_array([5; 32]),
+        };
+        let index = RESULTS_LOG_PAGE_ENTRIES + 1;
+        let mut data = vec![0; ResultsLogPage::ACCOUNT_LEN];
+        let offset = ResultsLogPage::entry_offset(index);
+        ResultsLogEntry::pack(entry, &mut data[offset..offset + ResultsLogEntry::LEN]).unwrap();
+        assert_eq!(ResultsLogPage::unpack_entry(&data, index).unwrap(), entry);
+
+        // Every page holds the same number of entries
+        assert_eq!(ResultsLog::page(index as u64), 1);
+        assert_eq!(ResultsLog::page(RESULTS_LOG_PAGE_ENTRIES as u64 - 1), 0);
+        assert_eq!(obj.page_count(), 1);
+        for (count, page_count) in &[
+            (0, 0),
+            (RESULTS_LOG_PAGE_ENTRIES, 1),
+            (RESULTS_LOG_PAGE_ENTRIES + 1, 2),
+        ] {
+            let log = ResultsLog {
+                count: *count as u64,
+                ..obj
+            };
+            assert_eq!(log.page_count(), *page_count);
+        }
+
+        // Hash chain depends on the previous head
+        let head = entry.next_head(&Hash::default());
+        assert_ne!(head, Hash::default());
+        assert_ne!(entry.next_head(&head), head);
+    }
+
+    #[test]
+    fn test_recipient_payout_packing() {
+        let obj = RecipientPayout {
+            is_initialized: true,
+            bump_seed: 251,
+            escrow: Pubkey::new_from_array([1; 32]),
+            owner: Pubkey::new_from_array([2; 32]),
+            amount: 5000,
+        };
+        let mut packed_obj: [u8; RecipientPayout::LEN] = [0; RecipientPayout::LEN];
+        RecipientPayout::pack(obj, &mut packed_obj).unwrap();
+        let unpacked_obj = RecipientPayout::unpack(&packed_obj).unwrap();
+        assert_eq!(unpacked_obj, obj);
+    }
+
+    #[test]
+    fn test_allow_list_packing() {
+        let obj = AllowList {
+            is_initialized: true,
+            authority: Pubkey::new_from_array([1; 32]),
+            count: 0,
+        };
+        let mut packed_obj: [u8; AllowList::LEN] = [0; AllowList::LEN];
+        AllowList::pack(obj, &mut packed_obj).unwrap();
+        let unpacked_obj = AllowList::unpack(&packed_obj).unwrap();
+        assert_eq!(unpacked_obj, obj);
+
+        // Owners are kept sorted whatever the insertion order
+        let mut list = obj;
+        let mut data = vec![0; AllowList::account_len(3)];
+        assert_eq!(AllowList::capacity(&data), 3);
+        let owners: Vec<Pubkey> = [5, 2, 9]
+            .iter()
+            .map(|byte| Pubkey::new_from_array([*byte; 32]))
+            .collect();
+        for owner in &owners {
+            assert!(list.insert(&mut data, owner).unwrap());
+        }
+        assert!(!list.insert(&mut data, &owners[0]).unwrap());
+        assert_eq!(list.count, 3);
+        assert_eq!(AllowList::unpack_owner(&data, 0).unwrap(), owners[1]);
+        assert_eq!(AllowList::unpack_owner(&data, 2).unwrap(), owners[2]);
+        assert_eq!(
+            list.insert(&mut data, &Pubkey::new_from_array([7; 32])),
+            Err(EscrowError::AllowListFull.into())
+        );
+
+        assert!(list.remove(&mut data, &owners[0]).unwrap());
+        assert!(!list.remove(&mut data, &owners[0]).unwrap());
+        assert_eq!(list.count, 2);
+        assert!(!list.contains(&data, &owners[0]).unwrap());
+        assert!(list.contains(&data, &owners[1]).unwrap());
+        assert!(list.contains(&data, &owners[2]).unwrap());
+    }
+
+    #[test]
+    fn test_vault_packing() {
+        let mut obj = EscrowVault {
+            is_initialized: true,
+            bump_seed: 255,
+            escrow: Pubkey::new_from_array([1; 32]),
+            index: 3,
+            token_mint: Pubkey::new_from_array([2; 32]),
+            token_account: Pubkey::new_from_array([3; 32]),
+            canceler_token_account: Pubkey::new_from_array([4; 32]),
+            total_amount: 5000,
+            sent_amount: 2000,
+            results_stored: true,
+        };
+        let mut packed_obj: [u8; EscrowVault::LEN] = [0; EscrowVault::LEN];
+        EscrowVault::pack(obj, &mut packed_obj).unwrap();
+        let unpacked_obj = EscrowVault::unpack(&packed_obj).unwrap();
+        assert_eq!(unpacked_obj, obj);
+
+        assert!(!obj.is_paid());
+        obj.sent_amount = 5000;
+        assert!(obj.is_paid());
+        obj.results_stored = false;
+        assert!(!obj.is_paid());
+    }
+
+    #[test]
+    fn test_template_packing() {
+        let obj = EscrowTemplate {
+            is_initialized: true,
+            authority: Pubkey::new_from_array([1; 32]),
+            token_mint: Pubkey::new_from_array([2; 32]),
+            canceler: Pubkey::new_from_array([3; 32]),
+            canceler_token_account: Pubkey::new_from_array([4; 32]),
+            guardian: Pubkey::new_from_array([5; 32]),
+            duration: 86400,
+            reputation_oracle: Pubkey::new_from_array([6; 32]),
+            reputation_oracle_token_account: Pubkey::new_from_array([7; 32]),
+            reputation_oracle_stake: 10,
+            recording_oracle: Pubkey::new_from_array([8; 32]),
+            recording_oracle_token_account: Pubkey::new_from_array([9; 32]),
+            recording_oracle_stake: 15,
+            max_payout_per_recipient: 5000,
+            min_payout: 10,
+            cancellation_fee: 0,
+            cancellation_fee_percent: 20,
+            cancel_notice: 3600,
+            allow_list: COption::Some(Pubkey::new_from_array([10; 32])),
+        };
+        let mut packed_obj: [u8; EscrowTemplate::LEN] = [0; EscrowTemplate::LEN];
+        EscrowTemplate::pack(obj, &mut packed_obj).unwrap();
+        let unpacked_obj = EscrowTemplate::unpack(&packed_obj).unwrap();
+        assert_eq!(unpacked_obj, obj);
     }
 }
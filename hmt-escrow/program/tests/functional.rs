@@ -1,16 +1,34 @@
 #![cfg(feature = "test-bpf")]
 
+use ::secp256k1::SecretKey;
+use hmt_escrow::secp256k1::{ETH_ADDRESS_LEN, ETH_ADDRESS_OFFSET};
 use hmt_escrow::state::DataHash;
 use hmt_escrow::state::DataUrl;
 use hmt_escrow::*;
-use solana_program::{hash::Hash, program_pack::Pack, pubkey::Pubkey, system_instruction};
+use solana_program::{
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    hash::Hash,
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_instruction,
+};
 use solana_program_test::*;
 use solana_sdk::{
-    signature::{Keypair, Signer},
-    transaction::Transaction,
+    account::Account,
+    instruction::InstructionError,
+    secp256k1::new_secp256k1_instruction,
+    signature::{keypair_from_seed, Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    transport::TransportError,
 };
 use std::str::FromStr;
 const DECIMALS: u8 = 9;
+const MAX_ORACLE_STAKE: u8 = 50;
+const MIN_DURATION: u64 = 60;
+const MAX_DURATION: u64 = 2592000;
 
 fn program_test() -> ProgramTest {
     let mut pc = ProgramTest::new(
@@ -26,9 +44,34 @@ fn program_test() -> ProgramTest {
         processor!(spl_token::processor::Processor::process),
     );
 
+    // Program data of the upgradeable escrow program, only its upgrade
+    // authority can initialize the config
+    pc.add_account(
+        program_data_address(),
+        Account {
+            lamports: 1_000_000_000,
+            data: bincode::serialize(&UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(upgrade_authority().pubkey()),
+            })
+            .unwrap(),
+            owner: bpf_loader_upgradeable::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
     pc
 }
 
+fn upgrade_authority() -> Keypair {
+    keypair_from_seed(&[7; 32]).unwrap()
+}
+
+fn program_data_address() -> Pubkey {
+    processor::Processor::find_program_data_address(&id())
+}
+
 async fn create_mint(
     banks_client: &mut BanksClient,
     payer: &Keypair,
@@ -97,647 +140,3975 @@ async fn create_token_account(
     banks_client.process_transaction(transaction).await.unwrap();
 }
 
-async fn create_escrow(
+async fn initialize_config(
     banks_client: &mut BanksClient,
     payer: &Keypair,
     recent_blockhash: &Hash,
-    escrow_account: &Keypair,
-    escrow_token_account: &Keypair,
-    launcher: &Pubkey,
-    canceler: &Pubkey,
-    canceler_token: &Keypair,
-    token_mint: &Pubkey,
-    duration: &u64,
+    allowed_mints: &[Pubkey],
 ) {
-    let rent = banks_client.get_rent().await.unwrap();
-    let account_rent = rent.minimum_balance(state::Escrow::LEN);
-
     let mut transaction = Transaction::new_with_payer(
-        &[
-            system_instruction::create_account(
-                &payer.pubkey(),
-                &escrow_account.pubkey(),
-                account_rent,
-                hmt_escrow::state::Escrow::LEN as u64,
-                &id(),
-            ),
-            instruction::initialize(
-                &id(),
-                &escrow_account.pubkey(),
-                token_mint,
-                &escrow_token_account.pubkey(),
-                &launcher,
-                &canceler,
-                &canceler_token.pubkey(),
-                *duration,
-            )
-            .unwrap(),
-        ],
+        &[instruction::initialize_config(
+            &id(),
+            &config_address(),
+            &payer.pubkey(),
+            &program_data_address(),
+            &upgrade_authority().pubkey(),
+            MAX_ORACLE_STAKE,
+            MIN_DURATION,
+            MAX_DURATION,
+            allowed_mints,
+        )
+        .unwrap()],
         Some(&payer.pubkey()),
     );
-    transaction.sign(&[payer, escrow_account], *recent_blockhash);
+    transaction.sign(&[payer, &upgrade_authority()], *recent_blockhash);
     banks_client.process_transaction(transaction).await.unwrap();
 }
 
-async fn setup_escrow(
+async fn update_config(
     banks_client: &mut BanksClient,
     payer: &Keypair,
     recent_blockhash: &Hash,
-    escrow_account: &Keypair,
-    trust_handler: &Keypair,
-    reputation_oracle: &Keypair,
-    reputation_oracle_token: &Keypair,
-    reputation_oracle_stake: &u8,
-    recording_oracle: &Keypair,
-    recording_oracle_token: &Keypair,
-    recording_oracle_stake: &u8,
-    manifest_url: &DataUrl,
-    manifest_hash: &DataHash,
+    paused: bool,
+    allowed_mints: &[Pubkey],
 ) {
     let mut transaction = Transaction::new_with_payer(
-        &[instruction::setup(
+        &[instruction::update_config(
             &id(),
-            &escrow_account.pubkey(),
-            &trust_handler.pubkey(),
-            &reputation_oracle.pubkey(),
-            &reputation_oracle_token.pubkey(),
-            *reputation_oracle_stake,
-            &recording_oracle.pubkey(),
-            &recording_oracle_token.pubkey(),
-            *recording_oracle_stake,
-            manifest_url,
-            manifest_hash,
+            &config_address(),
+            &payer.pubkey(),
+            &payer.pubkey(),
+            paused,
+            MAX_ORACLE_STAKE,
+            MIN_DURATION,
+            MAX_DURATION,
+            allowed_mints,
         )
         .unwrap()],
         Some(&payer.pubkey()),
     );
-    transaction.sign(&[payer, trust_handler], *recent_blockhash);
+    transaction.sign(&[payer], *recent_blockhash);
     banks_client.process_transaction(transaction).await.unwrap();
 }
 
-async fn store_results(
+fn config_address() -> Pubkey {
+    processor::Processor::find_config_address(&id()).0
+}
+
+fn oracle_stake_address(oracle: &Pubkey) -> Pubkey {
+    processor::Processor::find_oracle_stake_address(&id(), oracle).0
+}
+
+async fn update_staking_config(
     banks_client: &mut BanksClient,
     payer: &Keypair,
     recent_blockhash: &Hash,
-    escrow_account: &Keypair,
-    trust_handler: &Keypair,
-    total_amount: &f64,
-    total_recipients: &u64,
-    final_results_url: &DataUrl,
-    final_results_hash: &DataHash,
+    stake_mint: &Pubkey,
+    min_oracle_stake: f64,
+    stake_escrow_threshold: f64,
+    arbiter: &Pubkey,
 ) {
     let mut transaction = Transaction::new_with_payer(
-        &[instruction::store_results(
+        &[instruction::update_staking_config(
             &id(),
-            &escrow_account.pubkey(),
-            &trust_handler.pubkey(),
-            spl_token::ui_amount_to_amount(*total_amount, DECIMALS),
-            *total_recipients,
-            &final_results_url,
-            final_results_hash,
+            &config_address(),
+            &payer.pubkey(),
+            COption::Some(*stake_mint),
+            spl_token::ui_amount_to_amount(min_oracle_stake, DECIMALS),
+            spl_token::ui_amount_to_amount(stake_escrow_threshold, DECIMALS),
+            COption::Some(*arbiter),
         )
         .unwrap()],
         Some(&payer.pubkey()),
     );
-    transaction.sign(&[payer, trust_handler], *recent_blockhash);
+    transaction.sign(&[payer], *recent_blockhash);
     banks_client.process_transaction(transaction).await.unwrap();
 }
 
-async fn payout(
+async fn create_oracle_stake(
     banks_client: &mut BanksClient,
     payer: &Keypair,
     recent_blockhash: &Hash,
-    escrow_account: &Keypair,
-    trust_handler: &Keypair,
-    escrow_token_account: &Keypair,
-    escrow_authority: &Pubkey,
-    recipient_token_account: &Keypair,
-    reputation_oracle_token_account: &Keypair,
-    recording_oracle_token_account: &Keypair,
-    amount: &f64,
+    oracle: &Keypair,
+    vault: &Keypair,
+    stake_mint: &Pubkey,
 ) {
+    let oracle_stake = oracle_stake_address(&oracle.pubkey());
+    create_token_account(
+        banks_client,
+        payer,
+        recent_blockhash,
+        vault,
+        stake_mint,
+        &oracle_stake,
+    )
+    .await;
+
+    let rent = banks_client.get_rent().await.unwrap();
     let mut transaction = Transaction::new_with_payer(
-        &[instruction::payout(
-            &id(),
-            &escrow_account.pubkey(),
-            &trust_handler.pubkey(),
-            &escrow_token_account.pubkey(),
-            &escrow_authority,
-            &recipient_token_account.pubkey(),
-            &reputation_oracle_token_account.pubkey(),
-            &recording_oracle_token_account.pubkey(),
-            &spl_token::id(),
-            spl_token::ui_amount_to_amount(*amount, DECIMALS),
-        )
-        .unwrap()],
+        &[
+            system_instruction::transfer(
+                &payer.pubkey(),
+                &oracle.pubkey(),
+                rent.minimum_balance(state::OracleStake::LEN),
+            ),
+            instruction::initialize_oracle_stake(
+                &id(),
+                &oracle_stake,
+                &oracle.pubkey(),
+                &vault.pubkey(),
+                &config_address(),
+            )
+            .unwrap(),
+        ],
         Some(&payer.pubkey()),
     );
-    transaction.sign(&[payer, trust_handler], *recent_blockhash);
+    transaction.sign(&[payer, oracle], *recent_blockhash);
     banks_client.process_transaction(transaction).await.unwrap();
 }
 
-async fn cancel(
+async fn deposit_oracle_stake(
     banks_client: &mut BanksClient,
     payer: &Keypair,
     recent_blockhash: &Hash,
-    escrow_account: &Keypair,
-    trust_handler: &Keypair,
-    escrow_token_account: &Keypair,
-    escrow_authority: &Pubkey,
-    canceler_token_account: &Keypair,
+    oracle: &Keypair,
+    vault: &Keypair,
+    source_token_account: &Keypair,
+    amount: f64,
 ) {
     let mut transaction = Transaction::new_with_payer(
-        &[instruction::cancel(
+        &[instruction::deposit_oracle_stake(
             &id(),
-            &escrow_account.pubkey(),
-            &trust_handler.pubkey(),
-            &escrow_token_account.pubkey(),
-            &escrow_authority,
-            &canceler_token_account.pubkey(),
+            &oracle_stake_address(&oracle.pubkey()),
+            &vault.pubkey(),
+            &source_token_account.pubkey(),
+            &oracle.pubkey(),
             &spl_token::id(),
+            spl_token::ui_amount_to_amount(amount, DECIMALS),
         )
         .unwrap()],
         Some(&payer.pubkey()),
     );
-    transaction.sign(&[payer, trust_handler], *recent_blockhash);
+    transaction.sign(&[payer, oracle], *recent_blockhash);
     banks_client.process_transaction(transaction).await.unwrap();
 }
 
-async fn complete(
+fn results_log_address(escrow: &Pubkey) -> Pubkey {
+    processor::Processor::find_results_log_address(&id(), escrow).0
+}
+
+fn results_log_page_address(escrow: &Pubkey, page: u64) -> Pubkey {
+    processor::Processor::find_results_log_page_address(&id(), escrow, page).0
+}
+
+fn recipient_payout_address(escrow: &Pubkey, owner: &Pubkey) -> Pubkey {
+    processor::Processor::find_recipient_payout_address(&id(), escrow, owner).0
+}
+
+/// Recipient payout address of the token account owner, only if the escrow
+/// limits the payout per recipient
+async fn get_recipient_payout_address(
+    banks_client: &mut BanksClient,
+    escrow: &Pubkey,
+    recipient_token_account: &Pubkey,
+) -> Option<Pubkey> {
+    let escrow_account = banks_client
+        .get_account(*escrow)
+        .await
+        .expect("get_account")
+        .expect("cannot read escrow account data");
+    let escrow_info = state::Escrow::unpack_unchecked(escrow_account.data.as_slice()).unwrap();
+    if escrow_info.max_payout_per_recipient == 0 {
+        return None;
+    }
+    let token_account = banks_client
+        .get_account(*recipient_token_account)
+        .await
+        .expect("get_account")?;
+    let owner = spl_token::state::Account::unpack(token_account.data.as_slice())
+        .ok()?
+        .owner;
+    Some(recipient_payout_address(escrow, &owner))
+}
+
+async fn get_recipient_payout(
+    banks_client: &mut BanksClient,
+    escrow: &Pubkey,
+    owner: &Pubkey,
+) -> state::RecipientPayout {
+    let recipient_payout = banks_client
+        .get_account(recipient_payout_address(escrow, owner))
+        .await
+        .expect("get_account")
+        .expect("cannot read recipient payout account data");
+    state::RecipientPayout::unpack(recipient_payout.data.as_slice()).unwrap()
+}
+
+async fn get_results_log(banks_client: &mut BanksClient, escrow: &Pubkey) -> state::ResultsLog {
+    let results_log = banks_client
+        .get_account(results_log_address(escrow))
+        .await
+        .expect("get_account")
+        .expect("cannot read results log account data");
+    state::ResultsLog::unpack_from_slice(results_log.data.as_slice()).unwrap()
+}
+
+/// Reads all the results log entries from its pages
+async fn get_results_log_entries(
+    banks_client: &mut BanksClient,
+    escrow: &Pubkey,
+    log: &state::ResultsLog,
+) -> Vec<state::ResultsLogEntry> {
+    let mut entries = vec![];
+    for page in 0..log.page_count() {
+        let results_log_page = banks_client
+            .get_account(results_log_page_address(escrow, page))
+            .await
+            .expect("get_account")
+            .expect("cannot read results log page account data");
+        assert_eq!(
+            results_log_page.data.len(),
+            state::ResultsLogPage::ACCOUNT_LEN
+        );
+        let header =
+            state::ResultsLogPage::unpack_from_slice(results_log_page.data.as_slice()).unwrap();
+        assert_eq!(header.escrow, *escrow);
+        assert_eq!(header.page, page);
+
+        let first = page as usize * state::RESULTS_LOG_PAGE_ENTRIES;
+        let last = (first + state::RESULTS_LOG_PAGE_ENTRIES).min(log.count as usize);
+        for index in first..last {
+            entries
+                .push(state::ResultsLogPage::unpack_entry(&results_log_page.data, index).unwrap());
+        }
+    }
+    entries
+}
+
+async fn store_intermediate_results(
     banks_client: &mut BanksClient,
     payer: &Keypair,
     recent_blockhash: &Hash,
     escrow_account: &Keypair,
-    trust_handler: &Keypair,
-) {
+    recording_oracle: &Keypair,
+    url: &DataUrl,
+    hash: &DataHash,
+) -> Result<(), TransportError> {
+    let rent = banks_client.get_rent().await.unwrap();
+    let count = match banks_client
+        .get_account(results_log_address(&escrow_account.pubkey()))
+        .await
+        .unwrap()
+    {
+        Some(results_log) => {
+            state::ResultsLog::unpack_from_slice(results_log.data.as_slice())
+                .unwrap()
+                .count
+        }
+        None => 0,
+    };
     let mut transaction = Transaction::new_with_payer(
         &[
-            instruction::complete(&id(), &escrow_account.pubkey(), &trust_handler.pubkey())
-                .unwrap(),
+            system_instruction::transfer(
+                &payer.pubkey(),
+                &recording_oracle.pubkey(),
+                rent.minimum_balance(state::ResultsLog::LEN)
+                    + rent.minimum_balance(state::ResultsLogPage::ACCOUNT_LEN),
+            ),
+            instruction::store_intermediate_results(
+                &id(),
+                &escrow_account.pubkey(),
+                &results_log_address(&escrow_account.pubkey()),
+                &results_log_page_address(&escrow_account.pubkey(), state::ResultsLog::page(count)),
+                &recording_oracle.pubkey(),
+                url,
+                hash,
+            )
+            .unwrap(),
         ],
         Some(&payer.pubkey()),
     );
-    transaction.sign(&[payer, trust_handler], *recent_blockhash);
-    banks_client.process_transaction(transaction).await.unwrap();
+    transaction.sign(&[payer, recording_oracle], *recent_blockhash);
+    banks_client.process_transaction(transaction).await
 }
 
-async fn mint_to_escrow(
+fn reputation_address(worker: &Pubkey) -> Pubkey {
+    processor::Processor::find_reputation_address(&id(), worker).0
+}
+
+async fn update_reputation(
     banks_client: &mut BanksClient,
     payer: &Keypair,
     recent_blockhash: &Hash,
-    mint_pubkey: &Keypair,
-    account_pubkey: &Keypair,
-    owner_pubkey: &Keypair,
-    amount: f64,
-) {
+    escrow_account: &Keypair,
+    reputation_oracle: &Keypair,
+    updates: &[instruction::ReputationUpdate],
+) -> Result<(), TransportError> {
+    let reputation_accounts: Vec<Pubkey> = updates
+        .iter()
+        .map(|update| reputation_address(&update.worker))
+        .collect();
+    let reputation_job_accounts: Vec<Pubkey> = updates
+        .iter()
+        .map(|update| {
+            processor::Processor::find_reputation_job_address(
+                &id(),
+                &update.worker,
+                &escrow_account.pubkey(),
+            )
+            .0
+        })
+        .collect();
+    let rent = banks_client.get_rent().await.unwrap();
     let mut transaction = Transaction::new_with_payer(
-        &[spl_token::instruction::mint_to(
-            &spl_token::id(),
-            &mint_pubkey.pubkey(),
-            &account_pubkey.pubkey(),
-            &owner_pubkey.pubkey(),
-            &[],
-            spl_token::ui_amount_to_amount(amount, DECIMALS),
-        )
-        .unwrap()],
+        &[
+            system_instruction::transfer(
+                &payer.pubkey(),
+                &reputation_oracle.pubkey(),
+                (rent.minimum_balance(state::Reputation::LEN) + rent.minimum_balance(0))
+                    * updates.len() as u64,
+            ),
+            instruction::update_reputation(
+                &id(),
+                &escrow_account.pubkey(),
+                &reputation_oracle.pubkey(),
+                &reputation_accounts,
+                &reputation_job_accounts,
+                updates,
+            )
+            .unwrap(),
+        ],
         Some(&payer.pubkey()),
     );
-    transaction.sign(&[payer, owner_pubkey], *recent_blockhash);
-    banks_client.process_transaction(transaction).await.unwrap();
+    transaction.sign(&[payer, reputation_oracle], *recent_blockhash);
+    banks_client.process_transaction(transaction).await
 }
 
-struct EscrowAccount {
-    pub escrow: Keypair,
-    pub token_mint: Keypair,
-    pub escrow_token_account: Keypair,
-    pub launcher: Keypair,
-    pub canceler: Keypair,
-    pub canceler_token_account: Keypair,
-    pub duration: u64,
-    pub escrow_authority: Pubkey,
-    pub bump_seed: u8,
-    pub reputation_oracle: Keypair,
-    pub reputation_oracle_token: Keypair,
-    pub reputation_oracle_stake: u8,
-    pub recording_oracle: Keypair,
-    pub recording_oracle_token: Keypair,
-    pub recording_oracle_stake: u8,
-    pub manifest_url: DataUrl,
-    pub manifest_hash: DataHash,
-    pub final_results_url: DataUrl,
-    pub final_results_hash: DataHash,
-    pub total_amount: f64,
-    pub total_recipients: u64,
-    pub payout_amount: f64,
-    pub mint_authority: Keypair,
+async fn get_reputation(banks_client: &mut BanksClient, worker: &Pubkey) -> state::Reputation {
+    let reputation = banks_client
+        .get_account(reputation_address(worker))
+        .await
+        .expect("get_account")
+        .expect("cannot read reputation account data");
+    state::Reputation::unpack(reputation.data.as_slice()).unwrap()
 }
 
-impl EscrowAccount {
-    pub fn new() -> Self {
-        let escrow = Keypair::new();
-        let token_mint = Keypair::new();
-        let escrow_token_account = Keypair::new();
-        let launcher = Keypair::new();
-        let canceler = Keypair::new();
-        let canceler_token_account = Keypair::new();
+async fn get_oracle_stake(banks_client: &mut BanksClient, oracle: &Pubkey) -> state::OracleStake {
+    let oracle_stake = banks_client
+        .get_account(oracle_stake_address(oracle))
+        .await
+        .expect("get_account")
+        .expect("cannot read oracle stake account data");
+    state::OracleStake::unpack(oracle_stake.data.as_slice()).unwrap()
+}
 
-        let reputation_oracle = Keypair::new();
+async fn create_allow_list(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    allow_list: &Keypair,
+    authority: &Keypair,
+    capacity: usize,
+) {
+    let rent = banks_client.get_rent().await.unwrap();
+    let account_len = state::AllowList::account_len(capacity);
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &allow_list.pubkey(),
+                rent.minimum_balance(account_len),
+                account_len as u64,
+                &id(),
+            ),
+            instruction::initialize_allow_list(&id(), &allow_list.pubkey(), &authority.pubkey())
+                .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, allow_list, authority], *recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+async fn update_allow_list(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    allow_list: &Pubkey,
+    authority: &Keypair,
+    add: &[Pubkey],
+    remove: &[Pubkey],
+) -> Result<(), TransportError> {
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            instruction::update_allow_list(&id(), allow_list, &authority.pubkey(), add, remove)
+                .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, authority], *recent_blockhash);
+    banks_client.process_transaction(transaction).await
+}
+
+fn vault_address(escrow: &Pubkey, token_mint: &Pubkey) -> Pubkey {
+    processor::Processor::find_vault_address(&id(), escrow, token_mint).0
+}
+
+async fn add_vault(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    escrow_account: &Keypair,
+    trust_handler: &Keypair,
+    token_mint: &Pubkey,
+    vault_token_account: &Pubkey,
+    canceler_token_account: &Pubkey,
+) -> Result<(), TransportError> {
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::add_vault(
+            &id(),
+            &escrow_account.pubkey(),
+            &trust_handler.pubkey(),
+            &vault_address(&escrow_account.pubkey(), token_mint),
+            token_mint,
+            vault_token_account,
+            canceler_token_account,
+            &config_address(),
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, trust_handler], *recent_blockhash);
+    banks_client.process_transaction(transaction).await
+}
+
+async fn store_vault_results(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    escrow_account: &Keypair,
+    trust_handler: &Keypair,
+    vault: &Pubkey,
+    total_amount: f64,
+) {
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::store_vault_results(
+            &id(),
+            &escrow_account.pubkey(),
+            &trust_handler.pubkey(),
+            vault,
+            spl_token::ui_amount_to_amount(total_amount, DECIMALS),
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, trust_handler], *recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+async fn get_vault(banks_client: &mut BanksClient, vault: &Pubkey) -> state::EscrowVault {
+    let vault = banks_client
+        .get_account(*vault)
+        .await
+        .expect("get_account")
+        .expect("cannot read vault account data");
+    state::EscrowVault::unpack(vault.data.as_slice()).unwrap()
+}
+
+async fn create_program_account(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    account: &Keypair,
+    account_len: usize,
+) {
+    let rent = banks_client.get_rent().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::create_account(
+            &payer.pubkey(),
+            &account.pubkey(),
+            rent.minimum_balance(account_len),
+            account_len as u64,
+            &id(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, account], *recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+async fn initialize_template(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    template_account: &Pubkey,
+    authority: &Keypair,
+    template: &state::EscrowTemplate,
+) -> Result<(), TransportError> {
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::initialize_template(&id(), template_account, template).unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, authority], *recent_blockhash);
+    banks_client.process_transaction(transaction).await
+}
+
+async fn get_template(
+    banks_client: &mut BanksClient,
+    template_account: &Pubkey,
+) -> state::EscrowTemplate {
+    let template = banks_client
+        .get_account(*template_account)
+        .await
+        .expect("get_account")
+        .expect("cannot read template account data");
+    state::EscrowTemplate::unpack(template.data.as_slice()).unwrap()
+}
+
+async fn initialize_from_template(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    escrow_account: &Pubkey,
+    launcher: &Keypair,
+    template_account: &Pubkey,
+    template: &state::EscrowTemplate,
+    escrow_token_account: &Pubkey,
+    manifest_url: &DataUrl,
+    manifest_hash: &DataHash,
+) -> Result<(), TransportError> {
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::initialize_from_template(
+            &id(),
+            escrow_account,
+            template_account,
+            template,
+            escrow_token_account,
+            &config_address(),
+            &oracle_stake_address(&template.reputation_oracle),
+            &oracle_stake_address(&template.recording_oracle),
+            manifest_url,
+            manifest_hash,
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, launcher], *recent_blockhash);
+    banks_client.process_transaction(transaction).await
+}
+
+async fn create_escrow(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    escrow_account: &Keypair,
+    escrow_token_account: &Keypair,
+    launcher: &Pubkey,
+    canceler: &Pubkey,
+    canceler_token: &Keypair,
+    guardian: &Pubkey,
+    token_mint: &Pubkey,
+    duration: &u64,
+) {
+    let rent = banks_client.get_rent().await.unwrap();
+    let account_rent = rent.minimum_balance(state::Escrow::LEN);
+
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &escrow_account.pubkey(),
+                account_rent,
+                hmt_escrow::state::Escrow::LEN as u64,
+                &id(),
+            ),
+            instruction::initialize(
+                &id(),
+                &escrow_account.pubkey(),
+                token_mint,
+                &escrow_token_account.pubkey(),
+                &launcher,
+                &canceler,
+                &canceler_token.pubkey(),
+                &guardian,
+                &config_address(),
+                *duration,
+            )
+            .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, escrow_account], *recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+async fn setup_escrow(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    escrow_account: &Keypair,
+    trust_handler: &Keypair,
+    reputation_oracle: &Keypair,
+    reputation_oracle_token: &Keypair,
+    reputation_oracle_stake: &u8,
+    recording_oracle: &Keypair,
+    recording_oracle_token: &Keypair,
+    recording_oracle_stake: &u8,
+    manifest_url: &DataUrl,
+    manifest_hash: &DataHash,
+    max_payout_per_recipient: &f64,
+    min_payout: &f64,
+    cancellation_fee: &f64,
+    cancellation_fee_percent: &u8,
+    cancel_notice: &u64,
+    escrow_token_account: &Keypair,
+    allow_list: Option<&Pubkey>,
+) {
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::setup(
+            &id(),
+            &escrow_account.pubkey(),
+            &trust_handler.pubkey(),
+            &reputation_oracle.pubkey(),
+            &reputation_oracle_token.pubkey(),
+            *reputation_oracle_stake,
+            &recording_oracle.pubkey(),
+            &recording_oracle_token.pubkey(),
+            *recording_oracle_stake,
+            manifest_url,
+            manifest_hash,
+            spl_token::ui_amount_to_amount(*max_payout_per_recipient, DECIMALS),
+            spl_token::ui_amount_to_amount(*min_payout, DECIMALS),
+            spl_token::ui_amount_to_amount(*cancellation_fee, DECIMALS),
+            *cancellation_fee_percent,
+            *cancel_notice,
+            &config_address(),
+            &escrow_token_account.pubkey(),
+            &oracle_stake_address(&reputation_oracle.pubkey()),
+            &oracle_stake_address(&recording_oracle.pubkey()),
+            allow_list,
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, trust_handler], *recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+async fn store_results(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    escrow_account: &Keypair,
+    trust_handler: &Keypair,
+    total_amount: &f64,
+    total_recipients: &u64,
+    final_results_url: &DataUrl,
+    final_results_hash: &DataHash,
+    reputation_oracle: &Pubkey,
+    recording_oracle: &Pubkey,
+) {
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::store_results(
+            &id(),
+            &escrow_account.pubkey(),
+            &trust_handler.pubkey(),
+            spl_token::ui_amount_to_amount(*total_amount, DECIMALS),
+            *total_recipients,
+            &final_results_url,
+            final_results_hash,
+            &results_log_address(&escrow_account.pubkey()),
+            &config_address(),
+            &oracle_stake_address(reputation_oracle),
+            &oracle_stake_address(recording_oracle),
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, trust_handler], *recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+async fn payout(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    escrow_account: &Keypair,
+    trust_handler: &Keypair,
+    escrow_token_account: &Keypair,
+    escrow_authority: &Pubkey,
+    recipient_token_account: &Keypair,
+    reputation_oracle_token_account: &Keypair,
+    recording_oracle_token_account: &Keypair,
+    amount: &f64,
+    allow_list: Option<&Pubkey>,
+) {
+    let recipient_payout = get_recipient_payout_address(
+        banks_client,
+        &escrow_account.pubkey(),
+        &recipient_token_account.pubkey(),
+    )
+    .await;
+    let funder = payer.pubkey();
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::payout(
+            &id(),
+            &escrow_account.pubkey(),
+            &trust_handler.pubkey(),
+            &escrow_token_account.pubkey(),
+            &escrow_authority,
+            &recipient_token_account.pubkey(),
+            &reputation_oracle_token_account.pubkey(),
+            &recording_oracle_token_account.pubkey(),
+            &spl_token::id(),
+            spl_token::ui_amount_to_amount(*amount, DECIMALS),
+            allow_list,
+            recipient_payout
+                .as_ref()
+                .map(|recipient_payout| (recipient_payout, &funder)),
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, trust_handler], *recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+async fn cancel(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    escrow_account: &Keypair,
+    trust_handler: &Keypair,
+    escrow_token_account: &Keypair,
+    escrow_authority: &Pubkey,
+    canceler_token_account: &Keypair,
+    oracle_token_accounts: Option<(&Pubkey, &Pubkey)>,
+) {
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::cancel(
+            &id(),
+            &escrow_account.pubkey(),
+            &trust_handler.pubkey(),
+            &escrow_token_account.pubkey(),
+            &escrow_authority,
+            &canceler_token_account.pubkey(),
+            &spl_token::id(),
+            oracle_token_accounts,
+            &[],
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, trust_handler], *recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+async fn complete(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    escrow_account: &Keypair,
+    trust_handler: &Keypair,
+) {
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            instruction::complete(&id(), &escrow_account.pubkey(), &trust_handler.pubkey())
+                .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, trust_handler], *recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+async fn set_paused(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    escrow_account: &Keypair,
+    guardian: &Keypair,
+    paused: bool,
+) {
+    let instruction = if paused {
+        instruction::pause(&id(), &escrow_account.pubkey(), &guardian.pubkey())
+    } else {
+        instruction::unpause(&id(), &escrow_account.pubkey(), &guardian.pubkey())
+    };
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction.unwrap()], Some(&payer.pubkey()));
+    transaction.sign(&[payer, guardian], *recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+async fn set_permit_signer(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    escrow_account: &Keypair,
+    trust_handler: &Keypair,
+    permit_signer: &[u8; ETH_ADDRESS_LEN],
+) {
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::set_permit_signer(
+            &id(),
+            &escrow_account.pubkey(),
+            &trust_handler.pubkey(),
+            permit_signer,
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, trust_handler], *recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+/// Secp256k1 key of the reputation oracle signing payout permits
+fn permit_key() -> SecretKey {
+    SecretKey::parse(&[5; 32]).unwrap()
+}
+
+/// Ethereum address of the permit signing key
+fn permit_signer(key: &SecretKey) -> [u8; ETH_ADDRESS_LEN] {
+    let instruction = new_secp256k1_instruction(key, &[]);
+    let mut eth_address = [0; ETH_ADDRESS_LEN];
+    eth_address.copy_from_slice(
+        &instruction.data[ETH_ADDRESS_OFFSET..ETH_ADDRESS_OFFSET + ETH_ADDRESS_LEN],
+    );
+    eth_address
+}
+
+/// Signs the payout permit with the `key` and relays it, the Secp256k1 program
+/// instruction is the first one in the transaction. Without the `key` only
+/// the payout instruction is sent.
+#[allow(clippy::too_many_arguments)]
+async fn payout_with_permit(
+    banks_client: &mut BanksClient,
+    relayer: &Keypair,
+    recent_blockhash: &Hash,
+    escrow_account: &EscrowAccount,
+    key: Option<&SecretKey>,
+    recipient_token_account: &Pubkey,
+    nonce: u64,
+    expires: i64,
+    token_program_id: &Pubkey,
+) -> Result<(), TransportError> {
+    let amount = spl_token::ui_amount_to_amount(escrow_account.payout_amount, DECIMALS);
+    let recipient_payout = get_recipient_payout_address(
+        banks_client,
+        &escrow_account.escrow.pubkey(),
+        recipient_token_account,
+    )
+    .await;
+    let funder = relayer.pubkey();
+    let mut instructions: Vec<Instruction> = vec![];
+    if let Some(key) = key {
+        let message = instruction::payout_permit_message(
+            &escrow_account.escrow.pubkey(),
+            recipient_token_account,
+            amount,
+            nonce,
+            expires,
+        );
+        instructions.push(new_secp256k1_instruction(key, &message));
+    }
+    instructions.push(
+        instruction::payout_with_permit(
+            &id(),
+            &escrow_account.escrow.pubkey(),
+            &escrow_account.escrow_token_account.pubkey(),
+            &escrow_account.escrow_authority,
+            recipient_token_account,
+            &escrow_account.reputation_oracle_token.pubkey(),
+            &escrow_account.recording_oracle_token.pubkey(),
+            token_program_id,
+            amount,
+            nonce,
+            expires,
+            None,
+            recipient_payout
+                .as_ref()
+                .map(|recipient_payout| (recipient_payout, &funder)),
+        )
+        .unwrap(),
+    );
+
+    let mut transaction = Transaction::new_with_payer(&instructions, Some(&relayer.pubkey()));
+    transaction.sign(&[relayer], *recent_blockhash);
+    banks_client.process_transaction(transaction).await
+}
+
+async fn mint_to_escrow(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    mint_pubkey: &Keypair,
+    account_pubkey: &Keypair,
+    owner_pubkey: &Keypair,
+    amount: f64,
+) {
+    let mut transaction = Transaction::new_with_payer(
+        &[spl_token::instruction::mint_to(
+            &spl_token::id(),
+            &mint_pubkey.pubkey(),
+            &account_pubkey.pubkey(),
+            &owner_pubkey.pubkey(),
+            &[],
+            spl_token::ui_amount_to_amount(amount, DECIMALS),
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, owner_pubkey], *recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+struct EscrowAccount {
+    pub escrow: Keypair,
+    pub token_mint: Keypair,
+    pub escrow_token_account: Keypair,
+    pub launcher: Keypair,
+    pub canceler: Keypair,
+    pub canceler_token_account: Keypair,
+    pub guardian: Keypair,
+    pub duration: u64,
+    pub escrow_authority: Pubkey,
+    pub bump_seed: u8,
+    pub reputation_oracle: Keypair,
+    pub reputation_oracle_token: Keypair,
+    pub reputation_oracle_stake: u8,
+    pub recording_oracle: Keypair,
+    pub recording_oracle_token: Keypair,
+    pub recording_oracle_stake: u8,
+    pub manifest_url: DataUrl,
+    pub manifest_hash: DataHash,
+    pub final_results_url: DataUrl,
+    pub final_results_hash: DataHash,
+    pub total_amount: f64,
+    pub total_recipients: u64,
+    pub payout_amount: f64,
+    pub max_payout_per_recipient: f64,
+    pub min_payout: f64,
+    pub cancellation_fee: f64,
+    pub cancellation_fee_percent: u8,
+    pub cancel_notice: u64,
+    pub allow_list: Option<Pubkey>,
+    pub mint_authority: Keypair,
+}
+
+impl EscrowAccount {
+    pub fn new() -> Self {
+        let escrow = Keypair::new();
+        let token_mint = Keypair::new();
+        let escrow_token_account = Keypair::new();
+        let launcher = Keypair::new();
+        let canceler = Keypair::new();
+        let canceler_token_account = Keypair::new();
+        let guardian = Keypair::new();
+
+        let reputation_oracle = Keypair::new();
         let reputation_oracle_token = Keypair::new();
         let recording_oracle = Keypair::new();
         let recording_oracle_token = Keypair::new();
         let mint_authority = Keypair::new();
 
-        let manifest_array_for_hash = [1, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 9, 8, 7, 6, 5, 4, 3, 9, 9];
-        let final_array_for_hash = [7, 3, 4, 3, 4, 5, 6, 7, 2, 1, 6, 6, 0, 2, 2, 2, 3, 2, 4, 7];
-        let manifest_hash = DataHash::new_from_array(manifest_array_for_hash);
-        let final_results_hash = DataHash::new_from_array(final_array_for_hash);
-        //find authority bumpseed
-        let (escrow_authority, bump_seed) =
-            hmt_escrow::processor::Processor::find_authority_bump_seed(&id(), &escrow.pubkey());
+        let manifest_array_for_hash = [1, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 9, 8, 7, 6, 5, 4, 3, 9, 9];
+        let final_array_for_hash = [7, 3, 4, 3, 4, 5, 6, 7, 2, 1, 6, 6, 0, 2, 2, 2, 3, 2, 4, 7];
+        let manifest_hash = DataHash::new_from_array(manifest_array_for_hash);
+        let final_results_hash = DataHash::new_from_array(final_array_for_hash);
+        //find authority bumpseed
+        let (escrow_authority, bump_seed) =
+            hmt_escrow::processor::Processor::find_authority_bump_seed(&id(), &escrow.pubkey());
+
+        let manifest_url: DataUrl = match DataUrl::from_str("http://somemanifest.com") {
+            Ok(url) => url,
+            _ => Default::default(),
+        };
+
+        let final_results_url: DataUrl = match DataUrl::from_str("http://result.com") {
+            Ok(url) => url,
+            _ => Default::default(),
+        };
+        Self {
+            escrow,
+            token_mint,
+            escrow_token_account,
+            launcher,
+            canceler,
+            canceler_token_account,
+            guardian,
+            duration: 100000 as u64,
+            escrow_authority,
+            bump_seed,
+            reputation_oracle,
+            reputation_oracle_token,
+            reputation_oracle_stake: 10 as u8,
+            recording_oracle,
+            recording_oracle_token,
+            recording_oracle_stake: 15 as u8,
+            total_amount: 30.0 as f64,
+            total_recipients: 1 as u64,
+            payout_amount: 30.0 as f64,
+            max_payout_per_recipient: 30.0 as f64,
+            min_payout: 1.0 as f64,
+            cancellation_fee: 0.0 as f64,
+            cancellation_fee_percent: 0,
+            cancel_notice: 0,
+            allow_list: None,
+            mint_authority,
+            manifest_url,
+            manifest_hash,
+            final_results_url,
+            final_results_hash,
+        }
+    }
+
+    pub async fn initialize_escrow(
+        &self,
+        mut banks_client: &mut BanksClient,
+        payer: &Keypair,
+        recent_blockhash: &Hash,
+    ) {
+        initialize_config(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &[self.token_mint.pubkey()],
+        )
+        .await;
+
+        create_mint(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &self.token_mint,
+            &self.mint_authority.pubkey(),
+        )
+        .await;
+
+        //Creating token account for escrow
+        create_token_account(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &self.escrow_token_account,
+            &self.token_mint.pubkey(),
+            &self.escrow_authority,
+        )
+        .await;
+
+        //Creating token account for canceler
+        create_token_account(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &self.canceler_token_account,
+            &self.token_mint.pubkey(),
+            &self.canceler.pubkey(),
+        )
+        .await;
+        create_escrow(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &self.escrow,
+            &self.escrow_token_account,
+            &self.launcher.pubkey(),
+            &self.canceler.pubkey(),
+            &self.canceler_token_account,
+            &self.guardian.pubkey(),
+            &self.token_mint.pubkey(),
+            &self.duration,
+        )
+        .await;
+    }
+
+    pub async fn setup_escrow(
+        &self,
+        mut banks_client: &mut BanksClient,
+        payer: &Keypair,
+        recent_blockhash: &Hash,
+    ) {
+        //Creating token account for reputation_oracle_token
+        create_token_account(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &self.reputation_oracle_token,
+            &self.token_mint.pubkey(),
+            &self.reputation_oracle.pubkey(),
+        )
+        .await;
+
+        //Creating token account for recording_oracle_token
+        create_token_account(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &self.recording_oracle_token,
+            &self.token_mint.pubkey(),
+            &self.recording_oracle.pubkey(),
+        )
+        .await;
+        setup_escrow(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &self.escrow,
+            &self.launcher,
+            &self.reputation_oracle,
+            &self.reputation_oracle_token,
+            &self.reputation_oracle_stake,
+            &self.recording_oracle,
+            &self.recording_oracle_token,
+            &self.recording_oracle_stake,
+            &self.manifest_url,
+            &self.manifest_hash,
+            &self.max_payout_per_recipient,
+            &self.min_payout,
+            &self.cancellation_fee,
+            &self.cancellation_fee_percent,
+            &self.cancel_notice,
+            &self.escrow_token_account,
+            self.allow_list.as_ref(),
+        )
+        .await;
+    }
+
+    pub async fn store_results(
+        &self,
+        mut banks_client: &mut BanksClient,
+        payer: &Keypair,
+        recent_blockhash: &Hash,
+    ) {
+        store_results(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &self.escrow,
+            &self.launcher,
+            &self.total_amount,
+            &self.total_recipients,
+            &self.final_results_url,
+            &self.final_results_hash,
+            &self.reputation_oracle.pubkey(),
+            &self.recording_oracle.pubkey(),
+        )
+        .await;
+    }
+
+    pub async fn payout_escrow(
+        &self,
+        mut banks_client: &mut BanksClient,
+        payer: &Keypair,
+        recent_blockhash: &Hash,
+        recipient_token_account: &Keypair,
+    ) {
+        payout(
+            &mut banks_client,
+            payer,
+            &recent_blockhash,
+            &self.escrow,
+            &self.launcher,
+            &self.escrow_token_account,
+            &self.escrow_authority,
+            recipient_token_account,
+            &self.reputation_oracle_token,
+            &self.recording_oracle_token,
+            &self.payout_amount,
+            self.allow_list.as_ref(),
+        )
+        .await;
+    }
+
+    pub async fn cancel_escrow(
+        &self,
+        mut banks_client: &mut BanksClient,
+        payer: &Keypair,
+        recent_blockhash: &Hash,
+    ) {
+        let reputation_oracle_token = self.reputation_oracle_token.pubkey();
+        let recording_oracle_token = self.recording_oracle_token.pubkey();
+        let oracle_token_accounts =
+            if self.cancellation_fee != 0.0 || self.cancellation_fee_percent != 0 {
+                Some((&reputation_oracle_token, &recording_oracle_token))
+            } else {
+                None
+            };
+        cancel(
+            &mut banks_client,
+            payer,
+            &recent_blockhash,
+            &self.escrow,
+            &self.launcher,
+            &self.escrow_token_account,
+            &self.escrow_authority,
+            &self.canceler_token_account,
+            oracle_token_accounts,
+        )
+        .await;
+    }
+
+    pub async fn set_paused(
+        &self,
+        mut banks_client: &mut BanksClient,
+        payer: &Keypair,
+        recent_blockhash: &Hash,
+        paused: bool,
+    ) {
+        set_paused(
+            &mut banks_client,
+            payer,
+            &recent_blockhash,
+            &self.escrow,
+            &self.guardian,
+            paused,
+        )
+        .await;
+    }
+
+    pub async fn complete_escrow(
+        &self,
+        mut banks_client: &mut BanksClient,
+        payer: &Keypair,
+        recent_blockhash: &Hash,
+    ) {
+        complete(
+            &mut banks_client,
+            payer,
+            &recent_blockhash,
+            &self.escrow,
+            &self.launcher,
+        )
+        .await;
+    }
+}
+
+fn escrow_error(error: error::EscrowError) -> InstructionError {
+    match ProgramError::from(error) {
+        ProgramError::Custom(code) => InstructionError::Custom(code),
+        error => panic!("not a custom error: {:?}", error),
+    }
+}
+
+/// Checks that the instruction at `index` in the transaction failed with the `expected` error
+fn assert_instruction_error(
+    result: Result<(), TransportError>,
+    index: u8,
+    expected: InstructionError,
+) {
+    match result {
+        Err(TransportError::TransactionError(TransactionError::InstructionError(
+            error_index,
+            error,
+        ))) if error_index == index => assert_eq!(error, expected),
+        result => panic!(
+            "expected error {:?} in instruction {}, got {:?}",
+            expected, index, result
+        ),
+    }
+}
+
+fn assert_escrow_error(result: Result<(), TransportError>, expected: error::EscrowError) {
+    assert_instruction_error(result, 0, escrow_error(expected));
+}
+
+async fn check_escrow_account_info<F>(f: F, escrow: &EscrowAccount, banks_client: &mut BanksClient)
+where
+    F: Fn(state::Escrow),
+{
+    let escrow = banks_client
+        .get_account(escrow.escrow.pubkey())
+        .await
+        .expect("get_account")
+        .expect("cannot read escrow account data");
+
+    assert_eq!(escrow.data.len(), hmt_escrow::state::Escrow::LEN);
+    match state::Escrow::unpack_from_slice(escrow.data.as_slice()) {
+        Ok(escrow) => {
+            f(escrow);
+        }
+        Err(_) => assert!(false),
+    };
+}
+
+async fn check_token_account_info<F>(f: F, account: &Keypair, banks_client: &mut BanksClient)
+where
+    F: Fn(spl_token::state::Account),
+{
+    let account = banks_client
+        .get_account(account.pubkey())
+        .await
+        .expect("get_account")
+        .expect("cannot read token account data");
+
+    match spl_token::state::Account::unpack_from_slice(account.data.as_slice()) {
+        Ok(token_account) => {
+            f(token_account);
+        }
+        Err(_) => assert!(false),
+    };
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_initialize() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let escrow_account = EscrowAccount::new();
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let initialize_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Launched);
+        assert_eq!(escrow.bump_seed, escrow_account.bump_seed);
+        assert_eq!(escrow.token_mint, escrow_account.token_mint.pubkey());
+        assert_eq!(
+            escrow.token_account,
+            escrow_account.escrow_token_account.pubkey()
+        );
+        assert_eq!(escrow.canceler, escrow_account.canceler.pubkey());
+        assert_eq!(
+            escrow.canceler_token_account,
+            escrow_account.canceler_token_account.pubkey()
+        );
+        assert_eq!(escrow.guardian, escrow_account.guardian.pubkey());
+        assert_eq!(escrow.paused, false);
+    };
+
+    check_escrow_account_info(initialize_check, &escrow_account, &mut banks_client).await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_setup() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let escrow_account = EscrowAccount::new();
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    escrow_account
+        .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let setup_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Pending);
+        assert_eq!(escrow.bump_seed, escrow_account.bump_seed);
+        assert_eq!(escrow.token_mint, escrow_account.token_mint.pubkey());
+        assert_eq!(
+            escrow.token_account,
+            escrow_account.escrow_token_account.pubkey()
+        );
+
+        assert_eq!(escrow.canceler, escrow_account.canceler.pubkey());
+        assert_eq!(
+            escrow.canceler_token_account,
+            escrow_account.canceler_token_account.pubkey()
+        );
+
+        assert_eq!(
+            escrow.reputation_oracle.unwrap(),
+            escrow_account.reputation_oracle.pubkey()
+        );
+        assert_eq!(
+            escrow.reputation_oracle_token_account.unwrap(),
+            escrow_account.reputation_oracle_token.pubkey()
+        );
+
+        assert_eq!(
+            escrow.recording_oracle.unwrap(),
+            escrow_account.recording_oracle.pubkey()
+        );
+        assert_eq!(
+            escrow.recording_oracle_token_account.unwrap(),
+            escrow_account.recording_oracle_token.pubkey()
+        );
+
+        assert_eq!(
+            escrow.reputation_oracle_stake,
+            escrow_account.reputation_oracle_stake
+        );
+        assert_eq!(
+            escrow.recording_oracle_stake,
+            escrow_account.recording_oracle_stake
+        );
+    };
+
+    check_escrow_account_info(setup_check, &escrow_account, &mut banks_client).await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_store_results() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let escrow_account = EscrowAccount::new();
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    escrow_account
+        .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    escrow_account
+        .store_results(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let store_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Pending);
+        assert_eq!(
+            escrow.total_amount,
+            spl_token::ui_amount_to_amount(escrow_account.total_amount, DECIMALS)
+        );
+        assert_eq!(escrow.total_recipients, escrow_account.total_recipients);
+        assert_eq!(escrow.manifest_url, escrow_account.manifest_url);
+        assert_eq!(escrow.manifest_hash, escrow_account.manifest_hash);
+        assert_eq!(escrow.final_results_url, escrow_account.final_results_url);
+        assert_eq!(escrow.final_results_hash, escrow_account.final_results_hash);
+    };
+
+    check_escrow_account_info(store_check, &escrow_account, &mut banks_client).await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_store_results_below_sent() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let mut escrow_account = EscrowAccount::new();
+    escrow_account.total_amount = 90.0;
+    escrow_account.total_recipients = 3;
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let recipient = Keypair::new();
+    let recipient_token_account = Keypair::new();
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &recipient_token_account,
+        &escrow_account.token_mint.pubkey(),
+        &recipient.pubkey(),
+    )
+    .await;
+
+    escrow_account
+        .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    escrow_account
+        .store_results(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    mint_to_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint,
+        &escrow_account.escrow_token_account,
+        &escrow_account.mint_authority,
+        5000.0,
+    )
+    .await;
+    escrow_account
+        .payout_escrow(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &recipient_token_account,
+        )
+        .await;
+
+    let payout_amount = spl_token::ui_amount_to_amount(escrow_account.payout_amount, DECIMALS);
+    let partial_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Partial);
+        assert_eq!(escrow.sent_amount, payout_amount);
+        assert_eq!(escrow.sent_recipients, 1);
+    };
+    check_escrow_account_info(partial_check, &escrow_account, &mut banks_client).await;
+
+    let store_results_transaction = |total_amount: u64, total_recipients: u64| {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction::store_results(
+                &id(),
+                &escrow_account.escrow.pubkey(),
+                &escrow_account.launcher.pubkey(),
+                total_amount,
+                total_recipients,
+                &escrow_account.final_results_url,
+                &escrow_account.final_results_hash,
+                &results_log_address(&escrow_account.escrow.pubkey()),
+                &config_address(),
+                &oracle_stake_address(&escrow_account.reputation_oracle.pubkey()),
+                &oracle_stake_address(&escrow_account.recording_oracle.pubkey()),
+            )
+            .unwrap()],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+        transaction
+    };
+
+    // Totals below the amount or the recipients already paid are rejected
+    for (total_amount, total_recipients) in [(payout_amount - 1, 3), (payout_amount, 0)].iter() {
+        assert_escrow_error(
+            banks_client
+                .process_transaction(store_results_transaction(*total_amount, *total_recipients))
+                .await,
+            error::EscrowError::TooManyPayouts,
+        );
+    }
+
+    // Totals matching what was paid are accepted and leave nothing to pay
+    banks_client
+        .process_transaction(store_results_transaction(payout_amount, 1))
+        .await
+        .unwrap();
+    let store_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.total_amount, payout_amount);
+        assert_eq!(escrow.total_recipients, 1);
+        assert_eq!(escrow.sent_amount, payout_amount);
+    };
+    check_escrow_account_info(store_check, &escrow_account, &mut banks_client).await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_payout() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let escrow_account = EscrowAccount::new();
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let recipient = Keypair::new();
+    let recipient_token_account = Keypair::new();
+
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &recipient_token_account,
+        &escrow_account.token_mint.pubkey(),
+        &recipient.pubkey(),
+    )
+    .await;
+
+    escrow_account
+        .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    escrow_account
+        .store_results(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let escrow_token_for_payout = 5000.0;
+    let escrow_token_for_payout_to_mint =
+        spl_token::ui_amount_to_amount(escrow_token_for_payout, DECIMALS);
+
+    mint_to_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint,
+        &escrow_account.escrow_token_account,
+        &escrow_account.mint_authority,
+        escrow_token_for_payout,
+    )
+    .await;
+
+    escrow_account
+        .payout_escrow(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &recipient_token_account,
+        )
+        .await;
+
+    let stake_percent: f64 =
+        (escrow_account.reputation_oracle_stake + escrow_account.recording_oracle_stake).into();
+    let payout_amount_to_mint =
+        spl_token::ui_amount_to_amount(escrow_account.payout_amount, DECIMALS);
+    let result_amount =
+        escrow_account.payout_amount - (escrow_account.payout_amount * (stake_percent / 100.0));
+    let result_amount_to_mint = spl_token::ui_amount_to_amount(result_amount, DECIMALS);
+
+    let amount_check = |token_account: spl_token::state::Account| {
+        assert_eq!(token_account.amount, result_amount_to_mint)
+    };
+
+    check_token_account_info(amount_check, &recipient_token_account, &mut banks_client).await;
+
+    let store_check = |token_account: spl_token::state::Account| {
+        assert_eq!(
+            token_account.amount,
+            escrow_token_for_payout_to_mint - payout_amount_to_mint
+        );
+    };
+
+    check_token_account_info(
+        store_check,
+        &escrow_account.escrow_token_account,
+        &mut banks_client,
+    )
+    .await;
+
+    let reputation_oracle_stake: f64 = escrow_account.reputation_oracle_stake.into();
+    let result_amount = escrow_account.payout_amount * (reputation_oracle_stake / 100.0);
+    let reputation_oracle_payout = spl_token::ui_amount_to_amount(result_amount, DECIMALS);
+
+    let amount_check = |token_account: spl_token::state::Account| {
+        assert_eq!(token_account.amount, reputation_oracle_payout);
+    };
+    check_token_account_info(
+        amount_check,
+        &escrow_account.reputation_oracle_token,
+        &mut banks_client,
+    )
+    .await;
+
+    let recording_oracle_stake: f64 = escrow_account.recording_oracle_stake.into();
+    let result_amount = escrow_account.payout_amount * (recording_oracle_stake / 100.0);
+    let recording_oracle_payout = spl_token::ui_amount_to_amount(result_amount, DECIMALS);
+
+    let amount_check = |token_account: spl_token::state::Account| {
+        assert_eq!(token_account.amount, recording_oracle_payout);
+    };
+    check_token_account_info(
+        amount_check,
+        &escrow_account.recording_oracle_token,
+        &mut banks_client,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_payout_limits() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let mut escrow_account = EscrowAccount::new();
+    escrow_account.total_amount = 90.0;
+    escrow_account.total_recipients = 3;
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let recipient = Keypair::new();
+    let recipient_token_account = Keypair::new();
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &recipient_token_account,
+        &escrow_account.token_mint.pubkey(),
+        &recipient.pubkey(),
+    )
+    .await;
+
+    escrow_account
+        .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    escrow_account
+        .store_results(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    mint_to_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint,
+        &escrow_account.escrow_token_account,
+        &escrow_account.mint_authority,
+        5000.0,
+    )
+    .await;
+
+    let limits_check = |escrow: state::Escrow| {
+        assert_eq!(
+            escrow.max_payout_per_recipient,
+            spl_token::ui_amount_to_amount(escrow_account.max_payout_per_recipient, DECIMALS)
+        );
+        assert_eq!(
+            escrow.min_payout,
+            spl_token::ui_amount_to_amount(escrow_account.min_payout, DECIMALS)
+        );
+    };
+    check_escrow_account_info(limits_check, &escrow_account, &mut banks_client).await;
+
+    // Zero, too small and too large payouts are rejected
+    let recipient_payout =
+        recipient_payout_address(&escrow_account.escrow.pubkey(), &recipient.pubkey());
+    for (amount, expected) in [
+        (0, error::EscrowError::PayoutBelowMinimum),
+        (
+            spl_token::ui_amount_to_amount(0.5, DECIMALS),
+            error::EscrowError::PayoutBelowMinimum,
+        ),
+        (
+            spl_token::ui_amount_to_amount(31.0, DECIMALS),
+            error::EscrowError::PayoutAboveMaximum,
+        ),
+    ]
+    .iter()
+    {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction::payout(
+                &id(),
+                &escrow_account.escrow.pubkey(),
+                &escrow_account.launcher.pubkey(),
+                &escrow_account.escrow_token_account.pubkey(),
+                &escrow_account.escrow_authority,
+                &recipient_token_account.pubkey(),
+                &escrow_account.reputation_oracle_token.pubkey(),
+                &escrow_account.recording_oracle_token.pubkey(),
+                &spl_token::id(),
+                *amount,
+                None,
+                Some((&recipient_payout, &payer.pubkey())),
+            )
+            .unwrap()],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+        assert_escrow_error(
+            banks_client.process_transaction(transaction).await,
+            expected.clone(),
+        );
+    }
+
+    let sent_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Pending);
+        assert_eq!(escrow.sent_amount, 0);
+        assert_eq!(escrow.sent_recipients, 0);
+    };
+    check_escrow_account_info(sent_check, &escrow_account, &mut banks_client).await;
+
+    // Payout at the maximum is accepted
+    escrow_account
+        .payout_escrow(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &recipient_token_account,
+        )
+        .await;
+    let recipient_payout_info = get_recipient_payout(
+        &mut banks_client,
+        &escrow_account.escrow.pubkey(),
+        &recipient.pubkey(),
+    )
+    .await;
+    assert_eq!(
+        recipient_payout_info.amount,
+        spl_token::ui_amount_to_amount(escrow_account.max_payout_per_recipient, DECIMALS)
+    );
+
+    // The maximum applies to the total paid to the owner, even through another
+    // of its token accounts
+    let other_token_account = Keypair::new();
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &other_token_account,
+        &escrow_account.token_mint.pubkey(),
+        &recipient.pubkey(),
+    )
+    .await;
+    for (recipient_token_account, recipient_payout, expected) in [
+        (
+            &other_token_account,
+            recipient_payout,
+            escrow_error(error::EscrowError::PayoutAboveMaximum),
+        ),
+        (
+            &recipient_token_account,
+            Pubkey::new_unique(),
+            InstructionError::InvalidSeeds,
+        ),
+    ]
+    .iter()
+    {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction::payout(
+                &id(),
+                &escrow_account.escrow.pubkey(),
+                &escrow_account.launcher.pubkey(),
+                &escrow_account.escrow_token_account.pubkey(),
+                &escrow_account.escrow_authority,
+                &recipient_token_account.pubkey(),
+                &escrow_account.reputation_oracle_token.pubkey(),
+                &escrow_account.recording_oracle_token.pubkey(),
+                &spl_token::id(),
+                spl_token::ui_amount_to_amount(escrow_account.min_payout, DECIMALS),
+                None,
+                Some((recipient_payout, &payer.pubkey())),
+            )
+            .unwrap()],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+        assert_instruction_error(
+            banks_client.process_transaction(transaction).await,
+            0,
+            expected.clone(),
+        );
+    }
+
+    // Another owner has its own total
+    let other_recipient_token_account = Keypair::new();
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &other_recipient_token_account,
+        &escrow_account.token_mint.pubkey(),
+        &Pubkey::new_unique(),
+    )
+    .await;
+    escrow_account
+        .payout_escrow(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &other_recipient_token_account,
+        )
+        .await;
+    let sent_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Partial);
+        assert_eq!(escrow.sent_recipients, 2);
+    };
+    check_escrow_account_info(sent_check, &escrow_account, &mut banks_client).await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_setup_payout_limits() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let escrow_account = EscrowAccount::new();
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    for token_account in [
+        &escrow_account.reputation_oracle_token,
+        &escrow_account.recording_oracle_token,
+    ]
+    .iter()
+    {
+        create_token_account(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            token_account,
+            &escrow_account.token_mint.pubkey(),
+            &Pubkey::new_unique(),
+        )
+        .await;
+    }
+
+    // Minimum above the maximum is rejected, zero maximum means no limit
+    for (max_payout_per_recipient, min_payout, expected) in [
+        (10.0, 20.0, Some(error::EscrowError::InvalidPayoutLimits)),
+        (0.0, 20.0, None),
+    ]
+    .iter()
+    {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction::setup(
+                &id(),
+                &escrow_account.escrow.pubkey(),
+                &escrow_account.launcher.pubkey(),
+                &escrow_account.reputation_oracle.pubkey(),
+                &escrow_account.reputation_oracle_token.pubkey(),
+                escrow_account.reputation_oracle_stake,
+                &escrow_account.recording_oracle.pubkey(),
+                &escrow_account.recording_oracle_token.pubkey(),
+                escrow_account.recording_oracle_stake,
+                &escrow_account.manifest_url,
+                &escrow_account.manifest_hash,
+                spl_token::ui_amount_to_amount(*max_payout_per_recipient, DECIMALS),
+                spl_token::ui_amount_to_amount(*min_payout, DECIMALS),
+                0,
+                0,
+                0,
+                &config_address(),
+                &escrow_account.escrow_token_account.pubkey(),
+                &oracle_stake_address(&escrow_account.reputation_oracle.pubkey()),
+                &oracle_stake_address(&escrow_account.recording_oracle.pubkey()),
+                None,
+            )
+            .unwrap()],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+        let result = banks_client.process_transaction(transaction).await;
+        match expected {
+            Some(expected) => assert_escrow_error(result, expected.clone()),
+            None => result.unwrap(),
+        }
+    }
+
+    let limits_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Pending);
+        assert_eq!(escrow.max_payout_per_recipient, 0);
+        assert_eq!(
+            escrow.min_payout,
+            spl_token::ui_amount_to_amount(20.0, DECIMALS)
+        );
+    };
+    check_escrow_account_info(limits_check, &escrow_account, &mut banks_client).await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_setup_cancellation_fee() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let escrow_account = EscrowAccount::new();
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    for token_account in [
+        &escrow_account.reputation_oracle_token,
+        &escrow_account.recording_oracle_token,
+    ]
+    .iter()
+    {
+        create_token_account(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            token_account,
+            &escrow_account.token_mint.pubkey(),
+            &Pubkey::new_unique(),
+        )
+        .await;
+    }
+
+    // Fee is either fixed or percent of the balance, never both
+    for (cancellation_fee, cancellation_fee_percent, expected) in [
+        (5.0, 10, Some(error::EscrowError::InvalidCancellationFee)),
+        (0.0, 101, Some(error::EscrowError::InvalidCancellationFee)),
+        (5.0, 0, None),
+    ]
+    .iter()
+    {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction::setup(
+                &id(),
+                &escrow_account.escrow.pubkey(),
+                &escrow_account.launcher.pubkey(),
+                &escrow_account.reputation_oracle.pubkey(),
+                &escrow_account.reputation_oracle_token.pubkey(),
+                escrow_account.reputation_oracle_stake,
+                &escrow_account.recording_oracle.pubkey(),
+                &escrow_account.recording_oracle_token.pubkey(),
+                escrow_account.recording_oracle_stake,
+                &escrow_account.manifest_url,
+                &escrow_account.manifest_hash,
+                0,
+                0,
+                spl_token::ui_amount_to_amount(*cancellation_fee, DECIMALS),
+                *cancellation_fee_percent,
+                3600,
+                &config_address(),
+                &escrow_account.escrow_token_account.pubkey(),
+                &oracle_stake_address(&escrow_account.reputation_oracle.pubkey()),
+                &oracle_stake_address(&escrow_account.recording_oracle.pubkey()),
+                None,
+            )
+            .unwrap()],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+        let result = banks_client.process_transaction(transaction).await;
+        match expected {
+            Some(expected) => assert_escrow_error(result, expected.clone()),
+            None => result.unwrap(),
+        }
+    }
+
+    let fee_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Pending);
+        assert_eq!(
+            escrow.cancellation_fee,
+            spl_token::ui_amount_to_amount(5.0, DECIMALS)
+        );
+        assert_eq!(escrow.cancellation_fee_percent, 0);
+        assert!(escrow.cancel_notice_end > 0);
+    };
+    check_escrow_account_info(fee_check, &escrow_account, &mut banks_client).await;
+
+    // Cancel is blocked during the notice period
+    mint_to_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint,
+        &escrow_account.escrow_token_account,
+        &escrow_account.mint_authority,
+        30.0,
+    )
+    .await;
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::cancel(
+            &id(),
+            &escrow_account.escrow.pubkey(),
+            &escrow_account.launcher.pubkey(),
+            &escrow_account.escrow_token_account.pubkey(),
+            &escrow_account.escrow_authority,
+            &escrow_account.canceler_token_account.pubkey(),
+            &spl_token::id(),
+            Some((
+                &escrow_account.reputation_oracle_token.pubkey(),
+                &escrow_account.recording_oracle_token.pubkey(),
+            )),
+            &[],
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+    assert_escrow_error(
+        banks_client.process_transaction(transaction).await,
+        error::EscrowError::CancelNoticePeriod,
+    );
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_setup_cancel_notice() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let escrow_account = EscrowAccount::new();
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    for token_account in [
+        &escrow_account.reputation_oracle_token,
+        &escrow_account.recording_oracle_token,
+    ]
+    .iter()
+    {
+        create_token_account(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            token_account,
+            &escrow_account.token_mint.pubkey(),
+            &Pubkey::new_unique(),
+        )
+        .await;
+    }
+
+    let setup_transaction = |cancel_notice: u64| {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction::setup(
+                &id(),
+                &escrow_account.escrow.pubkey(),
+                &escrow_account.launcher.pubkey(),
+                &escrow_account.reputation_oracle.pubkey(),
+                &escrow_account.reputation_oracle_token.pubkey(),
+                escrow_account.reputation_oracle_stake,
+                &escrow_account.recording_oracle.pubkey(),
+                &escrow_account.recording_oracle_token.pubkey(),
+                escrow_account.recording_oracle_stake,
+                &escrow_account.manifest_url,
+                &escrow_account.manifest_hash,
+                0,
+                0,
+                0,
+                0,
+                cancel_notice,
+                &config_address(),
+                &escrow_account.escrow_token_account.pubkey(),
+                &oracle_stake_address(&escrow_account.reputation_oracle.pubkey()),
+                &oracle_stake_address(&escrow_account.recording_oracle.pubkey()),
+                None,
+            )
+            .unwrap()],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+        transaction
+    };
+
+    // Notice cannot outlast the escrow
+    assert_escrow_error(
+        banks_client
+            .process_transaction(setup_transaction(escrow_account.duration + 3600))
+            .await,
+        error::EscrowError::CancelNoticeTooLong,
+    );
+
+    banks_client
+        .process_transaction(setup_transaction(escrow_account.duration / 2))
+        .await
+        .unwrap();
+    let notice_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Pending);
+        assert!(escrow.cancel_notice_end > 0);
+        assert!(escrow.cancel_notice_end <= escrow.expires);
+    };
+    check_escrow_account_info(notice_check, &escrow_account, &mut banks_client).await;
+
+    // Paused escrow can be cancelled during the notice period
+    mint_to_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint,
+        &escrow_account.escrow_token_account,
+        &escrow_account.mint_authority,
+        10.0,
+    )
+    .await;
+    escrow_account
+        .set_paused(&mut banks_client, &payer, &recent_blockhash, true)
+        .await;
+    escrow_account
+        .cancel_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    let cancel_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Cancelled);
+    };
+    check_escrow_account_info(cancel_check, &escrow_account, &mut banks_client).await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_cancel_fee() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let mut escrow_account = EscrowAccount::new();
+    escrow_account.cancellation_fee_percent = 20;
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    escrow_account
+        .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    mint_to_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint,
+        &escrow_account.escrow_token_account,
+        &escrow_account.mint_authority,
+        30.0,
+    )
+    .await;
+
+    escrow_account
+        .cancel_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let cancel_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Cancelled);
+    };
+    check_escrow_account_info(cancel_check, &escrow_account, &mut banks_client).await;
+
+    // 20% of the balance is split 10:15 between the oracles, the rest is refunded
+    for (token_account, amount) in [
+        (&escrow_account.canceler_token_account, 24.0),
+        (&escrow_account.reputation_oracle_token, 2.4),
+        (&escrow_account.recording_oracle_token, 3.6),
+    ]
+    .iter()
+    {
+        let expected = spl_token::ui_amount_to_amount(*amount, DECIMALS);
+        let balance_check = |account: spl_token::state::Account| {
+            assert_eq!(account.amount, expected);
+        };
+        check_token_account_info(balance_check, token_account, &mut banks_client).await;
+    }
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_allow_list() {
+    let allow_list = Keypair::new();
+    let mut escrow_account = EscrowAccount::new();
+    escrow_account.allow_list = Some(allow_list.pubkey());
+    let worker = Keypair::new();
+
+    // Account of another program holding the data of a worker token account
+    let fake_token_account = Pubkey::new_unique();
+    let mut fake_token_data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint: escrow_account.token_mint.pubkey(),
+        owner: worker.pubkey(),
+        state: spl_token::state::AccountState::Initialized,
+        ..spl_token::state::Account::default()
+    }
+    .pack_into_slice(&mut fake_token_data);
+    let mut program_test = program_test();
+    program_test.add_account(
+        fake_token_account,
+        Account {
+            lamports: 1_000_000_000,
+            data: fake_token_data,
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let authority = Keypair::new();
+    create_allow_list(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &allow_list,
+        &authority,
+        2,
+    )
+    .await;
+
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let other = Keypair::new();
+    let worker_token_account = Keypair::new();
+    let other_token_account = Keypair::new();
+    for (owner, token_account) in [
+        (&worker, &worker_token_account),
+        (&other, &other_token_account),
+    ]
+    .iter()
+    {
+        create_token_account(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            token_account,
+            &escrow_account.token_mint.pubkey(),
+            &owner.pubkey(),
+        )
+        .await;
+    }
+
+    // Only the compliance authority can update the list, up to its capacity
+    assert_escrow_error(
+        update_allow_list(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &allow_list.pubkey(),
+            &escrow_account.launcher,
+            &[worker.pubkey()],
+            &[],
+        )
+        .await,
+        error::EscrowError::UnauthorizedSigner,
+    );
+    assert_escrow_error(
+        update_allow_list(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &allow_list.pubkey(),
+            &authority,
+            &[worker.pubkey(), other.pubkey(), Pubkey::new_unique()],
+            &[],
+        )
+        .await,
+        error::EscrowError::AllowListFull,
+    );
+    update_allow_list(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &allow_list.pubkey(),
+        &authority,
+        &[worker.pubkey(), other.pubkey()],
+        &[],
+    )
+    .await
+    .unwrap();
+    update_allow_list(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &allow_list.pubkey(),
+        &authority,
+        &[],
+        &[other.pubkey()],
+    )
+    .await
+    .unwrap();
+
+    escrow_account
+        .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    escrow_account
+        .store_results(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    mint_to_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint,
+        &escrow_account.escrow_token_account,
+        &escrow_account.mint_authority,
+        escrow_account.total_amount,
+    )
+    .await;
+
+    let allow_list_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.allow_list, COption::Some(allow_list.pubkey()));
+    };
+    check_escrow_account_info(allow_list_check, &escrow_account, &mut banks_client).await;
+
+    // Recipients not on the list and payouts without the list are rejected,
+    // even though the trusted handler signs them
+    for (recipient_token_account, allow_list_key, expected) in [
+        (
+            &other_token_account,
+            Some(allow_list.pubkey()),
+            error::EscrowError::RecipientNotAllowed,
+        ),
+        (
+            &worker_token_account,
+            None,
+            error::EscrowError::InvalidAllowList,
+        ),
+        (
+            &worker_token_account,
+            Some(Pubkey::new_unique()),
+            error::EscrowError::InvalidAllowList,
+        ),
+    ]
+    .iter()
+    {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction::payout(
+                &id(),
+                &escrow_account.escrow.pubkey(),
+                &escrow_account.launcher.pubkey(),
+                &escrow_account.escrow_token_account.pubkey(),
+                &escrow_account.escrow_authority,
+                &recipient_token_account.pubkey(),
+                &escrow_account.reputation_oracle_token.pubkey(),
+                &escrow_account.recording_oracle_token.pubkey(),
+                &spl_token::id(),
+                spl_token::ui_amount_to_amount(escrow_account.payout_amount, DECIMALS),
+                allow_list_key.as_ref(),
+                None,
+            )
+            .unwrap()],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+        assert_escrow_error(
+            banks_client.process_transaction(transaction).await,
+            expected.clone(),
+        );
+    }
+
+    // Owner is only read from SPL Token accounts, an account of another program
+    // naming the worker is rejected
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::payout(
+            &id(),
+            &escrow_account.escrow.pubkey(),
+            &escrow_account.launcher.pubkey(),
+            &escrow_account.escrow_token_account.pubkey(),
+            &escrow_account.escrow_authority,
+            &fake_token_account,
+            &escrow_account.reputation_oracle_token.pubkey(),
+            &escrow_account.recording_oracle_token.pubkey(),
+            &spl_token::id(),
+            spl_token::ui_amount_to_amount(escrow_account.payout_amount, DECIMALS),
+            Some(&allow_list.pubkey()),
+            None,
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+    assert_instruction_error(
+        banks_client.process_transaction(transaction).await,
+        0,
+        InstructionError::IncorrectProgramId,
+    );
+
+    // Recipient on the list is paid
+    escrow_account
+        .payout_escrow(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &worker_token_account,
+        )
+        .await;
+    let paid_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Paid);
+        assert_eq!(escrow.sent_recipients, 1);
+    };
+    check_escrow_account_info(paid_check, &escrow_account, &mut banks_client).await;
+}
 
-        let manifest_url: DataUrl = match DataUrl::from_str("http://somemanifest.com") {
-            Ok(url) => url,
-            _ => Default::default(),
-        };
+#[tokio::test]
+async fn test_hmt_escrow_cancel() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let escrow_account = EscrowAccount::new();
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let recipient = Keypair::new();
+    let recipient_token_account = Keypair::new();
+
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &recipient_token_account,
+        &escrow_account.token_mint.pubkey(),
+        &recipient.pubkey(),
+    )
+    .await;
+
+    escrow_account
+        .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    escrow_account
+        .store_results(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let escrow_token_for_payout = 5000.0;
+    let escrow_token_for_payout_to_mint =
+        spl_token::ui_amount_to_amount(escrow_token_for_payout, DECIMALS);
+
+    mint_to_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint,
+        &escrow_account.escrow_token_account,
+        &escrow_account.mint_authority,
+        escrow_token_for_payout,
+    )
+    .await;
+
+    escrow_account
+        .cancel_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let initialize_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Cancelled);
+    };
+    check_escrow_account_info(initialize_check, &escrow_account, &mut banks_client).await;
+
+    let store_check = |token_account: spl_token::state::Account| {
+        assert_eq!(token_account.amount, escrow_token_for_payout_to_mint);
+    };
+    check_token_account_info(
+        store_check,
+        &escrow_account.canceler_token_account,
+        &mut banks_client,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_complete() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let escrow_account = EscrowAccount::new();
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let recipient = Keypair::new();
+    let recipient_token_account = Keypair::new();
+
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &recipient_token_account,
+        &escrow_account.token_mint.pubkey(),
+        &recipient.pubkey(),
+    )
+    .await;
+
+    escrow_account
+        .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    escrow_account
+        .store_results(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let escrow_token_for_payout = 5000.0;
+
+    mint_to_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint,
+        &escrow_account.escrow_token_account,
+        &escrow_account.mint_authority,
+        escrow_token_for_payout,
+    )
+    .await;
+
+    escrow_account
+        .payout_escrow(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &recipient_token_account,
+        )
+        .await;
+
+    escrow_account
+        .complete_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let initialize_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Complete);
+    };
+    check_escrow_account_info(initialize_check, &escrow_account, &mut banks_client).await;
+}
+
+/// Escrow set up for two permit payouts, with the permit signer set
+async fn permit_escrow(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    recipient_token_account: &Keypair,
+) -> EscrowAccount {
+    let mut escrow_account = EscrowAccount::new();
+    escrow_account.total_amount = 60.0;
+    escrow_account.total_recipients = 2;
+    escrow_account
+        .initialize_escrow(banks_client, payer, recent_blockhash)
+        .await;
+
+    create_token_account(
+        banks_client,
+        payer,
+        recent_blockhash,
+        recipient_token_account,
+        &escrow_account.token_mint.pubkey(),
+        &Keypair::new().pubkey(),
+    )
+    .await;
+
+    escrow_account
+        .setup_escrow(banks_client, payer, recent_blockhash)
+        .await;
+    escrow_account
+        .store_results(banks_client, payer, recent_blockhash)
+        .await;
+    set_permit_signer(
+        banks_client,
+        payer,
+        recent_blockhash,
+        &escrow_account.escrow,
+        &escrow_account.launcher,
+        &permit_signer(&permit_key()),
+    )
+    .await;
+
+    mint_to_escrow(
+        banks_client,
+        payer,
+        recent_blockhash,
+        &escrow_account.token_mint,
+        &escrow_account.escrow_token_account,
+        &escrow_account.mint_authority,
+        5000.0,
+    )
+    .await;
+
+    escrow_account
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_payout_with_permit() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let recipient_token_account = Keypair::new();
+    let escrow_account = permit_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &recipient_token_account,
+    )
+    .await;
+
+    payout_with_permit(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account,
+        Some(&permit_key()),
+        &recipient_token_account.pubkey(),
+        0,
+        i64::MAX,
+        &spl_token::id(),
+    )
+    .await
+    .unwrap();
+
+    let permit_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Partial);
+        assert_eq!(escrow.payout_nonce, 1);
+        assert_eq!(escrow.sent_recipients, 1);
+        assert_eq!(
+            escrow.sent_amount,
+            spl_token::ui_amount_to_amount(escrow_account.payout_amount, DECIMALS)
+        );
+    };
+    check_escrow_account_info(permit_check, &escrow_account, &mut banks_client).await;
+
+    // Recipient gets the payout without the oracle fees
+    let recipient_check = |token_account: spl_token::state::Account| {
+        assert_eq!(
+            token_account.amount,
+            spl_token::ui_amount_to_amount(30.0 * 0.75, DECIMALS)
+        );
+    };
+    check_token_account_info(recipient_check, &recipient_token_account, &mut banks_client).await;
+
+    // The same permit relayed again is rejected
+    let relayer = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &relayer.pubkey(),
+            1_000_000_000,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    assert_instruction_error(
+        payout_with_permit(
+            &mut banks_client,
+            &relayer,
+            &recent_blockhash,
+            &escrow_account,
+            Some(&permit_key()),
+            &recipient_token_account.pubkey(),
+            0,
+            i64::MAX,
+            &spl_token::id(),
+        )
+        .await,
+        1,
+        escrow_error(error::EscrowError::InvalidPermitNonce),
+    );
+
+    // Permit with the next nonce which already expired
+    assert_instruction_error(
+        payout_with_permit(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &escrow_account,
+            Some(&permit_key()),
+            &recipient_token_account.pubkey(),
+            1,
+            0,
+            &spl_token::id(),
+        )
+        .await,
+        1,
+        escrow_error(error::EscrowError::PermitExpired),
+    );
+
+    let permit_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.payout_nonce, 1);
+        assert_eq!(escrow.sent_recipients, 1);
+    };
+    check_escrow_account_info(permit_check, &escrow_account, &mut banks_client).await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_payout_with_permit_checks() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let recipient_token_account = Keypair::new();
+    let escrow_account = permit_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &recipient_token_account,
+    )
+    .await;
+
+    // Permit without the Secp256k1 signature verification instruction
+    assert_escrow_error(
+        payout_with_permit(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &escrow_account,
+            None,
+            &recipient_token_account.pubkey(),
+            0,
+            i64::MAX,
+            &spl_token::id(),
+        )
+        .await,
+        error::EscrowError::InvalidPermitSignature,
+    );
+
+    // Permit signed by another key
+    assert_instruction_error(
+        payout_with_permit(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &escrow_account,
+            Some(&SecretKey::parse(&[6; 32]).unwrap()),
+            &recipient_token_account.pubkey(),
+            0,
+            i64::MAX,
+            &spl_token::id(),
+        )
+        .await,
+        1,
+        escrow_error(error::EscrowError::InvalidPermitSignature),
+    );
+
+    // Permit with a wrong nonce
+    assert_instruction_error(
+        payout_with_permit(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &escrow_account,
+            Some(&permit_key()),
+            &recipient_token_account.pubkey(),
+            1,
+            i64::MAX,
+            &spl_token::id(),
+        )
+        .await,
+        1,
+        escrow_error(error::EscrowError::InvalidPermitNonce),
+    );
+
+    // Escrow authority signature cannot be passed to another program
+    assert_instruction_error(
+        payout_with_permit(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &escrow_account,
+            Some(&permit_key()),
+            &recipient_token_account.pubkey(),
+            0,
+            i64::MAX,
+            &id(),
+        )
+        .await,
+        1,
+        InstructionError::IncorrectProgramId,
+    );
 
-        let final_results_url: DataUrl = match DataUrl::from_str("http://result.com") {
-            Ok(url) => url,
-            _ => Default::default(),
-        };
-        Self {
-            escrow,
-            token_mint,
-            escrow_token_account,
-            launcher,
-            canceler,
-            canceler_token_account,
-            duration: 100000 as u64,
-            escrow_authority,
-            bump_seed,
-            reputation_oracle,
-            reputation_oracle_token,
-            reputation_oracle_stake: 10 as u8,
-            recording_oracle,
-            recording_oracle_token,
-            recording_oracle_stake: 15 as u8,
-            total_amount: 30.0 as f64,
-            total_recipients: 1 as u64,
-            payout_amount: 30.0 as f64,
-            mint_authority,
-            manifest_url,
-            manifest_hash,
-            final_results_url,
-            final_results_hash,
-        }
-    }
+    // Recipient must be a token account
+    assert_instruction_error(
+        payout_with_permit(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &escrow_account,
+            Some(&permit_key()),
+            &escrow_account.launcher.pubkey(),
+            0,
+            i64::MAX,
+            &spl_token::id(),
+        )
+        .await,
+        1,
+        InstructionError::IncorrectProgramId,
+    );
 
-    pub async fn initialize_escrow(
-        &self,
-        mut banks_client: &mut BanksClient,
-        payer: &Keypair,
-        recent_blockhash: &Hash,
-    ) {
-        create_mint(
+    // Permits are rejected once the signer is unset
+    set_permit_signer(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.escrow,
+        &escrow_account.launcher,
+        &[0; ETH_ADDRESS_LEN],
+    )
+    .await;
+    assert_instruction_error(
+        payout_with_permit(
             &mut banks_client,
             &payer,
             &recent_blockhash,
-            &self.token_mint,
-            &self.mint_authority.pubkey(),
+            &escrow_account,
+            Some(&permit_key()),
+            &recipient_token_account.pubkey(),
+            0,
+            i64::MAX,
+            &spl_token::id(),
         )
+        .await,
+        1,
+        escrow_error(error::EscrowError::InvalidPermitSignature),
+    );
+
+    let permit_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Pending);
+        assert_eq!(escrow.permit_signer, [0; ETH_ADDRESS_LEN]);
+        assert_eq!(escrow.payout_nonce, 0);
+        assert_eq!(escrow.sent_amount, 0);
+    };
+    check_escrow_account_info(permit_check, &escrow_account, &mut banks_client).await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_pause() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let escrow_account = EscrowAccount::new();
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
         .await;
 
-        //Creating token account for escrow
-        create_token_account(
+    let recipient = Keypair::new();
+    let recipient_token_account = Keypair::new();
+
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &recipient_token_account,
+        &escrow_account.token_mint.pubkey(),
+        &recipient.pubkey(),
+    )
+    .await;
+
+    escrow_account
+        .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    escrow_account
+        .store_results(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    mint_to_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint,
+        &escrow_account.escrow_token_account,
+        &escrow_account.mint_authority,
+        5000.0,
+    )
+    .await;
+
+    escrow_account
+        .set_paused(&mut banks_client, &payer, &recent_blockhash, true)
+        .await;
+
+    let pause_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.paused, true);
+    };
+    check_escrow_account_info(pause_check, &escrow_account, &mut banks_client).await;
+
+    // Payouts are rejected while paused
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::payout(
+            &id(),
+            &escrow_account.escrow.pubkey(),
+            &escrow_account.launcher.pubkey(),
+            &escrow_account.escrow_token_account.pubkey(),
+            &escrow_account.escrow_authority,
+            &recipient_token_account.pubkey(),
+            &escrow_account.reputation_oracle_token.pubkey(),
+            &escrow_account.recording_oracle_token.pubkey(),
+            &spl_token::id(),
+            1,
+            None,
+            None,
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_err());
+
+    // Only the guardian can unpause
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::unpause(
+            &id(),
+            &escrow_account.escrow.pubkey(),
+            &escrow_account.launcher.pubkey(),
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_err());
+
+    escrow_account
+        .set_paused(&mut banks_client, &payer, &recent_blockhash, false)
+        .await;
+    escrow_account
+        .payout_escrow(
             &mut banks_client,
             &payer,
             &recent_blockhash,
-            &self.escrow_token_account,
-            &self.token_mint.pubkey(),
-            &self.escrow_authority,
+            &recipient_token_account,
         )
         .await;
 
-        //Creating token account for canceler
+    let unpause_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.paused, false);
+        assert_eq!(escrow.state, state::EscrowState::Paid);
+    };
+    check_escrow_account_info(unpause_check, &escrow_account, &mut banks_client).await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_config() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let escrow_account = EscrowAccount::new();
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let config = banks_client
+        .get_account(config_address())
+        .await
+        .expect("get_account")
+        .expect("cannot read config account data");
+    let config = state::EscrowConfig::unpack(config.data.as_slice()).unwrap();
+    assert_eq!(config.admin, payer.pubkey());
+    assert_eq!(config.paused, false);
+    assert_eq!(config.max_oracle_stake, MAX_ORACLE_STAKE);
+    assert_eq!(config.min_duration, MIN_DURATION);
+    assert_eq!(config.max_duration, MAX_DURATION);
+    assert_eq!(
+        config.allowed_mints(),
+        &[escrow_account.token_mint.pubkey()]
+    );
+
+    // Escrows with mints outside of the allow-list are rejected
+    let other_mint = Keypair::new();
+    create_mint(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &other_mint,
+        &escrow_account.mint_authority.pubkey(),
+    )
+    .await;
+    let other_escrow = Keypair::new();
+    let other_escrow_token_account = Keypair::new();
+    let (other_escrow_authority, _) =
+        processor::Processor::find_authority_bump_seed(&id(), &other_escrow.pubkey());
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &other_escrow_token_account,
+        &other_mint.pubkey(),
+        &other_escrow_authority,
+    )
+    .await;
+    let other_canceler_token_account = Keypair::new();
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &other_canceler_token_account,
+        &other_mint.pubkey(),
+        &escrow_account.canceler.pubkey(),
+    )
+    .await;
+    let rent = banks_client.get_rent().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &other_escrow.pubkey(),
+                rent.minimum_balance(state::Escrow::LEN),
+                state::Escrow::LEN as u64,
+                &id(),
+            ),
+            instruction::initialize(
+                &id(),
+                &other_escrow.pubkey(),
+                &other_mint.pubkey(),
+                &other_escrow_token_account.pubkey(),
+                &escrow_account.launcher.pubkey(),
+                &escrow_account.canceler.pubkey(),
+                &other_canceler_token_account.pubkey(),
+                &escrow_account.guardian.pubkey(),
+                &config_address(),
+                escrow_account.duration,
+            )
+            .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &other_escrow], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_err());
+
+    // Setup is rejected while the program is paused
+    update_config(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        true,
+        &[escrow_account.token_mint.pubkey()],
+    )
+    .await;
+
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.reputation_oracle_token,
+        &escrow_account.token_mint.pubkey(),
+        &escrow_account.reputation_oracle.pubkey(),
+    )
+    .await;
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.recording_oracle_token,
+        &escrow_account.token_mint.pubkey(),
+        &escrow_account.recording_oracle.pubkey(),
+    )
+    .await;
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::setup(
+            &id(),
+            &escrow_account.escrow.pubkey(),
+            &escrow_account.launcher.pubkey(),
+            &escrow_account.reputation_oracle.pubkey(),
+            &escrow_account.reputation_oracle_token.pubkey(),
+            escrow_account.reputation_oracle_stake,
+            &escrow_account.recording_oracle.pubkey(),
+            &escrow_account.recording_oracle_token.pubkey(),
+            escrow_account.recording_oracle_stake,
+            &escrow_account.manifest_url,
+            &escrow_account.manifest_hash,
+            spl_token::ui_amount_to_amount(escrow_account.max_payout_per_recipient, DECIMALS),
+            spl_token::ui_amount_to_amount(escrow_account.min_payout, DECIMALS),
+            0,
+            0,
+            0,
+            &config_address(),
+            &escrow_account.escrow_token_account.pubkey(),
+            &oracle_stake_address(&escrow_account.reputation_oracle.pubkey()),
+            &oracle_stake_address(&escrow_account.recording_oracle.pubkey()),
+            None,
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_err());
+
+    let setup_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Launched);
+    };
+    check_escrow_account_info(setup_check, &escrow_account, &mut banks_client).await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_config_requires_upgrade_authority() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+
+    // Anyone else signing as the upgrade authority cannot take over the config
+    let impostor = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::initialize_config(
+            &id(),
+            &config_address(),
+            &payer.pubkey(),
+            &program_data_address(),
+            &impostor.pubkey(),
+            MAX_ORACLE_STAKE,
+            MIN_DURATION,
+            MAX_DURATION,
+            &[],
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &impostor], recent_blockhash);
+    assert_escrow_error(
+        banks_client.process_transaction(transaction).await,
+        error::EscrowError::UnauthorizedSigner,
+    );
+    assert!(banks_client
+        .get_account(config_address())
+        .await
+        .unwrap()
+        .is_none());
+
+    initialize_config(&mut banks_client, &payer, &recent_blockhash, &[]).await;
+    let config = banks_client
+        .get_account(config_address())
+        .await
+        .expect("get_account")
+        .expect("cannot read config account data");
+    let config = state::EscrowConfig::unpack(config.data.as_slice()).unwrap();
+    assert_eq!(config.admin, payer.pubkey());
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_oracle_stake() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let escrow_account = EscrowAccount::new();
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let arbiter = Keypair::new();
+    update_staking_config(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint.pubkey(),
+        100.0,
+        1000.0,
+        &arbiter.pubkey(),
+    )
+    .await;
+
+    mint_to_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint,
+        &escrow_account.escrow_token_account,
+        &escrow_account.mint_authority,
+        5000.0,
+    )
+    .await;
+
+    // Oracles get some tokens to stake
+    for (oracle, oracle_token) in &[
+        (
+            &escrow_account.reputation_oracle,
+            &escrow_account.reputation_oracle_token,
+        ),
+        (
+            &escrow_account.recording_oracle,
+            &escrow_account.recording_oracle_token,
+        ),
+    ] {
         create_token_account(
             &mut banks_client,
             &payer,
             &recent_blockhash,
-            &self.canceler_token_account,
-            &self.token_mint.pubkey(),
-            &self.canceler.pubkey(),
+            oracle_token,
+            &escrow_account.token_mint.pubkey(),
+            &oracle.pubkey(),
         )
         .await;
-        create_escrow(
+        mint_to_escrow(
             &mut banks_client,
             &payer,
             &recent_blockhash,
-            &self.escrow,
-            &self.escrow_token_account,
-            &self.launcher.pubkey(),
-            &self.canceler.pubkey(),
-            &self.canceler_token_account,
-            &self.token_mint.pubkey(),
-            &self.duration,
+            &escrow_account.token_mint,
+            oracle_token,
+            &escrow_account.mint_authority,
+            200.0,
         )
         .await;
     }
 
-    pub async fn setup_escrow(
-        &self,
-        mut banks_client: &mut BanksClient,
-        payer: &Keypair,
-        recent_blockhash: &Hash,
-    ) {
-        //Creating token account for reputation_oracle_token
-        create_token_account(
+    // Setup is rejected for the escrow above the threshold without oracle stakes
+    let setup_instruction = instruction::setup(
+        &id(),
+        &escrow_account.escrow.pubkey(),
+        &escrow_account.launcher.pubkey(),
+        &escrow_account.reputation_oracle.pubkey(),
+        &escrow_account.reputation_oracle_token.pubkey(),
+        escrow_account.reputation_oracle_stake,
+        &escrow_account.recording_oracle.pubkey(),
+        &escrow_account.recording_oracle_token.pubkey(),
+        escrow_account.recording_oracle_stake,
+        &escrow_account.manifest_url,
+        &escrow_account.manifest_hash,
+        spl_token::ui_amount_to_amount(escrow_account.max_payout_per_recipient, DECIMALS),
+        spl_token::ui_amount_to_amount(escrow_account.min_payout, DECIMALS),
+        0,
+        0,
+        0,
+        &config_address(),
+        &escrow_account.escrow_token_account.pubkey(),
+        &oracle_stake_address(&escrow_account.reputation_oracle.pubkey()),
+        &oracle_stake_address(&escrow_account.recording_oracle.pubkey()),
+        None,
+    )
+    .unwrap();
+    let mut transaction =
+        Transaction::new_with_payer(&[setup_instruction.clone()], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_err());
+
+    // Both oracles lock their stakes
+    let reputation_oracle_vault = Keypair::new();
+    let recording_oracle_vault = Keypair::new();
+    for (oracle, vault) in &[
+        (&escrow_account.reputation_oracle, &reputation_oracle_vault),
+        (&escrow_account.recording_oracle, &recording_oracle_vault),
+    ] {
+        create_oracle_stake(
             &mut banks_client,
             &payer,
             &recent_blockhash,
-            &self.reputation_oracle_token,
-            &self.token_mint.pubkey(),
-            &self.reputation_oracle.pubkey(),
+            oracle,
+            vault,
+            &escrow_account.token_mint.pubkey(),
         )
         .await;
+    }
 
-        //Creating token account for recording_oracle_token
-        create_token_account(
-            &mut banks_client,
-            &payer,
-            &recent_blockhash,
-            &self.recording_oracle_token,
-            &self.token_mint.pubkey(),
-            &self.recording_oracle.pubkey(),
+    // Deposit is only credited through the token program
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::deposit_oracle_stake(
+            &id(),
+            &oracle_stake_address(&escrow_account.reputation_oracle.pubkey()),
+            &reputation_oracle_vault.pubkey(),
+            &escrow_account.reputation_oracle_token.pubkey(),
+            &escrow_account.reputation_oracle.pubkey(),
+            &id(),
+            spl_token::ui_amount_to_amount(150.0, DECIMALS),
         )
-        .await;
-        setup_escrow(
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(
+        &[&payer, &escrow_account.reputation_oracle],
+        recent_blockhash,
+    );
+    assert_instruction_error(
+        banks_client.process_transaction(transaction).await,
+        0,
+        InstructionError::IncorrectProgramId,
+    );
+
+    for (oracle, oracle_token, vault) in &[
+        (
+            &escrow_account.reputation_oracle,
+            &escrow_account.reputation_oracle_token,
+            &reputation_oracle_vault,
+        ),
+        (
+            &escrow_account.recording_oracle,
+            &escrow_account.recording_oracle_token,
+            &recording_oracle_vault,
+        ),
+    ] {
+        deposit_oracle_stake(
             &mut banks_client,
             &payer,
             &recent_blockhash,
-            &self.escrow,
-            &self.launcher,
-            &self.reputation_oracle,
-            &self.reputation_oracle_token,
-            &self.reputation_oracle_stake,
-            &self.recording_oracle,
-            &self.recording_oracle_token,
-            &self.recording_oracle_stake,
-            &self.manifest_url,
-            &self.manifest_hash,
+            oracle,
+            vault,
+            oracle_token,
+            150.0,
         )
         .await;
     }
 
-    pub async fn store_results(
-        &self,
-        mut banks_client: &mut BanksClient,
-        payer: &Keypair,
-        recent_blockhash: &Hash,
-    ) {
-        store_results(
+    // Transfer makes the transaction differ from the rejected one
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::transfer(&payer.pubkey(), &escrow_account.launcher.pubkey(), 1),
+            setup_instruction,
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let escrow = banks_client
+        .get_account(escrow_account.escrow.pubkey())
+        .await
+        .expect("get_account")
+        .expect("cannot read escrow account data");
+    let escrow = state::Escrow::unpack(escrow.data.as_slice()).unwrap();
+    assert_eq!(escrow.state, state::EscrowState::Pending);
+
+    let reputation_oracle_stake = get_oracle_stake(
+        &mut banks_client,
+        &escrow_account.reputation_oracle.pubkey(),
+    )
+    .await;
+    assert_eq!(
+        reputation_oracle_stake.amount,
+        spl_token::ui_amount_to_amount(150.0, DECIMALS)
+    );
+    assert_eq!(reputation_oracle_stake.locked_until, escrow.expires);
+
+    // Locked stake cannot be withdrawn
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::withdraw_oracle_stake(
+            &id(),
+            &oracle_stake_address(&escrow_account.recording_oracle.pubkey()),
+            &escrow_account.recording_oracle.pubkey(),
+            &recording_oracle_vault.pubkey(),
+            &escrow_account.recording_oracle_token.pubkey(),
+            &spl_token::id(),
+            spl_token::ui_amount_to_amount(150.0, DECIMALS),
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(
+        &[&payer, &escrow_account.recording_oracle],
+        recent_blockhash,
+    );
+    assert!(banks_client.process_transaction(transaction).await.is_err());
+
+    // Only the arbiter or the admin can slash
+    let slash_by = |slasher: &Pubkey, amount: f64| {
+        instruction::slash_oracle(
+            &id(),
+            &config_address(),
+            slasher,
+            &escrow_account.escrow.pubkey(),
+            &oracle_stake_address(&escrow_account.reputation_oracle.pubkey()),
+            &reputation_oracle_vault.pubkey(),
+            &escrow_account.canceler_token_account.pubkey(),
+            &spl_token::id(),
+            spl_token::ui_amount_to_amount(amount, DECIMALS),
+        )
+        .unwrap()
+    };
+    let mut transaction = Transaction::new_with_payer(
+        &[slash_by(&escrow_account.launcher.pubkey(), 50.0)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_err());
+
+    // Oracle stake signature is only passed to the token program
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::slash_oracle(
+            &id(),
+            &config_address(),
+            &arbiter.pubkey(),
+            &escrow_account.escrow.pubkey(),
+            &oracle_stake_address(&escrow_account.reputation_oracle.pubkey()),
+            &reputation_oracle_vault.pubkey(),
+            &escrow_account.canceler_token_account.pubkey(),
+            &id(),
+            spl_token::ui_amount_to_amount(50.0, DECIMALS),
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &arbiter], recent_blockhash);
+    assert_instruction_error(
+        banks_client.process_transaction(transaction).await,
+        0,
+        InstructionError::IncorrectProgramId,
+    );
+
+    let mut transaction =
+        Transaction::new_with_payer(&[slash_by(&arbiter.pubkey(), 50.0)], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &arbiter], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let reputation_oracle_stake = get_oracle_stake(
+        &mut banks_client,
+        &escrow_account.reputation_oracle.pubkey(),
+    )
+    .await;
+    assert_eq!(
+        reputation_oracle_stake.amount,
+        spl_token::ui_amount_to_amount(100.0, DECIMALS)
+    );
+    let canceler_check = |account: spl_token::state::Account| {
+        assert_eq!(
+            account.amount,
+            spl_token::ui_amount_to_amount(50.0, DECIMALS)
+        );
+    };
+    check_token_account_info(
+        canceler_check,
+        &escrow_account.canceler_token_account,
+        &mut banks_client,
+    )
+    .await;
+    let vault_check = |account: spl_token::state::Account| {
+        assert_eq!(
+            account.amount,
+            spl_token::ui_amount_to_amount(100.0, DECIMALS)
+        );
+    };
+    check_token_account_info(vault_check, &reputation_oracle_vault, &mut banks_client).await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_oracle_stake_funded_after_setup() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let mut escrow_account = EscrowAccount::new();
+    escrow_account.total_amount = 5000.0;
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    update_staking_config(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint.pubkey(),
+        100.0,
+        1000.0,
+        &Keypair::new().pubkey(),
+    )
+    .await;
+
+    // Empty escrow is set up without oracle stakes, then funded above the threshold
+    escrow_account
+        .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    mint_to_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint,
+        &escrow_account.escrow_token_account,
+        &escrow_account.mint_authority,
+        5000.0,
+    )
+    .await;
+
+    // Results above the threshold are rejected without oracle stakes
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::store_results(
+            &id(),
+            &escrow_account.escrow.pubkey(),
+            &escrow_account.launcher.pubkey(),
+            spl_token::ui_amount_to_amount(escrow_account.total_amount, DECIMALS),
+            escrow_account.total_recipients,
+            &escrow_account.final_results_url,
+            &escrow_account.final_results_hash,
+            &results_log_address(&escrow_account.escrow.pubkey()),
+            &config_address(),
+            &oracle_stake_address(&escrow_account.reputation_oracle.pubkey()),
+            &oracle_stake_address(&escrow_account.recording_oracle.pubkey()),
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+    assert_escrow_error(
+        banks_client.process_transaction(transaction).await,
+        error::EscrowError::InsufficientOracleStake,
+    );
+
+    // Both oracles stake, their stakes get locked by the results
+    for (oracle, oracle_token) in &[
+        (
+            &escrow_account.reputation_oracle,
+            &escrow_account.reputation_oracle_token,
+        ),
+        (
+            &escrow_account.recording_oracle,
+            &escrow_account.recording_oracle_token,
+        ),
+    ] {
+        mint_to_escrow(
             &mut banks_client,
             &payer,
             &recent_blockhash,
-            &self.escrow,
-            &self.launcher,
-            &self.total_amount,
-            &self.total_recipients,
-            &self.final_results_url,
-            &self.final_results_hash,
+            &escrow_account.token_mint,
+            oracle_token,
+            &escrow_account.mint_authority,
+            150.0,
         )
         .await;
-    }
-
-    pub async fn payout_escrow(
-        &self,
-        mut banks_client: &mut BanksClient,
-        payer: &Keypair,
-        recent_blockhash: &Hash,
-        recipient_token_account: &Keypair,
-    ) {
-        payout(
+        let vault = Keypair::new();
+        create_oracle_stake(
             &mut banks_client,
-            payer,
+            &payer,
             &recent_blockhash,
-            &self.escrow,
-            &self.launcher,
-            &self.escrow_token_account,
-            &self.escrow_authority,
-            recipient_token_account,
-            &self.reputation_oracle_token,
-            &self.recording_oracle_token,
-            &self.payout_amount,
+            oracle,
+            &vault,
+            &escrow_account.token_mint.pubkey(),
         )
         .await;
-    }
-
-    pub async fn cancel_escrow(
-        &self,
-        mut banks_client: &mut BanksClient,
-        payer: &Keypair,
-        recent_blockhash: &Hash,
-    ) {
-        cancel(
+        deposit_oracle_stake(
             &mut banks_client,
-            payer,
+            &payer,
             &recent_blockhash,
-            &self.escrow,
-            &self.launcher,
-            &self.escrow_token_account,
-            &self.escrow_authority,
-            &self.canceler_token_account,
+            oracle,
+            &vault,
+            oracle_token,
+            150.0,
         )
         .await;
     }
+    escrow_account
+        .store_results(&mut banks_client, &payer, &recent_blockhash)
+        .await;
 
-    pub async fn complete_escrow(
-        &self,
-        mut banks_client: &mut BanksClient,
-        payer: &Keypair,
-        recent_blockhash: &Hash,
-    ) {
-        complete(
+    let escrow = banks_client
+        .get_account(escrow_account.escrow.pubkey())
+        .await
+        .expect("get_account")
+        .expect("cannot read escrow account data");
+    let escrow = state::Escrow::unpack(escrow.data.as_slice()).unwrap();
+    for oracle in &[
+        &escrow_account.reputation_oracle,
+        &escrow_account.recording_oracle,
+    ] {
+        let oracle_stake = get_oracle_stake(&mut banks_client, &oracle.pubkey()).await;
+        assert_eq!(oracle_stake.locked_until, escrow.expires);
+    }
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_reputation() {
+    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
+    let mut escrow_account = EscrowAccount::new();
+    escrow_account
+        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let recipient = Keypair::new();
+    let recipient_token_account = Keypair::new();
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &recipient_token_account,
+        &escrow_account.token_mint.pubkey(),
+        &recipient.pubkey(),
+    )
+    .await;
+
+    escrow_account
+        .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    escrow_account
+        .store_results(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let other_worker = Keypair::new();
+    let update = |worker: &Keypair, delta: i64| instruction::ReputationUpdate {
+        worker: worker.pubkey(),
+        delta,
+    };
+
+    // Reputation cannot be updated before payouts
+    assert!(update_reputation(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.escrow,
+        &escrow_account.reputation_oracle,
+        &[update(&recipient, 1)],
+    )
+    .await
+    .is_err());
+
+    mint_to_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint,
+        &escrow_account.escrow_token_account,
+        &escrow_account.mint_authority,
+        5000.0,
+    )
+    .await;
+    escrow_account
+        .payout_escrow(
             &mut banks_client,
-            payer,
+            &payer,
             &recent_blockhash,
-            &self.escrow,
-            &self.launcher,
+            &recipient_token_account,
         )
         .await;
-    }
-}
 
-async fn check_escrow_account_info<F>(f: F, escrow: &EscrowAccount, banks_client: &mut BanksClient)
-where
-    F: Fn(state::Escrow),
-{
-    let escrow = banks_client
-        .get_account(escrow.escrow.pubkey())
-        .await
-        .expect("get_account")
-        .expect("cannot read escrow account data");
+    // Only the reputation oracle can update reputation
+    assert!(update_reputation(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.escrow,
+        &escrow_account.recording_oracle,
+        &[update(&recipient, 2)],
+    )
+    .await
+    .is_err());
 
-    assert_eq!(escrow.data.len(), hmt_escrow::state::Escrow::LEN);
-    match state::Escrow::unpack_from_slice(escrow.data.as_slice()) {
-        Ok(escrow) => {
-            f(escrow);
-        }
-        Err(_) => assert!(false),
-    };
-}
+    update_reputation(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.escrow,
+        &escrow_account.reputation_oracle,
+        &[update(&recipient, 10), update(&other_worker, -3)],
+    )
+    .await
+    .unwrap();
 
-async fn check_token_account_info<F>(f: F, account: &Keypair, banks_client: &mut BanksClient)
-where
-    F: Fn(spl_token::state::Account),
-{
-    let account = banks_client
-        .get_account(account.pubkey())
-        .await
-        .expect("get_account")
-        .expect("cannot read token account data");
+    let reputation = get_reputation(&mut banks_client, &recipient.pubkey()).await;
+    assert_eq!(reputation.worker, recipient.pubkey());
+    assert_eq!(reputation.score, 10);
+    assert_eq!(reputation.jobs_completed, 1);
+    assert_eq!(reputation.last_escrow, escrow_account.escrow.pubkey());
+    let reputation = get_reputation(&mut banks_client, &other_worker.pubkey()).await;
+    assert_eq!(reputation.score, -3);
 
-    match spl_token::state::Account::unpack_from_slice(account.data.as_slice()) {
-        Ok(token_account) => {
-            f(token_account);
-        }
-        Err(_) => assert!(false),
-    };
-}
+    // Repeated updates for the same escrow do not count as new jobs
+    update_reputation(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.escrow,
+        &escrow_account.reputation_oracle,
+        &[update(&recipient, 5)],
+    )
+    .await
+    .unwrap();
 
-#[tokio::test]
-async fn test_hmt_escrow_initialize() {
-    let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
-    let escrow_account = EscrowAccount::new();
+    let reputation = get_reputation(&mut banks_client, &recipient.pubkey()).await;
+    assert_eq!(reputation.score, 15);
+    assert_eq!(reputation.jobs_completed, 1);
+
+    // Second escrow of the same parties pays the recipient again
+    let first_escrow = Keypair::from_bytes(&escrow_account.escrow.to_bytes()).unwrap();
+    escrow_account.escrow = Keypair::new();
+    escrow_account.escrow_token_account = Keypair::new();
+    escrow_account.escrow_authority =
+        processor::Processor::find_authority_bump_seed(&id(), &escrow_account.escrow.pubkey()).0;
+    create_token_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.escrow_token_account,
+        &escrow_account.token_mint.pubkey(),
+        &escrow_account.escrow_authority,
+    )
+    .await;
+    create_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.escrow,
+        &escrow_account.escrow_token_account,
+        &escrow_account.launcher.pubkey(),
+        &escrow_account.canceler.pubkey(),
+        &escrow_account.canceler_token_account,
+        &escrow_account.guardian.pubkey(),
+        &escrow_account.token_mint.pubkey(),
+        &escrow_account.duration,
+    )
+    .await;
+    setup_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.escrow,
+        &escrow_account.launcher,
+        &escrow_account.reputation_oracle,
+        &escrow_account.reputation_oracle_token,
+        &escrow_account.reputation_oracle_stake,
+        &escrow_account.recording_oracle,
+        &escrow_account.recording_oracle_token,
+        &escrow_account.recording_oracle_stake,
+        &escrow_account.manifest_url,
+        &escrow_account.manifest_hash,
+        &escrow_account.max_payout_per_recipient,
+        &escrow_account.min_payout,
+        &escrow_account.cancellation_fee,
+        &escrow_account.cancellation_fee_percent,
+        &escrow_account.cancel_notice,
+        &escrow_account.escrow_token_account,
+        None,
+    )
+    .await;
     escrow_account
-        .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
+        .store_results(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+    mint_to_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.token_mint,
+        &escrow_account.escrow_token_account,
+        &escrow_account.mint_authority,
+        5000.0,
+    )
+    .await;
+    escrow_account
+        .payout_escrow(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &recipient_token_account,
+        )
         .await;
 
-    let initialize_check = |escrow: state::Escrow| {
-        assert_eq!(escrow.state, state::EscrowState::Launched);
-        assert_eq!(escrow.bump_seed, escrow_account.bump_seed);
-        assert_eq!(escrow.token_mint, escrow_account.token_mint.pubkey());
-        assert_eq!(
-            escrow.token_account,
-            escrow_account.escrow_token_account.pubkey()
-        );
-        assert_eq!(escrow.canceler, escrow_account.canceler.pubkey());
-        assert_eq!(
-            escrow.canceler_token_account,
-            escrow_account.canceler_token_account.pubkey()
-        );
-    };
+    // Updates alternating between the escrows count every escrow once
+    update_reputation(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.escrow,
+        &escrow_account.reputation_oracle,
+        &[update(&recipient, 1)],
+    )
+    .await
+    .unwrap();
+    update_reputation(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &first_escrow,
+        &escrow_account.reputation_oracle,
+        &[update(&recipient, 2)],
+    )
+    .await
+    .unwrap();
 
-    check_escrow_account_info(initialize_check, &escrow_account, &mut banks_client).await;
+    let reputation = get_reputation(&mut banks_client, &recipient.pubkey()).await;
+    assert_eq!(reputation.score, 18);
+    assert_eq!(reputation.jobs_completed, 2);
+    assert_eq!(reputation.last_escrow, first_escrow.pubkey());
 }
 
 #[tokio::test]
-async fn test_hmt_escrow_setup() {
+async fn test_hmt_escrow_intermediate_results() {
     let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
     let escrow_account = EscrowAccount::new();
     escrow_account
         .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
         .await;
-
     escrow_account
         .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
         .await;
 
-    let setup_check = |escrow: state::Escrow| {
-        assert_eq!(escrow.state, state::EscrowState::Pending);
-        assert_eq!(escrow.bump_seed, escrow_account.bump_seed);
-        assert_eq!(escrow.token_mint, escrow_account.token_mint.pubkey());
-        assert_eq!(
-            escrow.token_account,
-            escrow_account.escrow_token_account.pubkey()
-        );
+    let first_url = DataUrl::from_str("http://result.com/1").unwrap();
+    let first_hash = DataHash::new_from_array([1; 20]);
+    let second_url = DataUrl::from_str("http://result.com/2").unwrap();
+    let second_hash = DataHash::new_from_array([2; 20]);
 
-        assert_eq!(escrow.canceler, escrow_account.canceler.pubkey());
-        assert_eq!(
-            escrow.canceler_token_account,
-            escrow_account.canceler_token_account.pubkey()
-        );
+    // Only the recording oracle can store intermediate results
+    assert!(store_intermediate_results(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.escrow,
+        &escrow_account.launcher,
+        &first_url,
+        &first_hash,
+    )
+    .await
+    .is_err());
 
-        assert_eq!(
-            escrow.reputation_oracle.unwrap(),
-            escrow_account.reputation_oracle.pubkey()
-        );
-        assert_eq!(
-            escrow.reputation_oracle_token_account.unwrap(),
-            escrow_account.reputation_oracle_token.pubkey()
-        );
+    for (url, hash) in &[(&first_url, &first_hash), (&second_url, &second_hash)] {
+        store_intermediate_results(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &escrow_account.escrow,
+            &escrow_account.recording_oracle,
+            url,
+            hash,
+        )
+        .await
+        .unwrap();
+    }
 
-        assert_eq!(
-            escrow.recording_oracle.unwrap(),
-            escrow_account.recording_oracle.pubkey()
-        );
-        assert_eq!(
-            escrow.recording_oracle_token_account.unwrap(),
-            escrow_account.recording_oracle_token.pubkey()
-        );
+    let log = get_results_log(&mut banks_client, &escrow_account.escrow.pubkey()).await;
+    assert_eq!(log.escrow, escrow_account.escrow.pubkey());
+    assert_eq!(log.count, 2);
 
-        assert_eq!(
-            escrow.reputation_oracle_stake,
-            escrow_account.reputation_oracle_stake
-        );
-        assert_eq!(
-            escrow.recording_oracle_stake,
-            escrow_account.recording_oracle_stake
-        );
-    };
+    // Hash chain over all entries matches the log head
+    let log_entries =
+        get_results_log_entries(&mut banks_client, &escrow_account.escrow.pubkey(), &log).await;
+    let mut head = solana_program::hash::Hash::default();
+    for (entry, (url, hash)) in log_entries
+        .iter()
+        .zip([(&first_url, &first_hash), (&second_url, &second_hash)].iter())
+    {
+        assert_eq!(entry.url, **url);
+        assert_eq!(entry.hash, **hash);
+        assert_eq!(entry.signer, escrow_account.recording_oracle.pubkey());
+        head = entry.next_head(&head);
+    }
+    assert_eq!(head, log.head);
 
-    check_escrow_account_info(setup_check, &escrow_account, &mut banks_client).await;
+    // Final results commit to the log head
+    escrow_account
+        .store_results(&mut banks_client, &payer, &recent_blockhash)
+        .await;
+
+    let results_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.results_log_head, log.head);
+        assert_eq!(escrow.results_log_count, 2);
+    };
+    check_escrow_account_info(results_check, &escrow_account, &mut banks_client).await;
 }
 
 #[tokio::test]
-async fn test_hmt_escrow_store_results() {
+async fn test_hmt_escrow_intermediate_results_pages() {
     let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
     let escrow_account = EscrowAccount::new();
     escrow_account
         .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
         .await;
-
     escrow_account
         .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
         .await;
-    escrow_account
-        .store_results(&mut banks_client, &payer, &recent_blockhash)
-        .await;
 
-    let store_check = |escrow: state::Escrow| {
-        assert_eq!(escrow.state, state::EscrowState::Pending);
-        assert_eq!(
-            escrow.total_amount,
-            spl_token::ui_amount_to_amount(escrow_account.total_amount, DECIMALS)
-        );
-        assert_eq!(escrow.total_recipients, escrow_account.total_recipients);
-        assert_eq!(escrow.manifest_url, escrow_account.manifest_url);
-        assert_eq!(escrow.manifest_hash, escrow_account.manifest_hash);
-        assert_eq!(escrow.final_results_url, escrow_account.final_results_url);
-        assert_eq!(escrow.final_results_hash, escrow_account.final_results_hash);
-    };
+    // Filled page rolls over to a new one, every entry is kept
+    let total = state::RESULTS_LOG_PAGE_ENTRIES + 2;
+    let entries: Vec<(DataUrl, DataHash)> = (0..total)
+        .map(|index| {
+            (
+                DataUrl::from_str(&format!("http://result.com/{}", index)).unwrap(),
+                DataHash::new_from_array([index as u8; 20]),
+            )
+        })
+        .collect();
+    for (url, hash) in entries.iter() {
+        store_intermediate_results(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &escrow_account.escrow,
+            &escrow_account.recording_oracle,
+            url,
+            hash,
+        )
+        .await
+        .unwrap();
+    }
 
-    check_escrow_account_info(store_check, &escrow_account, &mut banks_client).await;
+    let log = get_results_log(&mut banks_client, &escrow_account.escrow.pubkey()).await;
+    assert_eq!(log.count, total as u64);
+    assert_eq!(log.page_count(), 2);
+
+    let log_entries =
+        get_results_log_entries(&mut banks_client, &escrow_account.escrow.pubkey(), &log).await;
+    assert_eq!(log_entries.len(), total);
+    let mut head = solana_program::hash::Hash::default();
+    for (entry, (url, hash)) in log_entries.iter().zip(entries.iter()) {
+        assert_eq!(entry.url, *url);
+        assert_eq!(entry.hash, *hash);
+        head = entry.next_head(&head);
+    }
+    assert_eq!(head, log.head);
+
+    // Entry must go to the page holding the next entry
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::store_intermediate_results(
+            &id(),
+            &escrow_account.escrow.pubkey(),
+            &results_log_address(&escrow_account.escrow.pubkey()),
+            &results_log_page_address(&escrow_account.escrow.pubkey(), 0),
+            &escrow_account.recording_oracle.pubkey(),
+            &entries[0].0,
+            &entries[0].1,
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(
+        &[&payer, &escrow_account.recording_oracle],
+        recent_blockhash,
+    );
+    assert_instruction_error(
+        banks_client.process_transaction(transaction).await,
+        0,
+        InstructionError::InvalidSeeds,
+    );
 }
 
 #[tokio::test]
-async fn test_hmt_escrow_payout() {
+async fn test_hmt_escrow_vaults() {
     let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
     let escrow_account = EscrowAccount::new();
     escrow_account
         .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
         .await;
 
-    let recipient = Keypair::new();
-    let recipient_token_account = Keypair::new();
+    let bonus_mint = Keypair::new();
+    let vault_token_account = Keypair::new();
+    let canceler_bonus_account = Keypair::new();
+    create_mint(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &bonus_mint,
+        &escrow_account.mint_authority.pubkey(),
+    )
+    .await;
+    for (token_account, owner) in [
+        (&vault_token_account, escrow_account.escrow_authority),
+        (&canceler_bonus_account, escrow_account.canceler.pubkey()),
+    ]
+    .iter()
+    {
+        create_token_account(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            token_account,
+            &bonus_mint.pubkey(),
+            owner,
+        )
+        .await;
+    }
 
-    create_token_account(
+    // Launcher funds the vault account
+    let rent = banks_client.get_rent().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &escrow_account.launcher.pubkey(),
+            rent.minimum_balance(state::EscrowVault::LEN),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Vault mint must be allowed by the program config
+    assert_escrow_error(
+        add_vault(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &escrow_account.escrow,
+            &escrow_account.canceler,
+            &bonus_mint.pubkey(),
+            &vault_token_account.pubkey(),
+            &canceler_bonus_account.pubkey(),
+        )
+        .await,
+        error::EscrowError::MintNotAllowed,
+    );
+    update_config(
         &mut banks_client,
         &payer,
         &recent_blockhash,
-        &recipient_token_account,
-        &escrow_account.token_mint.pubkey(),
-        &recipient.pubkey(),
+        false,
+        &[escrow_account.token_mint.pubkey(), bonus_mint.pubkey()],
     )
     .await;
+    add_vault(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.escrow,
+        &escrow_account.launcher,
+        &bonus_mint.pubkey(),
+        &vault_token_account.pubkey(),
+        &canceler_bonus_account.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    let vault = vault_address(&escrow_account.escrow.pubkey(), &bonus_mint.pubkey());
+    let vault_info = get_vault(&mut banks_client, &vault).await;
+    assert_eq!(vault_info.escrow, escrow_account.escrow.pubkey());
+    assert_eq!(vault_info.index, 0);
+    assert_eq!(vault_info.token_account, vault_token_account.pubkey());
+    assert!(!vault_info.results_stored);
+    let vault_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.vault_count, 1);
+        assert_eq!(escrow.unpaid_vaults, 1);
+    };
+    check_escrow_account_info(vault_check, &escrow_account, &mut banks_client).await;
 
     escrow_account
         .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
@@ -745,11 +4116,7 @@ async fn test_hmt_escrow_payout() {
     escrow_account
         .store_results(&mut banks_client, &payer, &recent_blockhash)
         .await;
-
-    let escrow_token_for_payout = 5000.0;
-    let escrow_token_for_payout_to_mint =
-        spl_token::ui_amount_to_amount(escrow_token_for_payout, DECIMALS);
-
+    let bonus_amount = 10.0;
     mint_to_escrow(
         &mut banks_client,
         &payer,
@@ -757,195 +4124,558 @@ async fn test_hmt_escrow_payout() {
         &escrow_account.token_mint,
         &escrow_account.escrow_token_account,
         &escrow_account.mint_authority,
-        escrow_token_for_payout,
+        escrow_account.total_amount,
+    )
+    .await;
+    mint_to_escrow(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &bonus_mint,
+        &vault_token_account,
+        &escrow_account.mint_authority,
+        bonus_amount,
     )
     .await;
 
-    escrow_account
-        .payout_escrow(
+    let worker = Keypair::new();
+    let worker_token_account = Keypair::new();
+    let worker_bonus_account = Keypair::new();
+    let reputation_oracle_bonus_account = Keypair::new();
+    let recording_oracle_bonus_account = Keypair::new();
+    for (token_account, token_mint, owner) in [
+        (
+            &worker_token_account,
+            escrow_account.token_mint.pubkey(),
+            worker.pubkey(),
+        ),
+        (&worker_bonus_account, bonus_mint.pubkey(), worker.pubkey()),
+        (
+            &reputation_oracle_bonus_account,
+            bonus_mint.pubkey(),
+            escrow_account.reputation_oracle.pubkey(),
+        ),
+        (
+            &recording_oracle_bonus_account,
+            bonus_mint.pubkey(),
+            escrow_account.recording_oracle.pubkey(),
+        ),
+    ]
+    .iter()
+    {
+        create_token_account(
             &mut banks_client,
             &payer,
             &recent_blockhash,
-            &recipient_token_account,
+            token_account,
+            token_mint,
+            owner,
         )
         .await;
+    }
 
-    let stake_percent: f64 =
-        (escrow_account.reputation_oracle_stake + escrow_account.recording_oracle_stake).into();
-    let payout_amount_to_mint =
-        spl_token::ui_amount_to_amount(escrow_account.payout_amount, DECIMALS);
-    let result_amount =
-        escrow_account.payout_amount - (escrow_account.payout_amount * (stake_percent / 100.0));
-    let result_amount_to_mint = spl_token::ui_amount_to_amount(result_amount, DECIMALS);
-
-    let amount_check = |token_account: spl_token::state::Account| {
-        assert_eq!(token_account.amount, result_amount_to_mint)
-    };
-
-    check_token_account_info(amount_check, &recipient_token_account, &mut banks_client).await;
-
-    let store_check = |token_account: spl_token::state::Account| {
-        assert_eq!(
-            token_account.amount,
-            escrow_token_for_payout_to_mint - payout_amount_to_mint
+    let vault_payout = |amount: f64, recent_blockhash: Hash| {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction::vault_payout(
+                &id(),
+                &escrow_account.escrow.pubkey(),
+                &escrow_account.launcher.pubkey(),
+                &vault,
+                &vault_token_account.pubkey(),
+                &escrow_account.escrow_authority,
+                &worker_bonus_account.pubkey(),
+                &reputation_oracle_bonus_account.pubkey(),
+                &recording_oracle_bonus_account.pubkey(),
+                &spl_token::id(),
+                spl_token::ui_amount_to_amount(amount, DECIMALS),
+                None,
+            )
+            .unwrap()],
+            Some(&payer.pubkey()),
         );
+        transaction.sign(&[&payer, &escrow_account.launcher], recent_blockhash);
+        transaction
     };
 
-    check_token_account_info(
-        store_check,
-        &escrow_account.escrow_token_account,
+    // Vault cannot pay out before its total is stored
+    assert_escrow_error(
+        banks_client
+            .process_transaction(vault_payout(1.0, recent_blockhash))
+            .await,
+        error::EscrowError::TooManyPayouts,
+    );
+    store_vault_results(
         &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.escrow,
+        &escrow_account.launcher,
+        &vault,
+        bonus_amount,
     )
     .await;
 
-    let reputation_oracle_stake: f64 = escrow_account.reputation_oracle_stake.into();
-    let result_amount = escrow_account.payout_amount * (reputation_oracle_stake / 100.0);
-    let reputation_oracle_payout = spl_token::ui_amount_to_amount(result_amount, DECIMALS);
-
-    let amount_check = |token_account: spl_token::state::Account| {
-        assert_eq!(token_account.amount, reputation_oracle_payout);
+    // Escrow is not paid until the vault is paid out too
+    escrow_account
+        .payout_escrow(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &worker_token_account,
+        )
+        .await;
+    let partial_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Partial);
+        assert_eq!(escrow.unpaid_vaults, 1);
     };
-    check_token_account_info(
-        amount_check,
-        &escrow_account.reputation_oracle_token,
-        &mut banks_client,
-    )
-    .await;
+    check_escrow_account_info(partial_check, &escrow_account, &mut banks_client).await;
 
-    let recording_oracle_stake: f64 = escrow_account.recording_oracle_stake.into();
-    let result_amount = escrow_account.payout_amount * (recording_oracle_stake / 100.0);
-    let recording_oracle_payout = spl_token::ui_amount_to_amount(result_amount, DECIMALS);
+    banks_client
+        .process_transaction(vault_payout(bonus_amount, recent_blockhash))
+        .await
+        .unwrap();
+    let paid_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Paid);
+        assert_eq!(escrow.unpaid_vaults, 0);
+        assert_eq!(escrow.sent_recipients, 1);
+    };
+    check_escrow_account_info(paid_check, &escrow_account, &mut banks_client).await;
 
-    let amount_check = |token_account: spl_token::state::Account| {
-        assert_eq!(token_account.amount, recording_oracle_payout);
+    let vault_info = get_vault(&mut banks_client, &vault).await;
+    assert_eq!(
+        vault_info.sent_amount,
+        spl_token::ui_amount_to_amount(bonus_amount, DECIMALS)
+    );
+    let worker_check = |token_account: spl_token::state::Account| {
+        assert_eq!(
+            token_account.amount,
+            spl_token::ui_amount_to_amount(7.5, DECIMALS)
+        );
+    };
+    check_token_account_info(worker_check, &worker_bonus_account, &mut banks_client).await;
+    let reputation_oracle_check = |token_account: spl_token::state::Account| {
+        assert_eq!(
+            token_account.amount,
+            spl_token::ui_amount_to_amount(1.0, DECIMALS)
+        );
     };
     check_token_account_info(
-        amount_check,
-        &escrow_account.recording_oracle_token,
+        reputation_oracle_check,
+        &reputation_oracle_bonus_account,
         &mut banks_client,
     )
     .await;
 }
 
-#[tokio::test]
-async fn test_hmt_escrow_cancel() {
+/// Cancels the escrow with a vault holding `bonus_amount` tokens and checks both refunds
+async fn check_cancel_vaults(escrow_amount: f64, bonus_amount: f64) {
     let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
     let escrow_account = EscrowAccount::new();
     escrow_account
         .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
         .await;
 
-    let recipient = Keypair::new();
-    let recipient_token_account = Keypair::new();
-
-    create_token_account(
+    let bonus_mint = Keypair::new();
+    let vault_token_account = Keypair::new();
+    let canceler_bonus_account = Keypair::new();
+    create_mint(
         &mut banks_client,
         &payer,
         &recent_blockhash,
-        &recipient_token_account,
-        &escrow_account.token_mint.pubkey(),
-        &recipient.pubkey(),
+        &bonus_mint,
+        &escrow_account.mint_authority.pubkey(),
     )
     .await;
-
-    escrow_account
-        .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
-        .await;
-    escrow_account
-        .store_results(&mut banks_client, &payer, &recent_blockhash)
+    for (token_account, owner) in [
+        (&vault_token_account, escrow_account.escrow_authority),
+        (&canceler_bonus_account, escrow_account.canceler.pubkey()),
+    ]
+    .iter()
+    {
+        create_token_account(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            token_account,
+            &bonus_mint.pubkey(),
+            owner,
+        )
         .await;
-
-    let escrow_token_for_payout = 5000.0;
-    let escrow_token_for_payout_to_mint =
-        spl_token::ui_amount_to_amount(escrow_token_for_payout, DECIMALS);
-
-    mint_to_escrow(
+    }
+    update_config(
         &mut banks_client,
         &payer,
         &recent_blockhash,
-        &escrow_account.token_mint,
-        &escrow_account.escrow_token_account,
-        &escrow_account.mint_authority,
-        escrow_token_for_payout,
+        false,
+        &[escrow_account.token_mint.pubkey(), bonus_mint.pubkey()],
     )
     .await;
 
-    escrow_account
-        .cancel_escrow(&mut banks_client, &payer, &recent_blockhash)
-        .await;
+    let rent = banks_client.get_rent().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &escrow_account.launcher.pubkey(),
+            rent.minimum_balance(state::EscrowVault::LEN),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    add_vault(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow_account.escrow,
+        &escrow_account.launcher,
+        &bonus_mint.pubkey(),
+        &vault_token_account.pubkey(),
+        &canceler_bonus_account.pubkey(),
+    )
+    .await
+    .unwrap();
 
-    let initialize_check = |escrow: state::Escrow| {
-        assert_eq!(escrow.state, state::EscrowState::Cancelled);
+    let vault = vault_address(&escrow_account.escrow.pubkey(), &bonus_mint.pubkey());
+    let vault_refund = instruction::VaultRefund {
+        vault,
+        token_account: vault_token_account.pubkey(),
+        canceler_token_account: canceler_bonus_account.pubkey(),
+    };
+    let cancel = |trusted_handler: &Keypair, vaults: &[instruction::VaultRefund]| {
+        let mut transaction = Transaction::new_with_payer(
+            &[instruction::cancel(
+                &id(),
+                &escrow_account.escrow.pubkey(),
+                &trusted_handler.pubkey(),
+                &escrow_account.escrow_token_account.pubkey(),
+                &escrow_account.escrow_authority,
+                &escrow_account.canceler_token_account.pubkey(),
+                &spl_token::id(),
+                None,
+                vaults,
+            )
+            .unwrap()],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, trusted_handler], recent_blockhash);
+        transaction
     };
-    check_escrow_account_info(initialize_check, &escrow_account, &mut banks_client).await;
 
-    let store_check = |token_account: spl_token::state::Account| {
-        assert_eq!(token_account.amount, escrow_token_for_payout_to_mint);
+    // Nothing to refund
+    assert_escrow_error(
+        banks_client
+            .process_transaction(cancel(&escrow_account.canceler, &[vault_refund]))
+            .await,
+        error::EscrowError::NotEnoughBalance,
+    );
+
+    for (mint, token_account, amount) in [
+        (
+            &escrow_account.token_mint,
+            &escrow_account.escrow_token_account,
+            escrow_amount,
+        ),
+        (&bonus_mint, &vault_token_account, bonus_amount),
+    ]
+    .iter()
+    {
+        if *amount > 0.0 {
+            mint_to_escrow(
+                &mut banks_client,
+                &payer,
+                &recent_blockhash,
+                mint,
+                token_account,
+                &escrow_account.mint_authority,
+                *amount,
+            )
+            .await;
+        }
+    }
+
+    // Cancel must refund every vault
+    assert!(banks_client
+        .process_transaction(cancel(&escrow_account.launcher, &[]))
+        .await
+        .is_err());
+    banks_client
+        .process_transaction(cancel(&escrow_account.launcher, &[vault_refund]))
+        .await
+        .unwrap();
+
+    let cancel_check = |escrow: state::Escrow| {
+        assert_eq!(escrow.state, state::EscrowState::Cancelled);
+    };
+    check_escrow_account_info(cancel_check, &escrow_account, &mut banks_client).await;
+    let escrow_refund_check = |token_account: spl_token::state::Account| {
+        assert_eq!(
+            token_account.amount,
+            spl_token::ui_amount_to_amount(escrow_amount, DECIMALS)
+        );
     };
     check_token_account_info(
-        store_check,
+        escrow_refund_check,
         &escrow_account.canceler_token_account,
         &mut banks_client,
     )
     .await;
+    let bonus_refund_check = |token_account: spl_token::state::Account| {
+        assert_eq!(
+            token_account.amount,
+            spl_token::ui_amount_to_amount(bonus_amount, DECIMALS)
+        );
+    };
+    check_token_account_info(
+        bonus_refund_check,
+        &canceler_bonus_account,
+        &mut banks_client,
+    )
+    .await;
 }
 
 #[tokio::test]
-async fn test_hmt_escrow_complete() {
+async fn test_hmt_escrow_cancel_vaults() {
+    check_cancel_vaults(30.0, 10.0).await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_cancel_vaults_only() {
+    // Escrow token account is empty, cancel still refunds the vault
+    check_cancel_vaults(0.0, 10.0).await;
+}
+
+#[tokio::test]
+async fn test_hmt_escrow_template() {
     let (mut banks_client, payer, recent_blockhash) = program_test().start().await;
     let escrow_account = EscrowAccount::new();
     escrow_account
         .initialize_escrow(&mut banks_client, &payer, &recent_blockhash)
         .await;
+    for (token_account, owner) in [
+        (
+            &escrow_account.reputation_oracle_token,
+            escrow_account.reputation_oracle.pubkey(),
+        ),
+        (
+            &escrow_account.recording_oracle_token,
+            escrow_account.recording_oracle.pubkey(),
+        ),
+    ]
+    .iter()
+    {
+        create_token_account(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            token_account,
+            &escrow_account.token_mint.pubkey(),
+            owner,
+        )
+        .await;
+    }
 
-    let recipient = Keypair::new();
-    let recipient_token_account = Keypair::new();
-
-    create_token_account(
+    let template_account = Keypair::new();
+    create_program_account(
         &mut banks_client,
         &payer,
         &recent_blockhash,
-        &recipient_token_account,
-        &escrow_account.token_mint.pubkey(),
-        &recipient.pubkey(),
+        &template_account,
+        state::EscrowTemplate::LEN,
     )
     .await;
+    let template = state::EscrowTemplate {
+        is_initialized: true,
+        authority: escrow_account.launcher.pubkey(),
+        token_mint: escrow_account.token_mint.pubkey(),
+        canceler: escrow_account.canceler.pubkey(),
+        canceler_token_account: escrow_account.canceler_token_account.pubkey(),
+        guardian: escrow_account.guardian.pubkey(),
+        duration: escrow_account.duration,
+        reputation_oracle: escrow_account.reputation_oracle.pubkey(),
+        reputation_oracle_token_account: escrow_account.reputation_oracle_token.pubkey(),
+        reputation_oracle_stake: escrow_account.reputation_oracle_stake,
+        recording_oracle: escrow_account.recording_oracle.pubkey(),
+        recording_oracle_token_account: escrow_account.recording_oracle_token.pubkey(),
+        recording_oracle_stake: escrow_account.recording_oracle_stake,
+        max_payout_per_recipient: 0,
+        min_payout: 0,
+        cancellation_fee: 0,
+        cancellation_fee_percent: 10,
+        cancel_notice: 0,
+        allow_list: COption::None,
+    };
 
-    escrow_account
-        .setup_escrow(&mut banks_client, &payer, &recent_blockhash)
-        .await;
-    escrow_account
-        .store_results(&mut banks_client, &payer, &recent_blockhash)
-        .await;
+    // Template values are checked the same way as by Setup
+    let invalid = state::EscrowTemplate {
+        reputation_oracle_stake: 60,
+        recording_oracle_stake: 50,
+        ..template
+    };
+    assert_escrow_error(
+        initialize_template(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &template_account.pubkey(),
+            &escrow_account.launcher,
+            &invalid,
+        )
+        .await,
+        error::EscrowError::StakeOutOfBounds,
+    );
+    let invalid = state::EscrowTemplate {
+        cancel_notice: escrow_account.duration + 1,
+        ..template
+    };
+    assert_escrow_error(
+        initialize_template(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &template_account.pubkey(),
+            &escrow_account.launcher,
+            &invalid,
+        )
+        .await,
+        error::EscrowError::CancelNoticeTooLong,
+    );
+    // Canceler token account must be a token account of the template mint
+    let invalid = state::EscrowTemplate {
+        canceler_token_account: escrow_account.reputation_oracle.pubkey(),
+        ..template
+    };
+    assert!(initialize_template(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &template_account.pubkey(),
+        &escrow_account.launcher,
+        &invalid,
+    )
+    .await
+    .is_err());
 
-    let escrow_token_for_payout = 5000.0;
+    initialize_template(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &template_account.pubkey(),
+        &escrow_account.launcher,
+        &template,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        get_template(&mut banks_client, &template_account.pubkey()).await,
+        template
+    );
 
-    mint_to_escrow(
+    // New escrow is initialized and set up from the template in one step
+    let escrow = Keypair::new();
+    let escrow_token_account = Keypair::new();
+    let escrow_authority =
+        processor::Processor::find_authority_bump_seed(&id(), &escrow.pubkey()).0;
+    create_token_account(
         &mut banks_client,
         &payer,
         &recent_blockhash,
-        &escrow_account.token_mint,
-        &escrow_account.escrow_token_account,
-        &escrow_account.mint_authority,
-        escrow_token_for_payout,
+        &escrow_token_account,
+        &escrow_account.token_mint.pubkey(),
+        &escrow_authority,
+    )
+    .await;
+    create_program_account(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow,
+        state::Escrow::LEN,
     )
     .await;
 
-    escrow_account
-        .payout_escrow(
+    // Only the template authority can use the template
+    let other_authority = state::EscrowTemplate {
+        authority: escrow_account.canceler.pubkey(),
+        ..template
+    };
+    assert_escrow_error(
+        initialize_from_template(
             &mut banks_client,
             &payer,
             &recent_blockhash,
-            &recipient_token_account,
+            &escrow.pubkey(),
+            &escrow_account.canceler,
+            &template_account.pubkey(),
+            &other_authority,
+            &escrow_token_account.pubkey(),
+            &escrow_account.manifest_url,
+            &escrow_account.manifest_hash,
         )
-        .await;
-
-    escrow_account
-        .complete_escrow(&mut banks_client, &payer, &recent_blockhash)
-        .await;
+        .await,
+        error::EscrowError::UnauthorizedSigner,
+    );
 
-    let initialize_check = |escrow: state::Escrow| {
-        assert_eq!(escrow.state, state::EscrowState::Complete);
+    // Accounts must match the template
+    let other_guardian = state::EscrowTemplate {
+        guardian: escrow_account.canceler.pubkey(),
+        ..template
     };
-    check_escrow_account_info(initialize_check, &escrow_account, &mut banks_client).await;
+    assert_escrow_error(
+        initialize_from_template(
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &escrow.pubkey(),
+            &escrow_account.launcher,
+            &template_account.pubkey(),
+            &other_guardian,
+            &escrow_token_account.pubkey(),
+            &escrow_account.manifest_url,
+            &escrow_account.manifest_hash,
+        )
+        .await,
+        error::EscrowError::InvalidTemplate,
+    );
+
+    initialize_from_template(
+        &mut banks_client,
+        &payer,
+        &recent_blockhash,
+        &escrow.pubkey(),
+        &escrow_account.launcher,
+        &template_account.pubkey(),
+        &template,
+        &escrow_token_account.pubkey(),
+        &escrow_account.manifest_url,
+        &escrow_account.manifest_hash,
+    )
+    .await
+    .unwrap();
+
+    let escrow = banks_client
+        .get_account(escrow.pubkey())
+        .await
+        .expect("get_account")
+        .expect("cannot read escrow account data");
+    let escrow = state::Escrow::unpack(escrow.data.as_slice()).unwrap();
+    assert_eq!(escrow.state, state::EscrowState::Pending);
+    assert_eq!(escrow.token_account, escrow_token_account.pubkey());
+    assert_eq!(escrow.launcher, escrow_account.launcher.pubkey());
+    assert_eq!(escrow.canceler, escrow_account.canceler.pubkey());
+    assert_eq!(escrow.guardian, escrow_account.guardian.pubkey());
+    assert_eq!(
+        escrow.reputation_oracle_token_account,
+        COption::Some(escrow_account.reputation_oracle_token.pubkey())
+    );
+    assert_eq!(
+        escrow.recording_oracle,
+        COption::Some(escrow_account.recording_oracle.pubkey())
+    );
+    assert_eq!(
+        escrow.recording_oracle_stake,
+        template.recording_oracle_stake
+    );
+    assert_eq!(escrow.cancellation_fee_percent, 10);
+    assert_eq!(escrow.manifest_url, escrow_account.manifest_url);
+    assert_eq!(escrow.manifest_hash, escrow_account.manifest_hash);
 }
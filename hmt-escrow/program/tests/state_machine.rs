@@ -0,0 +1,912 @@
+#![cfg(feature = "test-bpf")]
+
+//! Model-based tests: random instruction sequences run through the program and a reference
+//! model, both must agree on every result, escrow field and token balance
+
+use hmt_escrow::{
+    processor::Processor,
+    state::{DataHash, DataUrl, Escrow, EscrowState},
+    *,
+};
+use proptest::{collection::vec, prelude::*};
+use solana_program::{
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    clock::{Clock, UnixTimestamp},
+    hash::Hash,
+    instruction::Instruction,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    system_instruction, sysvar,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    signature::{keypair_from_seed, Keypair, Signer},
+    transaction::Transaction,
+    transport::TransportError,
+};
+
+const DECIMALS: u8 = 9;
+const MAX_ORACLE_STAKE: u8 = 50;
+const MIN_DURATION: u64 = 60;
+const MAX_DURATION: u64 = 86400;
+/// Durations are whole minutes plus this offset and warps are whole minutes, so expiry
+/// never falls within a few seconds of the clock even if the restarted bank clock drifts
+const EXPIRY_OFFSET: u64 = 30;
+const RECIPIENTS: usize = 2;
+const CASES: u32 = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Actor {
+    Launcher,
+    Canceler,
+    Guardian,
+    ReputationOracle,
+    RecordingOracle,
+    Stranger,
+}
+
+const ACTORS: [Actor; 6] = [
+    Actor::Launcher,
+    Actor::Canceler,
+    Actor::Guardian,
+    Actor::ReputationOracle,
+    Actor::RecordingOracle,
+    Actor::Stranger,
+];
+
+#[derive(Clone, Debug)]
+enum Action {
+    Initialize {
+        minutes: u64,
+    },
+    Setup {
+        signer: Actor,
+        reputation_oracle_stake: u8,
+        recording_oracle_stake: u8,
+        max_payout_per_recipient: u64,
+        min_payout: u64,
+        cancellation_fee: u64,
+        cancellation_fee_percent: u8,
+        cancel_notice: u64,
+    },
+    StoreResults {
+        signer: Actor,
+        total_amount: u64,
+        total_recipients: u64,
+    },
+    Payout {
+        signer: Actor,
+        recipient: usize,
+        amount: u64,
+    },
+    Cancel {
+        signer: Actor,
+    },
+    Complete {
+        signer: Actor,
+    },
+    /// Moves the clock forward
+    Warp {
+        minutes: i64,
+    },
+}
+
+fn actor() -> impl Strategy<Value = Actor> {
+    (0..ACTORS.len()).prop_map(|index| ACTORS[index])
+}
+
+fn initialize() -> impl Strategy<Value = Action> {
+    (0..120u64).prop_map(|minutes| Action::Initialize { minutes })
+}
+
+fn setup() -> impl Strategy<Value = Action> {
+    let limits = (prop_oneof![Just(0), 1..=300u64], 0..=100u64);
+    // Notice is whole minutes plus the expiry offset, so it never ends right at a warp
+    let cancellation = (
+        prop_oneof![Just(0), 1..=300u64],
+        prop_oneof![2 => Just(0), 1 => 1..=110u8],
+        prop_oneof![Just(0), 1..=60u64].prop_map(|minutes| match minutes {
+            0 => 0,
+            minutes => minutes * 60 + EXPIRY_OFFSET,
+        }),
+    );
+    (actor(), 0..=60u8, 0..=60u8, limits, cancellation).prop_map(
+        |(
+            signer,
+            reputation_oracle_stake,
+            recording_oracle_stake,
+            (max_payout, min_payout),
+            (cancellation_fee, cancellation_fee_percent, cancel_notice),
+        )| Action::Setup {
+            signer,
+            reputation_oracle_stake,
+            recording_oracle_stake,
+            max_payout_per_recipient: max_payout,
+            min_payout,
+            cancellation_fee,
+            cancellation_fee_percent,
+            cancel_notice,
+        },
+    )
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    prop_oneof![
+        1 => initialize(),
+        2 => setup(),
+        2 => (actor(), 0..=1_000u64, 0..=4u64).prop_map(
+            |(signer, total_amount, total_recipients)| Action::StoreResults {
+                signer,
+                total_amount,
+                total_recipients,
+            }
+        ),
+        4 => (actor(), 0..RECIPIENTS, 0..=500u64).prop_map(|(signer, recipient, amount)| {
+            Action::Payout {
+                signer,
+                recipient,
+                amount,
+            }
+        }),
+        1 => actor().prop_map(|signer| Action::Cancel { signer }),
+        2 => actor().prop_map(|signer| Action::Complete { signer }),
+        1 => (0..240i64).prop_map(|minutes| Action::Warp { minutes }),
+    ]
+}
+
+/// Sequences mostly start with a valid escrow so later instructions have something to act on
+fn actions() -> impl Strategy<Value = Vec<Action>> {
+    (initialize(), vec(action(), 0..32)).prop_map(|(first, mut rest)| {
+        rest.insert(0, first);
+        rest
+    })
+}
+
+/// Reference model of the escrow and its token accounts
+#[derive(Clone, Debug, Default)]
+struct Model {
+    state: EscrowState,
+    oracles_set: bool,
+    /// Seconds left until expiry by the bank clock
+    expires_in: i64,
+    reputation_oracle_stake: u8,
+    recording_oracle_stake: u8,
+    max_payout_per_recipient: u64,
+    min_payout: u64,
+    cancellation_fee: u64,
+    cancellation_fee_percent: u8,
+    /// Seconds left until the cancel notice period ends by the bank clock
+    cancel_notice_in: i64,
+    total_amount: u64,
+    total_recipients: u64,
+    sent_amount: u64,
+    sent_recipients: u64,
+    escrow_balance: u64,
+    canceler_balance: u64,
+    reputation_oracle_balance: u64,
+    recording_oracle_balance: u64,
+    recipient_balances: [u64; RECIPIENTS],
+    /// Totals paid to the recipient owners while the escrow limits them
+    recipient_payouts: [u64; RECIPIENTS],
+}
+
+impl Model {
+    fn is_trusted(&self, signer: Actor) -> bool {
+        match signer {
+            Actor::Launcher | Actor::Canceler => true,
+            Actor::ReputationOracle | Actor::RecordingOracle => self.oracles_set,
+            Actor::Guardian | Actor::Stranger => false,
+        }
+    }
+
+    /// Checks shared by the trusted handler instructions
+    fn is_allowed(&self, signer: Actor, states: &[EscrowState]) -> bool {
+        self.state != EscrowState::Uninitialized
+            && self.expires_in >= 0
+            && states.contains(&self.state)
+            && self.is_trusted(signer)
+    }
+
+    /// Applies the action if the program should accept it, returns whether it did
+    fn apply(&mut self, action: &Action) -> bool {
+        match *action {
+            Action::Initialize { minutes } => {
+                let duration = minutes * 60 + EXPIRY_OFFSET;
+                if self.state != EscrowState::Uninitialized
+                    || duration < MIN_DURATION
+                    || duration > MAX_DURATION
+                {
+                    return false;
+                }
+                self.state = EscrowState::Launched;
+                self.expires_in = duration as i64;
+            }
+            Action::Setup {
+                signer,
+                reputation_oracle_stake,
+                recording_oracle_stake,
+                max_payout_per_recipient,
+                min_payout,
+                cancellation_fee,
+                cancellation_fee_percent,
+                cancel_notice,
+            } => {
+                let total_stake = reputation_oracle_stake + recording_oracle_stake;
+                if !self.is_allowed(signer, &[EscrowState::Launched])
+                    || total_stake == 0
+                    || total_stake > MAX_ORACLE_STAKE
+                    || (max_payout_per_recipient != 0 && min_payout > max_payout_per_recipient)
+                    || cancellation_fee_percent > 100
+                    || (cancellation_fee != 0 && cancellation_fee_percent != 0)
+                    || cancel_notice as i64 > self.expires_in
+                {
+                    return false;
+                }
+                self.reputation_oracle_stake = reputation_oracle_stake;
+                self.recording_oracle_stake = recording_oracle_stake;
+                self.max_payout_per_recipient = max_payout_per_recipient;
+                self.min_payout = min_payout;
+                self.cancellation_fee = cancellation_fee;
+                self.cancellation_fee_percent = cancellation_fee_percent;
+                self.cancel_notice_in = cancel_notice as i64;
+                self.oracles_set = true;
+                self.state = EscrowState::Pending;
+            }
+            Action::StoreResults {
+                signer,
+                total_amount,
+                total_recipients,
+            } => {
+                if !self.is_allowed(signer, &[EscrowState::Pending, EscrowState::Partial])
+                    || total_amount < self.sent_amount
+                    || total_recipients < self.sent_recipients
+                {
+                    return false;
+                }
+                self.total_amount = total_amount;
+                self.total_recipients = total_recipients;
+            }
+            Action::Payout {
+                signer,
+                recipient,
+                amount,
+            } => {
+                if !self.is_allowed(signer, &[EscrowState::Pending, EscrowState::Partial])
+                    || amount == 0
+                    || amount < self.min_payout
+                    || (self.max_payout_per_recipient != 0
+                        && self.recipient_payouts[recipient] + amount
+                            > self.max_payout_per_recipient)
+                    || self.escrow_balance < amount
+                    || self.sent_amount + amount > self.total_amount
+                    || self.sent_recipients + 1 > self.total_recipients
+                {
+                    return false;
+                }
+                let reputation_oracle_fee = amount * self.reputation_oracle_stake as u64 / 100;
+                let recording_oracle_fee = amount * self.recording_oracle_stake as u64 / 100;
+                self.escrow_balance -= amount;
+                self.reputation_oracle_balance += reputation_oracle_fee;
+                self.recording_oracle_balance += recording_oracle_fee;
+                self.recipient_balances[recipient] +=
+                    amount - reputation_oracle_fee - recording_oracle_fee;
+                if self.max_payout_per_recipient != 0 {
+                    self.recipient_payouts[recipient] += amount;
+                }
+                self.sent_amount += amount;
+                self.sent_recipients += 1;
+                self.state = if self.sent_amount == self.total_amount
+                    && self.sent_recipients == self.total_recipients
+                {
+                    EscrowState::Paid
+                } else {
+                    EscrowState::Partial
+                };
+            }
+            Action::Cancel { signer } => {
+                // Cancel ignores pause and expiry
+                if self.state == EscrowState::Uninitialized
+                    || self.state == EscrowState::Paid
+                    || self.state == EscrowState::Complete
+                    || !self.is_trusted(signer)
+                    || self.cancel_notice_in > 0
+                    || self.escrow_balance == 0
+                {
+                    return false;
+                }
+                // Cancellation fee is split between the oracles in proportion to their fees
+                let fee = if self.cancellation_fee_percent != 0 {
+                    self.escrow_balance * self.cancellation_fee_percent as u64 / 100
+                } else {
+                    self.cancellation_fee.min(self.escrow_balance)
+                };
+                if fee != 0 {
+                    let total_stake =
+                        self.reputation_oracle_stake as u64 + self.recording_oracle_stake as u64;
+                    let reputation_oracle_fee =
+                        fee * self.reputation_oracle_stake as u64 / total_stake;
+                    self.reputation_oracle_balance += reputation_oracle_fee;
+                    self.recording_oracle_balance += fee - reputation_oracle_fee;
+                }
+                self.canceler_balance += self.escrow_balance - fee;
+                self.escrow_balance = 0;
+                self.state = EscrowState::Cancelled;
+            }
+            Action::Complete { signer } => {
+                if !self.is_allowed(signer, &[EscrowState::Paid]) {
+                    return false;
+                }
+                self.state = EscrowState::Complete;
+            }
+            Action::Warp { minutes } => {
+                if self.state != EscrowState::Uninitialized {
+                    self.expires_in -= minutes * 60;
+                    self.cancel_notice_in -= minutes * 60;
+                }
+            }
+        }
+        true
+    }
+}
+
+fn program_test() -> ProgramTest {
+    let mut pc = ProgramTest::new(
+        "hmt_escrow",
+        id(),
+        processor!(processor::Processor::process),
+    );
+
+    // Add SPL Token program
+    pc.add_program(
+        "spl_token",
+        spl_token::id(),
+        processor!(spl_token::processor::Processor::process),
+    );
+
+    // Program data of the upgradeable escrow program, its upgrade authority
+    // initializes the config
+    pc.add_account(
+        Processor::find_program_data_address(&id()),
+        Account {
+            lamports: 1_000_000_000,
+            data: bincode::serialize(&UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(upgrade_authority().pubkey()),
+            })
+            .unwrap(),
+            owner: bpf_loader_upgradeable::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    pc
+}
+
+fn upgrade_authority() -> Keypair {
+    keypair_from_seed(&[7; 32]).unwrap()
+}
+
+fn config_address() -> Pubkey {
+    Processor::find_config_address(&id()).0
+}
+
+/// Test bank with a transaction counter to keep repeated instructions unique
+struct Bank {
+    banks_client: BanksClient,
+    payer: Keypair,
+    recent_blockhash: Hash,
+    transactions: u64,
+}
+
+impl Bank {
+    async fn start(accounts: Vec<(Pubkey, Account)>) -> Self {
+        let mut program_test = program_test();
+        for (address, account) in accounts {
+            program_test.add_account(address, account);
+        }
+        let (banks_client, payer, recent_blockhash) = program_test.start().await;
+        Self {
+            banks_client,
+            payer,
+            recent_blockhash,
+            transactions: 0,
+        }
+    }
+
+    async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+        sink: &Pubkey,
+    ) -> Result<(), TransportError> {
+        // Identical transactions with the same blockhash would be rejected as duplicates
+        self.transactions += 1;
+        let mut all_instructions = vec![system_instruction::transfer(
+            &self.payer.pubkey(),
+            sink,
+            self.transactions,
+        )];
+        all_instructions.extend_from_slice(instructions);
+        let mut transaction =
+            Transaction::new_with_payer(&all_instructions, Some(&self.payer.pubkey()));
+        let mut all_signers: Vec<&dyn Signer> = vec![&self.payer];
+        all_signers.extend(signers.iter().map(|signer| *signer as &dyn Signer));
+        transaction.sign(&all_signers, self.recent_blockhash);
+        self.banks_client.process_transaction(transaction).await
+    }
+
+    async fn get_account(&mut self, address: &Pubkey) -> Option<Account> {
+        self.banks_client
+            .get_account(*address)
+            .await
+            .expect("get_account")
+    }
+
+    async fn token_balance(&mut self, address: &Pubkey) -> u64 {
+        let account = self.get_account(address).await.expect("token account");
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    async fn clock(&mut self) -> UnixTimestamp {
+        let account = self.get_account(&sysvar::clock::id()).await.unwrap();
+        bincode::deserialize::<Clock>(&account.data)
+            .unwrap()
+            .unix_timestamp
+    }
+}
+
+struct Harness {
+    bank: Bank,
+    actors: Vec<Keypair>,
+    token_mint: Keypair,
+    escrow: Keypair,
+    escrow_token_account: Keypair,
+    canceler_token_account: Keypair,
+    reputation_oracle_token_account: Keypair,
+    recording_oracle_token_account: Keypair,
+    recipient_token_accounts: Vec<Keypair>,
+    recipient_owners: Vec<Pubkey>,
+    sink: Keypair,
+}
+
+impl Harness {
+    /// Starts the bank with the program config and all token accounts,
+    /// the escrow token account holds `funding` tokens
+    async fn start(funding: u64) -> Self {
+        let mut harness = Self {
+            bank: Bank::start(vec![]).await,
+            actors: ACTORS.iter().map(|_| Keypair::new()).collect(),
+            token_mint: Keypair::new(),
+            escrow: Keypair::new(),
+            escrow_token_account: Keypair::new(),
+            canceler_token_account: Keypair::new(),
+            reputation_oracle_token_account: Keypair::new(),
+            recording_oracle_token_account: Keypair::new(),
+            recipient_token_accounts: (0..RECIPIENTS).map(|_| Keypair::new()).collect(),
+            recipient_owners: (0..RECIPIENTS).map(|_| Pubkey::new_unique()).collect(),
+            sink: Keypair::new(),
+        };
+
+        let payer = harness.bank.payer.pubkey();
+        let rent = harness.bank.banks_client.get_rent().await.unwrap();
+        harness
+            .bank
+            .process(
+                &[
+                    instruction::initialize_config(
+                        &id(),
+                        &config_address(),
+                        &payer,
+                        &Processor::find_program_data_address(&id()),
+                        &upgrade_authority().pubkey(),
+                        MAX_ORACLE_STAKE,
+                        MIN_DURATION,
+                        MAX_DURATION,
+                        &[],
+                    )
+                    .unwrap(),
+                    system_instruction::create_account(
+                        &payer,
+                        &harness.token_mint.pubkey(),
+                        rent.minimum_balance(spl_token::state::Mint::LEN),
+                        spl_token::state::Mint::LEN as u64,
+                        &spl_token::id(),
+                    ),
+                    spl_token::instruction::initialize_mint(
+                        &spl_token::id(),
+                        &harness.token_mint.pubkey(),
+                        &payer,
+                        None,
+                        DECIMALS,
+                    )
+                    .unwrap(),
+                ],
+                &[&harness.token_mint, &upgrade_authority()],
+                &harness.sink.pubkey(),
+            )
+            .await
+            .unwrap();
+
+        let authority = Processor::find_authority_bump_seed(&id(), &harness.escrow.pubkey()).0;
+        let mut token_accounts = vec![
+            (&harness.escrow_token_account, authority),
+            (
+                &harness.canceler_token_account,
+                harness.actor(Actor::Canceler).pubkey(),
+            ),
+            (
+                &harness.reputation_oracle_token_account,
+                harness.actor(Actor::ReputationOracle).pubkey(),
+            ),
+            (
+                &harness.recording_oracle_token_account,
+                harness.actor(Actor::RecordingOracle).pubkey(),
+            ),
+        ];
+        for (account, owner) in harness
+            .recipient_token_accounts
+            .iter()
+            .zip(harness.recipient_owners.iter())
+        {
+            token_accounts.push((account, *owner));
+        }
+        for (account, owner) in token_accounts {
+            harness
+                .bank
+                .process(
+                    &[
+                        system_instruction::create_account(
+                            &payer,
+                            &account.pubkey(),
+                            rent.minimum_balance(spl_token::state::Account::LEN),
+                            spl_token::state::Account::LEN as u64,
+                            &spl_token::id(),
+                        ),
+                        spl_token::instruction::initialize_account(
+                            &spl_token::id(),
+                            &account.pubkey(),
+                            &harness.token_mint.pubkey(),
+                            &owner,
+                        )
+                        .unwrap(),
+                    ],
+                    &[account],
+                    &harness.sink.pubkey(),
+                )
+                .await
+                .unwrap();
+        }
+
+        harness
+            .bank
+            .process(
+                &[spl_token::instruction::mint_to(
+                    &spl_token::id(),
+                    &harness.token_mint.pubkey(),
+                    &harness.escrow_token_account.pubkey(),
+                    &payer,
+                    &[],
+                    funding,
+                )
+                .unwrap()],
+                &[],
+                &harness.sink.pubkey(),
+            )
+            .await
+            .unwrap();
+        harness
+    }
+
+    fn actor(&self, actor: Actor) -> &Keypair {
+        &self.actors[actor as usize]
+    }
+
+    fn token_accounts(&self) -> Vec<Pubkey> {
+        let mut accounts = vec![
+            self.escrow_token_account.pubkey(),
+            self.canceler_token_account.pubkey(),
+            self.reputation_oracle_token_account.pubkey(),
+            self.recording_oracle_token_account.pubkey(),
+        ];
+        accounts.extend(self.recipient_token_accounts.iter().map(|a| a.pubkey()));
+        accounts
+    }
+
+    async fn get_escrow(&mut self) -> Option<Escrow> {
+        self.bank
+            .get_account(&self.escrow.pubkey())
+            .await
+            .map(|account| Escrow::unpack_unchecked(&account.data).unwrap())
+            .filter(|escrow| escrow.is_initialized())
+    }
+
+    async fn execute(&mut self, action: &Action) -> Result<(), TransportError> {
+        let escrow = self.escrow.pubkey();
+        let authority = Processor::find_authority_bump_seed(&id(), &escrow).0;
+        let (instructions, signer) = match *action {
+            Action::Initialize { minutes } => {
+                let rent = self.bank.banks_client.get_rent().await.unwrap();
+                let instructions = vec![
+                    system_instruction::create_account(
+                        &self.bank.payer.pubkey(),
+                        &escrow,
+                        rent.minimum_balance(Escrow::LEN),
+                        Escrow::LEN as u64,
+                        &id(),
+                    ),
+                    instruction::initialize(
+                        &id(),
+                        &escrow,
+                        &self.token_mint.pubkey(),
+                        &self.escrow_token_account.pubkey(),
+                        &self.actor(Actor::Launcher).pubkey(),
+                        &self.actor(Actor::Canceler).pubkey(),
+                        &self.canceler_token_account.pubkey(),
+                        &self.actor(Actor::Guardian).pubkey(),
+                        &config_address(),
+                        minutes * 60 + EXPIRY_OFFSET,
+                    )
+                    .unwrap(),
+                ];
+                (instructions, &self.escrow)
+            }
+            Action::Setup {
+                signer,
+                reputation_oracle_stake,
+                recording_oracle_stake,
+                max_payout_per_recipient,
+                min_payout,
+                cancellation_fee,
+                cancellation_fee_percent,
+                cancel_notice,
+            } => {
+                let reputation_oracle = self.actor(Actor::ReputationOracle).pubkey();
+                let recording_oracle = self.actor(Actor::RecordingOracle).pubkey();
+                let instruction = instruction::setup(
+                    &id(),
+                    &escrow,
+                    &self.actor(signer).pubkey(),
+                    &reputation_oracle,
+                    &self.reputation_oracle_token_account.pubkey(),
+                    reputation_oracle_stake,
+                    &recording_oracle,
+                    &self.recording_oracle_token_account.pubkey(),
+                    recording_oracle_stake,
+                    &DataUrl::default(),
+                    &DataHash::default(),
+                    max_payout_per_recipient,
+                    min_payout,
+                    cancellation_fee,
+                    cancellation_fee_percent,
+                    cancel_notice,
+                    &config_address(),
+                    &self.escrow_token_account.pubkey(),
+                    &Processor::find_oracle_stake_address(&id(), &reputation_oracle).0,
+                    &Processor::find_oracle_stake_address(&id(), &recording_oracle).0,
+                    None,
+                )
+                .unwrap();
+                (vec![instruction], &self.actors[signer as usize])
+            }
+            Action::StoreResults {
+                signer,
+                total_amount,
+                total_recipients,
+            } => {
+                let instruction = instruction::store_results(
+                    &id(),
+                    &escrow,
+                    &self.actor(signer).pubkey(),
+                    total_amount,
+                    total_recipients,
+                    &DataUrl::default(),
+                    &DataHash::default(),
+                    &Processor::find_results_log_address(&id(), &escrow).0,
+                    &config_address(),
+                    &Processor::find_oracle_stake_address(
+                        &id(),
+                        &self.actor(Actor::ReputationOracle).pubkey(),
+                    )
+                    .0,
+                    &Processor::find_oracle_stake_address(
+                        &id(),
+                        &self.actor(Actor::RecordingOracle).pubkey(),
+                    )
+                    .0,
+                )
+                .unwrap();
+                (vec![instruction], &self.actors[signer as usize])
+            }
+            Action::Payout {
+                signer,
+                recipient,
+                amount,
+            } => {
+                let instruction = instruction::payout(
+                    &id(),
+                    &escrow,
+                    &self.actor(signer).pubkey(),
+                    &self.escrow_token_account.pubkey(),
+                    &authority,
+                    &self.recipient_token_accounts[recipient].pubkey(),
+                    &self.reputation_oracle_token_account.pubkey(),
+                    &self.recording_oracle_token_account.pubkey(),
+                    &spl_token::id(),
+                    amount,
+                    None,
+                    Some((
+                        &Processor::find_recipient_payout_address(
+                            &id(),
+                            &escrow,
+                            &self.recipient_owners[recipient],
+                        )
+                        .0,
+                        &self.bank.payer.pubkey(),
+                    )),
+                )
+                .unwrap();
+                (vec![instruction], &self.actors[signer as usize])
+            }
+            Action::Cancel { signer } => {
+                let instruction = instruction::cancel(
+                    &id(),
+                    &escrow,
+                    &self.actor(signer).pubkey(),
+                    &self.escrow_token_account.pubkey(),
+                    &authority,
+                    &self.canceler_token_account.pubkey(),
+                    &spl_token::id(),
+                    // Only read when the escrow has a cancellation fee
+                    Some((
+                        &self.reputation_oracle_token_account.pubkey(),
+                        &self.recording_oracle_token_account.pubkey(),
+                    )),
+                    &[],
+                )
+                .unwrap();
+                (vec![instruction], &self.actors[signer as usize])
+            }
+            Action::Complete { signer } => {
+                let instruction =
+                    instruction::complete(&id(), &escrow, &self.actor(signer).pubkey()).unwrap();
+                (vec![instruction], &self.actors[signer as usize])
+            }
+            Action::Warp { minutes } => {
+                self.warp(minutes * 60).await;
+                return Ok(());
+            }
+        };
+        self.bank
+            .process(&instructions, &[signer], &self.sink.pubkey())
+            .await
+    }
+
+    /// The test bank clock cannot be moved, so the bank is restarted with the same accounts
+    /// and the escrow expiry moved back instead, which no instruction can tell apart
+    async fn warp(&mut self, seconds: i64) {
+        let mut escrow = match self.get_escrow().await {
+            Some(escrow) => escrow,
+            None => return,
+        };
+        let clock = self.bank.clock().await;
+        let expires_in = escrow.expires - clock - seconds;
+        let cancel_notice_in = escrow.cancel_notice_end - clock - seconds;
+
+        let mut addresses = self.token_accounts();
+        addresses.push(self.token_mint.pubkey());
+        addresses.push(self.escrow.pubkey());
+        addresses.push(config_address());
+        let mut accounts = vec![];
+        for address in addresses {
+            let mut account = self.bank.get_account(&address).await.unwrap();
+            if address == self.escrow.pubkey() {
+                // A throwaway bank shows the clock the restarted bank starts with
+                let clock = Bank::start(vec![]).await.clock().await;
+                escrow.expires = clock + expires_in;
+                if escrow.cancel_notice_end != 0 {
+                    escrow.cancel_notice_end = clock + cancel_notice_in;
+                }
+                Escrow::pack(escrow, &mut account.data).unwrap();
+            }
+            accounts.push((address, account));
+        }
+        self.bank = Bank::start(accounts).await;
+    }
+
+    /// Compares the escrow and balances with the model and checks the invariants
+    async fn check(&mut self, model: &Model, funding: u64, previous_state: EscrowState) {
+        let clock = self.bank.clock().await;
+        let state = match self.get_escrow().await {
+            Some(escrow) => {
+                assert_eq!(escrow.state, model.state);
+                assert_eq!(escrow.expires >= clock, model.expires_in >= 0);
+                assert_eq!(escrow.total_amount, model.total_amount);
+                assert_eq!(escrow.total_recipients, model.total_recipients);
+                assert_eq!(escrow.sent_amount, model.sent_amount);
+                assert_eq!(escrow.sent_recipients, model.sent_recipients);
+                if model.oracles_set {
+                    assert_eq!(
+                        escrow.reputation_oracle_stake,
+                        model.reputation_oracle_stake
+                    );
+                    assert_eq!(escrow.recording_oracle_stake, model.recording_oracle_stake);
+                    assert_eq!(
+                        escrow.max_payout_per_recipient,
+                        model.max_payout_per_recipient
+                    );
+                    assert_eq!(escrow.min_payout, model.min_payout);
+                    assert_eq!(escrow.cancellation_fee, model.cancellation_fee);
+                    assert_eq!(
+                        escrow.cancellation_fee_percent,
+                        model.cancellation_fee_percent
+                    );
+                    assert_eq!(escrow.cancel_notice_end > clock, model.cancel_notice_in > 0);
+                }
+                assert!(escrow.sent_amount <= escrow.total_amount);
+                escrow.state
+            }
+            None => EscrowState::Uninitialized,
+        };
+        assert_eq!(state, model.state);
+        if previous_state == EscrowState::Complete || previous_state == EscrowState::Cancelled {
+            assert_eq!(state, previous_state, "left a terminal state");
+        }
+
+        let mut balances = vec![];
+        for address in self.token_accounts() {
+            balances.push(self.bank.token_balance(&address).await);
+        }
+        let mut expected = vec![
+            model.escrow_balance,
+            model.canceler_balance,
+            model.reputation_oracle_balance,
+            model.recording_oracle_balance,
+        ];
+        expected.extend_from_slice(&model.recipient_balances);
+        assert_eq!(balances, expected);
+        assert_eq!(
+            balances.iter().sum::<u64>(),
+            funding,
+            "tokens not conserved"
+        );
+    }
+}
+
+async fn run(funding: u64, actions: &[Action]) {
+    let mut harness = Harness::start(funding).await;
+    let mut model = Model {
+        escrow_balance: funding,
+        ..Model::default()
+    };
+    for (step, action) in actions.iter().enumerate() {
+        let previous_state = model.state;
+        let expected = model.apply(action);
+        let result = harness.execute(action).await;
+        assert_eq!(
+            result.is_ok(),
+            expected,
+            "step {} {:?}: {:?}",
+            step,
+            action,
+            result
+        );
+        harness.check(&model, funding, previous_state).await;
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(CASES))]
+
+    #[test]
+    fn test_hmt_escrow_state_machine(funding in 0..=1_000u64, actions in actions()) {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(run(funding, &actions));
+    }
+}